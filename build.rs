@@ -3,7 +3,7 @@ use std::{
     path::{Path, PathBuf},
 };
 
-const EXERCISE_LIB: &'static str = "exercise_library";
+const EXERCISE_LIB: &str = "exercise_library";
 
 /// A helper function for recursively copying a directory.
 fn copy_dir<P, Q>(from: P, to: Q)
@@ -0,0 +1,153 @@
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{info, warn};
+use std::fs::{self, File};
+use std::path::{Component, Path, PathBuf};
+
+/// A directory of wodgen state and the name it is stored under in a backup.
+pub type StateDir<'a> = (&'a str, &'a Path);
+
+// --------------------------------------------------
+
+/// Writes the state directories into a gzipped tarball.
+///
+/// Directories that do not exist yet, e.g. the workouts of a fresh install, are
+/// left out of the backup.
+///
+/// # Errors
+///
+/// This function will return an error if the backup file cannot be written or
+/// a directory cannot be read.
+pub fn backup(file_path: &Path, dirs: &[StateDir]) -> Result<()> {
+    let file = File::create(file_path)
+        .with_context(|| format!("Failed to create file: {:?}", file_path))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for (name, dir) in dirs {
+        if !dir.is_dir() {
+            warn!("Skipping {:?}, it does not exist", dir);
+            continue;
+        }
+        builder
+            .append_dir_all(name, dir)
+            .with_context(|| format!("Failed to back up {:?}", dir))?;
+        info!("Backed up {:?} as {}", dir, name);
+    }
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Map a path inside a backup to the state directory it belongs in, refusing
+// anything that could escape it
+fn destination(entry_path: &Path, dirs: &[StateDir]) -> Option<PathBuf> {
+    let mut components = entry_path.components();
+    let Some(Component::Normal(name)) = components.next() else {
+        return None;
+    };
+    let (_, dir) = dirs.iter().find(|(n, _)| name == *n)?;
+    let rest = components.as_path();
+    if rest.components().all(|c| matches!(c, Component::Normal(_))) {
+        Some(dir.join(rest))
+    } else {
+        None
+    }
+}
+
+// --------------------------------------------------
+
+/// Restores the state directories from a backup written by `backup`, replacing
+/// the files it contains and leaving the others alone.
+///
+/// # Returns
+///
+/// The number of files restored.
+///
+/// # Errors
+///
+/// This function will return an error if the backup cannot be read or contains
+/// no wodgen state.
+pub fn restore(file_path: &Path, dirs: &[StateDir]) -> Result<usize> {
+    let file =
+        File::open(file_path).with_context(|| format!("Failed to open file: {:?}", file_path))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let mut restored = 0;
+    for entry in archive
+        .entries()
+        .with_context(|| format!("Failed to read backup: {:?}", file_path))?
+    {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.into_owned();
+        let Some(destination) = destination(&entry_path, dirs) else {
+            warn!("Skipping unexpected entry {:?} in the backup", entry_path);
+            continue;
+        };
+        if entry.header().entry_type().is_dir() {
+            fs::create_dir_all(&destination)?;
+        } else if entry.header().entry_type().is_file() {
+            if let Some(parent) = destination.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            entry
+                .unpack(&destination)
+                .with_context(|| format!("Failed to restore {:?}", destination))?;
+            restored += 1;
+        }
+    }
+    if restored == 0 {
+        bail!("No wodgen state found in the backup: {:?}", file_path);
+    }
+    Ok(restored)
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let library = tmp.path().join("library");
+        let workouts = tmp.path().join("workouts");
+        fs::create_dir_all(workouts.join("alice")).unwrap();
+        fs::create_dir_all(&library).unwrap();
+        fs::write(library.join("push.csv"), "name\ndip\n").unwrap();
+        fs::write(workouts.join("alice").join("2024_06_01.csv"), "group\n").unwrap();
+
+        let file = tmp.path().join("wodgen.tar.gz");
+        let dirs = [
+            ("exercise_library", library.as_path()),
+            ("workouts", workouts.as_path()),
+        ];
+        backup(&file, &dirs).unwrap();
+
+        fs::write(library.join("push.csv"), "broken").unwrap();
+        fs::remove_dir_all(&workouts).unwrap();
+        assert_eq!(restore(&file, &dirs).unwrap(), 2);
+        assert_eq!(
+            fs::read_to_string(library.join("push.csv")).unwrap(),
+            "name\ndip\n"
+        );
+        assert!(workouts.join("alice").join("2024_06_01.csv").exists());
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_destination_stays_in_state_dirs() {
+        let dirs = [("workouts", Path::new("/data/workouts"))];
+        assert_eq!(
+            destination(Path::new("workouts/a.csv"), &dirs),
+            Some(PathBuf::from("/data/workouts/a.csv"))
+        );
+        assert_eq!(
+            destination(Path::new("workouts/../../etc/passwd"), &dirs),
+            None
+        );
+        assert_eq!(destination(Path::new("other/a.csv"), &dirs), None);
+    }
+}
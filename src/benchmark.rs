@@ -0,0 +1,273 @@
+use crate::csv_utils::read_csv;
+use crate::error::{path_to_str, WodgenError};
+use crate::history::{load_workout, saved_workouts, workout_date};
+use crate::naming::slug;
+use crate::notation;
+use crate::substitute::find_exercise;
+use crate::units::{Distance, Duration, Units};
+use crate::{library, Exercise, WorkoutExercise};
+use anyhow::Result;
+use chrono::NaiveDate;
+use log::{info, warn};
+use serde::Deserialize;
+use std::path::Path;
+
+/// File in the exercise library directory holding the benchmarks, the fixed
+/// named workouts done with `wodgen benchmark`.
+pub const BENCHMARKS_FILE: &str = "benchmarks.csv";
+
+/// Marker of the file names of the benchmark sessions, between the date and
+/// the benchmark, e.g. `2024_06_01_benchmark_murph.csv`.
+pub const BENCHMARK_MARKER: &str = "_benchmark_";
+
+// --------------------------------------------------
+
+/// A row of a benchmark: an exercise of the library under the label of its
+/// group, e.g. `B1`, with its sets and goal.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BenchmarkRow {
+    /// Name of the benchmark the row belongs to, e.g. `Murph`
+    pub benchmark: String,
+    #[serde(deserialize_with = "notation::deserialize_group")]
+    pub group: u32,
+    /// Name of the exercise in the library, or the name it is shown under
+    pub exercise: String,
+    #[serde(default)]
+    pub sets: String,
+    #[serde(default)]
+    pub goal: String,
+    #[serde(default)]
+    pub notes: String,
+}
+
+// --------------------------------------------------
+
+/// Loads the benchmarks of an exercise library, none when it has no
+/// benchmarks file.
+///
+/// # Errors
+///
+/// This function will return an error if the file exists but cannot be parsed.
+pub fn load_benchmarks(exercise_library_dir: &Path) -> Result<Vec<BenchmarkRow>> {
+    let file_path = library::find_file(&exercise_library_dir.join(BENCHMARKS_FILE));
+    if !file_path.is_file() {
+        return Ok(Vec::new());
+    }
+    let rows = read_csv::<BenchmarkRow>(path_to_str(&file_path)?)?;
+    info!("Loaded {} benchmark rows from {:?}", rows.len(), file_path);
+    Ok(rows)
+}
+
+/// The names of the benchmarks, in the order of the file.
+pub fn names(rows: &[BenchmarkRow]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for row in rows {
+        if !names.contains(&row.benchmark) {
+            names.push(row.benchmark.clone());
+        }
+    }
+    names
+}
+
+/// Builds the workout of a benchmark, named as in the file whatever the case,
+/// each row prescribed as its exercise is programmed in the library, with the
+/// sets, goal and notes of the row when given.
+///
+/// # Errors
+///
+/// This function will return an error if no benchmark has the name, or an
+/// exercise of the benchmark is not in the library.
+pub fn build(
+    name: &str,
+    rows: &[BenchmarkRow],
+    library: &[Exercise],
+    units: Units,
+) -> Result<Vec<WorkoutExercise>> {
+    let rows: Vec<&BenchmarkRow> = rows
+        .iter()
+        .filter(|row| slug(&row.benchmark) == slug(name))
+        .collect();
+    if rows.is_empty() {
+        return Err(WodgenError::UnknownBenchmark(name.to_string()).into());
+    }
+    rows.iter()
+        .map(|row| {
+            let e = find_exercise(&row.exercise, library)
+                .ok_or_else(|| WodgenError::UnknownExercise(row.exercise.clone()))?;
+            let mut exercise = WorkoutExercise::from_exercise(row.group, e, units);
+            exercise.sets = row.sets.trim().to_string();
+            if !row.goal.trim().is_empty() {
+                exercise.goal = row.goal.trim().to_string();
+            }
+            if !row.notes.trim().is_empty() {
+                exercise.notes = row.notes.trim().to_string();
+            }
+            Ok(exercise)
+        })
+        .collect()
+}
+
+/// The file stem a benchmark session of a day is saved under.
+pub fn stem(date: &str, name: &str) -> String {
+    format!("{}{}{}", date, BENCHMARK_MARKER, slug(name))
+}
+
+/// Loads the saved sessions of a benchmark with their dates, oldest first.
+///
+/// Sessions that cannot be parsed are skipped with a warning.
+///
+/// # Errors
+///
+/// This function will return an error if the directory cannot be read.
+pub fn sessions(workouts_dir: &Path, name: &str) -> Result<Vec<(NaiveDate, Vec<WorkoutExercise>)>> {
+    let suffix = format!("{}{}", BENCHMARK_MARKER, slug(name));
+    let mut sessions = Vec::new();
+    for (modified, path) in saved_workouts(workouts_dir)? {
+        if !path
+            .file_stem()
+            .is_some_and(|stem| stem.to_string_lossy().ends_with(&suffix))
+        {
+            continue;
+        }
+        match load_workout(&path) {
+            Ok(workout) => sessions.push((workout_date(&path, modified), workout)),
+            Err(e) => warn!("Skipping benchmark session {:?}: {:#}", path, e),
+        }
+    }
+    sessions.sort_by_key(|(date, _)| *date);
+    Ok(sessions)
+}
+
+// Sum of the values logged in a column of every row, none when nothing was
+fn total<T>(
+    workout: &[WorkoutExercise],
+    cell: fn(&WorkoutExercise) -> &String,
+    value: fn(T) -> f64,
+) -> Option<f64>
+where
+    T: std::str::FromStr,
+{
+    let values: Vec<f64> = workout
+        .iter()
+        .filter(|e| e.group > 0)
+        .flat_map(|e| cell(e).split([',', ';', '/']))
+        .filter_map(|v| v.trim().parse::<T>().ok())
+        .map(value)
+        .collect();
+    (!values.is_empty()).then(|| values.iter().sum())
+}
+
+/// The score of a session: the reps, time and distance logged in it, each
+/// summed over its sets, e.g. `300 reps, 1.6km`. None when nothing was logged.
+pub fn score(workout: &[WorkoutExercise], units: Units) -> Option<String> {
+    let parts: Vec<String> = [
+        total::<u32>(workout, |e| &e.reps, f64::from).map(|reps| format!("{} reps", reps)),
+        total::<Duration>(workout, |e| &e.time, |t| f64::from(t.seconds()))
+            .map(|seconds| Duration::from_seconds(seconds as u32).render()),
+        total::<Distance>(workout, |e| &e.distance, |d| d.meters())
+            .map(|meters| Distance::from_meters(meters).render(units)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+/// Renders the scores of the earlier sessions of a benchmark, oldest first.
+pub fn render_scores(sessions: &[(NaiveDate, Vec<WorkoutExercise>)], units: Units) -> String {
+    if sessions.is_empty() {
+        return String::from("No previous score, this is the first time\n");
+    }
+    let mut output = String::from("Previous scores:\n");
+    for (date, workout) in sessions {
+        output.push_str(&format!(
+            "  {}: {}\n",
+            date,
+            score(workout, units).unwrap_or_else(|| String::from("nothing logged"))
+        ));
+    }
+    output
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExerciseLevel, ExerciseProgramming, ExerciseType};
+
+    fn exercise(name: &str, exercise_programming: ExerciseProgramming) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type: ExerciseType::Pull,
+            exercise_level: ExerciseLevel::Beginner,
+            exercise_programming,
+            goal: Some(String::from("8")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_benchmarks() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(load_benchmarks(tmp.path()).unwrap().is_empty());
+        std::fs::write(
+            tmp.path().join(BENCHMARKS_FILE),
+            "benchmark,group,exercise,sets,goal,notes\n\
+             Murph,B1,run,1,1.6km,\n\
+             Murph,C1,pull_up,20,5,partition as you like\n\
+             Cindy,B1,pull_up,,,\n",
+        )
+        .unwrap();
+        let rows = load_benchmarks(tmp.path()).unwrap();
+        assert_eq!(names(&rows), ["Murph", "Cindy"]);
+
+        let library = [
+            exercise("run", ExerciseProgramming::Distance),
+            exercise("pull_up", ExerciseProgramming::Reps),
+        ];
+        let workout = build("murph", &rows, &library, Units::Metric).unwrap();
+        assert_eq!(workout.len(), 2);
+        assert_eq!(workout[0].name, "Run");
+        assert_eq!(workout[0].distance, "X");
+        assert_eq!(workout[0].goal, "1.6km");
+        assert_eq!((workout[1].group, workout[1].sets.as_str()), (3, "20"));
+        assert_eq!(workout[1].notes, "partition as you like");
+        assert_eq!(
+            build("cindy", &rows, &library, Units::Metric).unwrap()[0].goal,
+            "8"
+        );
+        assert!(build("fran", &rows, &library, Units::Metric).is_err());
+        assert!(build("murph", &rows, &library[1..], Units::Metric).is_err());
+
+        assert_eq!(score(&workout, Units::Metric), None);
+        let mut logged = workout.clone();
+        logged[0].distance = String::from("1.6km");
+        logged[0].time = String::from("10:30");
+        logged[1].reps = String::from("5/5/4");
+        assert_eq!(
+            score(&logged, Units::Metric).unwrap(),
+            "14 reps, 10:30, 1.6km"
+        );
+
+        let workouts_dir = tmp.path().join("workouts");
+        std::fs::create_dir(&workouts_dir).unwrap();
+        assert_eq!(
+            render_scores(&sessions(&workouts_dir, "Murph").unwrap(), Units::Metric),
+            "No previous score, this is the first time\n"
+        );
+        let mut writer = csv::Writer::from_path(
+            workouts_dir.join(format!("{}.csv", stem("2024_06_01", "Murph"))),
+        )
+        .unwrap();
+        for row in &logged {
+            writer.serialize(row).unwrap();
+        }
+        writer.flush().unwrap();
+        std::fs::write(workouts_dir.join("2024_06_02.csv"), "group\n").unwrap();
+        assert_eq!(
+            render_scores(&sessions(&workouts_dir, "Murph").unwrap(), Units::Metric),
+            "Previous scores:\n  2024-06-01: 14 reps, 10:30, 1.6km\n"
+        );
+    }
+}
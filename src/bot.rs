@@ -0,0 +1,327 @@
+use crate::i18n::{Lang, Text};
+use crate::notation;
+use crate::{ExerciseProgramming, WorkoutExercise};
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
+use serde::Deserialize;
+use std::thread;
+use std::time::Duration;
+
+/// Base URL of the Telegram Bot API.
+pub const TELEGRAM_API: &str = "https://api.telegram.org";
+
+// Seconds a request for updates waits for a message before it returns empty
+const POLL_TIMEOUT_SECS: u32 = 30;
+
+// Seconds to wait before polling again after a network error
+const RETRY_SECS: u64 = 5;
+
+/// A Telegram bot, polling for the messages sent to it and answering them.
+pub struct Telegram {
+    url: String,
+}
+
+// Enum for what a message asks the bot: the commands, or logs of the latest
+// workout, one `<label or exercise> <values>` per line, e.g. `B1 10/10/8`
+#[derive(Debug, PartialEq)]
+pub enum Request {
+    Help,
+    Wod,
+    Log(Vec<(String, String)>),
+}
+
+// The answer of the Bot API to every method
+#[derive(Debug, Deserialize)]
+struct Answer<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+// An update of the bot, a message for those it answers
+#[derive(Debug, Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    chat: Chat,
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+// --------------------------------------------------
+
+/// Reads what a message asks the bot. Commands may carry the name of the bot,
+/// e.g. `/wod@wodgen_bot`, anything else is help.
+pub fn parse(text: &str) -> Request {
+    let text = text.trim();
+    if let Some(command) = text.strip_prefix('/') {
+        let command = command
+            .split(|c: char| c == '@' || c.is_whitespace())
+            .next()
+            .unwrap_or_default();
+        return match command.to_lowercase().as_str() {
+            "wod" => Request::Wod,
+            _ => Request::Help,
+        };
+    }
+    let entries: Option<Vec<(String, String)>> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.trim()
+                .rsplit_once(char::is_whitespace)
+                .map(|(exercise, values)| (exercise.trim().to_string(), values.to_string()))
+        })
+        .collect();
+    match entries {
+        Some(entries) if !entries.is_empty() => Request::Log(entries),
+        _ => Request::Help,
+    }
+}
+
+/// The help message, with the chat id the roster needs to know the athlete.
+pub fn help(chat_id: i64) -> String {
+    format!(
+        "/wod - generate your workout of the day\n\
+         Log the latest workout with one line per exercise, its label or name and what you did, e.g.\n\
+         B1 10/10/8\n\
+         Pull Up 12\n\
+         This chat is {}, the roster lists it as `telegram = {}`.",
+        chat_id, chat_id
+    )
+}
+
+/// Renders a workout as a chat message, one line per row under its label,
+/// with its filled in columns.
+pub fn render(workout: &[WorkoutExercise], title: &str, lang: Lang) -> String {
+    let mut lines = vec![format!("{} {}", lang.text(Text::Workout), title)];
+    for row in notation::rows(workout) {
+        let e = row.exercise;
+        if e.group == 0 {
+            lines.push(format!("{}: {}", e.name, e.notes));
+            continue;
+        }
+        let cells: Vec<String> = [
+            (Text::Sets, &e.sets),
+            (Text::Distance, &e.distance),
+            (Text::Time, &e.time),
+            (Text::Reps, &e.reps),
+            (Text::Goal, &e.goal),
+        ]
+        .iter()
+        .filter(|(_, cell)| !cell.is_empty())
+        .map(|(text, cell)| format!("{} {}", lang.text(*text), cell))
+        .collect();
+        let mut line = format!("{} {}", row.label, e.name);
+        if !cells.is_empty() {
+            line.push_str(&format!(": {}", cells.join(", ")));
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/// Logs values in a workout, in the column each exercise is prescribed in,
+/// finding the exercises by label or name. Returns a line about each entry.
+pub fn log(workout: &mut [WorkoutExercise], entries: &[(String, String)]) -> Vec<String> {
+    let labels = notation::labels(workout.iter());
+    entries
+        .iter()
+        .map(|(exercise, values)| {
+            let name = exercise.to_lowercase();
+            let index = labels
+                .iter()
+                .position(|label| label.eq_ignore_ascii_case(exercise))
+                .or_else(|| {
+                    workout
+                        .iter()
+                        .position(|e| e.group > 0 && e.name.to_lowercase() == name)
+                });
+            let Some(index) = index else {
+                return format!("No exercise {} in the workout", exercise);
+            };
+            let e = &mut workout[index];
+            let cell = match e.programming() {
+                Some(ExerciseProgramming::Distance) => &mut e.distance,
+                Some(ExerciseProgramming::Time) => &mut e.time,
+                Some(ExerciseProgramming::Reps) | None => &mut e.reps,
+            };
+            *cell = values.clone();
+            format!("Logged {} {}: {}", labels[index], e.name, values)
+        })
+        .collect()
+}
+
+// --------------------------------------------------
+
+impl Telegram {
+    /// A bot of the Bot API at a base URL, with the token BotFather gave.
+    pub fn new(api: &str, token: &str) -> Telegram {
+        Telegram {
+            url: format!("{}/bot{}", api.trim_end_matches('/'), token),
+        }
+    }
+
+    // Calls a method of the Bot API with parameters in JSON
+    fn call<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        parameters: serde_json::Value,
+    ) -> Result<T> {
+        let response = ureq::post(&format!("{}/{}", self.url, method))
+            .config()
+            .http_status_as_error(false)
+            .build()
+            .header("Content-Type", "application/json")
+            .send(parameters.to_string())
+            .with_context(|| format!("Failed to call the Telegram method {}", method))?;
+        let body = response.into_body().read_to_string()?;
+        let answer: Answer<T> = serde_json::from_str(&body)
+            .with_context(|| format!("Failed to parse the answer to {}", method))?;
+        match answer.result {
+            Some(result) if answer.ok => Ok(result),
+            _ => bail!(
+                "Telegram refused {}: {}",
+                method,
+                answer.description.unwrap_or_default()
+            ),
+        }
+    }
+
+    /// Answers every message sent to the bot with what `answer` returns for
+    /// its chat id and text, or the error it returns, until the bot fails. A
+    /// reply that cannot be sent is logged and skipped.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the Bot API refuses the bot,
+    /// e.g. with an invalid token. Network errors are retried.
+    pub fn run(&self, mut answer: impl FnMut(i64, &str) -> Result<String>) -> Result<()> {
+        let me: serde_json::Value = self.call("getMe", serde_json::json!({}))?;
+        info!(
+            "Running Telegram bot @{}",
+            me["username"].as_str().unwrap_or("?")
+        );
+        let mut offset = 0;
+        loop {
+            let updates: Vec<Update> = match self.call(
+                "getUpdates",
+                serde_json::json!({ "offset": offset, "timeout": POLL_TIMEOUT_SECS }),
+            ) {
+                Ok(updates) => updates,
+                Err(e) if e.downcast_ref::<ureq::Error>().is_some() => {
+                    warn!("{:#}, retrying in {}s", e, RETRY_SECS);
+                    thread::sleep(Duration::from_secs(RETRY_SECS));
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            for update in updates {
+                offset = update.update_id + 1;
+                let Some(Message {
+                    chat,
+                    text: Some(text),
+                }) = update.message
+                else {
+                    continue;
+                };
+                info!("Message from chat {}: {}", chat.id, text);
+                let reply = answer(chat.id, &text).unwrap_or_else(|e| {
+                    warn!("{:#}", e);
+                    format!("{:#}", e)
+                });
+                // A chat that blocked the bot must not stop it for the others
+                if let Err(e) = self.call::<serde_json::Value>(
+                    "sendMessage",
+                    serde_json::json!({ "chat_id": chat.id, "text": reply }),
+                ) {
+                    warn!("Failed to reply to chat {}: {:#}", chat.id, e);
+                }
+            }
+        }
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workout() -> Vec<WorkoutExercise> {
+        let row = |group: u32, name: &str, reps: &str, time: &str| WorkoutExercise {
+            group,
+            name: String::from(name),
+            sets: String::from("3"),
+            reps: String::from(reps),
+            time: String::from(time),
+            ..Default::default()
+        };
+        vec![
+            WorkoutExercise {
+                name: String::from("Note"),
+                notes: String::from("Easy day"),
+                ..Default::default()
+            },
+            row(2, "Push Up", "X", ""),
+            row(2, "Plank", "", "0:45"),
+        ]
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_parse_messages() {
+        assert_eq!(parse("/wod"), Request::Wod);
+        assert_eq!(parse("/WOD@wodgen_bot now"), Request::Wod);
+        assert_eq!(parse("/start"), Request::Help);
+        assert_eq!(parse("hello"), Request::Help);
+        assert_eq!(
+            parse("B1 10/10/8\n\n  Push Up 12 \n"),
+            Request::Log(vec![
+                (String::from("B1"), String::from("10/10/8")),
+                (String::from("Push Up"), String::from("12")),
+            ])
+        );
+        assert!(help(42).contains("telegram = 42"));
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_log_and_render() {
+        let mut workout = workout();
+        let replies = log(
+            &mut workout,
+            &[
+                (String::from("b1"), String::from("10/10/8")),
+                (String::from("plank"), String::from("0:50")),
+                (String::from("C1"), String::from("5")),
+            ],
+        );
+        assert_eq!(
+            replies,
+            [
+                "Logged B1 Push Up: 10/10/8",
+                "Logged B2 Plank: 0:50",
+                "No exercise C1 in the workout"
+            ]
+        );
+        assert_eq!(workout[2].time, "0:50");
+        assert_eq!(
+            render(&workout, "2024_06_01", Lang::En),
+            "Workout 2024_06_01\n\
+             Note: Easy day\n\
+             B1 Push Up: sets 3, reps 10/10/8\n\
+             B2 Plank: sets 3, time 0:50"
+        );
+    }
+}
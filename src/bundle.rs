@@ -0,0 +1,246 @@
+use crate::export;
+use crate::i18n::Lang;
+use crate::media;
+use crate::WorkoutExercise;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use log::info;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Version of the bundle manifest, raised whenever a field changes meaning or
+/// goes away.
+pub const BUNDLE_VERSION: u32 = 1;
+
+// File of the bundle listing the others
+const MANIFEST_FILE: &str = "manifest.json";
+
+// Directory of the bundle holding the exercise thumbnails
+const MEDIA_DIR: &str = "media";
+
+/// What a bundle holds, saved in it as `manifest.json`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub title: String,
+    pub date: NaiveDate,
+    pub lang: Lang,
+    /// Number of exercises in the workout, without the session notes
+    pub exercises: usize,
+    pub files: Vec<BundledFile>,
+}
+
+/// A file of a bundle, with its size and SHA-256 so clients can check it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundledFile {
+    pub path: String,
+    pub bytes: u64,
+    pub sha256: String,
+    /// The media reference of the exercises a thumbnail was copied from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+// --------------------------------------------------
+
+// Hex digest of the SHA-256 of a file
+fn sha256(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// The local copy of an exercise thumbnail, the cached one for remote images,
+// none when there is no such file
+fn local_thumbnail(reference: &str, media_dir: &Path) -> Option<PathBuf> {
+    let path = if media::is_url(reference) {
+        media::cached_path(media_dir, reference)
+    } else {
+        PathBuf::from(reference)
+    };
+    path.is_file().then_some(path)
+}
+
+// --------------------------------------------------
+
+/// Writes a workout to a zip bundle for another device or a client: the
+/// workout as CSV, HTML and PDF, the thumbnails of its exercises found
+/// locally or in the media cache, and a manifest listing them. The CSV and
+/// HTML refer to the thumbnails of the bundle, remote ones that are not cached
+/// are left as links.
+///
+/// # Errors
+///
+/// This function will return an error if a thumbnail cannot be read or the
+/// bundle cannot be written.
+pub fn write_bundle(
+    output: &Path,
+    title: &str,
+    date: NaiveDate,
+    lang: Lang,
+    workout: &[WorkoutExercise],
+    media_dir: &Path,
+) -> Result<Manifest> {
+    let mut workout = workout.to_vec();
+    // Paths of the thumbnails in the bundle by their local copy
+    let mut thumbnails: BTreeMap<PathBuf, (String, String)> = BTreeMap::new();
+    for row in workout.iter_mut().filter(|row| !row.image.is_empty()) {
+        let Some(path) = local_thumbnail(&row.image, media_dir) else {
+            info!("Thumbnail {} is not cached, left as a link", row.image);
+            continue;
+        };
+        let count = thumbnails.len();
+        let (bundled, _) = thumbnails.entry(path.clone()).or_insert_with(|| {
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            // Local thumbnails of different directories may share a name
+            (
+                format!("{}/{}_{}", MEDIA_DIR, count, file_name),
+                row.image.clone(),
+            )
+        });
+        row.image = bundled.clone();
+    }
+
+    let mut files: Vec<(String, Vec<u8>, Option<String>)> = vec![
+        (
+            format!("{}.csv", title),
+            export::render_csv(&workout, lang)?,
+            None,
+        ),
+        (
+            format!("{}.html", title),
+            export::render_html(&workout, title, lang, media_dir).into_bytes(),
+            None,
+        ),
+        (
+            format!("{}.pdf", title),
+            export::render_pdf(&workout, title, lang),
+            None,
+        ),
+    ];
+    for (path, (bundled, source)) in thumbnails {
+        let content =
+            fs::read(&path).with_context(|| format!("Failed to open file: {:?}", path))?;
+        files.push((bundled, content, Some(source)));
+    }
+
+    let manifest = Manifest {
+        version: BUNDLE_VERSION,
+        title: title.to_string(),
+        date,
+        lang,
+        exercises: workout.iter().filter(|row| row.group > 0).count(),
+        files: files
+            .iter()
+            .map(|(path, content, source)| BundledFile {
+                path: path.clone(),
+                bytes: content.len() as u64,
+                sha256: sha256(content),
+                source: source.clone(),
+            })
+            .collect(),
+    };
+    let json = serde_json::to_string_pretty(&manifest)? + "\n";
+    files.push((MANIFEST_FILE.to_string(), json.into_bytes(), None));
+
+    let file =
+        File::create(output).with_context(|| format!("Failed to create file: {:?}", output))?;
+    let mut zip = ZipWriter::new(file);
+    for (path, content, source) in &files {
+        // Images are compressed already
+        let method = if source.is_some() {
+            CompressionMethod::Stored
+        } else {
+            CompressionMethod::Deflated
+        };
+        zip.start_file(
+            path,
+            SimpleFileOptions::default().compression_method(method),
+        )?;
+        zip.write_all(content)?;
+    }
+    zip.finish()
+        .with_context(|| format!("Failed to write bundle: {:?}", output))?;
+    info!("Bundled {} files into {:?}", files.len(), output);
+    Ok(manifest)
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    #[test]
+    fn test_write_bundle() {
+        let tmp = tempfile::tempdir().unwrap();
+        let media_dir = tmp.path().join("media");
+        fs::create_dir(&media_dir).unwrap();
+        let url = "https://example.com/img/pull_up.png";
+        fs::write(media::cached_path(&media_dir, url), b"cached").unwrap();
+        let local = tmp.path().join("push_up.png");
+        fs::write(&local, b"local").unwrap();
+
+        let row = |name: &str, image: &str| WorkoutExercise {
+            group: 2,
+            name: String::from(name),
+            reps: String::from("X"),
+            image: String::from(image),
+            ..Default::default()
+        };
+        let workout = [
+            row("Pull Up", url),
+            row("Push Up", &local.to_string_lossy()),
+            row("Row", "https://example.com/img/row.png"),
+            row("Pull Up", url),
+        ];
+        let output = tmp.path().join("2024_06_01.zip");
+        let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let manifest =
+            write_bundle(&output, "2024_06_01", date, Lang::En, &workout, &media_dir).unwrap();
+        assert_eq!(manifest.exercises, 4);
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths[..3],
+            ["2024_06_01.csv", "2024_06_01.html", "2024_06_01.pdf"]
+        );
+        // The cached and the local thumbnail, once each, the missing one left out
+        assert_eq!(paths.len(), 5);
+        assert!(manifest
+            .files
+            .iter()
+            .any(|f| f.source.as_deref() == Some(url)));
+
+        let mut archive = ZipArchive::new(File::open(&output).unwrap()).unwrap();
+        let mut read = |name: &str| {
+            let mut content = String::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            content
+        };
+        let read_back: Manifest = serde_json::from_str(&read(MANIFEST_FILE)).unwrap();
+        assert_eq!(read_back.files.len(), 5);
+        let html = read("2024_06_01.html");
+        for file in &manifest.files[3..] {
+            assert!(html.contains(&format!("src=\"{}\"", file.path)));
+            assert_eq!(read(&file.path).len() as u64, file.bytes);
+        }
+        assert!(html.contains("src=\"https://example.com/img/row.png\""));
+        assert!(read("2024_06_01.csv").starts_with("# wodgen workout 3\ngroup,name,sets,"));
+    }
+}
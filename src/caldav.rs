@@ -0,0 +1,256 @@
+use crate::bot;
+use crate::error::WodgenError;
+use crate::i18n::{Lang, Text};
+use crate::WorkoutExercise;
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use log::info;
+use serde::Deserialize;
+
+// Longest line of a calendar object, in bytes, longer ones are folded
+const LINE_OCTETS: usize = 75;
+
+// --------------------------------------------------
+
+// Enum for what a session becomes in the calendar: a task due on its day, or
+// an all-day event
+#[derive(Debug, Default, PartialEq, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Component {
+    #[default]
+    Todo,
+    Event,
+}
+
+/// The CalDAV calendar `--caldav` creates a task per session in, under
+/// `[caldav]` in the user config. The `WODGEN_CALDAV_*` environment variables
+/// take precedence, e.g. to keep the password out of the file.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalDavConfig {
+    /// URL of the calendar collection, e.g. `https://dav.example.com/calendars/me/training/`
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub component: Component,
+}
+
+// --------------------------------------------------
+
+impl CalDavConfig {
+    /// Overrides the settings with the `WODGEN_CALDAV_URL`, `_USERNAME` and
+    /// `_PASSWORD` variables found by `var`.
+    pub fn with_env(mut self, var: impl Fn(&str) -> Option<String>) -> CalDavConfig {
+        for (name, setting) in [
+            ("WODGEN_CALDAV_URL", &mut self.url),
+            ("WODGEN_CALDAV_USERNAME", &mut self.username),
+            ("WODGEN_CALDAV_PASSWORD", &mut self.password),
+        ] {
+            if let Some(value) = var(name) {
+                *setting = Some(value);
+            }
+        }
+        self
+    }
+}
+
+// --------------------------------------------------
+
+// Escape the characters with a meaning in a text value of a calendar object
+fn escape_text(input: &str) -> String {
+    input
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Fold a content line into lines of at most 75 bytes, the next ones starting
+// with a space, without splitting a character
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > LINE_OCTETS {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+/// Renders the session of a day as an iCalendar object holding a task due
+/// that day, or an all-day event, with the workout as its description. The
+/// `uid` identifies the session, so saving it again replaces it.
+pub fn render_ics(
+    workout: &[WorkoutExercise],
+    title: &str,
+    lang: Lang,
+    date: NaiveDate,
+    uid: &str,
+    component: Component,
+    now: DateTime<Utc>,
+) -> String {
+    let day = date.format("%Y%m%d");
+    let (name, when) = match component {
+        Component::Todo => ("VTODO", vec![format!("DUE;VALUE=DATE:{}", day)]),
+        Component::Event => (
+            "VEVENT",
+            vec![
+                format!("DTSTART;VALUE=DATE:{}", day),
+                format!(
+                    "DTEND;VALUE=DATE:{}",
+                    (date + Duration::days(1)).format("%Y%m%d")
+                ),
+            ],
+        ),
+    };
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        format!("PRODID:-//wodgen//wodgen {}//EN", env!("CARGO_PKG_VERSION")),
+        format!("BEGIN:{}", name),
+        format!("UID:{}", uid),
+        format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")),
+    ];
+    lines.extend(when);
+    lines.push(format!(
+        "SUMMARY:{}",
+        escape_text(&format!("{} {}", lang.text(Text::Workout), title))
+    ));
+    lines.push(format!(
+        "DESCRIPTION:{}",
+        escape_text(&bot::render(workout, title, lang))
+    ));
+    lines.push(format!("END:{}", name));
+    lines.push(String::from("END:VCALENDAR"));
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+/// Saves a calendar object in the calendar as `<uid>.ics`, replacing the one
+/// saved before under the same `uid`.
+///
+/// # Errors
+///
+/// This function will return `WodgenError::NoCalDavUrl` if no calendar is set,
+/// or an error if the server refuses the object.
+pub fn save(caldav: &CalDavConfig, uid: &str, ics: &str) -> Result<()> {
+    let url = caldav.url.as_deref().ok_or(WodgenError::NoCalDavUrl)?;
+    let url = format!("{}/{}.ics", url.trim_end_matches('/'), uid);
+    let mut request = ureq::put(&url).header("Content-Type", "text/calendar; charset=utf-8");
+    if let Some(username) = &caldav.username {
+        let credentials = format!("{}:{}", username, caldav.password.as_deref().unwrap_or(""));
+        request = request.header(
+            "Authorization",
+            format!("Basic {}", STANDARD.encode(credentials)),
+        );
+    }
+    request
+        .send(ics)
+        .with_context(|| format!("Failed to save the session to the calendar at {}", url))?;
+    info!("Saved the session to the calendar at {}", url);
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    // Accepts one HTTP request, answers it with 201 and returns it
+    fn serve_once(listener: TcpListener) -> thread::JoinHandle<String> {
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let (mut request, mut length) = (String::new(), 0);
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str(&String::from_utf8(body).unwrap());
+            let mut stream = stream;
+            stream
+                .write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            request
+        })
+    }
+
+    #[test]
+    fn test_save_session() {
+        let workout = [WorkoutExercise {
+            group: 2,
+            name: String::from("Push Up"),
+            sets: String::from("3"),
+            reps: String::from("X"),
+            goal: String::from("10, slow"),
+            ..Default::default()
+        }];
+        let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let now = DateTime::parse_from_rfc3339("2024-05-31T20:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let ics = render_ics(
+            &workout,
+            "2024_06_01",
+            Lang::En,
+            date,
+            "wodgen-2024_06_01",
+            Component::Todo,
+            now,
+        );
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.contains(
+            "BEGIN:VTODO\r\nUID:wodgen-2024_06_01\r\nDTSTAMP:20240531T200000Z\r\nDUE;VALUE=DATE:20240601\r\n"
+        ));
+        assert!(ics.contains("SUMMARY:Workout 2024_06_01\r\n"));
+        assert!(ics.contains("DESCRIPTION:Workout 2024_06_01\\nB1 Push Up: sets 3\\, "));
+        assert!(ics.ends_with("END:VTODO\r\nEND:VCALENDAR\r\n"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= LINE_OCTETS));
+
+        let event = render_ics(&workout, "t", Lang::En, date, "u", Component::Event, now);
+        assert!(event.contains("DTSTART;VALUE=DATE:20240601\r\nDTEND;VALUE=DATE:20240602\r\n"));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = serve_once(listener);
+        let caldav = CalDavConfig {
+            username: Some(String::from("me")),
+            password: Some(String::from("secret")),
+            ..Default::default()
+        }
+        .with_env(|name| {
+            (name == "WODGEN_CALDAV_URL").then(|| format!("http://127.0.0.1:{}/training/", port))
+        });
+        save(&caldav, "wodgen-2024_06_01", &ics).unwrap();
+        let request = server.join().unwrap();
+        assert!(request.starts_with("PUT /training/wodgen-2024_06_01.ics HTTP/1.1\r\n"));
+        assert!(request
+            .to_lowercase()
+            .contains("authorization: basic bwu6c2vjcmv0\r\n"));
+        assert!(request.ends_with(&ics));
+
+        let error = save(&CalDavConfig::default(), "u", &ics);
+        assert!(error
+            .unwrap_err()
+            .to_string()
+            .contains("No CalDAV calendar"));
+    }
+}
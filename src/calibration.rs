@@ -0,0 +1,232 @@
+use crate::csv_utils::{read_csv, write_csv};
+use crate::error::path_to_str;
+use crate::{filter_by_level, Exercise, ExerciseLevel, ExerciseType};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// File in the state directory holding the calibrated level of each exercise
+/// type.
+pub const CALIBRATION_FILE: &str = "calibration.csv";
+
+// --------------------------------------------------
+
+// Enum for the capability tests calibrating the level of an exercise type, each
+// a max effort in one set: reps, or seconds for the plank
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Test {
+    PullUps,
+    PushUps,
+    Squats,
+    Plank,
+}
+
+/// A number logged for a capability test, e.g. `pull_ups,8`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedNumber {
+    pub test: Test,
+    pub value: u32,
+}
+
+/// The level an exercise type is trained at in place of `--level`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Calibration {
+    pub exercise_type: ExerciseType,
+    pub exercise_level: ExerciseLevel,
+    /// Result of the capability test the level was read from
+    pub test: Test,
+    pub value: u32,
+    pub calibrated_at: DateTime<Utc>,
+}
+
+/// The level of each exercise type of a session: the calibrated one, or that
+/// of `--level` for the others.
+#[derive(Debug, Clone, Default)]
+pub struct Levels {
+    /// Level of the session, that of the types not calibrated
+    pub session: ExerciseLevel,
+    calibrated: HashMap<ExerciseType, ExerciseLevel>,
+}
+
+// --------------------------------------------------
+
+impl Test {
+    /// The exercise type the test calibrates.
+    pub fn exercise_type(&self) -> ExerciseType {
+        match self {
+            Test::PullUps => ExerciseType::Pull,
+            Test::PushUps => ExerciseType::Push,
+            Test::Squats => ExerciseType::Legs,
+            Test::Plank => ExerciseType::Core,
+        }
+    }
+
+    // Results from which the test reads the intermediate and the advanced
+    // levels
+    fn thresholds(&self) -> (u32, u32) {
+        match self {
+            Test::PullUps => (5, 12),
+            Test::PushUps => (15, 40),
+            Test::Squats => (30, 60),
+            Test::Plank => (60, 120),
+        }
+    }
+
+    /// A result of the test, e.g. `8 pull-ups` or `90s plank`.
+    pub fn describe(&self, value: u32) -> String {
+        match self {
+            Test::PullUps => format!("{} pull-ups", value),
+            Test::PushUps => format!("{} push-ups", value),
+            Test::Squats => format!("{} squats", value),
+            Test::Plank => format!("{}s plank", value),
+        }
+    }
+
+    /// The level a result of the test calls for.
+    pub fn level(&self, value: u32) -> ExerciseLevel {
+        let (intermediate, advanced) = self.thresholds();
+        if value >= advanced {
+            ExerciseLevel::Advanced
+        } else if value >= intermediate {
+            ExerciseLevel::Intermediate
+        } else {
+            ExerciseLevel::Beginner
+        }
+    }
+}
+
+/// Loads the calibrated levels, none when the file does not exist.
+///
+/// # Errors
+///
+/// This function will return an error if the file exists but cannot be parsed.
+pub fn load_calibrations(file_path: &Path) -> Result<Vec<Calibration>> {
+    if !file_path.exists() {
+        return Ok(Vec::new());
+    }
+    read_csv::<Calibration>(path_to_str(file_path)?)
+}
+
+/// Loads numbers logged for the capability tests, the last of each test being
+/// the one kept.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or parsed.
+pub fn import_numbers(file_path: &Path) -> Result<Vec<LoggedNumber>> {
+    let numbers = read_csv::<LoggedNumber>(path_to_str(file_path)?)
+        .with_context(|| format!("Failed to import the logged numbers: {:?}", file_path))?;
+    let mut kept: Vec<LoggedNumber> = Vec::new();
+    for number in numbers {
+        kept.retain(|n| n.test != number.test);
+        kept.push(number);
+    }
+    Ok(kept)
+}
+
+/// Calibrates the level of the exercise types from results of the capability
+/// tests, replacing the levels they had.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or written.
+pub fn calibrate(file_path: &Path, numbers: &[LoggedNumber]) -> Result<Vec<Calibration>> {
+    let mut calibrations = load_calibrations(file_path)?;
+    for number in numbers {
+        let calibration = Calibration {
+            exercise_type: number.test.exercise_type(),
+            exercise_level: number.test.level(number.value),
+            test: number.test,
+            value: number.value,
+            calibrated_at: Utc::now(),
+        };
+        info!(
+            "Calibrated {:?} at {:?} level from {}",
+            calibration.exercise_type,
+            calibration.exercise_level,
+            number.test.describe(number.value)
+        );
+        calibrations.retain(|c| c.exercise_type != calibration.exercise_type);
+        calibrations.push(calibration);
+    }
+    calibrations.sort_by_key(|c| format!("{:?}", c.exercise_type));
+    if let Some(state_dir) = file_path.parent() {
+        fs::create_dir_all(state_dir)?;
+    }
+    write_csv(path_to_str(file_path)?, calibrations.clone())?;
+    Ok(calibrations)
+}
+
+// --------------------------------------------------
+
+impl From<ExerciseLevel> for Levels {
+    fn from(session: ExerciseLevel) -> Levels {
+        Levels {
+            session,
+            calibrated: HashMap::new(),
+        }
+    }
+}
+
+impl Levels {
+    /// The levels of a session at `session`, the calibrated types at theirs.
+    pub fn calibrated(session: ExerciseLevel, calibrations: &[Calibration]) -> Levels {
+        Levels {
+            session,
+            calibrated: calibrations
+                .iter()
+                .map(|c| (c.exercise_type.clone(), c.exercise_level.clone()))
+                .collect(),
+        }
+    }
+
+    /// The level of an exercise type.
+    pub fn of(&self, t: &ExerciseType) -> &ExerciseLevel {
+        self.calibrated.get(t).unwrap_or(&self.session)
+    }
+
+    /// Whether an exercise is at or below the level of its type.
+    pub fn allows(&self, e: &Exercise) -> bool {
+        filter_by_level(e, self.of(&e.exercise_type))
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calibrate_levels() {
+        assert_eq!(Test::PullUps.level(4), ExerciseLevel::Beginner);
+        assert_eq!(Test::PullUps.level(5), ExerciseLevel::Intermediate);
+        assert_eq!(Test::Plank.level(150), ExerciseLevel::Advanced);
+
+        let tmp = tempfile::tempdir().unwrap();
+        let logged = tmp.path().join("logged.csv");
+        fs::write(&logged, "test,value\npull_ups,6\nsquats,20\npull_ups,14\n").unwrap();
+        let numbers = import_numbers(&logged).unwrap();
+        assert_eq!(numbers.len(), 2);
+
+        let file_path = tmp.path().join("state").join(CALIBRATION_FILE);
+        calibrate(&file_path, &numbers).unwrap();
+        let number = LoggedNumber {
+            test: Test::Squats,
+            value: 35,
+        };
+        let calibrations = calibrate(&file_path, &[number]).unwrap();
+        assert_eq!(calibrations.len(), 2);
+        assert_eq!(load_calibrations(&file_path).unwrap().len(), 2);
+
+        let levels = Levels::calibrated(ExerciseLevel::Beginner, &calibrations);
+        assert_eq!(levels.of(&ExerciseType::Pull), &ExerciseLevel::Advanced);
+        assert_eq!(levels.of(&ExerciseType::Legs), &ExerciseLevel::Intermediate);
+        assert_eq!(levels.of(&ExerciseType::Push), &ExerciseLevel::Beginner);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{
+    filter_by_category, rotation_categories, Exercise, ExerciseCategory, ExerciseLevel,
+    ExerciseType,
+};
+use serde::{Deserialize, Serialize};
+
+// Enum for the categories the groups of a workout pick from in turn: the main
+// lifts first down to the accessories, up to the main lifts in the middle group
+// and back down, the main lifts or the accessories only
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+pub enum CategoryStrategy {
+    #[default]
+    Standard,
+    Pyramid,
+    AllPrimary,
+    AccessoryOnly,
+}
+
+/// The category rotation of a workout: its strategy and how many groups it
+/// spreads over.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Rotation {
+    pub strategy: CategoryStrategy,
+    pub groups: u32,
+}
+
+// --------------------------------------------------
+
+// The accessories of a type, core exercises having none but secondary ones
+fn accessories(t: &ExerciseType) -> &'static [ExerciseCategory] {
+    match t {
+        ExerciseType::Core => &[ExerciseCategory::Secondary],
+        _ => &[ExerciseCategory::Accessory],
+    }
+}
+
+impl Rotation {
+    /// The categories a slot of the rotation picks from in a group, counted
+    /// from 0.
+    pub fn categories(
+        &self,
+        group: u32,
+        level: &ExerciseLevel,
+        t: &ExerciseType,
+    ) -> &'static [ExerciseCategory] {
+        match self.strategy {
+            CategoryStrategy::Standard => rotation_categories(group, level, t),
+            CategoryStrategy::Pyramid => {
+                let peak = self.groups / 2;
+                match group.abs_diff(peak) {
+                    0 => rotation_categories(0, level, t),
+                    1 => &[ExerciseCategory::Secondary],
+                    _ => accessories(t),
+                }
+            }
+            CategoryStrategy::AllPrimary => &[ExerciseCategory::Primary],
+            CategoryStrategy::AccessoryOnly => accessories(t),
+        }
+    }
+
+    /// Whether the category of an exercise fits a slot of the rotation in a
+    /// group.
+    pub fn fits(&self, e: &Exercise, group: u32, level: &ExerciseLevel, t: &ExerciseType) -> bool {
+        match self.strategy {
+            CategoryStrategy::Standard => filter_by_category(e, group, level, t),
+            _ => self
+                .categories(group, level, t)
+                .contains(&e.exercise_category),
+        }
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_categories() {
+        let level = ExerciseLevel::Intermediate;
+        let categories = |strategy, groups, t: &ExerciseType| -> Vec<&[ExerciseCategory]> {
+            let rotation = Rotation { strategy, groups };
+            (0..groups)
+                .map(|group| rotation.categories(group, &level, t))
+                .collect()
+        };
+        use ExerciseCategory::*;
+        assert_eq!(
+            categories(CategoryStrategy::Standard, 4, &ExerciseType::Pull),
+            [
+                &[Primary][..],
+                &[Primary, Secondary],
+                &[Secondary, Accessory],
+                &[Accessory]
+            ]
+        );
+        assert_eq!(
+            categories(CategoryStrategy::Pyramid, 5, &ExerciseType::Pull),
+            [
+                &[Accessory][..],
+                &[Secondary],
+                &[Primary],
+                &[Secondary],
+                &[Accessory]
+            ]
+        );
+        assert_eq!(
+            categories(CategoryStrategy::Pyramid, 2, &ExerciseType::Core),
+            [&[Secondary][..], &[Primary]]
+        );
+        assert_eq!(
+            categories(CategoryStrategy::AllPrimary, 2, &ExerciseType::Legs),
+            [&[Primary][..], &[Primary]]
+        );
+        assert_eq!(
+            categories(CategoryStrategy::AccessoryOnly, 1, &ExerciseType::Core),
+            [&[Secondary][..]]
+        );
+    }
+}
@@ -0,0 +1,402 @@
+use crate::completion::is_done;
+use crate::display::to_title_case;
+use crate::export::escape_html;
+use crate::history::{best_value, logged_sets};
+use crate::png::{text_width, Canvas, Rgb};
+use crate::units::{Distance, Duration, Units};
+use crate::WorkoutExercise;
+use chrono::{Datelike, NaiveDate};
+
+// Blocks of a sparkline, from the lowest value to the highest
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// Blocks ending a bar, filling an eighth of a cell more each
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+// Cells of the longest bar
+const BAR_WIDTH: usize = 30;
+
+// Size in pixels of the images of a chart, and the room left around the bars
+// for the title, the values and the labels
+const IMAGE_WIDTH: usize = 640;
+const IMAGE_HEIGHT: usize = 240;
+const MARGIN: usize = 20;
+const TOP: usize = 50;
+const BOTTOM: usize = 30;
+
+// Colors of the images of a chart
+const BAR_COLOR: Rgb = [0x4a, 0x7e, 0xbb];
+const TEXT_COLOR: Rgb = [0x33, 0x33, 0x33];
+const BACKGROUND: Rgb = [0xff, 0xff, 0xff];
+
+// --------------------------------------------------
+
+// Enum for what a chart shows per week: the sets logged, the share of planned
+// sessions completed, or the best performance of an exercise
+#[derive(Debug, PartialEq, Clone, Copy, clap::ValueEnum)]
+pub enum Metric {
+    Volume,
+    Adherence,
+    Progress,
+}
+
+/// The value of a chart for one bar, none when there is nothing to show.
+#[derive(Debug, PartialEq)]
+pub struct Point {
+    /// What the bar stands for, e.g. the week `2024-W23` or the day `06-03`
+    pub label: String,
+    pub value: Option<f64>,
+    /// The value as shown next to its bar, e.g. `24 sets` or `1:30`
+    pub text: String,
+}
+
+/// A bar chart of the training, e.g. of the last weeks, oldest first.
+#[derive(Debug)]
+pub struct Chart {
+    pub title: String,
+    pub points: Vec<Point>,
+}
+
+// --------------------------------------------------
+
+/// The Monday starting the ISO week of a date.
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Whether a workout row is the exercise, as written in the workout or in the
+/// library, in any case.
+pub fn is_exercise(e: &WorkoutExercise, exercise: &str) -> bool {
+    let name = e.name.to_lowercase();
+    let exercise = exercise.trim().to_lowercase();
+    name == exercise || name == to_title_case(&exercise).to_lowercase()
+}
+
+// The best performance logged for an exercise: its reps, or else its time or
+// its distance, and how to show it
+fn best_performance(rows: &[&WorkoutExercise], units: Units) -> Option<(f64, String)> {
+    let best = |value: fn(&WorkoutExercise) -> Option<f64>| {
+        rows.iter()
+            .filter_map(|e| value(e))
+            .fold(None, |best: Option<f64>, value| {
+                Some(best.map_or(value, |best| best.max(value)))
+            })
+    };
+    if let Some(reps) = best(|e| best_value::<u32>(&e.reps).map(f64::from)) {
+        return Some((reps, format!("{} reps", reps)));
+    }
+    if let Some(seconds) = best(|e| best_value::<Duration>(&e.time).map(|d| d.seconds().into())) {
+        return Some((seconds, Duration::from_seconds(seconds as u32).render()));
+    }
+    best(|e| best_value::<Distance>(&e.distance).map(|d| d.meters()))
+        .map(|meters| (meters, Distance::from_meters(meters).render(units)))
+}
+
+/// Renders values as a sparkline scaled from 0 to the highest value, a blank
+/// for each missing one.
+pub fn sparkline(values: &[Option<f64>]) -> String {
+    let max = values.iter().flatten().fold(0.0, |max: f64, v| max.max(*v));
+    values
+        .iter()
+        .map(|value| match value {
+            Some(_) if max <= 0.0 => SPARKS[0],
+            Some(value) => {
+                let index = (value / max * (SPARKS.len() - 1) as f64).round() as usize;
+                SPARKS[index.min(SPARKS.len() - 1)]
+            }
+            None => ' ',
+        })
+        .collect()
+}
+
+// A bar of `value` cells out of `max`, in eighths of a cell
+fn bar(value: f64, max: f64) -> String {
+    if max <= 0.0 {
+        return String::new();
+    }
+    let eighths = (value / max * (BAR_WIDTH * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    let rest = eighths % 8;
+    if rest > 0 {
+        bar.push(EIGHTHS[rest]);
+    }
+    bar
+}
+
+// --------------------------------------------------
+
+impl Chart {
+    /// Builds the chart of a metric over the last `weeks` ISO weeks up to
+    /// today's, from the saved workouts. Progress charts follow `exercise`.
+    pub fn build(
+        metric: Metric,
+        exercise: Option<&str>,
+        workouts: &[(NaiveDate, Vec<WorkoutExercise>)],
+        done: &[NaiveDate],
+        weeks: u32,
+        today: NaiveDate,
+        units: Units,
+    ) -> Chart {
+        let this_week = week_start(today);
+        let points = (0..weeks)
+            .rev()
+            .map(|weeks_ago| {
+                let start = this_week - chrono::Duration::weeks(weeks_ago as i64);
+                let in_week: Vec<&(NaiveDate, Vec<WorkoutExercise>)> = workouts
+                    .iter()
+                    .filter(|(date, _)| week_start(*date) == start && *date <= today)
+                    .collect();
+                let value = match metric {
+                    Metric::Volume => {
+                        let sets: usize = in_week
+                            .iter()
+                            .flat_map(|(_, workout)| workout.iter().filter(|e| e.group > 0))
+                            .map(logged_sets)
+                            .sum();
+                        Some((sets as f64, format!("{} sets", sets)))
+                    }
+                    Metric::Adherence if in_week.is_empty() => None,
+                    Metric::Adherence => {
+                        let completed = in_week
+                            .iter()
+                            .filter(|(date, workout)| is_done(*date, workout, done))
+                            .count();
+                        let percent = completed * 100 / in_week.len();
+                        Some((
+                            percent as f64,
+                            format!("{}% ({} of {})", percent, completed, in_week.len()),
+                        ))
+                    }
+                    Metric::Progress => {
+                        let rows: Vec<&WorkoutExercise> = in_week
+                            .iter()
+                            .flat_map(|(_, workout)| workout.iter())
+                            .filter(|e| exercise.is_some_and(|exercise| is_exercise(e, exercise)))
+                            .collect();
+                        best_performance(&rows, units)
+                    }
+                };
+                Point {
+                    label: start.format("%G-W%V").to_string(),
+                    value: value.as_ref().map(|(value, _)| *value),
+                    text: value.map_or_else(|| String::from("-"), |(_, text)| text),
+                }
+            })
+            .collect();
+
+        let title = match metric {
+            Metric::Volume => String::from("Sets logged per week"),
+            Metric::Adherence => String::from("Planned sessions completed per week"),
+            Metric::Progress => format!(
+                "Best performance per week of {}",
+                exercise.map_or_else(String::new, to_title_case)
+            ),
+        };
+        Chart { title, points }
+    }
+
+    /// Renders the chart for the terminal: its title over a sparkline of the
+    /// weeks, then one bar per week.
+    pub fn render(&self) -> String {
+        let values: Vec<Option<f64>> = self.points.iter().map(|p| p.value).collect();
+        let max = values.iter().flatten().fold(0.0, |max: f64, v| max.max(*v));
+        let mut output = format!("{}\n{}\n\n", self.title, sparkline(&values));
+        for point in &self.points {
+            let bar = point
+                .value
+                .map_or_else(String::new, |value| bar(value, max));
+            output.push_str(&format!(
+                "{} {:<width$} {}\n",
+                point.label,
+                bar,
+                point.text,
+                width = BAR_WIDTH
+            ));
+        }
+        output
+    }
+
+    // The slot of each bar and its height at a value, in pixels
+    fn layout(&self) -> (usize, impl Fn(f64) -> usize) {
+        let max = self
+            .points
+            .iter()
+            .filter_map(|p| p.value)
+            .fold(0.0, f64::max);
+        let slot = (IMAGE_WIDTH - 2 * MARGIN) / self.points.len().max(1);
+        let height = move |value: f64| {
+            if max <= 0.0 {
+                return 0;
+            }
+            (value / max * (IMAGE_HEIGHT - TOP - BOTTOM) as f64).round() as usize
+        };
+        (slot, height)
+    }
+
+    /// Renders the chart as an SVG image: its title, then one bar per point
+    /// with its value above and its label below.
+    pub fn render_svg(&self) -> String {
+        let (slot, height) = self.layout();
+        let baseline = IMAGE_HEIGHT - BOTTOM;
+        let mut output = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"11\">\n",
+            w = IMAGE_WIDTH,
+            h = IMAGE_HEIGHT
+        );
+        output.push_str(&format!(
+            "<text x=\"{}\" y=\"20\" font-size=\"14\">{}</text>\n",
+            MARGIN,
+            escape_html(&self.title)
+        ));
+        for (i, point) in self.points.iter().enumerate() {
+            let center = MARGIN + i * slot + slot / 2;
+            if let Some(value) = point.value {
+                let bar = height(value);
+                output.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#4a7ebb\"/>\n",
+                    center - slot * 3 / 8,
+                    baseline - bar,
+                    slot * 3 / 4,
+                    bar
+                ));
+            }
+            output.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+                center,
+                baseline - point.value.map_or(0, &height) - 4,
+                escape_html(&point.text)
+            ));
+            output.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+                center,
+                baseline + 16,
+                escape_html(&point.label)
+            ));
+        }
+        output.push_str(&format!(
+            "<line x1=\"{}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\" stroke=\"#333\"/>\n</svg>\n",
+            MARGIN,
+            IMAGE_WIDTH - MARGIN,
+            y = baseline
+        ));
+        output
+    }
+
+    /// Renders the chart as a PNG image, like the SVG one but without its
+    /// title, and with the numbers of the values and labels only.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the image cannot be encoded.
+    pub fn render_png(&self) -> std::io::Result<Vec<u8>> {
+        let (slot, height) = self.layout();
+        let baseline = IMAGE_HEIGHT - BOTTOM;
+        let mut canvas = Canvas::new(IMAGE_WIDTH, IMAGE_HEIGHT, BACKGROUND);
+        for (i, point) in self.points.iter().enumerate() {
+            let center = MARGIN + i * slot + slot / 2;
+            if let Some(value) = point.value {
+                let bar = height(value);
+                canvas.fill_rect(
+                    center - slot * 3 / 8,
+                    baseline - bar,
+                    slot * 3 / 4,
+                    bar,
+                    BAR_COLOR,
+                );
+                let text = format!("{:.0}", value);
+                canvas.draw_text(
+                    center.saturating_sub(text_width(&text) / 2),
+                    baseline - bar - 14,
+                    &text,
+                    TEXT_COLOR,
+                );
+            }
+            canvas.draw_text(
+                center.saturating_sub(text_width(&point.label) / 2),
+                baseline + 8,
+                &point.label,
+                TEXT_COLOR,
+            );
+        }
+        canvas.fill_rect(MARGIN, baseline, IMAGE_WIDTH - 2 * MARGIN, 1, TEXT_COLOR);
+        canvas.encode()
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(day: &str, name: &str, reps: &str) -> (NaiveDate, Vec<WorkoutExercise>) {
+        let workout = vec![WorkoutExercise {
+            group: 2,
+            name: String::from(name),
+            reps: String::from(reps),
+            ..Default::default()
+        }];
+        (NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap(), workout)
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_weekly_charts() {
+        let workouts = vec![
+            session("2024-06-03", "Dip", "10/10"),
+            session("2024-06-05", "Pull-up - Regular", "X"),
+            session("2024-06-12", "Pull-up - Regular", "6/5/5"),
+            session("2024-06-13", "Dip", "12"),
+        ];
+        let today = NaiveDate::from_ymd_opt(2024, 6, 14).unwrap();
+
+        let volume = Chart::build(
+            Metric::Volume,
+            None,
+            &workouts,
+            &[],
+            3,
+            today,
+            Units::Metric,
+        );
+        let values: Vec<Option<f64>> = volume.points.iter().map(|p| p.value).collect();
+        assert_eq!(values, [Some(0.0), Some(2.0), Some(4.0)]);
+        assert_eq!(volume.points[2].label, "2024-W24");
+
+        let adherence = Chart::build(
+            Metric::Adherence,
+            None,
+            &workouts,
+            &[],
+            3,
+            today,
+            Units::Metric,
+        );
+        assert_eq!(adherence.points[0].text, "-");
+        assert_eq!(adherence.points[1].text, "50% (1 of 2)");
+
+        let progress = Chart::build(
+            Metric::Progress,
+            Some("dip"),
+            &workouts,
+            &[],
+            2,
+            today,
+            Units::Metric,
+        );
+        assert_eq!(progress.points[0].text, "10 reps");
+        assert_eq!(progress.points[1].value, Some(12.0));
+        assert!(progress
+            .render()
+            .starts_with("Best performance per week of Dip\n"));
+
+        assert_eq!(sparkline(&[Some(0.0), None, Some(4.0), Some(2.0)]), "▁ █▅");
+        let svg = volume.render_svg();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(">4 sets</text>"));
+        assert!(svg.contains("height=\"160\""));
+        assert!(volume.render_png().unwrap().starts_with(b"\x89PNG"));
+        assert_eq!(bar(1.0, 2.0).chars().count(), BAR_WIDTH / 2);
+    }
+}
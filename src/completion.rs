@@ -0,0 +1,128 @@
+use crate::csv_utils::{read_csv, write_csv};
+use crate::error::path_to_str;
+use crate::status::is_completed;
+use crate::WorkoutExercise;
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// File in the workouts directory indexing the sessions marked done, left out
+/// of the saved workouts.
+pub const COMPLETIONS_FILE: &str = "completions.csv";
+
+// --------------------------------------------------
+
+/// A session marked done with `wodgen done`, logged or not.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Completion {
+    /// Day of the workout
+    pub date: NaiveDate,
+    pub completed_at: DateTime<Utc>,
+    #[serde(default)]
+    pub note: String,
+}
+
+// --------------------------------------------------
+
+/// Loads the sessions marked done, none when the index does not exist.
+///
+/// # Errors
+///
+/// This function will return an error if the index exists but cannot be parsed.
+pub fn load_completions(workouts_dir: &Path) -> Result<Vec<Completion>> {
+    let file_path = workouts_dir.join(COMPLETIONS_FILE);
+    if !file_path.exists() {
+        return Ok(Vec::new());
+    }
+    read_csv::<Completion>(path_to_str(&file_path)?)
+}
+
+/// Loads the days of the sessions marked done.
+///
+/// # Errors
+///
+/// This function will return an error if the index exists but cannot be parsed.
+pub fn done_dates(workouts_dir: &Path) -> Result<Vec<NaiveDate>> {
+    Ok(load_completions(workouts_dir)?
+        .into_iter()
+        .map(|c| c.date)
+        .collect())
+}
+
+/// Marks the session of a day done, replacing an earlier mark of the day.
+///
+/// # Errors
+///
+/// This function will return an error if the index cannot be read or written.
+pub fn mark_done(workouts_dir: &Path, date: NaiveDate, note: &str) -> Result<Completion> {
+    let completion = Completion {
+        date,
+        completed_at: Utc::now(),
+        note: note.trim().to_string(),
+    };
+    let mut completions = load_completions(workouts_dir)?;
+    completions.retain(|c| c.date != date);
+    completions.push(completion.clone());
+    completions.sort_by_key(|c| c.date);
+    write_csv(
+        path_to_str(&workouts_dir.join(COMPLETIONS_FILE))?,
+        completions,
+    )?;
+    info!("Marked the session of {} done", date);
+    Ok(completion)
+}
+
+/// Whether the session of a day was done: marked done, or with anything
+/// logged in its workout.
+pub fn is_done(date: NaiveDate, workout: &[WorkoutExercise], done: &[NaiveDate]) -> bool {
+    done.contains(&date) || is_completed(workout)
+}
+
+/// Whether the session of a day was skipped: planned before `today`, and
+/// neither marked done nor logged.
+pub fn is_skipped(
+    date: NaiveDate,
+    workout: &[WorkoutExercise],
+    done: &[NaiveDate],
+    today: NaiveDate,
+) -> bool {
+    date < today && !is_done(date, workout, done)
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_mark_done() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(done_dates(tmp.path()).unwrap().is_empty());
+
+        mark_done(tmp.path(), date("2024-06-05"), "").unwrap();
+        mark_done(tmp.path(), date("2024-06-03"), "easy").unwrap();
+        mark_done(tmp.path(), date("2024-06-05"), " knee ok ").unwrap();
+        let completions = load_completions(tmp.path()).unwrap();
+        assert_eq!(completions.len(), 2);
+        assert_eq!(completions[1].note, "knee ok");
+        let done = done_dates(tmp.path()).unwrap();
+        assert_eq!(done, [date("2024-06-03"), date("2024-06-05")]);
+
+        let planned = vec![WorkoutExercise {
+            group: 2,
+            name: String::from("Dip"),
+            ..Default::default()
+        }];
+        let today = date("2024-06-06");
+        assert!(!is_skipped(date("2024-06-05"), &planned, &done, today));
+        assert!(is_skipped(date("2024-06-04"), &planned, &done, today));
+        assert!(!is_skipped(today, &planned, &done, today));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::caldav::CalDavConfig;
+use crate::email::SmtpConfig;
+use crate::export::Obsidian;
+use crate::naming::Naming;
+use crate::plates::Inventory;
+use crate::snooze::SnoozePolicy;
+use crate::split::Split;
+use crate::sync::SyncConfig;
+use crate::test_day::Retest;
+use crate::volume::{VolumeLandmarks, WeeklyTarget};
+use anyhow::{Context, Result};
+use log::info;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Settings about the user that hold across runs, read from `config.toml` in
+/// the platform config directory.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UserConfig {
+    /// Body mass in kilograms, used to estimate the energy spent in a workout
+    pub body_mass_kg: Option<f64>,
+    /// Weekly set thresholds per muscle, under `[volume]`
+    pub volume: VolumeLandmarks,
+    /// Weekly sets, reps-equivalent or sessions per exercise type or muscle,
+    /// under `[targets]`
+    pub targets: HashMap<String, WeeklyTarget>,
+    /// Bar, plates and dumbbells loads are rounded to, under `[inventory]`
+    pub inventory: Inventory,
+    /// Exercise types per weekday for `wodgen today`, under `[split]`
+    pub split: Split,
+    /// Days picked exercises stay snoozed, per type or category, under `[snooze]`
+    pub snooze: SnoozePolicy,
+    /// Server workouts are emailed through with --email, under `[smtp]`
+    pub smtp: SmtpConfig,
+    /// Calendar sessions are saved to with --caldav, under `[caldav]`
+    pub caldav: CalDavConfig,
+    /// Remote and credentials of `wodgen sync`, under `[sync]`
+    pub sync: SyncConfig,
+    /// Fun names of the sessions, under `[naming]`
+    pub naming: Naming,
+    /// Vault folder and tags of the notes of `--format obsidian`, under `[obsidian]`
+    pub obsidian: Obsidian,
+    /// Days between the max-effort test days, under `[test_day]`
+    pub test_day: Retest,
+}
+
+// --------------------------------------------------
+
+/// Loads the user configuration, all settings are unset when the file does
+/// not exist.
+///
+/// # Errors
+///
+/// This function will return an error if the file exists but cannot be read or
+/// parsed.
+pub fn load_config(file_path: &Path) -> Result<UserConfig> {
+    if !file_path.is_file() {
+        return Ok(UserConfig::default());
+    }
+    let content = fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to open file: {:?}", file_path))?;
+    let config = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse config: {:?}", file_path))?;
+    info!("Loaded the user config from {:?}", file_path);
+    Ok(config)
+}
@@ -0,0 +1,242 @@
+use crate::equipment::Equipment;
+use crate::error::WodgenError;
+use crate::roster::deserialize_value_enum;
+use crate::split;
+use crate::template::{Slot, TypeCount};
+use crate::{Args, Exercise, ExerciseLevel};
+use anyhow::{Context, Result};
+use serde::de;
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Version of the constraints file format, the only one read.
+pub const CONSTRAINTS_VERSION: u32 = 1;
+
+// --------------------------------------------------
+
+/// Every constraint of a run bundled in one TOML file, passed with
+/// `--constraints` so a complex request can be repeated as is. The constraints
+/// set take precedence over the command line.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Constraints {
+    /// The file the constraints were read from
+    #[serde(skip)]
+    pub path: PathBuf,
+    /// Version of the file format, the current one when unset
+    pub version: Option<u32>,
+    /// Exercise slots of each group, like --types
+    pub types: Option<Vec<Slot>>,
+    /// Number of strength groups, like --groups
+    pub groups: Option<u32>,
+    /// Length of the session in minutes, sets the time budget like --duration,
+    /// and the number of groups unless they are set
+    pub duration: Option<u32>,
+    /// Number of exercises of each type per group, like --per-group
+    pub per_group: Option<Vec<TypeCount>>,
+    #[serde(deserialize_with = "deserialize_value_enum")]
+    pub level: Option<ExerciseLevel>,
+    pub bodyweight: Option<bool>,
+    /// Exercises to leave out, on top of those of --exclude
+    pub exclude: Vec<String>,
+    /// Only pick strength exercises with at least one of these tags
+    pub tags: Vec<String>,
+    /// Leave out exercises with any of these tags
+    pub exclude_tags: Vec<String>,
+    /// Equipment at hand, replacing the registry for the run
+    #[serde(deserialize_with = "deserialize_equipment")]
+    pub equipment: Option<Vec<Equipment>>,
+}
+
+// --------------------------------------------------
+
+// Reads the equipment written like it is added, e.g. `40kg dumbbells x2`
+fn deserialize_equipment<'de, D>(deserializer: D) -> Result<Option<Vec<Equipment>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<Vec<String>>::deserialize(deserializer)?
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| item.parse().map_err(de::Error::custom))
+                .collect()
+        })
+        .transpose()
+}
+
+/// Loads the constraints of a TOML file and checks them.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read, does not
+/// follow the format, e.g. with an unknown key or level, or its constraints
+/// contradict each other.
+pub fn load_constraints(file_path: &Path) -> Result<Constraints> {
+    let content = fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to open file: {:?}", file_path))?;
+    let mut constraints: Constraints = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse constraints: {:?}", file_path))?;
+    constraints.path = file_path.to_path_buf();
+    constraints.validate()?;
+    Ok(constraints)
+}
+
+// --------------------------------------------------
+
+impl Constraints {
+    // Checks what the format alone cannot
+    fn validate(&self) -> Result<(), WodgenError> {
+        let invalid = |reason| WodgenError::InvalidConstraints(self.path.clone(), reason);
+        if self
+            .version
+            .is_some_and(|version| version != CONSTRAINTS_VERSION)
+        {
+            return Err(invalid("unsupported version, only version 1 is read"));
+        }
+        if self.types.as_ref().is_some_and(Vec::is_empty) {
+            return Err(invalid("types is empty, leave it out to use --types"));
+        }
+        if self.groups == Some(0) || self.duration == Some(0) {
+            return Err(invalid("groups and duration must be at least 1"));
+        }
+        Ok(())
+    }
+
+    /// The options of a run under these constraints: theirs over those of the
+    /// command line, the excluded exercises of both.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if neither sets the exercise types
+    /// and there is no template.
+    pub fn apply(&self, args: Args) -> Result<Args, WodgenError> {
+        let types = self.types.clone().unwrap_or(args.types);
+        if types.is_empty() && args.template.is_none() {
+            return Err(WodgenError::InvalidConstraints(
+                self.path.clone(),
+                "no exercise types, set types or pass --types",
+            ));
+        }
+        let mut exclude = args.exclude;
+        exclude.extend(
+            self.exclude
+                .iter()
+                .filter(|name| !exclude.contains(name))
+                .cloned()
+                .collect::<Vec<String>>(),
+        );
+        Ok(Args {
+            types,
+            groups: self
+                .groups
+                .or(self.duration.map(split::groups_for))
+                .unwrap_or(args.groups),
+            duration: self.duration.or(args.duration),
+            per_group: self.per_group.clone().unwrap_or(args.per_group),
+            level: self.level.clone().unwrap_or(args.level),
+            bodyweight: self.bodyweight.unwrap_or(args.bodyweight),
+            exclude,
+            ..args
+        })
+    }
+
+    /// Whether the excluded tags let the exercise be picked.
+    pub fn allows(&self, exercise: &Exercise) -> bool {
+        !exercise
+            .tags
+            .iter()
+            .any(|tag| self.exclude_tags.contains(tag))
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExerciseCategory, ExerciseType};
+
+    fn exercise(tags: &[&str]) -> Exercise {
+        Exercise {
+            name: String::from("jump_squat"),
+            exercise_type: ExerciseType::Legs,
+            exercise_category: ExerciseCategory::Secondary,
+            exercise_level: ExerciseLevel::Beginner,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn parse(content: &str) -> Result<Constraints> {
+        let tmp = tempfile::tempdir()?;
+        let file_path = tmp.path().join("constraints.toml");
+        fs::write(&file_path, content)?;
+        load_constraints(&file_path)
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_load_and_apply_constraints() {
+        let constraints = parse(
+            r#"
+            version = 1
+            types = ["pull:any", "core"]
+            duration = 50
+            per_group = ["core=1-2"]
+            level = "beginner"
+            exclude = ["dip__regular", "plank"]
+            tags = ["skill"]
+            exclude_tags = ["jumping"]
+            equipment = ["rings", "40kg dumbbells x2"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(constraints.equipment.as_ref().unwrap()[1].count, 2);
+
+        let args = constraints
+            .apply(Args {
+                groups: 2,
+                exclude: vec![String::from("plank")],
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(args.types.len(), 2);
+        assert_eq!(args.types[0].exercise_type, ExerciseType::Pull);
+        assert_eq!(args.groups, 3);
+        assert_eq!(args.duration, Some(50));
+        assert_eq!(args.per_group[0].max, 2);
+        assert_eq!(args.level, ExerciseLevel::Beginner);
+        assert_eq!(args.exclude, ["plank", "dip__regular"]);
+
+        assert!(!constraints.allows(&exercise(&["jumping", "legs"])));
+        assert!(constraints.allows(&exercise(&["skill"])));
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_invalid_constraints() {
+        for (content, error) in [
+            ("version = 2", "unsupported version"),
+            ("types = []", "types is empty"),
+            ("groups = 0", "at least 1"),
+            ("minutes = 30", "unknown field"),
+            ("level = \"expert\"", "possible values"),
+            ("equipment = [\"x2\"]", "No equipment name"),
+        ] {
+            let message = format!("{:#}", parse(content).unwrap_err());
+            assert!(message.contains(error), "{}", message);
+        }
+        // The groups set win over those of the duration, which stays the budget
+        let args = parse("types = [\"push\"]\ngroups = 2\nduration = 90")
+            .unwrap()
+            .apply(Args::default())
+            .unwrap();
+        assert_eq!((args.groups, args.duration), (2, Some(90)));
+        let error = parse("").unwrap().apply(Args::default()).unwrap_err();
+        assert!(error.to_string().contains("no exercise types"));
+    }
+}
@@ -1,7 +1,59 @@
 use anyhow::{Context, Result};
-use csv::{Reader, Writer};
-use serde::de::DeserializeOwned;
-use std::fs::File;
+use csv::{Reader, ReaderBuilder, StringRecord, Writer, WriterBuilder};
+use serde::de::{self, DeserializeOwned, SeqAccess, Visitor};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// Delimiter of the CSV files read and written, a comma unless set otherwise
+static DELIMITER: AtomicU8 = AtomicU8::new(b',');
+
+// --------------------------------------------------
+
+/// Sets the delimiter of the CSV files read and written from now on, e.g. `;`
+/// for files exported from a spreadsheet in a locale with decimal commas.
+pub fn set_delimiter(delimiter: u8) {
+    DELIMITER.store(delimiter, Ordering::Relaxed);
+}
+
+/// Parses a CSV delimiter: a single ASCII character, e.g. `;`, or `tab`.
+///
+/// # Errors
+///
+/// This function will return an error if the delimiter is not a single ASCII
+/// character.
+pub fn parse_delimiter(delimiter: &str) -> Result<u8, String> {
+    match delimiter {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ if delimiter.len() == 1 && delimiter.is_ascii() => Ok(delimiter.as_bytes()[0]),
+        _ => Err(format!(
+            "invalid delimiter {:?}, expected a single character such as ; or tab",
+            delimiter
+        )),
+    }
+}
+
+// Opens a CSV file for reading with the delimiter set. A UTF-8 byte order mark,
+// as spreadsheets write, is skipped, and so are the lines starting with
+// `comment` when given
+fn open_reader(file_path: &str, comment: Option<u8>) -> Result<Reader<File>> {
+    let file =
+        File::open(file_path).with_context(|| format!("Failed to open file: {}", file_path))?;
+    Ok(ReaderBuilder::new()
+        .delimiter(DELIMITER.load(Ordering::Relaxed))
+        .comment(comment)
+        .from_reader(file))
+}
+
+/// A CSV writer builder with the delimiter set.
+pub fn writer_builder() -> WriterBuilder {
+    let mut builder = WriterBuilder::new();
+    builder.delimiter(DELIMITER.load(Ordering::Relaxed));
+    builder
+}
+
+// --------------------------------------------------
 
 /// Reads a CSV file and deserializes its content into a vector of type `T`.
 ///
@@ -18,26 +70,107 @@ use std::fs::File;
 /// This function will return an error if the file cannot be opened, or if any record cannot be deserialized.
 pub fn read_csv<T: DeserializeOwned>(file_path: &str) -> Result<Vec<T>> {
     // Open the file
-    let file = File::open(file_path).with_context(|| format!("Failed to open file: {}", file_path))?;
-    let mut rdr = Reader::from_reader(file);
+    let mut rdr = open_reader(file_path, None)?;
+
+    // Deserialize each record and collect them into a vector
+    rdr.deserialize()
+        .enumerate()
+        .map(|(i, result)| {
+            result.with_context(|| {
+                format!(
+                    "Failed to deserialize record at line {} in {}",
+                    i + 1,
+                    file_path
+                )
+            })
+        })
+        .collect()
+}
+
+// --------------------------------------------------
+
+/// Reads a CSV file and deserializes its records by header name, after
+/// renaming the headers through `rename`. Useful for files whose headers were
+/// translated, whatever the order of their columns. Headers `rename` maps to
+/// None keep their name. Lines starting with `#`, such as the version line of
+/// a saved workout, are skipped.
+///
+/// # Arguments
+///
+/// * `file_path` - A string slice that holds the name of the file to be read.
+/// * `rename` - Maps a header to the field it stands for.
+///
+/// # Returns
+///
+/// * `Result<Vec<T>>` - A result containing a vector of deserialized records of type `T` if successful, or an error if not.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be opened, or if any record cannot be deserialized.
+pub fn read_csv_renaming_headers<T: DeserializeOwned>(
+    file_path: &str,
+    rename: impl Fn(&str) -> Option<&str>,
+) -> Result<Vec<T>> {
+    // Open the file
+    let mut rdr = open_reader(file_path, Some(b'#'))?;
+
+    // Replace the headers by the names of the fields they stand for
+    let headers: StringRecord = rdr
+        .headers()
+        .with_context(|| format!("Failed to read headers in {}", file_path))?
+        .iter()
+        .map(|header| rename(header).unwrap_or(header).to_string())
+        .collect();
+    rdr.set_headers(headers);
 
     // Deserialize each record and collect them into a vector
     rdr.deserialize()
         .enumerate()
         .map(|(i, result)| {
-            result.with_context(|| format!("Failed to deserialize record at line {} in {}", i + 1, file_path))
+            result.with_context(|| {
+                format!(
+                    "Failed to deserialize record at line {} in {}",
+                    i + 1,
+                    file_path
+                )
+            })
         })
         .collect()
 }
 
 // --------------------------------------------------
 
-/// Writes a vector of serializable data to a CSV file.
+/// Reads the header record of a CSV file, skipping the lines starting with `#`
+/// before it.
+///
+/// # Arguments
+///
+/// * `file_path` - A string slice that holds the name of the file to be read.
+///
+/// # Returns
+///
+/// * `Result<Vec<String>>` - A result containing the headers if successful, or an error if not.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be opened or its first line cannot be read.
+pub fn read_headers(file_path: &str) -> Result<Vec<String>> {
+    let mut rdr = open_reader(file_path, Some(b'#'))?;
+    let headers = rdr
+        .headers()
+        .with_context(|| format!("Failed to read headers in {}", file_path))?;
+    Ok(headers.iter().map(String::from).collect())
+}
+
+// --------------------------------------------------
+
+/// Writes serializable records to a CSV file, one at a time so that large
+/// exports are streamed rather than collected first.
 ///
 /// # Arguments
 ///
 /// * `file` - A string slice that holds the name of the file to be written.
-/// * `data` - A vector of data to be serialized and written to the file.
+/// * `data` - The records to be serialized and written to the file, e.g. a vector or an iterator.
 ///
 /// # Returns
 ///
@@ -46,19 +179,212 @@ pub fn read_csv<T: DeserializeOwned>(file_path: &str) -> Result<Vec<T>> {
 /// # Errors
 ///
 /// This function will return an error if the file cannot be created, or if any record cannot be serialized.
-pub fn write_csv<T: serde::Serialize>(file: &str, data: Vec<T>) -> Result<()> {
+pub fn write_csv<T: serde::Serialize>(file: &str, data: impl IntoIterator<Item = T>) -> Result<()> {
     // Create a CSV writer for the specified file
-    let mut wtr = Writer::from_path(file)
+    let mut wtr = writer_builder()
+        .from_path(file)
         .with_context(|| format!("Failed to create CSV writer for file: {}", file))?;
+    write_records(&mut wtr, file, data)
+}
+
+// --------------------------------------------------
+
+/// Appends serializable records to a CSV file, e.g. to log entries one by one
+/// without rewriting the whole file. The header record is only written when
+/// the file is new or empty.
+///
+/// # Arguments
+///
+/// * `file` - A string slice that holds the name of the file to be appended to.
+/// * `data` - The records to be serialized and appended to the file.
+///
+/// # Returns
+///
+/// * `Result<()>` - An empty result if successful, or an error if not.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be opened, or if any record cannot be serialized.
+pub fn append_csv<T: serde::Serialize>(
+    file: &str,
+    data: impl IntoIterator<Item = T>,
+) -> Result<()> {
+    let has_records = fs::metadata(file).is_ok_and(|metadata| metadata.len() > 0);
+    let handle = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)
+        .with_context(|| format!("Failed to open file for appending: {}", file))?;
+    let mut wtr = writer_builder()
+        .has_headers(!has_records)
+        .from_writer(handle);
+    write_records(&mut wtr, file, data)
+}
 
-    // Serialize each record and write it to the file
+// --------------------------------------------------
+
+// Serialize each record as it comes and write it, then flush the writer to
+// ensure all data is written to the file
+fn write_records<W: Write, T: serde::Serialize>(
+    wtr: &mut Writer<W>,
+    file: &str,
+    data: impl IntoIterator<Item = T>,
+) -> Result<()> {
     data.into_iter().enumerate().try_for_each(|(i, record)| {
         wtr.serialize(record)
             .with_context(|| format!("Failed to serialize record at index {}", i))
     })?;
-
-    // Flush the writer to ensure all data is written to the file
     wtr.flush()
         .with_context(|| format!("Failed to flush CSV writer for file: {}", file))?;
     Ok(())
 }
+
+// --------------------------------------------------
+
+/// Deserializes a list field: a `;`-separated CSV field, e.g. `chest;triceps`,
+/// or a list of strings in TOML and JSON files.
+///
+/// Empty fields and missing columns deserialize into an empty list.
+pub fn deserialize_list<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserializer.deserialize_any(ListVisitor)
+}
+
+/// Deserializes a flag that is off unless set, e.g. `testable`: an empty field
+/// is false, like a missing column with `#[serde(default)]`.
+pub fn deserialize_false_if_empty<'de, D>(deserializer: D) -> std::result::Result<bool, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(<Option<bool> as serde::Deserialize>::deserialize(deserializer)?.unwrap_or(false))
+}
+
+/// Deserializes a flag that holds unless set otherwise, e.g. `active`: an
+/// empty field is true, like a missing column with [`default_true`].
+pub fn deserialize_true_if_empty<'de, D>(deserializer: D) -> std::result::Result<bool, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(<Option<bool> as serde::Deserialize>::deserialize(deserializer)?.unwrap_or(true))
+}
+
+/// The value of a flag whose column is missing, see [`deserialize_true_if_empty`].
+pub fn default_true() -> bool {
+    true
+}
+
+// Visitor accepting both a joined field and a sequence of items
+struct ListVisitor;
+
+impl<'de> Visitor<'de> for ListVisitor {
+    type Value = Vec<String>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a `;`-separated string or a list of strings")
+    }
+
+    fn visit_str<E: de::Error>(self, field: &str) -> std::result::Result<Vec<String>, E> {
+        Ok(field
+            .split(';')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect())
+    }
+
+    // CSV infers the type of a field, a lone item may look like a number or a bool
+    fn visit_bool<E: de::Error>(self, v: bool) -> std::result::Result<Vec<String>, E> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<Vec<String>, E> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<Vec<String>, E> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<Vec<String>, E> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn visit_none<E: de::Error>(self) -> std::result::Result<Vec<String>, E> {
+        Ok(Vec::new())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<Vec<String>, E> {
+        Ok(Vec::new())
+    }
+
+    fn visit_some<D: serde::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Vec<String>, D::Error> {
+        deserializer.deserialize_any(ListVisitor)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> std::result::Result<Vec<String>, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element::<String>()? {
+            items.push(item);
+        }
+        Ok(items)
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spreadsheet_exports() {
+        assert_eq!(parse_delimiter(";"), Ok(b';'));
+        assert_eq!(parse_delimiter("tab"), Ok(b'\t'));
+        assert!(parse_delimiter(";;").is_err());
+        assert!(parse_delimiter("é").is_err());
+
+        // The byte order mark does not end up in the first header
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("push.csv");
+        std::fs::write(&file, "\u{feff}name,goal\ndip,10\n").unwrap();
+        let file = file.to_str().unwrap();
+        assert_eq!(read_headers(file).unwrap(), ["name", "goal"]);
+        let rows: Vec<(String, u32)> = read_csv(file).unwrap();
+        assert_eq!(rows, [(String::from("dip"), 10)]);
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_append_csv() {
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        struct Entry {
+            name: String,
+            reps: u32,
+        }
+        let entry = |name: &str, reps| Entry {
+            name: String::from(name),
+            reps,
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("log.csv");
+        let file = file.to_str().unwrap();
+        append_csv(file, [entry("dip", 10)]).unwrap();
+        append_csv(file, (11..13).map(|reps| entry("dip", reps))).unwrap();
+        assert_eq!(
+            read_csv::<Entry>(file).unwrap(),
+            [entry("dip", 10), entry("dip", 11), entry("dip", 12)]
+        );
+        assert!(fs::read_to_string(file)
+            .unwrap()
+            .starts_with("name,reps\ndip,10\n"));
+    }
+}
@@ -0,0 +1,265 @@
+use crate::notation::letter;
+use crate::{display, Exercise, ExerciseType, WorkoutExercise};
+use chrono::NaiveDate;
+
+// Enum for the differences between two workouts
+#[derive(Debug)]
+pub enum Change<'a> {
+    Added(&'a WorkoutExercise),
+    Removed(&'a WorkoutExercise),
+    Changed {
+        name: &'a str,
+        fields: Vec<FieldChange>,
+    },
+}
+
+/// A prescription field that differs between two occurrences of an exercise.
+#[derive(Debug, PartialEq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+// --------------------------------------------------
+
+// Compare the fields of two occurrences of the same exercise
+fn field_changes(old: &WorkoutExercise, new: &WorkoutExercise) -> Vec<FieldChange> {
+    [
+        ("group", letter(old.group), letter(new.group)),
+        ("sets", old.sets.clone(), new.sets.clone()),
+        ("distance", old.distance.clone(), new.distance.clone()),
+        ("time", old.time.clone(), new.time.clone()),
+        ("reps", old.reps.clone(), new.reps.clone()),
+        ("goal", old.goal.clone(), new.goal.clone()),
+        ("notes", old.notes.clone(), new.notes.clone()),
+    ]
+    .into_iter()
+    .filter(|(_, old, new)| old != new)
+    .map(|(field, old, new)| FieldChange { field, old, new })
+    .collect()
+}
+
+// --------------------------------------------------
+
+/// Computes the differences between two workouts.
+///
+/// Exercises are matched by name, so an exercise moved to another group shows up
+/// as a change rather than as a removal and an addition.
+pub fn diff<'a>(old: &'a [WorkoutExercise], new: &'a [WorkoutExercise]) -> Vec<Change<'a>> {
+    let mut changes = Vec::new();
+    let mut unmatched: Vec<&WorkoutExercise> = new.iter().collect();
+
+    for old_exercise in old {
+        match unmatched.iter().position(|e| e.name == old_exercise.name) {
+            Some(index) => {
+                let new_exercise = unmatched.remove(index);
+                let fields = field_changes(old_exercise, new_exercise);
+                if !fields.is_empty() {
+                    changes.push(Change::Changed {
+                        name: &new_exercise.name,
+                        fields,
+                    });
+                }
+            }
+            None => changes.push(Change::Removed(old_exercise)),
+        }
+    }
+    changes.extend(unmatched.into_iter().map(Change::Added));
+    changes
+}
+
+// --------------------------------------------------
+
+/// Renders the differences as a human readable report, one change per line.
+pub fn render(changes: &[Change]) -> String {
+    if changes.is_empty() {
+        return String::from("No differences\n");
+    }
+    let mut output = String::new();
+    for change in changes {
+        let line = match change {
+            Change::Added(e) => format!("+ {} (group {})", e.name, letter(e.group)),
+            Change::Removed(e) => format!("- {} (group {})", e.name, letter(e.group)),
+            Change::Changed { name, fields } => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|f| format!("{}: {:?} -> {:?}", f.field, f.old, f.new))
+                    .collect();
+                format!("~ {} ({})", name, fields.join(", "))
+            }
+        };
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+// --------------------------------------------------
+
+// The strength exercises of a workout, looked up in the library
+fn strength_exercises<'a>(
+    workout: &'a [WorkoutExercise],
+    library: &'a [Exercise],
+) -> Vec<(&'a WorkoutExercise, &'a Exercise)> {
+    workout
+        .iter()
+        .filter(|w| w.group > 1)
+        .filter_map(|w| {
+            library
+                .iter()
+                .find(|e| {
+                    e.exercise_type != ExerciseType::Cooldown && display::exercise_name(e) == w.name
+                })
+                .map(|e| (w, e))
+        })
+        .collect()
+}
+
+// The strength types of a workout, in the order of the exercise types
+fn strength_types(exercises: &[(&WorkoutExercise, &Exercise)]) -> Vec<ExerciseType> {
+    let mut types: Vec<ExerciseType> = Vec::new();
+    for (_, e) in exercises {
+        if !types.contains(&e.exercise_type) {
+            types.push(e.exercise_type.clone());
+        }
+    }
+    types.sort_by_key(|t| format!("{:?}", t));
+    types
+}
+
+/// Renders which strength exercises of a new workout are new and which repeat
+/// the last saved session of the same types, e.g. `+ Ring Row (group B)` and
+/// `= Dip (group C)`, to judge its variety before accepting it.
+pub fn render_preview(
+    workout: &[WorkoutExercise],
+    dated_workouts: &[(NaiveDate, Vec<WorkoutExercise>)],
+    library: &[Exercise],
+) -> String {
+    let exercises = strength_exercises(workout, library);
+    let types = strength_types(&exercises);
+    let session = types
+        .iter()
+        .map(|t| format!("{:?}", t))
+        .collect::<Vec<String>>()
+        .join(" and ");
+    let last = dated_workouts
+        .iter()
+        .rev()
+        .find(|(_, saved)| strength_types(&strength_exercises(saved, library)) == types);
+    let Some((date, last)) = last else {
+        return format!("No earlier {} session, every exercise is new\n", session);
+    };
+    let mut output = format!("Compared to the last {} session, on {}:\n", session, date);
+    let mut repeated = 0;
+    for (w, _) in &exercises {
+        let symbol = if last.iter().any(|e| e.name == w.name) {
+            repeated += 1;
+            '='
+        } else {
+            '+'
+        };
+        output.push_str(&format!(
+            "{} {} (group {})\n",
+            symbol,
+            w.name,
+            letter(w.group)
+        ));
+    }
+    output.push_str(&format!(
+        "{} new, {} repeated\n",
+        exercises.len() - repeated,
+        repeated
+    ));
+    output
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExerciseLevel;
+
+    fn workout_exercise(group: u32, name: &str, goal: &str) -> WorkoutExercise {
+        WorkoutExercise {
+            group,
+            name: String::from(name),
+            reps: String::from("X"),
+            goal: String::from(goal),
+            ..Default::default()
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_diff() {
+        let old = vec![
+            workout_exercise(2, "Dip - Regular", "15"),
+            workout_exercise(2, "Pull-up - Regular", "10"),
+            workout_exercise(3, "Plank Front", ""),
+        ];
+        let new = vec![
+            workout_exercise(2, "Dip - Regular", "15"),
+            workout_exercise(3, "Pull-up - Regular", "12"),
+            workout_exercise(3, "Hollow Body Hold", ""),
+        ];
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(
+            render(&changes),
+            "~ Pull-up - Regular (group: \"B\" -> \"C\", goal: \"10\" -> \"12\")\n\
+             - Plank Front (group C)\n\
+             + Hollow Body Hold (group C)\n"
+        );
+        assert_eq!(render(&diff(&old, &old)), "No differences\n");
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_render_preview() {
+        let exercise = |name: &str, exercise_type| Exercise {
+            name: String::from(name),
+            exercise_type,
+            exercise_level: ExerciseLevel::Beginner,
+            ..Default::default()
+        };
+        let library = vec![
+            exercise("dip__regular", ExerciseType::Push),
+            exercise("pike_push_up", ExerciseType::Push),
+            exercise("plank__front", ExerciseType::Core),
+            exercise("pull_up__regular", ExerciseType::Pull),
+        ];
+        let date = |day| NaiveDate::from_ymd_opt(2024, 6, day).unwrap();
+        let saved = vec![
+            (
+                date(1),
+                vec![
+                    workout_exercise(2, "Dip - Regular", ""),
+                    workout_exercise(3, "Plank - Front", ""),
+                ],
+            ),
+            (date(2), vec![workout_exercise(2, "Pull-up - Regular", "")]),
+        ];
+        let workout = vec![
+            workout_exercise(1, "Skill Block", ""),
+            workout_exercise(2, "Dip - Regular", ""),
+            workout_exercise(2, "Pike Push Up", ""),
+            workout_exercise(3, "Plank - Front", ""),
+        ];
+        assert_eq!(
+            render_preview(&workout, &saved, &library),
+            "Compared to the last Core and Push session, on 2024-06-01:\n\
+             = Dip - Regular (group B)\n\
+             + Pike Push Up (group B)\n\
+             = Plank - Front (group C)\n\
+             1 new, 2 repeated\n"
+        );
+        assert_eq!(
+            render_preview(&workout[..3], &saved, &library),
+            "No earlier Push session, every exercise is new\n"
+        );
+    }
+}
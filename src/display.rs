@@ -0,0 +1,172 @@
+use crate::csv_utils::read_csv;
+use crate::error::path_to_str;
+use crate::i18n::Lang;
+use crate::{Exercise, WorkoutExercise};
+use anyhow::{Context, Result};
+use log::info;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+// A translated name of an exercise, a row of a names file of the library
+#[derive(Debug, Deserialize)]
+struct Alias {
+    name: String,
+    display_name: String,
+}
+
+// --------------------------------------------------
+
+/// Pretty prints a name of the library, e.g. `dip__regular` as `Dip - Regular`.
+pub fn to_title_case(input: &str) -> String {
+    input
+        .replace("__", " - ")
+        .replace('_', " ")
+        .split_whitespace()
+        .map(|word| {
+            let mut c = word.chars();
+            match c.next() {
+                None => String::new(),
+                Some(first) => first.to_uppercase().collect::<String>() + c.as_str(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// The name an exercise is shown under in workouts, exports and reports: its
+/// `display_name` when the library sets one, e.g. `GHD Sit-up`, otherwise its
+/// name pretty printed.
+pub fn exercise_name(e: &Exercise) -> String {
+    match e.display_name.as_deref().map(str::trim) {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => to_title_case(&e.name),
+    }
+}
+
+/// The name shown for a name of the library, that of its exercise when the
+/// library holds it.
+pub fn library_name(name: &str, library: &[Exercise]) -> String {
+    library
+        .iter()
+        .find(|e| e.name == name)
+        .map_or_else(|| to_title_case(name), exercise_name)
+}
+
+/// Name of the file of the library translating the exercise names to a
+/// language, e.g. `names.de.csv`.
+pub fn aliases_file(lang: Lang) -> String {
+    format!("names.{}.csv", format!("{:?}", lang).to_lowercase())
+}
+
+/// Loads the translated names of the exercises, keyed by the names they are
+/// shown under, none when the library has no names file for the language.
+/// The file maps the names of the library, or the shown ones, to translations.
+///
+/// # Errors
+///
+/// This function will return an error if the file exists but cannot be parsed.
+pub fn load_aliases(
+    exercise_library_dir: &Path,
+    lang: Lang,
+    library: &[Exercise],
+) -> Result<HashMap<String, String>> {
+    let file_path = exercise_library_dir.join(aliases_file(lang));
+    if !file_path.exists() {
+        return Ok(HashMap::new());
+    }
+    let aliases: HashMap<String, String> = read_csv::<Alias>(path_to_str(&file_path)?)
+        .with_context(|| format!("Failed to load the exercise names: {:?}", file_path))?
+        .into_iter()
+        .filter(|alias| !alias.display_name.trim().is_empty())
+        .map(|alias| {
+            (
+                library_name(&alias.name, library),
+                alias.display_name.trim().to_string(),
+            )
+        })
+        .collect();
+    info!(
+        "Loaded {} exercise names from {:?}",
+        aliases.len(),
+        file_path
+    );
+    Ok(aliases)
+}
+
+/// The rows of a workout with the exercise names translated, for the output
+/// people read. Saved workouts, the history and the other state keep the
+/// untranslated names.
+pub fn localize(
+    workout: &[WorkoutExercise],
+    aliases: &HashMap<String, String>,
+) -> Vec<WorkoutExercise> {
+    workout
+        .iter()
+        .map(|e| WorkoutExercise {
+            name: aliases.get(&e.name).unwrap_or(&e.name).clone(),
+            ..e.clone()
+        })
+        .collect()
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExerciseType;
+
+    fn exercise(name: &str, display_name: Option<&str>) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            display_name: display_name.map(String::from),
+            exercise_type: ExerciseType::Core,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_display_names() {
+        assert_eq!(to_title_case("dip__regular"), "Dip - Regular");
+        assert_eq!(to_title_case("GHD sit_up"), "GHD Sit Up");
+
+        let library = [
+            exercise("GHD sit_up", Some("GHD Sit-up")),
+            exercise("l_sit", Some(" L-sit ")),
+            exercise("hollow_hold", Some("")),
+        ];
+        assert_eq!(exercise_name(&library[0]), "GHD Sit-up");
+        assert_eq!(exercise_name(&library[1]), "L-sit");
+        assert_eq!(exercise_name(&library[2]), "Hollow Hold");
+        assert_eq!(library_name("l_sit", &library), "L-sit");
+        assert_eq!(library_name("v_up", &library), "V Up");
+    }
+
+    #[test]
+    fn test_localize_names() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(load_aliases(tmp.path(), Lang::De, &[]).unwrap().is_empty());
+
+        std::fs::write(
+            tmp.path().join(aliases_file(Lang::De)),
+            "name,display_name\nl_sit,L-Sitz\nHollow Hold,Hohlkörper\nv_up,\n",
+        )
+        .unwrap();
+        let library = [exercise("l_sit", Some("L-sit"))];
+        let aliases = load_aliases(tmp.path(), Lang::De, &library).unwrap();
+        assert_eq!(aliases.len(), 2);
+
+        let row = |name: &str| WorkoutExercise {
+            group: 2,
+            name: String::from(name),
+            ..Default::default()
+        };
+        let workout = [row("L-sit"), row("Hollow Hold"), row("V Up")];
+        let names: Vec<String> = localize(&workout, &aliases)
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, ["L-Sitz", "Hohlkörper", "V Up"]);
+    }
+}
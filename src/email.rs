@@ -0,0 +1,246 @@
+use crate::error::WodgenError;
+use crate::export;
+use crate::i18n::{Lang, Text};
+use crate::WorkoutExercise;
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use log::info;
+use serde::Deserialize;
+use std::path::Path;
+
+// Enum for how the connection to the SMTP server is secured: TLS from the
+// start, usually on port 465, STARTTLS, usually on port 587, or none for a
+// relay on the local network
+#[derive(Debug, Default, PartialEq, Clone, Copy, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Security {
+    Tls,
+    #[default]
+    Starttls,
+    None,
+}
+
+/// The SMTP server workouts are emailed through, under `[smtp]` in the user
+/// config. The `WODGEN_SMTP_*` environment variables take precedence, e.g.
+/// to keep the password out of the file.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmtpConfig {
+    pub host: Option<String>,
+    /// Port of the server [default: the one of the security]
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Sender of the emails [default: the username]
+    pub from: Option<String>,
+    pub security: Security,
+}
+
+// --------------------------------------------------
+
+impl SmtpConfig {
+    /// Overrides the settings with the `WODGEN_SMTP_HOST`, `_PORT`,
+    /// `_USERNAME`, `_PASSWORD`, `_FROM` and `_SECURITY` variables found by
+    /// `var`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the port or the security is
+    /// invalid.
+    pub fn with_env(mut self, var: impl Fn(&str) -> Option<String>) -> Result<SmtpConfig> {
+        for (name, setting) in [
+            ("WODGEN_SMTP_HOST", &mut self.host),
+            ("WODGEN_SMTP_USERNAME", &mut self.username),
+            ("WODGEN_SMTP_PASSWORD", &mut self.password),
+            ("WODGEN_SMTP_FROM", &mut self.from),
+        ] {
+            if let Some(value) = var(name) {
+                *setting = Some(value);
+            }
+        }
+        if let Some(port) = var("WODGEN_SMTP_PORT") {
+            self.port = Some(
+                port.parse()
+                    .with_context(|| format!("Invalid WODGEN_SMTP_PORT: {:?}", port))?,
+            );
+        }
+        if let Some(security) = var("WODGEN_SMTP_SECURITY") {
+            self.security = Security::from_str(&security, true)
+                .map_err(|e| anyhow!("Invalid WODGEN_SMTP_SECURITY: {}", e))?;
+        }
+        Ok(self)
+    }
+
+    // The transport to the server, logged in when there are credentials
+    fn transport(&self) -> Result<SmtpTransport> {
+        let host = self.host.as_deref().ok_or(WodgenError::NoSmtpHost)?;
+        let mut builder = match self.security {
+            Security::Tls => SmtpTransport::relay(host)?,
+            Security::Starttls => SmtpTransport::starttls_relay(host)?,
+            Security::None => SmtpTransport::builder_dangerous(host),
+        };
+        if let Some(port) = self.port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&self.username, &self.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(builder.build())
+    }
+}
+
+// --------------------------------------------------
+
+/// Emails a workout: the HTML sheet with the Markdown one as the plain text
+/// alternative, and the workout attached as CSV. Remote images and videos stay
+/// links, the local copies mean nothing to the recipients.
+///
+/// # Errors
+///
+/// This function will return `WodgenError::NoSmtpHost` if no server is set, or
+/// an error if an address is invalid or the server refuses the email.
+pub fn send_workout(
+    smtp: &SmtpConfig,
+    to: &[String],
+    workout: &[WorkoutExercise],
+    title: &str,
+    lang: Lang,
+) -> Result<()> {
+    let transport = smtp.transport()?;
+    let from = smtp
+        .from
+        .as_ref()
+        .or(smtp.username.as_ref())
+        .ok_or_else(|| {
+            anyhow!("No sender for the email, set from under [smtp] in the user config or WODGEN_SMTP_FROM")
+        })?;
+    let mut message = Message::builder()
+        .from(
+            from.parse()
+                .with_context(|| format!("Invalid sender address: {:?}", from))?,
+        )
+        .subject(format!("{} {}", lang.text(Text::Workout), title));
+    for address in to {
+        message = message.to(address
+            .parse()
+            .with_context(|| format!("Invalid email address: {:?}", address))?);
+    }
+
+    let no_media = Path::new("");
+    let message = message.multipart(
+        MultiPart::mixed()
+            .multipart(MultiPart::alternative_plain_html(
+                export::render_markdown(workout, title, lang, no_media),
+                export::render_html(workout, title, lang, no_media),
+            ))
+            .singlepart(Attachment::new(format!("{}.csv", title)).body(
+                export::render_csv(workout, lang)?,
+                ContentType::parse("text/csv").expect("a valid content type"),
+            )),
+    )?;
+    transport
+        .send(&message)
+        .with_context(|| format!("Failed to email the workout to {}", to.join(", ")))?;
+    info!("Emailed the workout to {}", to.join(", "));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    // Accepts one connection like an SMTP server and returns the data received
+    fn serve_once(listener: TcpListener) -> thread::JoinHandle<String> {
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut stream = stream;
+            let (mut data, mut in_data) = (String::new(), false);
+            stream.write_all(b"220 localhost ready\r\n").unwrap();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 {
+                let reply: &[u8] = if in_data {
+                    if line == ".\r\n" {
+                        in_data = false;
+                        b"250 queued\r\n"
+                    } else {
+                        data.push_str(&line);
+                        b""
+                    }
+                } else if line.starts_with("DATA") {
+                    in_data = true;
+                    b"354 go ahead\r\n"
+                } else if line.starts_with("QUIT") {
+                    stream.write_all(b"221 bye\r\n").unwrap();
+                    break;
+                } else {
+                    b"250 ok\r\n"
+                };
+                stream.write_all(reply).unwrap();
+                line.clear();
+            }
+            data
+        })
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_send_workout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = serve_once(listener);
+
+        let smtp = SmtpConfig {
+            host: Some(String::from("127.0.0.1")),
+            from: Some(String::from("coach@example.com")),
+            ..Default::default()
+        }
+        .with_env(|name| match name {
+            "WODGEN_SMTP_PORT" => Some(port.to_string()),
+            "WODGEN_SMTP_SECURITY" => Some(String::from("none")),
+            _ => None,
+        })
+        .unwrap();
+        let workout = [WorkoutExercise {
+            group: 2,
+            name: String::from("Push Up"),
+            reps: String::from("X"),
+            ..Default::default()
+        }];
+        send_workout(
+            &smtp,
+            &[String::from("client@example.com")],
+            &workout,
+            "2024_06_01",
+            Lang::En,
+        )
+        .unwrap();
+
+        let data = server.join().unwrap();
+        assert!(data.contains("Subject: Workout 2024_06_01"));
+        assert!(data.contains("To: client@example.com"));
+        assert!(data.contains("Content-Type: text/html"));
+        assert!(data.contains("filename=\"2024_06_01.csv\""));
+
+        assert!(SmtpConfig::default()
+            .with_env(|_| None)
+            .unwrap()
+            .host
+            .is_none());
+        assert!(SmtpConfig::default()
+            .with_env(|name| (name == "WODGEN_SMTP_PORT").then(|| String::from("smtp")))
+            .is_err());
+        let error = send_workout(&SmtpConfig::default(), &[], &workout, "t", Lang::En);
+        assert!(error.unwrap_err().to_string().contains("No SMTP server"));
+    }
+}
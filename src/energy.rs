@@ -0,0 +1,95 @@
+use crate::history::best_value;
+use crate::units::Duration;
+use crate::{display, Exercise, WorkoutExercise};
+
+// Sets assumed when the workout leaves the number open
+const DEFAULT_SETS: u32 = 3;
+
+// Minutes a set takes with its rest, unless its time prescription is longer
+const MINUTES_PER_SET: f64 = 2.0;
+
+// --------------------------------------------------
+
+// Minutes spent on an exercise of the workout
+fn minutes(exercise: &WorkoutExercise) -> f64 {
+    let sets = exercise.sets.trim().parse::<u32>().unwrap_or(DEFAULT_SETS);
+    let set_minutes = best_value::<Duration>(&exercise.time).map_or(MINUTES_PER_SET, |time| {
+        (time.seconds() as f64 / 60.0).max(MINUTES_PER_SET)
+    });
+    sets as f64 * set_minutes
+}
+
+/// Estimates the energy spent in a workout in kilocalories, as the MET value
+/// of each exercise times the body mass times the hours spent on it.
+///
+/// The time spent on an exercise is estimated from its sets and time
+/// prescription, and exercises without a MET value in the library are left out.
+///
+/// # Returns
+///
+/// The estimate, or None when no exercise of the workout has a MET value.
+pub fn estimate_kcal(
+    workout: &[WorkoutExercise],
+    exercises: &[Exercise],
+    body_mass_kg: f64,
+) -> Option<f64> {
+    let kcal: Vec<f64> = workout
+        .iter()
+        .filter(|w| w.group > 0)
+        .filter_map(|w| {
+            let met = exercises
+                .iter()
+                .find(|e| display::exercise_name(e) == w.name)?
+                .met?;
+            Some(met * body_mass_kg * minutes(w) / 60.0)
+        })
+        .collect();
+    (!kcal.is_empty()).then(|| kcal.iter().sum())
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExerciseLevel;
+
+    fn exercise(name: &str, met: Option<f64>) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_level: ExerciseLevel::Beginner,
+            met,
+            ..Default::default()
+        }
+    }
+
+    fn row(name: &str, sets: &str, time: &str) -> WorkoutExercise {
+        WorkoutExercise {
+            group: 2,
+            name: String::from(name),
+            sets: String::from(sets),
+            time: String::from(time),
+            ..Default::default()
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_estimate_kcal() {
+        let exercises = vec![
+            exercise("dip", Some(6.0)),
+            exercise("plank", Some(3.0)),
+            exercise("push_up", None),
+        ];
+        // 3 sets of 2 minutes of dips, then 2 sets of 5 minutes of planks
+        let workout = vec![
+            row("Dip", "", ""),
+            row("Plank", "2", "5:00"),
+            row("Push Up", "", ""),
+        ];
+        let kcal = estimate_kcal(&workout, &exercises, 80.0).unwrap();
+        assert!((kcal - (6.0 * 80.0 * 0.1 + 3.0 * 80.0 * 10.0 / 60.0)).abs() < 1e-9);
+        assert!(estimate_kcal(&workout[2..], &exercises, 80.0).is_none());
+    }
+}
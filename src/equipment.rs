@@ -0,0 +1,239 @@
+use crate::csv_utils::{read_csv, write_csv};
+use crate::error::path_to_str;
+use crate::plates::Inventory;
+use crate::units::{Units, Weight};
+use crate::Exercise;
+use anyhow::Result;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// File next to the user config holding the equipment registry.
+pub const EQUIPMENT_FILE: &str = "equipment.csv";
+
+// --------------------------------------------------
+
+/// A piece of equipment at hand, e.g. `rings` or `40kg dumbbells x2`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Equipment {
+    /// Name of the equipment, lowercase, e.g. `dumbbells`
+    pub name: String,
+    /// Weight of each piece, for loadable equipment
+    pub weight_kg: Option<f64>,
+    /// Number of pieces
+    pub count: u32,
+}
+
+// --------------------------------------------------
+
+// The name of a piece of equipment without its plural, so that `dumbbell` in
+// the library matches `dumbbells` in the registry
+fn key(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    name.strip_suffix('s').map(str::to_string).unwrap_or(name)
+}
+
+impl FromStr for Equipment {
+    type Err = String;
+
+    /// Parses equipment like `rings`, `pull-up bar` or `40kg dumbbells x2`: an
+    /// optional weight, a name and an optional count.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words: Vec<&str> = s.split_whitespace().collect();
+        let weight = words.first().and_then(|w| w.parse::<Weight>().ok());
+        if weight.is_some() {
+            words.remove(0);
+        }
+        let count = match words.last().and_then(|w| w.strip_prefix(['x', 'X'])) {
+            Some(count) => {
+                let count = count
+                    .parse::<u32>()
+                    .map_err(|_| format!("Invalid count in {:?}", s))?;
+                words.pop();
+                count
+            }
+            None => 1,
+        };
+        if words.is_empty() {
+            return Err(format!("No equipment name in {:?}", s));
+        }
+        Ok(Equipment {
+            name: words.join(" ").to_lowercase(),
+            weight_kg: weight.map(|w| w.kilograms()),
+            count,
+        })
+    }
+}
+
+impl Equipment {
+    /// Renders the equipment like it is added, e.g. `40kg dumbbells x2`.
+    pub fn render(&self, units: Units) -> String {
+        let mut output = self.name.clone();
+        if let Some(weight_kg) = self.weight_kg {
+            output = format!(
+                "{} {}",
+                Weight::from_kilograms(weight_kg).render(units),
+                output
+            );
+        }
+        if self.count > 1 {
+            output = format!("{} x{}", output, self.count);
+        }
+        output
+    }
+}
+
+// --------------------------------------------------
+
+/// The equipment registry next to the given user config file.
+pub fn registry_file(config_file: &Path) -> PathBuf {
+    config_file.with_file_name(EQUIPMENT_FILE)
+}
+
+/// Loads the equipment registry, empty when the file does not exist.
+///
+/// # Errors
+///
+/// This function will return an error if the file exists but cannot be parsed.
+pub fn load_equipment(file_path: &Path) -> Result<Vec<Equipment>> {
+    if !file_path.exists() {
+        return Ok(Vec::new());
+    }
+    read_csv::<Equipment>(path_to_str(file_path)?)
+}
+
+/// Adds equipment to the registry, replacing the count of the same equipment
+/// of the same weight.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or written.
+pub fn add_equipment(file_path: &Path, items: Vec<Equipment>) -> Result<()> {
+    let mut registry = load_equipment(file_path)?;
+    for item in items {
+        registry.retain(|e| !(e.name == item.name && e.weight_kg == item.weight_kg));
+        info!("Added {} to the equipment", item.render(Units::Metric));
+        registry.push(item);
+    }
+    registry.sort_by(|a, b| {
+        (&a.name, a.weight_kg)
+            .partial_cmp(&(&b.name, b.weight_kg))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    save(file_path, registry)
+}
+
+/// Removes equipment from the registry, every weight of it unless one is given.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or written.
+pub fn remove_equipment(file_path: &Path, items: &[Equipment]) -> Result<()> {
+    let mut registry = load_equipment(file_path)?;
+    registry.retain(|e| {
+        !items.iter().any(|item| {
+            key(&item.name) == key(&e.name)
+                && (item.weight_kg.is_none() || item.weight_kg == e.weight_kg)
+        })
+    });
+    save(file_path, registry)
+}
+
+// Write the registry, creating the config directory if needed
+fn save(file_path: &Path, registry: Vec<Equipment>) -> Result<()> {
+    if let Some(config_dir) = file_path.parent() {
+        fs::create_dir_all(config_dir)?;
+    }
+    write_csv(path_to_str(file_path)?, registry)
+}
+
+// --------------------------------------------------
+
+/// Whether the equipment an exercise needs is all in the registry.
+pub fn is_equipped(exercise: &Exercise, registry: &[Equipment]) -> bool {
+    exercise
+        .equipment
+        .iter()
+        .all(|needed| registry.iter().any(|e| key(&e.name) == key(needed)))
+}
+
+/// Leaves out the exercises needing equipment that is not in the registry, and
+/// returns their names. An empty registry leaves every exercise in.
+pub fn filter_by_equipment(exercises: &mut Vec<Exercise>, registry: &[Equipment]) -> Vec<String> {
+    if registry.is_empty() {
+        return Vec::new();
+    }
+    let unequipped: Vec<String> = exercises
+        .iter()
+        .filter(|e| !is_equipped(e, registry))
+        .map(|e| e.name.clone())
+        .collect();
+    exercises.retain(|e| !unequipped.contains(&e.name));
+    if !unequipped.is_empty() {
+        info!(
+            "Left out {} exercises needing equipment not at hand, {} exercises remaining",
+            unequipped.len(),
+            exercises.len()
+        );
+    }
+    unequipped
+}
+
+/// Adds the weighted equipment of the registry to the load inventory: the
+/// dumbbells, the plates, two of which make a pair, and the barbell, unless
+/// the config already sets one.
+pub fn extend_inventory(inventory: &mut Inventory, registry: &[Equipment]) {
+    for item in registry {
+        let Some(weight_kg) = item.weight_kg else {
+            continue;
+        };
+        let weight = Weight::from_kilograms(weight_kg);
+        match key(&item.name).as_str() {
+            "dumbbell" => inventory.dumbbells.push(weight),
+            "plate" => inventory
+                .plates
+                .extend(std::iter::repeat_n(weight, item.count as usize / 2)),
+            "barbell" | "bar" => {
+                inventory.bar.get_or_insert(weight);
+            }
+            _ => (),
+        }
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_extend_inventory() {
+        let dumbbells: Equipment = "40kg dumbbells x2".parse().unwrap();
+        assert_eq!(
+            dumbbells,
+            Equipment {
+                name: String::from("dumbbells"),
+                weight_kg: Some(40.0),
+                count: 2,
+            }
+        );
+        assert_eq!(dumbbells.render(Units::Metric), "40kg dumbbells x2");
+        let bar: Equipment = "Pull-up Bar".parse().unwrap();
+        assert_eq!(bar.render(Units::Metric), "pull-up bar");
+        assert!("20kg".parse::<Equipment>().is_err());
+        assert!("plates xa".parse::<Equipment>().is_err());
+
+        let registry: Vec<Equipment> = ["rings", "45lb plates x4", "40kg dumbbells x2"]
+            .iter()
+            .map(|item| item.parse().unwrap())
+            .collect();
+        let mut inventory = Inventory::default();
+        extend_inventory(&mut inventory, &registry);
+        assert_eq!(inventory.plates.len(), 2);
+        assert_eq!(inventory.dumbbells, [Weight::from_kilograms(40.0)]);
+        assert_eq!(inventory.bar, None);
+    }
+}
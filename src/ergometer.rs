@@ -0,0 +1,211 @@
+use crate::export::escape_html;
+use crate::i18n::{Lang, Text};
+use crate::notation;
+use crate::units::Duration;
+use crate::WorkoutExercise;
+
+// Power of the work and of the rest of the intervals, as shares of the FTP
+const WORK_POWER: f64 = 0.9;
+const REST_POWER: f64 = 0.5;
+
+// --------------------------------------------------
+
+// Enum for the files ergometer intervals are exported to: Zwift workouts, also
+// read by TrainerRoad and most training apps, or ERG files in watts
+#[derive(Debug, Default, PartialEq, Clone, Copy, clap::ValueEnum)]
+pub enum ErgFormat {
+    #[default]
+    Zwo,
+    Erg,
+}
+
+/// A block of intervals: the stations of a group, each worked then rested for
+/// the seconds given, gone through for its rounds.
+#[derive(Debug, PartialEq)]
+pub struct Block {
+    /// Label of the group, e.g. `B`
+    pub label: String,
+    pub rounds: u32,
+    /// Seconds of work and of rest of each station
+    pub stations: Vec<(u32, u32)>,
+}
+
+// --------------------------------------------------
+
+impl ErgFormat {
+    /// Returns the file extension used for this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ErgFormat::Zwo => "zwo",
+            ErgFormat::Erg => "erg",
+        }
+    }
+}
+
+// --------------------------------------------------
+
+/// Finds the interval blocks of a saved workout: the groups with rounds run on
+/// the clock of their interval, whatever their exercises, and those with rounds
+/// whose exercises are all held for the time of their goal, rested `rest`
+/// seconds after each. Other groups, and the cooldown without rounds, are
+/// left out.
+pub fn blocks(workout: &[WorkoutExercise], rest: u32) -> Vec<Block> {
+    let mut groups: Vec<u32> = workout
+        .iter()
+        .filter(|e| e.group > 0)
+        .map(|e| e.group)
+        .collect();
+    groups.dedup();
+    groups
+        .into_iter()
+        .filter_map(|group| {
+            let rows: Vec<&WorkoutExercise> = workout
+                .iter()
+                .filter(|e| e.group == group && !notation::is_warm_up(e))
+                .collect();
+            let rounds = rows.first()?.sets.trim().parse::<u32>().ok()?;
+            let stations = match rows.iter().find_map(|e| e.interval) {
+                Some(interval) => vec![(interval.work, interval.transition); rows.len()],
+                None => rows
+                    .iter()
+                    .map(|e| {
+                        let time = e.goal.trim().parse::<Duration>().ok()?;
+                        (!e.time.is_empty()).then_some((time.seconds(), rest))
+                    })
+                    .collect::<Option<Vec<(u32, u32)>>>()?,
+            };
+            Some(Block {
+                label: notation::letter(group),
+                rounds,
+                stations,
+            })
+        })
+        .collect()
+}
+
+/// Renders interval blocks as a Zwift workout, a repeated on and off interval
+/// for the blocks of one station, a step per work and rest otherwise.
+pub fn render_zwo(blocks: &[Block], title: &str, lang: Lang) -> String {
+    let mut output = format!(
+        "<workout_file>\n    <author>wodgen</author>\n    <name>{}</name>\n    <sportType>bike</sportType>\n    <workout>\n",
+        escape_html(&format!("{} {}", lang.text(Text::Workout), title))
+    );
+    let step = |seconds: u32, power: f64| {
+        format!(
+            "        <SteadyState Duration=\"{}\" Power=\"{:.2}\"/>\n",
+            seconds, power
+        )
+    };
+    for block in blocks {
+        if let [(work, rest)] = block.stations[..] {
+            output.push_str(&format!(
+                "        <IntervalsT Repeat=\"{}\" OnDuration=\"{}\" OffDuration=\"{}\" OnPower=\"{:.2}\" OffPower=\"{:.2}\"/>\n",
+                block.rounds, work, rest, WORK_POWER, REST_POWER
+            ));
+            continue;
+        }
+        for _ in 0..block.rounds {
+            for (work, rest) in &block.stations {
+                output.push_str(&step(*work, WORK_POWER));
+                output.push_str(&step(*rest, REST_POWER));
+            }
+        }
+    }
+    output.push_str("    </workout>\n</workout_file>\n");
+    output
+}
+
+/// Renders interval blocks as an ERG file, the watts of each step at a share
+/// of `ftp` from its start to its end minute.
+pub fn render_erg(blocks: &[Block], title: &str, lang: Lang, ftp: u32) -> String {
+    let mut output = format!(
+        "[COURSE HEADER]\nVERSION = 2\nUNITS = METRIC\nDESCRIPTION = {} {}\nMINUTES WATTS\n[END COURSE HEADER]\n[COURSE DATA]\n",
+        lang.text(Text::Workout),
+        title
+    );
+    let mut seconds = 0;
+    for block in blocks {
+        for _ in 0..block.rounds {
+            for (work, rest) in &block.stations {
+                for (length, power) in [(*work, WORK_POWER), (*rest, REST_POWER)] {
+                    let watts = (f64::from(ftp) * power).round();
+                    for at in [seconds, seconds + length] {
+                        output.push_str(&format!("{:.2}\t{}\n", f64::from(at) / 60.0, watts));
+                    }
+                    seconds += length;
+                }
+            }
+        }
+    }
+    output.push_str("[END COURSE DATA]\n");
+    output
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rounds::Interval;
+
+    fn row(group: u32, sets: &str, time: &str, reps: &str, goal: &str) -> WorkoutExercise {
+        WorkoutExercise {
+            group,
+            name: String::from("Row Erg"),
+            sets: String::from(sets),
+            time: String::from(time),
+            reps: String::from(reps),
+            goal: String::from(goal),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_interval_blocks() {
+        let mut mixed = row(2, "2", "X", "", "");
+        mixed.interval = Some(Interval {
+            work: 40,
+            transition: 20,
+        });
+        let workout = vec![
+            row(1, "", "", "", ""),
+            mixed,
+            row(2, "2", "", "X", "10"),
+            row(3, "3", "X", "", "30s"),
+            row(4, "3", "", "X", "10"),
+            // The cooldown has no rounds
+            row(5, "", "X", "", "45s"),
+        ];
+        let blocks = blocks(&workout, 15);
+        assert_eq!(
+            blocks,
+            [
+                Block {
+                    label: String::from("B"),
+                    rounds: 2,
+                    stations: vec![(40, 20), (40, 20)],
+                },
+                Block {
+                    label: String::from("C"),
+                    rounds: 3,
+                    stations: vec![(30, 15)],
+                },
+            ]
+        );
+
+        let zwo = render_zwo(&blocks, "2024_06_01", Lang::En);
+        assert!(zwo.contains("<name>Workout 2024_06_01</name>"));
+        assert_eq!(
+            zwo.matches("<SteadyState Duration=\"40\" Power=\"0.90\"/>")
+                .count(),
+            4
+        );
+        assert!(zwo.contains(
+            "<IntervalsT Repeat=\"3\" OnDuration=\"30\" OffDuration=\"15\" OnPower=\"0.90\" OffPower=\"0.50\"/>"
+        ));
+
+        let erg = render_erg(&blocks, "2024_06_01", Lang::En, 200);
+        assert!(erg.contains("[COURSE DATA]\n0.00\t180\n0.67\t180\n0.67\t100\n1.00\t100\n"));
+        assert!(erg.ends_with("6.25\t100\n[END COURSE DATA]\n"));
+    }
+}
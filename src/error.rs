@@ -0,0 +1,141 @@
+use crate::ExerciseType;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Errors raised by wodgen itself.
+///
+/// These are surfaced to the user through `anyhow`, so each message should tell
+/// the user what went wrong and, where possible, how to fix it.
+#[derive(Debug, Error)]
+pub enum WodgenError {
+    #[error("Path {0:?} is not valid UTF-8, please use a path with only UTF-8 characters")]
+    NonUtf8Path(PathBuf),
+
+    #[error("Could not determine the home directory, please pass the directories explicitly")]
+    NoHomeDir,
+
+    #[error(
+        "Exercise library not found at {0:?}, copy the exercise_library directory there or pass --exercise-library-dir"
+    )]
+    LibraryNotFound(PathBuf),
+
+    #[error("No saved workouts found in {0:?}, generate one first or pass the workout file")]
+    NoWorkouts(PathBuf),
+
+    #[error("No saved run for workout {0:?}, only workouts generated since runs are saved can be replayed")]
+    NoRunOf(PathBuf),
+
+    #[error("No roster of athletes, pass --roster")]
+    NoRoster,
+
+    #[error("No exercise types for athlete {0:?}, set types in the roster or pass --types")]
+    NoTypes(String),
+
+    #[error("Exercise {0:?} not found in the exercise library")]
+    UnknownExercise(String),
+
+    #[error("Exercise {0:?} is a bodyweight exercise, training maxes are for weighted exercises")]
+    BodyweightTrainingMax(String),
+
+    #[error("Exercise {0:?} not found in workout {1:?}")]
+    ExerciseNotInWorkout(String, PathBuf),
+
+    #[error(
+        "No exercise types for template block {0:?}, set types in the template or pass --types"
+    )]
+    NoBlockTypes(String),
+
+    #[error("Remote {0:?} is not supported, S3 buckets and Git repositories cannot be synced with, use an http(s):// WebDAV URL or a directory")]
+    UnsupportedRemote(String),
+
+    #[error("No remote to sync with, pass --remote or set remote under [sync] in the user config or WODGEN_SYNC_REMOTE")]
+    NoSyncRemote,
+
+    #[error("Unknown exercise library format for {0:?}, use a .csv, .toml or .json file")]
+    UnknownLibraryFormat(PathBuf),
+
+    #[error("Taxonomy node {0:?} {1}, fix it in taxonomy.toml of the exercise library")]
+    InvalidTaxonomy(String, &'static str),
+
+    #[error("Invalid constraints {0:?}: {1}")]
+    InvalidConstraints(PathBuf, &'static str),
+
+    #[error(
+        "{0} groups are too many, at most {1} fit a workout, lower the groups or the duration"
+    )]
+    TooManyGroups(u32, u32),
+
+    #[error("The workout takes {0}, past the {1} min budget, lower the groups, pick a focus with shorter rests or pass --allow-over-budget:\n{2}")]
+    OverBudget(String, u32, String),
+
+    #[error("Need {0}, only {1} available, add exercises to the library, reduce --groups or pass --auto-groups")]
+    PoolTooSmall(String, usize),
+
+    #[error("Unknown profile {0:?}, available profiles: {1}")]
+    UnknownProfile(String, String),
+
+    #[error(
+        "No session planned for {0} in the split, add one under [split] in the user config or run wodgen -t <TYPES>"
+    )]
+    RestDay(String),
+
+    #[error("The split plans {1:?} exercises on {0}, but the exercise library has none")]
+    SplitTypeMissing(String, ExerciseType),
+
+    #[error(
+        "The split plans a session of 0 minutes on {0}, give it a duration of a minute or more"
+    )]
+    SplitNoMinutes(String),
+
+    #[error("No run found for {0} in {1:?}, only workouts generated since runs are saved can be regenerated")]
+    NoRun(String, PathBuf),
+
+    #[error("No workout saved for {0} in {1:?}")]
+    NoWorkoutOn(String, PathBuf),
+
+    #[error("No interval block in workout {0:?}, only groups with rounds run on the clock or in time can be exported")]
+    NoIntervals(PathBuf),
+
+    #[error("No SMTP server to email the workout through, set host under [smtp] in the user config or WODGEN_SMTP_HOST")]
+    NoSmtpHost,
+
+    #[error("No CalDAV calendar to save the session to, set url under [caldav] in the user config or WODGEN_CALDAV_URL")]
+    NoCalDavUrl,
+
+    #[error("Workout {0:?} uses version {1} of the workout format, update wodgen to read it")]
+    UnsupportedWorkoutVersion(PathBuf, u32),
+
+    #[error("Invalid period {0:?}, use a month like 2024-06 or an ISO week like 2024-W23")]
+    InvalidPeriod(String),
+
+    #[error("No exercise file is mapped for type {0:?}")]
+    MissingExerciseFile(ExerciseType),
+
+    #[error(
+        "No {0:?} exercises left to pick from, add more to the library or wait for snoozed ones to expire"
+    )]
+    EmptyPool(ExerciseType),
+
+    #[error("No exercise to test, set testable to true for a few exercises of the library")]
+    NoTestableExercises,
+
+    #[error("Benchmark {0:?} not found, run wodgen benchmark to list those of benchmarks.csv")]
+    UnknownBenchmark(String),
+
+    #[error(
+        "Config {0:?} exists already, edit it or pass another --config to set up from scratch"
+    )]
+    ConfigExists(PathBuf),
+
+    #[error("Failed to initialize the logger: {0}")]
+    Logger(#[from] log::SetLoggerError),
+}
+
+// --------------------------------------------------
+
+/// Converts a path to a string slice, failing with `WodgenError::NonUtf8Path`
+/// instead of panicking when the path is not valid UTF-8.
+pub fn path_to_str(path: &Path) -> Result<&str, WodgenError> {
+    path.to_str()
+        .ok_or_else(|| WodgenError::NonUtf8Path(path.to_path_buf()))
+}
@@ -0,0 +1,340 @@
+use crate::calibration::Levels;
+use crate::notation;
+use crate::profile::Profile;
+use crate::template::{Slot, Template};
+use crate::{display, Exercise};
+use log::info;
+
+// Names listed per line of the report before the rest is only counted
+const MAX_NAMES: usize = 5;
+
+// --------------------------------------------------
+
+/// An exercise picked for a group of the workout.
+#[derive(Debug, Clone)]
+pub struct Pick {
+    /// Index of the group among the strength groups, starting at 0
+    pub group: u32,
+    /// Index of the template block the group belongs to
+    pub block: usize,
+    pub slot: Slot,
+    pub name: String,
+}
+
+/// The options the exercises were filtered with before the picks.
+#[derive(Debug)]
+pub struct Filters<'a> {
+    pub levels: &'a Levels,
+    pub bodyweight: bool,
+    pub exclude: &'a [String],
+    pub snoozed: &'a [String],
+    pub profile: Option<&'a Profile>,
+    /// Exercises left out for training a muscle that reached its MRV this week
+    pub rested: &'a [String],
+    /// Exercises left out for training an area given with --sore
+    pub sore: &'a [String],
+    /// Exercises left out for needing equipment that is not in the registry
+    pub unequipped: &'a [String],
+    /// Exercises left out for a prerequisite not met yet
+    pub locked: &'a [String],
+}
+
+// Enum for the reasons an exercise of the right type was not picked
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Skip {
+    Retired,
+    NotBodyweight,
+    Excluded,
+    Snoozed,
+    Profile,
+    Unequipped,
+    Locked,
+    Rested,
+    Sore,
+    Level,
+    Category,
+    Tags,
+    Taken,
+}
+
+/// The exercises left out of the pool before the picks, per reason, so that
+/// an exercise that never shows up can be traced to the filter removing it.
+#[derive(Debug, Default)]
+pub struct Audit {
+    removed: Vec<(Skip, Vec<String>)>,
+}
+
+impl Skip {
+    fn describe(&self) -> &'static str {
+        match self {
+            Skip::Retired => "retired",
+            Skip::NotBodyweight => "not bodyweight",
+            Skip::Excluded => "excluded",
+            Skip::Snoozed => "snoozed",
+            Skip::Profile => "left out by the profile",
+            Skip::Unequipped => "needs equipment not at hand",
+            Skip::Locked => "prerequisites not met, see wodgen goals",
+            Skip::Rested => "trains a muscle at its MRV",
+            Skip::Sore => "trains a sore area",
+            Skip::Level => "above the level",
+            Skip::Category => "wrong category for the group",
+            Skip::Tags => "missing the block tags",
+            Skip::Taken => "already in the workout",
+        }
+    }
+}
+
+impl Audit {
+    /// Records the exercises a filter left out, by their library names.
+    pub fn record(&mut self, skip: Skip, names: Vec<String>) {
+        if names.is_empty() {
+            return;
+        }
+        info!(
+            "Filtered out {} exercises: {}",
+            names.len(),
+            skip.describe()
+        );
+        match self.removed.iter_mut().find(|(s, _)| *s == skip) {
+            Some((_, removed)) => removed.extend(names),
+            None => self.removed.push((skip, names)),
+        }
+    }
+
+    /// Renders the exercises left out per reason, under their names in the
+    /// library, nothing when none was.
+    pub fn render(&self, library: &[Exercise]) -> String {
+        if self.removed.is_empty() {
+            return String::new();
+        }
+        let total: usize = self.removed.iter().map(|(_, names)| names.len()).sum();
+        let mut output = format!("Left out before the picks ({}):\n", total);
+        for (skip, names) in &self.removed {
+            let names: Vec<String> = names
+                .iter()
+                .map(|name| display::library_name(name, library))
+                .collect();
+            output.push_str(&format!(
+                "  {} ({}): {}\n",
+                skip.describe(),
+                names.len(),
+                list_names(&names)
+            ));
+        }
+        output
+    }
+}
+
+// --------------------------------------------------
+
+// The first filter an exercise fails for a pick, in the order they are applied
+fn skip_reason(
+    exercise: &Exercise,
+    pick: &Pick,
+    template: &Template,
+    filters: &Filters,
+    taken: &[&str],
+) -> Option<Skip> {
+    let block = &template.blocks[pick.block];
+    if !exercise.active {
+        Some(Skip::Retired)
+    } else if filters.bodyweight && !exercise.bodyweight {
+        Some(Skip::NotBodyweight)
+    } else if filters.exclude.contains(&exercise.name) {
+        Some(Skip::Excluded)
+    } else if filters.snoozed.contains(&exercise.name) {
+        Some(Skip::Snoozed)
+    } else if filters.profile.is_some_and(|p| !p.allows(exercise)) {
+        Some(Skip::Profile)
+    } else if filters.unequipped.contains(&exercise.name) {
+        Some(Skip::Unequipped)
+    } else if filters.locked.contains(&exercise.name) {
+        Some(Skip::Locked)
+    } else if filters.rested.contains(&exercise.name) {
+        Some(Skip::Rested)
+    } else if filters.sore.contains(&exercise.name) {
+        Some(Skip::Sore)
+    } else if taken.contains(&exercise.name.as_str()) {
+        Some(Skip::Taken)
+    } else if !filters.levels.allows(exercise) {
+        Some(Skip::Level)
+    } else if !block.accepts(exercise) {
+        Some(Skip::Tags)
+    } else if !pick.slot.fits(
+        exercise,
+        pick.group,
+        filters.levels.of(&pick.slot.exercise_type),
+        &template.rotation(),
+    ) {
+        Some(Skip::Category)
+    } else {
+        None
+    }
+}
+
+// Join names, listing at most MAX_NAMES of them
+fn list_names(names: &[String]) -> String {
+    let mut list = names
+        .iter()
+        .take(MAX_NAMES)
+        .cloned()
+        .collect::<Vec<String>>()
+        .join(", ");
+    if names.len() > MAX_NAMES {
+        list.push_str(&format!(" and {} more", names.len() - MAX_NAMES));
+    }
+    list
+}
+
+// --------------------------------------------------
+
+/// Renders a report of why each exercise was picked: the filters it passed, the
+/// other exercises that were just as eligible and why the rest were skipped.
+///
+/// `library` holds every exercise of the picked types, before any filtering,
+/// and `audit` what the filters left out of the pool, which comes first.
+pub fn render(
+    picks: &[Pick],
+    library: &[Exercise],
+    template: &Template,
+    filters: &Filters,
+    audit: &Audit,
+) -> String {
+    let mut output = audit.render(library);
+    for (index, pick) in picks.iter().enumerate() {
+        let taken: Vec<&str> = picks[..index].iter().map(|p| p.name.as_str()).collect();
+        let block = &template.blocks[pick.block];
+        output.push_str(&format!(
+            "Group {}, {}: {}\n",
+            notation::letter(pick.group + 2),
+            pick.slot.label(),
+            display::library_name(&pick.name, library)
+        ));
+
+        let mut passed = vec!["type", "level", "category"];
+        if !block.tags.is_empty() {
+            passed.push("tags");
+        }
+        if filters.bodyweight {
+            passed.push("bodyweight");
+        }
+        passed.extend(["not excluded", "not snoozed"]);
+        if filters.profile.is_some() {
+            passed.push("profile");
+        }
+        output.push_str(&format!("  Passed: {}\n", passed.join(", ")));
+
+        let mut eligible = Vec::new();
+        let mut skipped: Vec<(Skip, Vec<String>)> = Vec::new();
+        for exercise in library
+            .iter()
+            .filter(|e| pick.slot.accepts(e) && e.name != pick.name)
+        {
+            let name = display::exercise_name(exercise);
+            match skip_reason(exercise, pick, template, filters, &taken) {
+                None => eligible.push(name),
+                Some(skip) => match skipped.iter_mut().find(|(s, _)| *s == skip) {
+                    Some((_, names)) => names.push(name),
+                    None => skipped.push((skip, vec![name])),
+                },
+            }
+        }
+        if !eligible.is_empty() {
+            output.push_str(&format!(
+                "  Also eligible, lost the shuffle ({}): {}\n",
+                eligible.len(),
+                list_names(&eligible)
+            ));
+        }
+        for (skip, names) in skipped {
+            output.push_str(&format!(
+                "  Skipped, {} ({}): {}\n",
+                skip.describe(),
+                names.len(),
+                list_names(&names)
+            ));
+        }
+    }
+    output
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExerciseCategory, ExerciseLevel, ExerciseType};
+
+    fn exercise(name: &str, category: ExerciseCategory, bodyweight: bool) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_category: category,
+            exercise_level: ExerciseLevel::Beginner,
+            bodyweight,
+            ..Default::default()
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_render_explanation() {
+        let library = vec![
+            exercise("dip", ExerciseCategory::Primary, true),
+            exercise("push_up", ExerciseCategory::Primary, true),
+            exercise("pike_push_up", ExerciseCategory::Primary, true),
+            exercise("bench_press", ExerciseCategory::Primary, false),
+            exercise("wall_slide", ExerciseCategory::Accessory, true),
+        ];
+        let template = Template::from_types(&[ExerciseType::Push.into()], 1);
+        let snoozed = [String::from("pike_push_up")];
+        let filters = Filters {
+            levels: &Levels::from(ExerciseLevel::Intermediate),
+            bodyweight: true,
+            exclude: &[],
+            snoozed: &snoozed,
+            profile: None,
+            rested: &[],
+            sore: &[],
+            unequipped: &[],
+            locked: &[],
+        };
+        let picks = [Pick {
+            group: 0,
+            block: 0,
+            slot: ExerciseType::Push.into(),
+            name: String::from("dip"),
+        }];
+
+        assert_eq!(
+            render(&picks, &library, &template, &filters, &Audit::default()),
+            "Group B, Push: Dip\n\
+             \x20 Passed: type, level, category, bodyweight, not excluded, not snoozed\n\
+             \x20 Also eligible, lost the shuffle (1): Push Up\n\
+             \x20 Skipped, snoozed (1): Pike Push Up\n\
+             \x20 Skipped, not bodyweight (1): Bench Press\n\
+             \x20 Skipped, wrong category for the group (1): Wall Slide\n"
+        );
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_audit_counts_per_reason() {
+        let mut audit = Audit::default();
+        audit.record(Skip::Snoozed, vec![String::from("pike_push_up")]);
+        audit.record(Skip::Excluded, Vec::new());
+        audit.record(
+            Skip::NotBodyweight,
+            (1..=7).map(|i| format!("press_{}", i)).collect(),
+        );
+        audit.record(Skip::Snoozed, vec![String::from("dip__regular")]);
+
+        assert_eq!(
+            audit.render(&[]),
+            "Left out before the picks (9):\n\
+             \x20 snoozed (2): Pike Push Up, Dip - Regular\n\
+             \x20 not bodyweight (7): Press 1, Press 2, Press 3, Press 4, Press 5 and 2 more\n"
+        );
+    }
+}
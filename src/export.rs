@@ -0,0 +1,757 @@
+use crate::csv_utils::writer_builder;
+use crate::i18n::{Lang, Text};
+use crate::media;
+use crate::notation;
+use crate::pdf::{self, Document, PAGE_HEIGHT};
+use crate::WorkoutExercise;
+use anyhow::Result;
+use chrono::NaiveDate;
+use qrcode::render::svg;
+use qrcode::{Color, QrCode};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Maximum width of exercise thumbnails in pixels
+const THUMBNAIL_WIDTH: u32 = 120;
+
+// Width of the QR codes of the videos, in pixels in HTML and points in PDF
+const QR_WIDTH: u32 = 96;
+const PDF_QR_WIDTH: f32 = 44.0;
+
+// Margin of PDF pages and font sizes of their title and table, in points
+const PDF_MARGIN: f32 = 40.0;
+const PDF_TITLE_SIZE: f32 = 16.0;
+const PDF_TEXT_SIZE: f32 = 9.0;
+
+// Columns of the PDF table, as indexes of the workout headers, and their
+// widths in points. The images and scaling are left out of print, the videos
+// are QR codes
+const PDF_COLUMNS: [(usize, f32); 9] = [
+    (0, 30.0),
+    (1, 125.0),
+    (2, 35.0),
+    (3, 40.0),
+    (4, 40.0),
+    (5, 45.0),
+    (6, 60.0),
+    (8, 50.0),
+    (10, 90.0),
+];
+
+// Index of the video column in the workout headers
+const VIDEO_COLUMN: usize = 8;
+
+/// Version of the workout schema, of both the JSON and the CSV files, raised
+/// whenever a field changes meaning or goes away so older wodgen versions
+/// refuse the files they would misread.
+pub const WORKOUT_FILE_VERSION: u32 = 3;
+
+/// Start of the line a CSV workout opens with, followed by the version, e.g.
+/// `# wodgen workout 3`. Files written before it are version 1.
+pub const CSV_VERSION_PREFIX: &str = "# wodgen workout ";
+
+// --------------------------------------------------
+
+// Enum for the formats a workout can be saved in
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Format {
+    Csv,
+    Markdown,
+    Html,
+    Json,
+    Pdf,
+    Obsidian,
+}
+
+/// A workout saved as JSON: the schema version, the title and language of the
+/// workout and its rows, under their labels when written. Files written before
+/// the version was saved are version 1.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkoutFile<T> {
+    #[serde(default = "first_version")]
+    pub version: u32,
+    pub title: String,
+    #[serde(default)]
+    pub lang: Lang,
+    pub exercises: Vec<T>,
+}
+
+/// Where `--format obsidian` writes its notes, under `[obsidian]` in the user
+/// config: a folder of the vault, the workouts directory when unset, and the
+/// tags of the notes, `workout` when unset.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Obsidian {
+    pub vault: Option<PathBuf>,
+    pub tags: Vec<String>,
+}
+
+/// The properties of the Obsidian note of a workout, written as its YAML
+/// frontmatter for the vault to query the sessions by.
+#[derive(Debug)]
+pub struct Frontmatter {
+    pub date: NaiveDate,
+    /// Exercise types of the workout, e.g. `pull`
+    pub types: Vec<String>,
+    pub tags: Vec<String>,
+    /// Estimated minutes of the workout
+    pub minutes: u32,
+}
+
+// The version of the files saved without one
+fn first_version() -> u32 {
+    1
+}
+
+impl Format {
+    /// Returns the file extension used for this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Markdown => "md",
+            Format::Html => "html",
+            Format::Json => "json",
+            Format::Pdf => "pdf",
+            Format::Obsidian => "md",
+        }
+    }
+}
+
+impl Obsidian {
+    /// The tags of the notes.
+    pub fn tags(&self) -> Vec<String> {
+        if self.tags.is_empty() {
+            vec![String::from("workout")]
+        } else {
+            self.tags.clone()
+        }
+    }
+}
+
+// --------------------------------------------------
+
+/// Escapes the characters that would break a Markdown table cell.
+pub fn escape_markdown(input: &str) -> String {
+    input.replace('|', "\\|")
+}
+
+/// Escapes the characters that have a meaning in HTML.
+pub fn escape_html(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Cut a text to the characters that fit a PDF column, ending with an ellipsis
+// when cut
+fn fit_column(text: &str, width: f32) -> String {
+    let fitting = ((width - 4.0) / pdf::char_width(PDF_TEXT_SIZE)) as usize;
+    if text.chars().count() <= fitting {
+        return text.to_string();
+    }
+    let mut fitted: String = text.chars().take(fitting.saturating_sub(1)).collect();
+    fitted.push('…');
+    fitted
+}
+
+// Split the session notes, stored as group 0 rows labelled by their name,
+// from the exercises
+fn split_session_notes(workout: &[WorkoutExercise]) -> (Vec<(&str, &str)>, Vec<&WorkoutExercise>) {
+    let (notes, exercises): (Vec<&WorkoutExercise>, Vec<&WorkoutExercise>) =
+        workout.iter().partition(|e| e.group == 0);
+    let notes = notes
+        .iter()
+        .map(|e| (e.name.as_str(), e.notes.as_str()))
+        .collect();
+    (notes, exercises)
+}
+
+// --------------------------------------------------
+
+// Quote a YAML string, escaping the backslashes and the quotes
+fn yaml_string(input: &str) -> String {
+    format!("\"{}\"", input.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Renders a workout as a Markdown document with a table of exercises.
+///
+/// Images are embedded as thumbnails and videos as links, using the cached copy
+/// in `media_dir` for remote media when there is one.
+pub fn render_markdown(
+    workout: &[WorkoutExercise],
+    title: &str,
+    lang: Lang,
+    media_dir: &Path,
+) -> String {
+    markdown(workout, title, lang, media_dir, |name| name.to_string())
+}
+
+/// Renders a workout as a note of an Obsidian vault: the Markdown document,
+/// each exercise a wiki-link to its own note, under a YAML frontmatter holding
+/// the date, the types, the tags and the estimated minutes.
+pub fn render_obsidian(
+    workout: &[WorkoutExercise],
+    title: &str,
+    lang: Lang,
+    media_dir: &Path,
+    frontmatter: &Frontmatter,
+) -> String {
+    let list = |values: &[String]| -> String {
+        values
+            .iter()
+            .map(|v| format!("\n  - {}", yaml_string(v)))
+            .collect()
+    };
+    format!(
+        "---\ndate: {}\ntypes:{}\ntags:{}\nduration: {}\n---\n\n{}",
+        frontmatter.date.format("%Y-%m-%d"),
+        list(&frontmatter.types),
+        list(&frontmatter.tags),
+        frontmatter.minutes,
+        markdown(workout, title, lang, media_dir, |name| format!(
+            "[[{}]]",
+            name
+        ))
+    )
+}
+
+// Render the Markdown document of a workout, the exercise names through `name`
+fn markdown(
+    workout: &[WorkoutExercise],
+    title: &str,
+    lang: Lang,
+    media_dir: &Path,
+    name: impl Fn(&str) -> String,
+) -> String {
+    let headers = lang.workout_headers();
+    let (notes, workout) = split_session_notes(workout);
+    let mut output = format!("# {} {}\n\n", lang.text(Text::Workout), title);
+    for (label, note) in notes {
+        output.push_str(&format!("> **{}:** {}\n\n", label, note));
+    }
+    output.push_str(&format!("| {} |\n", headers.join(" | ")));
+    output.push_str(&format!("|{}\n", " --- |".repeat(headers.len())));
+
+    for (label, e) in notation::labels(workout.iter().copied())
+        .into_iter()
+        .zip(workout)
+    {
+        let image = if e.image.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "<img src=\"{}\" alt=\"{}\" width=\"{}\">",
+                escape_html(&media::resolve(&e.image, media_dir)),
+                escape_html(&e.name),
+                THUMBNAIL_WIDTH
+            )
+        };
+        let video = if e.video.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "[{}](<{}>)",
+                lang.text(Text::Video),
+                media::resolve(&e.video, media_dir)
+            )
+        };
+        let cells = [
+            label,
+            name(&e.name),
+            e.sets.clone(),
+            e.distance.clone(),
+            e.time.clone(),
+            e.reps.clone(),
+            e.goal.clone(),
+            image,
+            video,
+            e.scaling.clone(),
+            e.notes.clone(),
+        ];
+        let cells: Vec<String> = cells.iter().map(|c| escape_markdown(c)).collect();
+        output.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    output
+}
+
+/// Renders a workout as a saved CSV workout: the version line, then the
+/// headers of its language and the typed columns.
+///
+/// # Errors
+///
+/// This function will return an error if a row cannot be serialized.
+pub fn render_csv(workout: &[WorkoutExercise], lang: Lang) -> Result<Vec<u8>> {
+    let version = format!("{}{}\n", CSV_VERSION_PREFIX, WORKOUT_FILE_VERSION);
+    let mut wtr = writer_builder()
+        .has_headers(false)
+        .from_writer(version.into_bytes());
+    wtr.write_record(notation::csv_headers(lang))?;
+    for row in notation::rows(workout) {
+        wtr.serialize(row)?;
+    }
+    Ok(wtr.into_inner()?)
+}
+
+/// Renders a workout as a JSON document, the rows under their labels with the
+/// fields of the CSV columns, so it reads back like a CSV workout, typed
+/// columns included.
+///
+/// # Errors
+///
+/// This function will return an error if the workout cannot be serialized.
+pub fn render_json(
+    workout: &[WorkoutExercise],
+    title: &str,
+    lang: Lang,
+) -> serde_json::Result<String> {
+    let file = WorkoutFile {
+        version: WORKOUT_FILE_VERSION,
+        title: title.to_string(),
+        lang,
+        exercises: notation::rows(workout).collect(),
+    };
+    Ok(serde_json::to_string_pretty(&file)? + "\n")
+}
+
+/// Renders a workout on a single line for notifications, e.g. `Workout
+/// 2024_06_01: B1 Push Up 3x10, B2 Plank 3x0:45`, each exercise with its sets
+/// and its distance, time or reps. Session notes are left out.
+pub fn render_compact(workout: &[WorkoutExercise], title: &str, lang: Lang) -> String {
+    let (_, workout) = split_session_notes(workout);
+    let exercises: Vec<String> = notation::labels(workout.iter().copied())
+        .into_iter()
+        .zip(workout)
+        .map(|(label, e)| {
+            let amount = [&e.distance, &e.time, &e.reps]
+                .into_iter()
+                .find(|cell| !cell.is_empty());
+            match (e.sets.is_empty(), amount) {
+                (false, Some(amount)) => format!("{} {} {}x{}", label, e.name, e.sets, amount),
+                (true, Some(amount)) => format!("{} {} {}", label, e.name, amount),
+                (_, None) => format!("{} {}", label, e.name),
+            }
+        })
+        .collect();
+    format!(
+        "{} {}: {}",
+        lang.text(Text::Workout),
+        title,
+        exercises.join(", ")
+    )
+}
+
+// --------------------------------------------------
+
+// The QR code of a remote video, for printed sheets to still give access to
+// the demos. Local videos have none, they mean nothing on another device
+fn video_qr(video: &str) -> Option<QrCode> {
+    media::is_url(video)
+        .then(|| QrCode::new(video).ok())
+        .flatten()
+}
+
+// Draws a QR code on a PDF page, from the top left corner of its quiet zone,
+// with a rectangle per run of dark modules of each row
+fn draw_qr(document: &mut Document, code: &QrCode, x: f32, y: f32, width: f32) {
+    // Modules of the code and the quiet zone of 2 modules around it
+    let modules = code.width();
+    let module = width / (modules + 4) as f32;
+    let colors = code.to_colors();
+    for (row, line) in colors.chunks(modules).enumerate() {
+        let mut column = 0;
+        while column < modules {
+            let run = line[column..]
+                .iter()
+                .take_while(|color| **color == Color::Dark)
+                .count();
+            if run > 0 {
+                document.rect(
+                    x + (column + 2) as f32 * module,
+                    y + (row + 2) as f32 * module,
+                    run as f32 * module,
+                    module,
+                );
+            }
+            column += run.max(1);
+        }
+    }
+}
+
+// --------------------------------------------------
+
+/// Renders a workout as a standalone HTML page with a table of exercises.
+///
+/// Images are embedded as thumbnails and videos as links, using the cached copy
+/// in `media_dir` for remote media when there is one, and remote videos also as
+/// QR codes for printed sheets.
+pub fn render_html(
+    workout: &[WorkoutExercise],
+    title: &str,
+    lang: Lang,
+    media_dir: &Path,
+) -> String {
+    let title = format!("{} {}", lang.text(Text::Workout), escape_html(title));
+    let (notes, workout) = split_session_notes(workout);
+    let mut output = String::new();
+    output.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    output.push_str(&format!("<title>{}</title>\n", title));
+    output.push_str(&format!(
+        "<style>\nbody {{ font-family: sans-serif; }}\ntable {{ border-collapse: collapse; }}\nth, td {{ border: 1px solid #ccc; padding: 4px 8px; }}\nimg.thumbnail {{ max-width: {}px; }}\n</style>\n",
+        THUMBNAIL_WIDTH
+    ));
+    output.push_str("</head>\n<body>\n");
+    output.push_str(&format!("<h1>{}</h1>\n", title));
+    for (label, note) in notes {
+        output.push_str(&format!(
+            "<p class=\"note\"><strong>{}:</strong> {}</p>\n",
+            escape_html(label),
+            escape_html(note)
+        ));
+    }
+    output.push_str("<table>\n<tr>");
+    for header in lang.workout_headers() {
+        output.push_str(&format!("<th>{}</th>", escape_html(header)));
+    }
+    output.push_str("</tr>\n");
+
+    for (label, e) in notation::labels(workout.iter().copied())
+        .into_iter()
+        .zip(workout)
+    {
+        let image = if e.image.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "<img class=\"thumbnail\" src=\"{}\" alt=\"{}\">",
+                escape_html(&media::resolve(&e.image, media_dir)),
+                escape_html(&e.name)
+            )
+        };
+        let mut video = if e.video.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "<a href=\"{}\">{}</a>",
+                escape_html(&media::resolve(&e.video, media_dir)),
+                lang.text(Text::Video)
+            )
+        };
+        if let Some(code) = video_qr(&e.video) {
+            let image = code
+                .render::<svg::Color>()
+                .min_dimensions(QR_WIDTH, QR_WIDTH)
+                .build();
+            // Inline in the page, without the XML declaration
+            let start = image.find("<svg").unwrap_or_default();
+            video.push_str(&format!(
+                "<br><span class=\"qr\">{}</span>",
+                &image[start..]
+            ));
+        }
+        output.push_str("<tr>");
+        for cell in [
+            label,
+            e.name.clone(),
+            e.sets.clone(),
+            e.distance.clone(),
+            e.time.clone(),
+            e.reps.clone(),
+            e.goal.clone(),
+        ] {
+            output.push_str(&format!("<td>{}</td>", escape_html(&cell)));
+        }
+        output.push_str(&format!(
+            "<td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            image,
+            video,
+            escape_html(&e.scaling),
+            escape_html(&e.notes)
+        ));
+    }
+    output.push_str("</table>\n</body>\n</html>\n");
+    output
+}
+
+// --------------------------------------------------
+
+/// Renders a workout as a printable PDF document with a table of exercises,
+/// starting a new page when the table runs off one. Texts too long for their
+/// column are cut, the images left out and remote videos printed as QR codes.
+pub fn render_pdf(workout: &[WorkoutExercise], title: &str, lang: Lang) -> Vec<u8> {
+    let headers = lang.workout_headers();
+    let (notes, workout) = split_session_notes(workout);
+    let line_height = PDF_TEXT_SIZE * 1.6;
+    let mut document = Document::new();
+    let mut y = PDF_MARGIN + PDF_TITLE_SIZE;
+    document.text(
+        PDF_MARGIN,
+        y,
+        PDF_TITLE_SIZE,
+        true,
+        &format!("{} {}", lang.text(Text::Workout), title),
+    );
+    y += line_height * 2.0;
+    for (label, note) in notes {
+        document.text(
+            PDF_MARGIN,
+            y,
+            PDF_TEXT_SIZE,
+            false,
+            &format!("{}: {}", label, note),
+        );
+        y += line_height;
+    }
+    y += line_height;
+
+    let draw_row = |document: &mut Document, y: f32, cells: &[String], bold: bool| {
+        let mut x = PDF_MARGIN;
+        for ((_, width), cell) in PDF_COLUMNS.iter().zip(cells) {
+            if !cell.is_empty() {
+                document.text(x, y, PDF_TEXT_SIZE, bold, &fit_column(cell, *width));
+            }
+            x += width;
+        }
+    };
+    let header_cells: Vec<String> = PDF_COLUMNS
+        .iter()
+        .map(|(index, _)| headers[*index].to_string())
+        .collect();
+    let video_x = PDF_MARGIN
+        + PDF_COLUMNS
+            .iter()
+            .take_while(|(index, _)| *index != VIDEO_COLUMN)
+            .map(|(_, width)| width)
+            .sum::<f32>();
+    draw_row(&mut document, y, &header_cells, true);
+    // Depth of the last row under its baseline, more with a QR code
+    let mut depth = 0.0;
+    for (label, e) in notation::labels(workout.iter().copied())
+        .into_iter()
+        .zip(workout)
+    {
+        let code = video_qr(&e.video);
+        y += depth + line_height;
+        depth = if code.is_some() {
+            PDF_QR_WIDTH - PDF_TEXT_SIZE
+        } else {
+            0.0
+        };
+        if y + depth > PAGE_HEIGHT - PDF_MARGIN {
+            document.add_page();
+            y = PDF_MARGIN + PDF_TEXT_SIZE;
+            draw_row(&mut document, y, &header_cells, true);
+            y += line_height;
+        }
+        if let Some(code) = code {
+            draw_qr(
+                &mut document,
+                &code,
+                video_x,
+                y - PDF_TEXT_SIZE,
+                PDF_QR_WIDTH,
+            );
+        }
+        let cells = [
+            label,
+            e.name.clone(),
+            e.sets.clone(),
+            e.distance.clone(),
+            e.time.clone(),
+            e.reps.clone(),
+            e.goal.clone(),
+            String::new(),
+            e.notes.clone(),
+        ];
+        draw_row(&mut document, y, &cells, false);
+    }
+    document.encode()
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExerciseProgramming;
+
+    fn create_test_workout() -> Vec<WorkoutExercise> {
+        vec![WorkoutExercise {
+            group: 2,
+            name: String::from("Push Up"),
+            reps: String::from("X"),
+            goal: String::from("10 | 12"),
+            image: String::from("img/push_up.png"),
+            video: String::from("video/push_up.mp4"),
+            programming: Some(ExerciseProgramming::Reps),
+            ..Default::default()
+        }]
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_render_json_reads_back() {
+        let workout = create_test_workout();
+        let output = render_json(&workout, "2024_06_01", Lang::De).unwrap();
+        assert!(output.contains("\"group\": \"B1\""));
+        assert!(output.contains("\"programming\": \"Reps\""));
+        let file: WorkoutFile<WorkoutExercise> = serde_json::from_str(&output).unwrap();
+        assert_eq!(file.version, WORKOUT_FILE_VERSION);
+        assert_eq!(file.title, "2024_06_01");
+        assert_eq!(file.lang, Lang::De);
+        assert_eq!(file.exercises, workout);
+
+        // Files saved before the version and the language are version 1
+        let file: WorkoutFile<WorkoutExercise> =
+            serde_json::from_str(r#"{"title": "t", "exercises": []}"#).unwrap();
+        assert_eq!(file.version, 1);
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_render_compact_fits_one_line() {
+        let mut workout = create_test_workout();
+        workout.insert(
+            0,
+            WorkoutExercise {
+                name: String::from("Note"),
+                notes: String::from("Easy day"),
+                ..Default::default()
+            },
+        );
+        workout.push(WorkoutExercise {
+            group: 2,
+            name: String::from("Plank"),
+            sets: String::from("3"),
+            time: String::from("0:45"),
+            ..Default::default()
+        });
+        assert_eq!(
+            render_compact(&workout, "2024_06_01", Lang::En),
+            "Workout 2024_06_01: B1 Push Up X, B2 Plank 3x0:45"
+        );
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_render_markdown_includes_thumbnail() {
+        let output = render_markdown(
+            &create_test_workout(),
+            "2024_06_01",
+            Lang::En,
+            Path::new("/cache"),
+        );
+        assert!(output.starts_with("# Workout 2024_06_01\n"));
+        assert!(output.contains("<img src=\"img/push_up.png\" alt=\"Push Up\" width=\"120\">"));
+        assert!(output.contains("[video](<video/push_up.mp4>)"));
+        assert!(output.contains("10 \\| 12"));
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_render_obsidian_note() {
+        let frontmatter = Frontmatter {
+            date: NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+            types: vec![String::from("push")],
+            tags: Obsidian::default().tags(),
+            minutes: 6,
+        };
+        let output = render_obsidian(
+            &create_test_workout(),
+            "2024_06_01",
+            Lang::En,
+            Path::new("/cache"),
+            &frontmatter,
+        );
+        assert!(output.starts_with(
+            "---\ndate: 2024-06-01\ntypes:\n  - \"push\"\ntags:\n  - \"workout\"\nduration: 6\n---\n\n# Workout 2024_06_01\n"
+        ));
+        assert!(output.contains("| B1 | [[Push Up]] |"));
+        assert_eq!(yaml_string("a \"b\""), "\"a \\\"b\\\"\"");
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_render_session_note_above_table() {
+        let mut workout = create_test_workout();
+        workout[0].notes = String::from("Elbows in");
+        workout.insert(
+            0,
+            WorkoutExercise {
+                name: String::from("Note"),
+                notes: String::from("Focus on scapular control"),
+                ..Default::default()
+            },
+        );
+        let output = render_markdown(&workout, "2024_06_01", Lang::En, Path::new("/cache"));
+        assert!(output.contains("> **Note:** Focus on scapular control\n"));
+        assert!(output.contains("| Elbows in |\n"));
+        assert_eq!(output.matches("| Note |").count(), 0);
+
+        let output = render_html(&workout, "2024_06_01", Lang::En, Path::new("/cache"));
+        assert!(output.contains("<strong>Note:</strong> Focus on scapular control</p>"));
+        assert!(output.contains("<td>Elbows in</td>"));
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_render_html_includes_thumbnail() {
+        let output = render_html(
+            &create_test_workout(),
+            "2024_06_01",
+            Lang::De,
+            Path::new("/cache"),
+        );
+        assert!(output.contains("<h1>Training 2024_06_01</h1>"));
+        assert!(
+            output.contains("<img class=\"thumbnail\" src=\"img/push_up.png\" alt=\"Push Up\">")
+        );
+        assert!(output.contains("<th>wiederholungen</th>"));
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_render_pdf_pages_the_table() {
+        let output = render_pdf(&create_test_workout(), "2024_06_01", Lang::En);
+        let text = String::from_utf8_lossy(&output);
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.contains("(Workout 2024_06_01) Tj"));
+        assert!(text.contains("(Push Up) Tj"));
+        assert!(text.contains("/Count 1"));
+
+        let workout = vec![create_test_workout()[0].clone(); 80];
+        let output = render_pdf(&workout, "2024_06_01", Lang::En);
+        assert!(String::from_utf8_lossy(&output).contains("/Count 2"));
+        assert_eq!(fit_column("Push Up", 140.0), "Push Up");
+        assert_eq!(fit_column("Single Arm Push Up", 30.0).chars().count(), 5);
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_remote_videos_get_qr_codes() {
+        let mut workout = create_test_workout();
+        assert!(video_qr(&workout[0].video).is_none());
+        workout[0].video = String::from("https://example.com/video/push_up.mp4");
+
+        let html = render_html(&workout, "2024_06_01", Lang::En, Path::new("/cache"));
+        assert!(html.contains("<br><span class=\"qr\"><svg xmlns="));
+        assert!(!html.contains("<?xml"));
+
+        let pdf = render_pdf(&workout, "2024_06_01", Lang::En);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("(video) Tj"));
+        assert!(text.matches(" re f").count() > 50);
+        // Rows with a QR code are taller, the table runs off the first page sooner
+        let output = render_pdf(&vec![workout[0].clone(); 20], "2024_06_01", Lang::En);
+        assert!(String::from_utf8_lossy(&output).contains("/Count 2"));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::calibration::Levels;
+use crate::category::{CategoryStrategy, Rotation};
+use crate::{
+    filter_by_category, filter_by_level, rotation_categories, Exercise, ExerciseCategory,
+    ExerciseLevel, ExerciseType, MAX_GROUPS,
+};
+use clap::ValueEnum;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+// Seeds replayed by every run of the tests, one per line before the options
+// of the run that saved it
+const REGRESSION_SEEDS: &str = include_str!("../tests/fuzz_seeds.txt");
+
+// Number of fresh seeds tried by every run of the tests
+const FRESH_SEEDS: u64 = 256;
+
+// Number of exercises of the pool drawn for a seed
+const POOL_SIZE: usize = 24;
+
+// --------------------------------------------------
+
+// Any of the values of an enum
+fn any<T: ValueEnum + Clone>(rng: &mut StdRng) -> T {
+    T::value_variants().choose(rng).cloned().unwrap()
+}
+
+// An exercise of the library as a seed draws it
+fn exercise(index: usize, rng: &mut StdRng) -> Exercise {
+    Exercise {
+        name: format!("exercise_{}", index),
+        exercise_type: any(rng),
+        exercise_category: any(rng),
+        exercise_level: any(rng),
+        bodyweight: rng.gen(),
+        ..Default::default()
+    }
+}
+
+// The seeds of the regression file, skipping the comments
+fn regression_seeds(content: &str) -> Vec<u64> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let seed = line.split_whitespace().next().unwrap_or_default();
+            seed.parse()
+                .unwrap_or_else(|_| panic!("Invalid seed in tests/fuzz_seeds.txt: {}", line))
+        })
+        .collect()
+}
+
+// Checks the filters on the pool and the workout group drawn from a seed
+fn check(seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let pool: Vec<Exercise> = (0..POOL_SIZE).map(|i| exercise(i, &mut rng)).collect();
+    let level: ExerciseLevel = any(&mut rng);
+    let t: ExerciseType = any(&mut rng);
+    let rotation = Rotation {
+        strategy: any(&mut rng),
+        groups: rng.gen_range(1..=MAX_GROUPS),
+    };
+    let group = rng.gen_range(0..rotation.groups);
+    let levels = Levels::from(level.clone());
+
+    let categories = rotation.categories(group, &level, &t);
+    assert!(!categories.is_empty(), "seed {}: no category", seed);
+    if rotation.strategy == CategoryStrategy::Standard {
+        assert_eq!(categories, rotation_categories(group, &level, &t));
+        if group == 0 && level == ExerciseLevel::Beginner {
+            assert!(
+                !categories.contains(&ExerciseCategory::Primary),
+                "seed {}: a beginner starts with a main lift",
+                seed
+            );
+        }
+    }
+    for e in &pool {
+        // The same answer every time, whatever was asked before
+        let allowed = filter_by_level(e, &level);
+        assert_eq!(allowed, filter_by_level(e, &level), "seed {}", seed);
+        assert_eq!(allowed, e.exercise_level <= level, "seed {}: {:?}", seed, e);
+        assert_eq!(allowed, levels.allows(e), "seed {}: {:?}", seed, e);
+        // Allowed at a level, allowed at the levels above
+        for above in ExerciseLevel::value_variants()
+            .iter()
+            .filter(|l| **l > level)
+        {
+            assert!(
+                !allowed || filter_by_level(e, above),
+                "seed {}: {:?}",
+                seed,
+                e
+            );
+        }
+
+        let fits = rotation.fits(e, group, &level, &t);
+        assert_eq!(fits, rotation.fits(e, group, &level, &t), "seed {}", seed);
+        assert_eq!(
+            fits,
+            categories.contains(&e.exercise_category),
+            "seed {}: {:?} in group {} of {:?}",
+            seed,
+            e,
+            group,
+            rotation
+        );
+        if rotation.strategy == CategoryStrategy::Standard {
+            assert_eq!(
+                fits,
+                filter_by_category(e, group, &level, &t),
+                "seed {}",
+                seed
+            );
+        }
+    }
+}
+
+// --------------------------------------------------
+
+// Each seed draws a pool of exercises and a group of a workout, and the level
+// and category filters must agree with what they promise for every exercise.
+// The seeds saved to tests/fuzz_seeds.txt, e.g. by `wodgen simulate
+// --save-failing`, are replayed before the fresh ones, which follow
+// WODGEN_FUZZ_SEED when set
+#[test]
+fn test_fuzz_filters() {
+    let base = std::env::var("WODGEN_FUZZ_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or_else(rand::random::<u64>);
+    for seed in regression_seeds(REGRESSION_SEEDS) {
+        check(seed);
+    }
+    for offset in 0..FRESH_SEEDS {
+        let seed = base.wrapping_add(offset);
+        let result = std::panic::catch_unwind(|| check(seed));
+        assert!(
+            result.is_ok(),
+            "seed {} failed, add it to tests/fuzz_seeds.txt and rerun with WODGEN_FUZZ_SEED={}",
+            seed,
+            seed
+        );
+    }
+}
+
+#[test]
+fn test_regression_seeds() {
+    assert_eq!(
+        regression_seeds("# Comment\n\n42 -t pull -g 3\n7\n"),
+        [42, 7]
+    );
+}
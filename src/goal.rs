@@ -0,0 +1,212 @@
+use crate::csv_utils::{read_csv, write_csv};
+use crate::display;
+use crate::error::path_to_str;
+use crate::history::History;
+use crate::prerequisite::Prerequisite;
+use crate::substitute::find_exercise;
+use crate::units::Units;
+use crate::Exercise;
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// File in the state directory holding the goal the workouts build toward.
+pub const GOAL_FILE: &str = "goal.csv";
+
+/// How much likelier the exercises of the prerequisite chain of the goal are
+/// drawn than the others.
+pub const GOAL_WEIGHT: f64 = 3.0;
+
+// Prefix of the goals named after the first rep of an exercise, e.g.
+// `first_muscle_up`
+const FIRST_PREFIX: &str = "first_";
+
+// --------------------------------------------------
+
+/// A goal set with `wodgen goal set`: an exercise to unlock, by a day or not.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Goal {
+    /// Name the goal was set under, e.g. `first_muscle_up`
+    pub name: String,
+    /// Library name of the exercise the goal unlocks
+    pub exercise: String,
+    pub by: Option<NaiveDate>,
+    pub set_at: DateTime<Utc>,
+}
+
+// --------------------------------------------------
+
+/// Finds the exercise a goal is named after, by its library or display name,
+/// with or without the `first_` prefix, e.g. `muscle_up` for `first_muscle_up`.
+pub fn find_goal_exercise<'a>(name: &str, library: &'a [Exercise]) -> Option<&'a Exercise> {
+    find_exercise(name, library).or_else(|| {
+        name.trim()
+            .strip_prefix(FIRST_PREFIX)
+            .and_then(|name| find_exercise(name, library))
+    })
+}
+
+/// Loads the goal, none when no goal was set.
+///
+/// # Errors
+///
+/// This function will return an error if the file exists but cannot be parsed.
+pub fn load_goal(file_path: &Path) -> Result<Option<Goal>> {
+    if !file_path.exists() {
+        return Ok(None);
+    }
+    Ok(read_csv::<Goal>(path_to_str(file_path)?)?.pop())
+}
+
+/// Sets the goal, replacing the one set before.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be written.
+pub fn set_goal(file_path: &Path, goal: Goal) -> Result<()> {
+    if let Some(state_dir) = file_path.parent() {
+        fs::create_dir_all(state_dir)?;
+    }
+    info!("Set the goal {} to unlock {}", goal.name, goal.exercise);
+    write_csv(path_to_str(file_path)?, [goal])
+}
+
+/// The library names of the exercise of a goal and of those it needs, its
+/// prerequisites and theirs in turn, the exercise first.
+pub fn chain(exercise: &str, library: &[Exercise]) -> Vec<String> {
+    let mut chain = vec![exercise.to_string()];
+    let mut next = 0;
+    while let Some(name) = chain.get(next).cloned() {
+        next += 1;
+        let Some(e) = library.iter().find(|e| e.name == name) else {
+            continue;
+        };
+        for p in Prerequisite::of(e) {
+            if let Some(needed) = find_exercise(&p.exercise, library) {
+                if !chain.contains(&needed.name) {
+                    chain.push(needed.name.clone());
+                }
+            }
+        }
+    }
+    chain
+}
+
+impl Goal {
+    /// Renders the progress toward the goal: the days left, then the
+    /// prerequisites of its chain with the best sets logged toward them.
+    pub fn render(
+        &self,
+        library: &[Exercise],
+        history: &History,
+        today: NaiveDate,
+        units: Units,
+    ) -> String {
+        let mut output = format!("Goal: {}", display::library_name(&self.exercise, library));
+        if let Some(by) = self.by {
+            let days = (by - today).num_days();
+            output.push_str(&match days {
+                0.. => format!(" by {}, {} days left", by, days),
+                _ => format!(" by {}, {} days overdue", by, -days),
+            });
+        }
+        output.push('\n');
+
+        let prerequisites: Vec<Prerequisite> = chain(&self.exercise, library)
+            .iter()
+            .filter_map(|name| library.iter().find(|e| e.name == *name))
+            .flat_map(Prerequisite::of)
+            .collect();
+        let met = prerequisites
+            .iter()
+            .filter(|p| p.is_met(history, library))
+            .count();
+        if met == prerequisites.len() {
+            output.push_str("  Every prerequisite met, the goal is unlocked\n");
+            return output;
+        }
+        output.push_str(&format!(
+            "  {} of {} prerequisites met\n",
+            met,
+            prerequisites.len()
+        ));
+        for p in &prerequisites {
+            output.push_str(&format!("  {}\n", p.render(history, library, units)));
+        }
+        output
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WorkoutExercise;
+    use std::time::SystemTime;
+
+    fn exercise(name: &str, prerequisites: &[&str]) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type: crate::ExerciseType::Pull,
+            exercise_category: crate::ExerciseCategory::Primary,
+            exercise_level: crate::ExerciseLevel::Advanced,
+            exercise_programming: crate::ExerciseProgramming::Reps,
+            prerequisites: prerequisites.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_goal_chain_and_progress() {
+        let library = [
+            exercise("pull_up", &["chin_up: 5"]),
+            exercise("chin_up", &[]),
+            exercise("dip", &["pull_up: 1"]),
+            exercise("muscle_up", &["pull_up: 10", "Dip: 10"]),
+        ];
+        let goal_exercise = find_goal_exercise("first_muscle_up", &library).unwrap();
+        assert_eq!(goal_exercise.name, "muscle_up");
+        assert!(find_goal_exercise("first_planche", &library).is_none());
+        assert_eq!(
+            chain(&goal_exercise.name, &library),
+            ["muscle_up", "pull_up", "dip", "chin_up"]
+        );
+
+        let tmp = tempfile::tempdir().unwrap();
+        let file_path = tmp.path().join(GOAL_FILE);
+        assert_eq!(load_goal(&file_path).unwrap(), None);
+        let today = NaiveDate::from_ymd_opt(2024, 11, 1).unwrap();
+        let goal = Goal {
+            name: String::from("first_muscle_up"),
+            exercise: String::from("muscle_up"),
+            by: NaiveDate::from_ymd_opt(2024, 12, 1),
+            set_at: Utc::now(),
+        };
+        set_goal(&file_path, goal.clone()).unwrap();
+        assert_eq!(load_goal(&file_path).unwrap(), Some(goal.clone()));
+
+        let mut history = History::default();
+        history.record(
+            SystemTime::now(),
+            &[WorkoutExercise {
+                group: 2,
+                name: String::from("Chin Up"),
+                reps: String::from("6"),
+                ..Default::default()
+            }],
+        );
+        assert_eq!(
+            goal.render(&library, &history, today, Units::Metric),
+            "Goal: Muscle Up by 2024-12-01, 30 days left\n  \
+             1 of 4 prerequisites met\n  \
+             Pull Up: 10 reps (best: nothing logged)\n  \
+             Dip: 10 reps (best: nothing logged)\n  \
+             Chin Up: 5 reps (best: 6 reps), done\n  \
+             Pull Up: 1 rep (best: nothing logged)\n"
+        );
+    }
+}
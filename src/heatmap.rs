@@ -0,0 +1,188 @@
+use crate::chart::week_start;
+use crate::notation;
+use crate::WorkoutExercise;
+use chrono::{Datelike, NaiveDate};
+
+// Cells of the days with training, from the least to the most
+const SHADES: [char; 4] = ['░', '▒', '▓', '█'];
+
+// Cell of a day without training, and of a day still to come
+const REST: char = '·';
+const FUTURE: char = ' ';
+
+// Labels of the rows, blank for every other weekday like a contribution graph
+const WEEKDAYS: [&str; 7] = ["Mon", "", "Wed", "", "Fri", "", "Sun"];
+
+// Width of the weekday labels and of each week column
+const LABEL_WIDTH: usize = 4;
+const COLUMN_WIDTH: usize = 2;
+
+/// A calendar heatmap of the last weeks of training: how many exercises of
+/// interest were in the workouts of each day.
+#[derive(Debug)]
+pub struct Heatmap {
+    pub title: String,
+    /// Monday of the first week
+    pub start: NaiveDate,
+    pub today: NaiveDate,
+    /// Exercises of each day from `start`, up to the Sunday of this week
+    pub counts: Vec<usize>,
+}
+
+// --------------------------------------------------
+
+// The cell of a day: a rest day, or a shade of its count against the most of
+// any day
+fn cell(count: usize, max: usize) -> char {
+    if count == 0 || max == 0 {
+        return REST;
+    }
+    let level = (count * SHADES.len()).div_ceil(max);
+    SHADES[level.clamp(1, SHADES.len()) - 1]
+}
+
+// --------------------------------------------------
+
+impl Heatmap {
+    /// Builds the heatmap of the last `weeks` ISO weeks up to today's from the
+    /// saved workouts, counting the exercises `matches` keeps, warm-up sets
+    /// and session notes left out.
+    pub fn build(
+        title: String,
+        workouts: &[(NaiveDate, Vec<WorkoutExercise>)],
+        weeks: u32,
+        today: NaiveDate,
+        matches: impl Fn(&WorkoutExercise) -> bool,
+    ) -> Heatmap {
+        let start = week_start(today) - chrono::Duration::weeks(weeks as i64 - 1);
+        let mut counts = vec![0; weeks as usize * 7];
+        for (date, workout) in workouts {
+            let Ok(day) = usize::try_from((*date - start).num_days()) else {
+                continue;
+            };
+            if *date > today || day >= counts.len() {
+                continue;
+            }
+            counts[day] += workout
+                .iter()
+                .filter(|e| e.group > 1 && !notation::is_warm_up(e) && matches(e))
+                .count();
+        }
+        Heatmap {
+            title,
+            start,
+            today,
+            counts,
+        }
+    }
+
+    /// Number of days with at least one exercise of interest.
+    pub fn active_days(&self) -> usize {
+        self.counts.iter().filter(|count| **count > 0).count()
+    }
+
+    /// Renders the heatmap for the terminal: its title, the months over the
+    /// week columns, one row per weekday and a legend.
+    pub fn render(&self) -> String {
+        let weeks = self.counts.len() / 7;
+        let max = self.counts.iter().copied().max().unwrap_or(0);
+
+        // The month of each column starting in it, where it fits, the last one
+        // running past the columns
+        let mut months = " ".repeat(LABEL_WIDTH + weeks * COLUMN_WIDTH + 3);
+        let mut free = 0;
+        for week in 0..weeks {
+            let monday = self.start + chrono::Duration::weeks(week as i64);
+            let position = LABEL_WIDTH + week * COLUMN_WIDTH;
+            let starts_month = week == 0 || monday.day() <= 7;
+            if starts_month && position >= free {
+                let label = monday.format("%b").to_string();
+                months.replace_range(position..position + label.len(), &label);
+                free = position + label.len() + 1;
+            }
+        }
+
+        let mut output = format!("{}\n{}\n", self.title, months.trim_end());
+        for (weekday, label) in WEEKDAYS.iter().enumerate() {
+            let mut row = format!("{:<width$}", label, width = LABEL_WIDTH);
+            for week in 0..weeks {
+                let day = week * 7 + weekday;
+                let date = self.start + chrono::Duration::days(day as i64);
+                let cell = if date > self.today {
+                    FUTURE
+                } else {
+                    cell(self.counts[day], max)
+                };
+                row.push(cell);
+                row.push(' ');
+            }
+            output.push_str(row.trim_end());
+            output.push('\n');
+        }
+        let shades: String = SHADES.iter().map(|shade| format!("{} ", shade)).collect();
+        output.push_str(&format!(
+            "{:<width$}Less {} {}More\n",
+            "",
+            REST,
+            shades,
+            width = LABEL_WIDTH
+        ));
+        output
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workout(names: &[&str]) -> Vec<WorkoutExercise> {
+        names
+            .iter()
+            .map(|name| WorkoutExercise {
+                group: 2,
+                name: String::from(*name),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_heatmap_counts_and_renders_days() {
+        // A Wednesday
+        let today = NaiveDate::from_ymd_opt(2024, 7, 3).unwrap();
+        let date = |month, day| NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+        let workouts = vec![
+            (date(5, 1), workout(&["Squat"])),
+            (date(6, 10), workout(&["Squat", "Dip", "Plank", "Row"])),
+            (date(6, 10), workout(&["Squat"])),
+            (date(7, 1), workout(&["Dip"])),
+            (date(7, 4), workout(&["Squat"])),
+        ];
+        let heatmap = Heatmap::build(String::from("Training days"), &workouts, 4, today, |_| true);
+        assert_eq!(heatmap.start, date(6, 10));
+        assert_eq!(heatmap.counts.len(), 28);
+        assert_eq!((heatmap.counts[0], heatmap.counts[21]), (5, 1));
+        assert_eq!(heatmap.active_days(), 2);
+
+        let output = heatmap.render();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "Training days");
+        assert_eq!(lines[1], "    Jun   Jul");
+        assert_eq!(lines[2], "Mon █ · · ░");
+        // Thursday to Sunday of this week are still to come
+        assert_eq!(lines[5], "    · · ·");
+        assert_eq!(lines[9], "    Less · ░ ▒ ▓ █ More");
+
+        let squats = Heatmap::build(String::from("Squat"), &workouts, 4, today, |e| {
+            e.name == "Squat"
+        });
+        assert_eq!(squats.counts[0], 2);
+        assert_eq!(squats.active_days(), 1);
+        assert_eq!(cell(1, 2), '▒');
+        assert_eq!(cell(0, 2), REST);
+    }
+}
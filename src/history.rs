@@ -0,0 +1,532 @@
+use crate::completion::COMPLETIONS_FILE;
+use crate::csv_utils::read_csv_renaming_headers;
+use crate::error::path_to_str;
+use crate::error::WodgenError;
+use crate::export::{WorkoutFile, CSV_VERSION_PREFIX, WORKOUT_FILE_VERSION};
+use crate::i18n::{Lang, Text};
+use crate::notation;
+use crate::rounds;
+use crate::units::{Distance, Duration, Units};
+use crate::WorkoutExercise;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Rendered in place of a variable there is no history for yet
+const MISSING_VALUE: &str = "?";
+
+// Steps over the last performance hinted at: reps, seconds and share of the
+// distance
+const REPS_STEP: u32 = 1;
+const SECONDS_STEP: u32 = 5;
+const DISTANCE_STEP: f64 = 0.05;
+
+const SECONDS_PER_WEEK: u64 = 7 * 24 * 60 * 60;
+
+// --------------------------------------------------
+
+// What an athlete logged for an exercise: the latest and the best values
+#[derive(Debug, Default)]
+struct Performance {
+    last_reps: Option<u32>,
+    last_time: Option<Duration>,
+    last_distance: Option<Distance>,
+    pr_reps: Option<u32>,
+    pr_time: Option<Duration>,
+    pr_distance: Option<Distance>,
+    // Number of the latest workout the exercise appears in, counting from 1
+    last_workout: usize,
+    // Number of workouts the exercise appears in
+    appearances: usize,
+}
+
+/// The performances logged in the saved workouts, used to render the template
+/// variables in goals and to score the variety of new workouts.
+#[derive(Debug, Default)]
+pub struct History {
+    exercises: HashMap<String, Performance>,
+    first_workout: Option<SystemTime>,
+    workouts: usize,
+}
+
+// --------------------------------------------------
+
+/// Lists the saved CSV and JSON workouts in a directory, oldest first. A JSON
+/// workout saved next to the CSV of the same session is left out, as is the
+/// index of the sessions marked done.
+///
+/// # Errors
+///
+/// This function will return an error if the directory exists but cannot be read.
+pub fn saved_workouts(workouts_dir: &Path) -> Result<Vec<(SystemTime, PathBuf)>> {
+    let mut workouts = Vec::new();
+    if workouts_dir.is_dir() {
+        for entry in fs::read_dir(workouts_dir)
+            .with_context(|| format!("Failed to read directory: {:?}", workouts_dir))?
+        {
+            let path = entry?.path();
+            let saved = match path.extension().and_then(|e| e.to_str()) {
+                _ if path.ends_with(COMPLETIONS_FILE) => false,
+                Some("csv") => true,
+                Some("json") => !path.with_extension("csv").is_file(),
+                _ => false,
+            };
+            if saved {
+                workouts.push((fs::metadata(&path)?.modified()?, path));
+            }
+        }
+    }
+    workouts.sort();
+    Ok(workouts)
+}
+
+/// Loads a workout saved as JSON with its title and language.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or parsed, or
+/// if it was saved by a newer version of wodgen with a schema this one does
+/// not know.
+pub fn load_json_workout(file_path: &Path) -> Result<WorkoutFile<WorkoutExercise>> {
+    let content = fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to open file: {:?}", file_path))?;
+    let mut file: WorkoutFile<WorkoutExercise> = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse the workout: {:?}", file_path))?;
+    if file.version > WORKOUT_FILE_VERSION {
+        return Err(
+            WodgenError::UnsupportedWorkoutVersion(file_path.to_path_buf(), file.version).into(),
+        );
+    }
+    if file.version < notation::ROW_KINDS_VERSION {
+        notation::mark_warm_ups(&mut file.exercises);
+    }
+    if file.version < rounds::INTERVALS_VERSION {
+        rounds::mark_intervals(&mut file.exercises);
+    }
+    Ok(file)
+}
+
+/// Reads the version of a CSV workout from its first line, 1 for the files
+/// written before the version line.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or its
+/// version line holds no number.
+pub fn csv_workout_version(file_path: &Path) -> Result<u32> {
+    let file =
+        File::open(file_path).with_context(|| format!("Failed to open file: {:?}", file_path))?;
+    let mut line = String::new();
+    BufReader::new(file).read_line(&mut line)?;
+    match line.trim_end().strip_prefix(CSV_VERSION_PREFIX) {
+        Some(version) => version
+            .parse()
+            .with_context(|| format!("Invalid workout version {:?} in {:?}", version, file_path)),
+        None => Ok(1),
+    }
+}
+
+/// Loads a saved workout, a JSON file or a CSV file with its headers in any
+/// language, telling them apart by the extension. The columns of a CSV file
+/// are matched by their headers, so the files saved before a column was added
+/// read back with it empty.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or parsed, or
+/// if it was saved by a newer version of wodgen with a schema this one does
+/// not know.
+pub fn load_workout(file_path: &Path) -> Result<Vec<WorkoutExercise>> {
+    let workout = if file_path.extension().is_some_and(|e| e == "json") {
+        load_json_workout(file_path)?.exercises
+    } else {
+        let version = csv_workout_version(file_path)?;
+        if version > WORKOUT_FILE_VERSION {
+            return Err(
+                WodgenError::UnsupportedWorkoutVersion(file_path.to_path_buf(), version).into(),
+            );
+        }
+        let mut workout =
+            read_csv_renaming_headers::<WorkoutExercise>(path_to_str(file_path)?, |header| {
+                Lang::workout_field(header)
+            })?;
+        if version < notation::ROW_KINDS_VERSION {
+            notation::mark_warm_ups(&mut workout);
+        }
+        if version < rounds::INTERVALS_VERSION {
+            rounds::mark_intervals(&mut workout);
+        }
+        workout
+    };
+    info!("Loaded {} exercises from {:?}", workout.len(), file_path);
+    Ok(workout)
+}
+
+// --------------------------------------------------
+
+/// Returns the day a workout was generated for, read from its file name, e.g.
+/// `2024_06_01.csv` or `01_06_2024_shared.csv`, or from the time it was last
+/// modified when the name holds no date.
+pub fn workout_date(path: &Path, modified: SystemTime) -> NaiveDate {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let prefix: String = stem.chars().take(10).collect();
+    ["%Y_%m_%d", "%d_%m_%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(&prefix, format).ok())
+        .unwrap_or_else(|| DateTime::<Local>::from(modified).date_naive())
+}
+
+/// Loads the saved workouts of a directory with their dates, oldest first.
+///
+/// Workouts that cannot be parsed are skipped with a warning.
+///
+/// # Errors
+///
+/// This function will return an error if the directory cannot be read.
+pub fn dated_workouts(workouts_dir: &Path) -> Result<Vec<(NaiveDate, Vec<WorkoutExercise>)>> {
+    let mut workouts = Vec::new();
+    for (modified, path) in saved_workouts(workouts_dir)? {
+        match load_workout(&path) {
+            Ok(workout) => workouts.push((workout_date(&path, modified), workout)),
+            Err(e) => warn!("Skipping workout {:?}: {:#}", path, e),
+        }
+    }
+    workouts.sort_by_key(|(date, _)| *date);
+    Ok(workouts)
+}
+
+/// Returns the number of sets logged for an exercise, counting one per value in
+/// its reps, time or distance cell, e.g. 3 for `10/10/8`.
+pub fn logged_sets(exercise: &WorkoutExercise) -> usize {
+    [&exercise.reps, &exercise.time, &exercise.distance]
+        .iter()
+        .map(|cell| {
+            cell.split([',', ';', '/'])
+                .filter(|value| {
+                    let value = value.trim();
+                    value.parse::<u32>().is_ok()
+                        || value.parse::<Duration>().is_ok()
+                        || value.parse::<Distance>().is_ok()
+                })
+                .count()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+// --------------------------------------------------
+
+/// Parses the best value logged in a cell, e.g. "12" or "10/10/8" for one per set.
+pub fn best_value<T: std::str::FromStr + PartialOrd>(cell: &str) -> Option<T> {
+    cell.split([',', ';', '/'])
+        .filter_map(|value| value.trim().parse::<T>().ok())
+        .fold(None, |best, value| match best {
+            Some(best) if best >= value => Some(best),
+            _ => Some(value),
+        })
+}
+
+// Keep the larger of the personal record and a new value
+fn personal_record<T: PartialOrd>(record: Option<T>, value: Option<T>) -> Option<T> {
+    match (record, value) {
+        (Some(record), Some(value)) if value > record => Some(value),
+        (None, value) => value,
+        (record, _) => record,
+    }
+}
+
+// --------------------------------------------------
+
+impl History {
+    /// Loads the history from the workouts saved in a directory.
+    ///
+    /// Workouts that cannot be parsed, e.g. because they were edited by hand,
+    /// are skipped with a warning rather than failing the generation.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory cannot be read.
+    pub fn load(workouts_dir: &Path) -> Result<History> {
+        let mut history = History::default();
+        let workouts = saved_workouts(workouts_dir)?;
+        for (modified, path) in &workouts {
+            match load_workout(path) {
+                Ok(workout) => history.record(*modified, &workout),
+                Err(e) => warn!("Skipping workout {:?} in the history: {:#}", path, e),
+            }
+        }
+        info!("Loaded the history of {} workouts", workouts.len());
+        Ok(history)
+    }
+
+    /// Records the values logged in a workout, workouts must come oldest first.
+    pub fn record(&mut self, modified: SystemTime, workout: &[WorkoutExercise]) {
+        self.first_workout.get_or_insert(modified);
+        self.workouts += 1;
+        for e in workout {
+            let performance = self.exercises.entry(e.name.clone()).or_default();
+            if performance.last_workout != self.workouts {
+                performance.appearances += 1;
+            }
+            performance.last_workout = self.workouts;
+            let reps = best_value::<u32>(&e.reps);
+            let time = best_value::<Duration>(&e.time);
+            let distance = best_value::<Distance>(&e.distance);
+            performance.last_reps = reps.or(performance.last_reps);
+            performance.last_time = time.or(performance.last_time);
+            performance.last_distance = distance.or(performance.last_distance);
+            performance.pr_reps = personal_record(performance.pr_reps, reps);
+            performance.pr_time = personal_record(performance.pr_time, time);
+            performance.pr_distance = personal_record(performance.pr_distance, distance);
+        }
+    }
+
+    /// Whether the exercise with the given workout name appears in one of the
+    /// latest `workouts` saved workouts.
+    pub fn is_recent(&self, exercise: &str, workouts: usize) -> bool {
+        self.exercises
+            .get(exercise)
+            .is_some_and(|p| p.last_workout + workouts > self.workouts)
+    }
+
+    /// The best reps, time and distance logged in a set of the exercise with
+    /// the given workout name.
+    pub fn records(&self, exercise: &str) -> (Option<u32>, Option<Duration>, Option<Distance>) {
+        self.exercises
+            .get(exercise)
+            .map_or((None, None, None), |p| {
+                (p.pr_reps, p.pr_time, p.pr_distance)
+            })
+    }
+
+    /// Number of saved workouts the exercise with the given workout name
+    /// appears in.
+    pub fn appearances(&self, exercise: &str) -> usize {
+        self.exercises.get(exercise).map_or(0, |p| p.appearances)
+    }
+
+    /// Hints at one step over the last performance logged for the exercise of
+    /// a row, in the column the row prescribes: `+1 rep vs last time`, `+5s`
+    /// or 5% more distance. None when nothing was logged for it yet.
+    pub fn overload_hint(&self, row: &WorkoutExercise, lang: Lang, units: Units) -> Option<String> {
+        let performance = self.exercises.get(&row.name)?;
+        let step = if !row.reps.is_empty() {
+            performance.last_reps?;
+            format!("+{} {}", REPS_STEP, lang.text(Text::Rep))
+        } else if !row.time.is_empty() {
+            performance.last_time?;
+            format!("+{}", Duration::from_seconds(SECONDS_STEP).render())
+        } else if !row.distance.is_empty() {
+            let last = performance.last_distance?;
+            format!(
+                "+{}",
+                Distance::from_meters(last.meters() * DISTANCE_STEP).render(units)
+            )
+        } else {
+            return None;
+        };
+        Some(format!("{} {}", step, lang.text(Text::VsLastTime)))
+    }
+
+    // Training week, counted from the first saved workout
+    fn week(&self) -> u64 {
+        let elapsed = self
+            .first_workout
+            .and_then(|first| SystemTime::now().duration_since(first).ok())
+            .map_or(0, |elapsed| elapsed.as_secs());
+        elapsed / SECONDS_PER_WEEK + 1
+    }
+
+    // Value of a template variable, None if the variable does not exist
+    fn variable(&self, name: &str, exercise: &str, units: Units) -> Option<String> {
+        let performance = self.exercises.get(exercise);
+        let value = match name {
+            "week" => Some(self.week().to_string()),
+            "last_reps" => performance.and_then(|p| p.last_reps).map(|r| r.to_string()),
+            "pr_reps" => performance.and_then(|p| p.pr_reps).map(|r| r.to_string()),
+            "last_time" => performance.and_then(|p| p.last_time).map(|t| t.render()),
+            "pr_time" => performance.and_then(|p| p.pr_time).map(|t| t.render()),
+            "last_distance" => performance
+                .and_then(|p| p.last_distance)
+                .map(|d| d.render(units)),
+            "pr_distance" => performance
+                .and_then(|p| p.pr_distance)
+                .map(|d| d.render(units)),
+            _ => return None,
+        };
+        Some(value.unwrap_or_else(|| String::from(MISSING_VALUE)))
+    }
+
+    /// Renders the template variables in a goal, e.g. `beat {pr_reps}`, for the
+    /// exercise with the given workout name.
+    ///
+    /// Variables without history yet render as `?`, unknown ones are kept as
+    /// they are so typos stay visible in the workout.
+    pub fn render(&self, template: &str, exercise: &str, units: Units) -> String {
+        let mut output = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let Some(length) = rest[start..].find('}') else {
+                break;
+            };
+            let end = start + length;
+            output.push_str(&rest[..start]);
+            match self.variable(&rest[start + 1..end], exercise, units) {
+                Some(value) => output.push_str(&value),
+                None => {
+                    warn!("Unknown template variable {:?}", &rest[start..=end]);
+                    output.push_str(&rest[start..=end]);
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        output.push_str(rest);
+        output
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logged(name: &str, reps: &str, time: &str) -> WorkoutExercise {
+        WorkoutExercise {
+            group: 2,
+            name: String::from(name),
+            reps: String::from(reps),
+            time: String::from(time),
+            ..Default::default()
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_render_template_variables() {
+        let mut history = History::default();
+        history.record(
+            SystemTime::now(),
+            &[logged("Pull-up", "10/9/8", ""), logged("Plank", "", "1:30")],
+        );
+        history.record(
+            SystemTime::now(),
+            &[logged("Pull-up", "9", ""), logged("Plank", "", "X")],
+        );
+
+        let render = |template, exercise| history.render(template, exercise, Units::Metric);
+        assert_eq!(render("{last_reps}+1", "Pull-up"), "9+1");
+        assert_eq!(render("beat {pr_reps}", "Pull-up"), "beat 10");
+        assert_eq!(render("{last_time} / {pr_time}", "Plank"), "1:30 / 1:30");
+        assert_eq!(render("{pr_reps}", "Dip"), "?");
+        assert_eq!(render("week {week}", "Dip"), "week 1");
+        assert_eq!(render("{typo} {", "Dip"), "{typo} {");
+
+        let row = |name, reps, time| WorkoutExercise {
+            reps: String::from(reps),
+            time: String::from(time),
+            ..logged(name, "", "")
+        };
+        let hint = |row| history.overload_hint(&row, Lang::En, Units::Metric);
+        assert_eq!(
+            hint(row("Pull-up", "X", "")).as_deref(),
+            Some("+1 rep vs last time")
+        );
+        assert_eq!(
+            hint(row("Plank", "", "X")).as_deref(),
+            Some("+5s vs last time")
+        );
+        assert_eq!(hint(row("Pull-up", "", "X")), None);
+        assert_eq!(hint(row("Dip", "X", "")), None);
+
+        assert!(history.is_recent("Pull-up", 1));
+        assert_eq!(history.appearances("Pull-up"), 2);
+        assert!(!history.is_recent("Dip", 3));
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_workouts_round_trip() {
+        use crate::export::{render_csv, render_json};
+        use crate::i18n::Lang;
+        use crate::ExerciseProgramming;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let workout = vec![
+            WorkoutExercise {
+                name: String::from("Note"),
+                notes: String::from("slow"),
+                ..Default::default()
+            },
+            logged("Pull-up", "10/9/8", ""),
+            WorkoutExercise {
+                programming: Some(ExerciseProgramming::Time),
+                interval: Some(rounds::Interval {
+                    work: 40,
+                    transition: 20,
+                }),
+                ..logged("Plank", "", "X")
+            },
+            // Named like a warm-up set in English, only its kind says it is one
+            WorkoutExercise {
+                kind: notation::RowKind::WarmUp,
+                ..logged("Pull-up", "", "")
+            },
+            logged("Warm-up", "", ""),
+        ];
+        let csv = tmp.path().join("2024_06_01.csv");
+        fs::write(&csv, render_csv(&workout, Lang::Fr).unwrap()).unwrap();
+        assert_eq!(csv_workout_version(&csv).unwrap(), WORKOUT_FILE_VERSION);
+        assert_eq!(load_workout(&csv).unwrap(), workout);
+
+        let json = tmp.path().join("2024_06_02.json");
+        fs::write(
+            &json,
+            render_json(&workout, "2024_06_02", Lang::Fr).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(load_workout(&json).unwrap(), workout);
+
+        let newer = tmp.path().join("2024_06_03.json");
+        fs::write(&newer, r#"{"version": 99, "title": "t", "exercises": []}"#).unwrap();
+        assert!(load_workout(&newer).is_err());
+        let newer = tmp.path().join("2024_06_04.csv");
+        fs::write(&newer, "# wodgen workout 99\ngroup,name\n").unwrap();
+        assert!(load_workout(&newer).is_err());
+        // Saved before the labels, the images, the scaling and the notes
+        let baseline = tmp.path().join("2024_05_31.csv");
+        fs::write(
+            &baseline,
+            "group,name,sets,distance,time,reps,goal,video\n2,Échauffement,1,,,,,\n2,Pull-up,,,,10/9/8,,pull_up.mp4\n",
+        )
+        .unwrap();
+        assert_eq!(
+            load_workout(&baseline).unwrap(),
+            [
+                WorkoutExercise {
+                    sets: String::from("1"),
+                    kind: notation::RowKind::WarmUp,
+                    ..logged("Échauffement", "", "")
+                },
+                WorkoutExercise {
+                    video: String::from("pull_up.mp4"),
+                    ..logged("Pull-up", "10/9/8", "")
+                }
+            ]
+        );
+
+        // The newer workout is skipped, the JSON copy of a CSV workout too
+        fs::write(tmp.path().join("2024_06_01.json"), "{}").unwrap();
+        assert_eq!(dated_workouts(tmp.path()).unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,276 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+// Enum for the languages the generated workouts can be written in
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Lang {
+    #[default]
+    En,
+    De,
+    Fr,
+    Es,
+}
+
+// Enum for the translatable pieces of text in the generated output
+#[derive(Debug, Clone, Copy)]
+pub enum Text {
+    Group,
+    Name,
+    Sets,
+    Distance,
+    Time,
+    Reps,
+    Goal,
+    Image,
+    Video,
+    SkillBlock,
+    Workout,
+    Scaling,
+    Beginner,
+    Intermediate,
+    Advanced,
+    Notes,
+    Note,
+    Finisher,
+    Minutes,
+    YouGoIGo,
+    Shared,
+    Partners,
+    Playground,
+    GrandFinale,
+    Energy,
+    RoundsOf,
+    Total,
+    WarmUp,
+    Rep,
+    VsLastTime,
+    Sore,
+    On,
+    Transition,
+    TestDay,
+    MaxReps,
+    MaxHold,
+    MaxDistance,
+}
+
+// Translation tables, indexed by `Text`
+const EN: [&str; 37] = [
+    "group",
+    "name",
+    "sets",
+    "distance",
+    "time",
+    "reps",
+    "goal",
+    "image",
+    "video",
+    "Skill Block",
+    "Workout",
+    "scaling",
+    "Beginner",
+    "Intermediate",
+    "Advanced",
+    "notes",
+    "Note",
+    "Finisher",
+    "Minutes",
+    "You go, I go",
+    "Shared, split the work",
+    "partners",
+    "Playground",
+    "Grand Finale",
+    "Estimated energy",
+    "rounds of",
+    "total",
+    "Warm-up",
+    "rep",
+    "vs last time",
+    "Sore",
+    "on",
+    "transition",
+    "Test Day",
+    "max reps in one set",
+    "max hold",
+    "max distance",
+];
+const DE: [&str; 37] = [
+    "gruppe",
+    "name",
+    "sätze",
+    "distanz",
+    "zeit",
+    "wiederholungen",
+    "ziel",
+    "bild",
+    "video",
+    "Technikblock",
+    "Training",
+    "skalierung",
+    "Anfänger",
+    "Fortgeschritten",
+    "Profi",
+    "notizen",
+    "Notiz",
+    "Finisher",
+    "Minuten",
+    "Abwechselnd",
+    "Gemeinsam, Arbeit aufteilen",
+    "Partner",
+    "Spielplatz",
+    "Großes Finale",
+    "Geschätzter Energieverbrauch",
+    "Runden von",
+    "gesamt",
+    "Aufwärmsatz",
+    "Wdh.",
+    "ggü. letztem Mal",
+    "Muskelkater",
+    "Arbeit",
+    "Wechsel",
+    "Testtag",
+    "max. Wiederholungen am Stück",
+    "max. Haltezeit",
+    "max. Distanz",
+];
+const FR: [&str; 37] = [
+    "groupe",
+    "nom",
+    "séries",
+    "distance",
+    "temps",
+    "répétitions",
+    "objectif",
+    "image",
+    "vidéo",
+    "Bloc technique",
+    "Séance",
+    "adaptation",
+    "Débutant",
+    "Intermédiaire",
+    "Avancé",
+    "notes",
+    "Note",
+    "Finisher",
+    "Minutes",
+    "Chacun son tour",
+    "En commun, partagez le travail",
+    "partenaires",
+    "Terrain de jeu",
+    "Grand final",
+    "Énergie estimée",
+    "tours de",
+    "total",
+    "Échauffement",
+    "rép.",
+    "par rapport à la dernière fois",
+    "Courbatures",
+    "d'effort",
+    "de transition",
+    "Journée de test",
+    "max. de répétitions d'affilée",
+    "tenue max.",
+    "distance max.",
+];
+const ES: [&str; 37] = [
+    "grupo",
+    "nombre",
+    "series",
+    "distancia",
+    "tiempo",
+    "repeticiones",
+    "objetivo",
+    "imagen",
+    "vídeo",
+    "Bloque de técnica",
+    "Entrenamiento",
+    "escalado",
+    "Principiante",
+    "Intermedio",
+    "Avanzado",
+    "notas",
+    "Nota",
+    "Finalizador",
+    "Minutos",
+    "Por turnos",
+    "Compartido, repartid el trabajo",
+    "compañeros",
+    "Patio de juegos",
+    "Gran final",
+    "Energía estimada",
+    "rondas de",
+    "total",
+    "Calentamiento",
+    "rep.",
+    "respecto a la última vez",
+    "Agujetas",
+    "de trabajo",
+    "de transición",
+    "Día de test",
+    "máx. repeticiones seguidas",
+    "sostén máximo",
+    "distancia máxima",
+];
+
+/// Column headers of a saved workout, in order.
+pub const WORKOUT_HEADERS: [Text; 11] = [
+    Text::Group,
+    Text::Name,
+    Text::Sets,
+    Text::Distance,
+    Text::Time,
+    Text::Reps,
+    Text::Goal,
+    Text::Image,
+    Text::Video,
+    Text::Scaling,
+    Text::Notes,
+];
+
+// --------------------------------------------------
+
+impl Lang {
+    /// Returns the translation of `text` in this language.
+    pub fn text(&self, text: Text) -> &'static str {
+        let table = match self {
+            Lang::En => &EN,
+            Lang::De => &DE,
+            Lang::Fr => &FR,
+            Lang::Es => &ES,
+        };
+        table[text as usize]
+    }
+
+    /// Returns the translated column headers of a saved workout.
+    pub fn workout_headers(&self) -> Vec<&'static str> {
+        WORKOUT_HEADERS.iter().map(|t| self.text(*t)).collect()
+    }
+
+    /// Returns the field of a saved workout a column header stands for, in any
+    /// language, e.g. `group` for `gruppe`. None for any other header.
+    pub fn workout_field(header: &str) -> Option<&'static str> {
+        let header = header.trim().to_lowercase();
+        Lang::value_variants().iter().find_map(|lang| {
+            WORKOUT_HEADERS
+                .iter()
+                .find(|text| lang.text(**text) == header)
+                .map(|text| Lang::En.text(*text))
+        })
+    }
+
+    /// Detects the language of a saved workout from its first column header,
+    /// e.g. `gruppe` for German.
+    pub fn from_header(header: &str) -> Option<Lang> {
+        Lang::value_variants()
+            .iter()
+            .find(|lang| lang.text(Text::Group) == header.trim())
+            .copied()
+    }
+
+    /// Returns the `chrono` format string used for dates in file names.
+    pub fn date_format(&self) -> &'static str {
+        match self {
+            Lang::En => "%Y_%m_%d",
+            Lang::De | Lang::Fr | Lang::Es => "%d_%m_%Y",
+        }
+    }
+}
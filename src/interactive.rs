@@ -0,0 +1,202 @@
+use crate::calibration::Levels;
+use crate::explain::Pick;
+use crate::notation;
+use crate::random;
+use crate::template::Template;
+use crate::units::Units;
+use crate::{display, Exercise, WorkoutExercise};
+use anyhow::Result;
+use rand::seq::SliceRandom;
+use std::io::{BufRead, Write};
+
+// Enum for the answers to the prompt shown for each exercise
+#[derive(Debug, PartialEq)]
+enum Answer {
+    Accept,
+    Reroll,
+    Skip,
+}
+
+/// Reviews the picked exercises one by one before the workout is saved.
+#[derive(Debug)]
+pub struct Review<'a> {
+    /// The filtered exercises the picks were drawn from
+    pub pool: &'a [Exercise],
+    pub template: &'a Template,
+    pub levels: &'a Levels,
+    pub units: Units,
+}
+
+// --------------------------------------------------
+
+// Prompt until a valid answer is given, the end of the input accepts
+fn ask<R: BufRead, W: Write>(input: &mut R, output: &mut W, prompt: &str) -> Result<Answer> {
+    loop {
+        write!(output, "{} [a]ccept / [r]eroll / [s]kip: ", prompt)?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            writeln!(output)?;
+            return Ok(Answer::Accept);
+        }
+        match line.trim().to_lowercase().as_str() {
+            "" | "a" | "accept" => return Ok(Answer::Accept),
+            "r" | "reroll" => return Ok(Answer::Reroll),
+            "s" | "skip" => return Ok(Answer::Skip),
+            _ => writeln!(output, "Please answer a, r or s")?,
+        }
+    }
+}
+
+// --------------------------------------------------
+
+impl Review<'_> {
+    // Pick another exercise that satisfies the same filters as `pick`
+    fn reroll(
+        &self,
+        pick: &Pick,
+        workout: &[WorkoutExercise],
+        rejected: &[String],
+    ) -> Option<&Exercise> {
+        let block = &self.template.blocks[pick.block];
+        let candidates: Vec<&Exercise> = self
+            .pool
+            .iter()
+            .filter(|e| pick.slot.accepts(e))
+            .filter(|e| self.levels.allows(e))
+            .filter(|e| block.accepts(e))
+            .filter(|e| {
+                pick.slot.fits(
+                    e,
+                    pick.group,
+                    self.levels.of(&pick.slot.exercise_type),
+                    &self.template.rotation(),
+                )
+            })
+            .filter(|e| !rejected.contains(&e.name))
+            .filter(|e| !workout.iter().any(|w| w.name == display::exercise_name(e)))
+            .collect();
+        random::with_rng(|rng| candidates.choose(rng).copied())
+    }
+
+    /// Asks whether to accept, reroll or skip each picked exercise, updating the
+    /// workout and the picks with the answers.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the input cannot be read or the
+    /// prompt cannot be written.
+    pub fn run<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        workout: &mut Vec<WorkoutExercise>,
+        picks: &mut Vec<Pick>,
+    ) -> Result<()> {
+        let mut rejected = Vec::new();
+        let mut index = 0;
+        while index < picks.len() {
+            let pick = &picks[index];
+            let group = pick.group + 2;
+            let name = display::library_name(&pick.name, self.pool);
+            let Some(row) = workout
+                .iter()
+                .position(|w| w.group == group && w.name == name)
+            else {
+                index += 1;
+                continue;
+            };
+            let prompt = format!(
+                "Group {}, {}: {}",
+                notation::letter(group),
+                pick.slot.label(),
+                name
+            );
+            match ask(input, output, &prompt)? {
+                Answer::Accept => index += 1,
+                Answer::Skip => {
+                    workout.remove(row);
+                    picks.remove(index);
+                }
+                Answer::Reroll => match self.reroll(pick, workout, &rejected) {
+                    Some(exercise) => {
+                        rejected.push(pick.name.clone());
+                        workout[row] = WorkoutExercise::from_exercise(group, exercise, self.units);
+                        picks[index].name = exercise.name.clone();
+                    }
+                    None => writeln!(output, "No other exercise fits this group")?,
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExerciseLevel, ExerciseType};
+    use std::io::Cursor;
+
+    fn exercise(name: &str, exercise_type: ExerciseType) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type,
+            exercise_level: ExerciseLevel::Beginner,
+            ..Default::default()
+        }
+    }
+
+    fn pick(name: &str, exercise_type: ExerciseType) -> Pick {
+        Pick {
+            group: 0,
+            block: 0,
+            slot: exercise_type.into(),
+            name: String::from(name),
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_review() {
+        let pool = vec![
+            exercise("dip", ExerciseType::Push),
+            exercise("push_up", ExerciseType::Push),
+            exercise("pull_up", ExerciseType::Pull),
+        ];
+        let template =
+            Template::from_types(&[ExerciseType::Push.into(), ExerciseType::Pull.into()], 1);
+        let review = Review {
+            pool: &pool,
+            template: &template,
+            levels: &Levels::from(ExerciseLevel::Intermediate),
+            units: Units::Metric,
+        };
+        let mut workout = vec![
+            WorkoutExercise::from_exercise(2, &pool[0], Units::Metric),
+            WorkoutExercise::from_exercise(2, &pool[2], Units::Metric),
+        ];
+        let mut picks = vec![
+            pick("dip", ExerciseType::Push),
+            pick("pull_up", ExerciseType::Pull),
+        ];
+
+        // Reroll the dip, reroll again with nothing left, accept and skip the pull up
+        let mut input = Cursor::new("r\nr\nx\na\ns\n");
+        let mut output = Vec::new();
+        review
+            .run(&mut input, &mut output, &mut workout, &mut picks)
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("No other exercise fits this group"));
+        assert!(output.contains("Please answer a, r or s"));
+        assert_eq!(workout.len(), 1);
+        assert_eq!(workout[0].name, "Push Up");
+        assert_eq!(picks.len(), 1);
+        assert_eq!(picks[0].name, "push_up");
+    }
+}
@@ -0,0 +1,271 @@
+use crate::csv_utils::{read_csv, writer_builder};
+use crate::error::{path_to_str, WodgenError};
+use crate::{Exercise, ExerciseType};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Columns of an exercise CSV file, in the order they are written
+const EXERCISE_COLUMNS: [&str; 21] = [
+    "name",
+    "display_name",
+    "exercise_type",
+    "exercise_category",
+    "exercise_level",
+    "exercise_programming",
+    "bodyweight",
+    "goal",
+    "image",
+    "video",
+    "progression",
+    "pattern",
+    "muscles",
+    "cue",
+    "tags",
+    "met",
+    "equipment",
+    "stretches",
+    "prerequisites",
+    "testable",
+    "active",
+];
+
+// --------------------------------------------------
+
+// Enum for the formats an exercise library file can be written in
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LibraryFormat {
+    Csv,
+    Toml,
+    Json,
+}
+
+/// The layout of TOML and JSON library files, a list of exercises under an
+/// `exercises` key.
+#[derive(Debug, Serialize, Deserialize)]
+struct LibraryFile {
+    exercises: Vec<Exercise>,
+}
+
+// --------------------------------------------------
+
+impl LibraryFormat {
+    /// Detects the format of a library file from its extension.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WodgenError::UnknownLibraryFormat` for any extension other than
+    /// `csv`, `toml` and `json`.
+    pub fn from_path(path: &Path) -> Result<LibraryFormat, WodgenError> {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .as_deref()
+        {
+            Some("csv") => Ok(LibraryFormat::Csv),
+            Some("toml") => Ok(LibraryFormat::Toml),
+            Some("json") => Ok(LibraryFormat::Json),
+            _ => Err(WodgenError::UnknownLibraryFormat(path.to_path_buf())),
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            LibraryFormat::Csv => "csv",
+            LibraryFormat::Toml => "toml",
+            LibraryFormat::Json => "json",
+        }
+    }
+}
+
+// --------------------------------------------------
+
+/// Finds a library file in any of the supported formats, e.g. `push.toml` for
+/// `push.csv`. CSV is preferred when several exist, and the CSV path is returned
+/// when none does.
+pub fn find_file(csv_path: &Path) -> PathBuf {
+    [LibraryFormat::Csv, LibraryFormat::Toml, LibraryFormat::Json]
+        .iter()
+        .map(|format| csv_path.with_extension(format.extension()))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| csv_path.to_path_buf())
+}
+
+// --------------------------------------------------
+
+/// Reads the exercises of a library file in the format given by its extension.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or parsed.
+pub fn read_exercises(file_path: &Path) -> Result<Vec<Exercise>> {
+    let exercises = match LibraryFormat::from_path(file_path)? {
+        LibraryFormat::Csv => read_csv::<Exercise>(path_to_str(file_path)?)?,
+        LibraryFormat::Toml => {
+            let content = read_file(file_path)?;
+            toml::from_str::<LibraryFile>(&content)
+                .with_context(|| format!("Failed to parse {:?}", file_path))?
+                .exercises
+        }
+        LibraryFormat::Json => {
+            let content = read_file(file_path)?;
+            serde_json::from_str::<LibraryFile>(&content)
+                .with_context(|| format!("Failed to parse {:?}", file_path))?
+                .exercises
+        }
+    };
+    Ok(exercises)
+}
+
+fn read_file(file_path: &Path) -> Result<String> {
+    fs::read_to_string(file_path).with_context(|| format!("Failed to read file: {:?}", file_path))
+}
+
+/// Hex digest of the SHA-256 of the names and contents of the library files,
+/// each once, so any change to the library shows, e.g. `2997c347…`.
+///
+/// # Errors
+///
+/// This function will return an error if a file cannot be read.
+pub fn content_hash(file_paths: &HashMap<ExerciseType, PathBuf>) -> Result<String> {
+    let mut files: Vec<&PathBuf> = file_paths.values().filter(|path| path.is_file()).collect();
+    files.sort();
+    files.dedup();
+    let mut hasher = Sha256::new();
+    for file in files {
+        let content = fs::read(file).with_context(|| format!("Failed to read file: {:?}", file))?;
+        hasher.update(file.file_name().unwrap_or_default().as_encoded_bytes());
+        hasher.update(b"\n");
+        hasher.update(&content);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+// --------------------------------------------------
+
+// Render a field of an exercise as a CSV cell, lists are joined with `;`
+fn csv_cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| csv_cell(Some(item)))
+            .collect::<Vec<String>>()
+            .join(";"),
+        Some(other) => other.to_string(),
+    }
+}
+
+// Write exercises as CSV rows
+fn write_csv_exercises(file_path: &Path, exercises: &[Exercise]) -> Result<()> {
+    let mut wtr = writer_builder()
+        .from_path(file_path)
+        .with_context(|| format!("Failed to create CSV writer for file: {:?}", file_path))?;
+    wtr.write_record(EXERCISE_COLUMNS)?;
+    for exercise in exercises {
+        let value = serde_json::to_value(exercise)?;
+        wtr.write_record(EXERCISE_COLUMNS.iter().map(|c| csv_cell(value.get(c))))?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Writes exercises to a library file in the format given by its extension.
+///
+/// # Errors
+///
+/// This function will return an error if the format is unknown or the file
+/// cannot be written.
+pub fn write_exercises(file_path: &Path, exercises: Vec<Exercise>) -> Result<()> {
+    let content = match LibraryFormat::from_path(file_path)? {
+        LibraryFormat::Csv => return write_csv_exercises(file_path, &exercises),
+        LibraryFormat::Toml => toml::to_string_pretty(&LibraryFile { exercises })?,
+        LibraryFormat::Json => serde_json::to_string_pretty(&LibraryFile { exercises })? + "\n",
+    };
+    fs::write(file_path, content)
+        .with_context(|| format!("Failed to write file: {:?}", file_path))?;
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSV: &str = "name,exercise_type,exercise_category,exercise_level,exercise_programming,bodyweight,goal,video,muscles\n\
+                       dip,Push,Primary,Beginner,Reps,true,15,dip.mp4,chest;triceps\n\
+                       push_up,Push,Secondary,Beginner,Reps,true,,,\n";
+
+    #[test]
+    fn test_content_hash_changes_with_the_library() {
+        let tmp = tempfile::tempdir().unwrap();
+        let csv_path = tmp.path().join("push.csv");
+        fs::write(&csv_path, CSV).unwrap();
+        let file_paths = HashMap::from([
+            (ExerciseType::Push, csv_path.clone()),
+            (ExerciseType::Pull, csv_path.clone()),
+            (ExerciseType::Core, tmp.path().join("core.csv")),
+        ]);
+        let hash = content_hash(&file_paths).unwrap();
+        assert_eq!(hash.len(), 64);
+        // Shared and missing files change nothing
+        let push = HashMap::from([(ExerciseType::Push, csv_path.clone())]);
+        assert_eq!(content_hash(&push).unwrap(), hash);
+
+        fs::write(
+            &csv_path,
+            format!("{}row,Pull,Primary,Beginner,Reps,true,,,\n", CSV),
+        )
+        .unwrap();
+        assert_ne!(content_hash(&file_paths).unwrap(), hash);
+    }
+
+    #[test]
+    fn test_round_trip_through_every_format() {
+        let tmp = tempfile::tempdir().unwrap();
+        let csv_path = tmp.path().join("push.csv");
+        fs::write(&csv_path, CSV).unwrap();
+        let mut exercises = read_exercises(&csv_path).unwrap();
+        // Active without the column, retired ones stay so
+        assert!(exercises.iter().all(|e| e.active));
+        exercises[1].active = false;
+
+        for extension in ["toml", "json", "csv"] {
+            let path = tmp.path().join(format!("converted.{}", extension));
+            write_exercises(&path, exercises.clone()).unwrap();
+            let converted = read_exercises(&path).unwrap();
+            assert_eq!(converted.len(), 2);
+            assert_eq!(converted[0].muscles, ["chest", "triceps"]);
+            assert_eq!(converted[0].goal.as_deref(), Some("15"));
+            assert!(converted[1].goal.is_none());
+            assert!(converted[0].active && !converted[1].active);
+        }
+
+        let toml = fs::read_to_string(tmp.path().join("converted.toml")).unwrap();
+        assert!(toml.contains("[[exercises]]"));
+        assert!(toml.contains("muscles = [\n    \"chest\",\n    \"triceps\",\n]"));
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_find_file_in_any_format() {
+        let tmp = tempfile::tempdir().unwrap();
+        let csv_path = tmp.path().join("core.csv");
+        assert_eq!(find_file(&csv_path), csv_path);
+        fs::write(tmp.path().join("core.json"), "{\"exercises\": []}").unwrap();
+        assert_eq!(find_file(&csv_path), tmp.path().join("core.json"));
+        assert!(LibraryFormat::from_path(Path::new("core.xml")).is_err());
+    }
+}
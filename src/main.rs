@@ -1,17 +1,92 @@
+mod backup;
+mod benchmark;
+mod bot;
+mod bundle;
+mod caldav;
+mod calibration;
+mod category;
+mod chart;
+mod completion;
+mod config;
+mod constraints;
 mod csv_utils;
+mod diff;
+mod display;
+mod email;
+mod energy;
+mod equipment;
+mod ergometer;
+mod error;
+mod explain;
+mod export;
+#[cfg(test)]
+mod fuzz;
+mod goal;
+mod heatmap;
+mod history;
+mod i18n;
+mod interactive;
+mod library;
+mod media;
+mod naming;
+mod notation;
+mod novelty;
+mod outcome;
+mod pacing;
+mod paths;
+mod pdf;
+mod plates;
+mod plugin;
+mod png;
+mod preflight;
+mod prerequisite;
+mod profile;
+mod random;
+mod report;
+mod roster;
+mod rounds;
+mod run;
+mod score;
+mod script;
+mod share;
+mod simulate;
+mod skill;
+mod snooze;
+mod soreness;
+mod split;
+mod status;
+mod substitute;
+mod sync;
+mod taxonomy;
+mod template;
+mod test_day;
+mod training_max;
+mod units;
+mod volume;
+mod wellness;
+mod wizard;
 
-use crate::csv_utils::{read_csv, write_csv};
-use anyhow::Result;
-use chrono::Local;
+use crate::csv_utils::{read_csv, read_headers, write_csv};
+use crate::display::to_title_case;
+use crate::error::{path_to_str, WodgenError};
+use crate::export::Format;
+use crate::i18n::{Lang, Text};
+use crate::template::Template;
+use crate::units::{Distance, Duration, Units};
+use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use clap::Parser;
-use log::info;
+use chrono::{Datelike, Local, NaiveDate};
+use clap::{Parser, Subcommand, ValueEnum};
+use log::{info, warn};
 use rand::seq::SliceRandom;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use simplelog::*;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 // --------------------------------------------------
 
@@ -21,8 +96,12 @@ const CORE_FILE: &str = "core.csv";
 const LEGS_FILE: &str = "legs.csv";
 const PULL_FILE: &str = "pull.csv";
 const PUSH_FILE: &str = "push.csv";
+const EXERCISES_FILE: &str = "exercises.csv";
 const SNOOZED_FILE: &str = "snoozed.csv";
 
+// Most super-sets in a workout, past which only accessories are left to pick
+const MAX_GROUPS: u32 = 8;
+
 const SNOOZE_PERIOD: i64 = 7; // Snooze period in days
 
 // --------------------------------------------------
@@ -38,23 +117,26 @@ enum ExerciseType {
 }
 
 // Enum for different exercise categories
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, clap::ValueEnum)]
 enum ExerciseCategory {
     Primary,
     Secondary,
     Accessory,
 }
 
-// Enum for different exercise levels
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, clap::ValueEnum)]
+// Enum for different exercise levels, ordered from easiest to hardest
+#[derive(
+    Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, clap::ValueEnum,
+)]
 enum ExerciseLevel {
     Beginner,
+    #[default]
     Intermediate,
     Advanced,
 }
 
 // Enum for different exercise programming types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 enum ExerciseProgramming {
     Distance,
     Reps,
@@ -99,13 +181,76 @@ enum ExerciseProgramming {
 #[allow(dead_code)]
 struct Exercise {
     name: String,
+    // Shown in place of the name pretty printed, e.g. `GHD Sit-up`
+    #[serde(default)]
+    display_name: Option<String>,
     exercise_type: ExerciseType,
     exercise_category: ExerciseCategory,
     exercise_level: ExerciseLevel,
     exercise_programming: ExerciseProgramming,
     bodyweight: bool,
     goal: Option<String>,
+    #[serde(default)]
+    image: Option<String>,
     video: String,
+    #[serde(default)]
+    progression: Option<String>,
+    #[serde(default)]
+    pattern: Option<String>,
+    #[serde(default, deserialize_with = "csv_utils::deserialize_list")]
+    muscles: Vec<String>,
+    #[serde(default)]
+    cue: Option<String>,
+    #[serde(default, deserialize_with = "csv_utils::deserialize_list")]
+    tags: Vec<String>,
+    #[serde(default)]
+    met: Option<f64>,
+    #[serde(default, deserialize_with = "csv_utils::deserialize_list")]
+    equipment: Vec<String>,
+    #[serde(default, deserialize_with = "csv_utils::deserialize_list")]
+    stretches: Vec<String>,
+    // What has to be logged before the exercise is picked, e.g. `pull_up: 10`
+    #[serde(default, deserialize_with = "csv_utils::deserialize_list")]
+    prerequisites: Vec<String>,
+    // Whether `wodgen test-day` benchmarks the exercise
+    #[serde(default, deserialize_with = "csv_utils::deserialize_false_if_empty")]
+    testable: bool,
+    // Retired exercises are never picked, their rows kept for the history
+    #[serde(
+        default = "csv_utils::default_true",
+        deserialize_with = "csv_utils::deserialize_true_if_empty"
+    )]
+    active: bool,
+}
+
+// The row of a bodyweight exercise done for reps with every optional column
+// empty, for the fields not set to read as they would from a bare CSV row
+impl Default for Exercise {
+    fn default() -> Exercise {
+        Exercise {
+            name: String::new(),
+            display_name: None,
+            exercise_type: ExerciseType::Push,
+            exercise_category: ExerciseCategory::Primary,
+            exercise_level: ExerciseLevel::default(),
+            exercise_programming: ExerciseProgramming::Reps,
+            bodyweight: true,
+            goal: None,
+            image: None,
+            video: String::new(),
+            progression: None,
+            pattern: None,
+            muscles: Vec::new(),
+            cue: None,
+            tags: Vec::new(),
+            met: None,
+            equipment: Vec::new(),
+            stretches: Vec::new(),
+            prerequisites: Vec::new(),
+            testable: false,
+            active: true,
+        }
+    }
 }
 
 // --------------------------------------------------
@@ -131,10 +276,12 @@ struct Exercise {
 //     schemes.choose(&mut rng).unwrap().clone()
 // }
 
-// Struct to represent a workout exercise
-#[derive(Debug, Serialize)]
+// Struct to represent a workout exercise, saved under the label of its group,
+// e.g. B2, and read back from a label or a group number
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 #[allow(dead_code)]
 struct WorkoutExercise {
+    #[serde(deserialize_with = "notation::deserialize_group")]
     group: u32,
     name: String,
     sets: String,
@@ -142,12 +289,42 @@ struct WorkoutExercise {
     time: String,
     reps: String,
     goal: String,
+    #[serde(default)]
+    image: String,
     video: String,
+    #[serde(default)]
+    scaling: String,
+    #[serde(default)]
+    notes: String,
+    // How the exercise of the library is prescribed, none for the other rows
+    #[serde(default)]
+    programming: Option<ExerciseProgramming>,
+    #[serde(default, deserialize_with = "notation::deserialize_kind")]
+    kind: notation::RowKind,
+    // The clock of the group when it runs as a circuit
+    #[serde(default)]
+    interval: Option<rounds::Interval>,
 }
 
 impl WorkoutExercise {
+    // How the exercise is prescribed: as saved, or for the rows saved without
+    // it, from the first of the distance, time and reps columns that is
+    // filled in. None for session notes and blocks
+    fn programming(&self) -> Option<ExerciseProgramming> {
+        self.programming.clone().or_else(|| {
+            [
+                (&self.distance, ExerciseProgramming::Distance),
+                (&self.time, ExerciseProgramming::Time),
+                (&self.reps, ExerciseProgramming::Reps),
+            ]
+            .into_iter()
+            .find(|(cell, _)| !cell.is_empty())
+            .map(|(_, programming)| programming)
+        })
+    }
+
     // Create a WorkoutExercise from an Exercise
-    fn from_exercise(group: u32, exercise: &Exercise) -> WorkoutExercise {
+    fn from_exercise(group: u32, exercise: &Exercise, units: Units) -> WorkoutExercise {
         let (distance, time, reps, sets) = match exercise.exercise_programming {
             ExerciseProgramming::Distance => (
                 String::from("X"),
@@ -172,19 +349,44 @@ impl WorkoutExercise {
 
         WorkoutExercise {
             group,
-            name: to_title_case(&exercise.name),
+            name: display::exercise_name(exercise),
             sets,
             distance,
             time,
             reps,
-            goal: exercise.goal.clone().unwrap_or_default(),
+            goal: exercise
+                .goal
+                .as_deref()
+                .map(|goal| render_goal(goal, &exercise.exercise_programming, units))
+                .unwrap_or_default(),
+            image: exercise.image.clone().unwrap_or_default(),
             video: exercise.video.clone(),
+            scaling: String::new(),
+            notes: exercise.cue.clone().unwrap_or_default(),
+            programming: Some(exercise.exercise_programming.clone()),
+            kind: notation::RowKind::Exercise,
+            interval: None,
         }
     }
 }
 
+// Render a goal in the requested units, free-form goals are kept as they are
+fn render_goal(goal: &str, programming: &ExerciseProgramming, units: Units) -> String {
+    match programming {
+        ExerciseProgramming::Distance => goal
+            .parse::<Distance>()
+            .map(|distance| distance.render(units))
+            .unwrap_or_else(|_| goal.to_string()),
+        ExerciseProgramming::Time => goal
+            .parse::<Duration>()
+            .map(|duration| duration.render())
+            .unwrap_or_else(|_| goal.to_string()),
+        ExerciseProgramming::Reps => goal.to_string(),
+    }
+}
+
 // Struct to represent a snoozed exercise
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct SnoozedExercise {
     name: String,
     #[serde(with = "chrono::serde::ts_seconds")]
@@ -193,490 +395,4955 @@ struct SnoozedExercise {
 
 // --------------------------------------------------
 
-// Command line arguments struct
+// Command line interface struct
 #[derive(Debug, Parser)]
-#[command(author, version, about)]
+#[command(
+    author,
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    args_override_self = true
+)]
 /// Workout generator based on specified types and level
-struct Args {
-    /// Exercise types to include in the workout, e.g., core, legs, pull, push
-    #[arg(
-        short,
-        long,
-        value_name = "TYPES",
-        required = true,
-        num_args = 1..,
-        value_parser = clap::builder::EnumValueParser::<ExerciseType>::new(),
-    )]
-    types: Vec<ExerciseType>,
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    /// Number of super-sets to include in the workout
-    #[arg(short, long, value_name = "GROUPS", default_value = "2")]
-    groups: u32,
+    #[command(flatten)]
+    args: Args,
 
-    /// Level of difficulty for the workout
+    /// Delimiter of the CSV files read and written, e.g. ; for spreadsheet exports
     #[arg(
-        short,
         long,
-        value_name = "LEVEL",
-        default_value = "intermediate",
-        value_parser = clap::builder::EnumValueParser::<ExerciseLevel>::new(),
+        global = true,
+        value_name = "CHAR",
+        env = "WODGEN_CSV_DELIMITER",
+        default_value = ",",
+        value_parser = csv_utils::parse_delimiter
     )]
-    level: ExerciseLevel,
+    csv_delimiter: u8,
+}
 
-    /// Path to the exercise library directory
-    #[arg(
-        short,
-        long,
-        value_name = "EXERCISE_LIBRARY_DIR",
-        default_value = "./exercise_library"
-    )]
-    exercise_library_dir: PathBuf,
+// Subcommands, generating a workout is the default when none is given
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Generate a workout, same as running wodgen without a subcommand
+    Generate(Box<Args>),
 
-    /// Path to the workouts directory
-    #[arg(short, long, value_name = "WORKOUTS_DIR", default_value = "./workouts")]
-    workouts_dir: PathBuf,
+    /// Ask about your training, write the config, set up the exercise library
+    /// and generate a first workout. Runs by itself on the first run
+    Setup {
+        /// Path to the user config file to write [default: <CONFIG_DIR>/config.toml]
+        #[arg(long, value_name = "CONFIG_FILE", env = "WODGEN_CONFIG")]
+        config: Option<PathBuf>,
 
-    /// Whether to include only bodyweight exercises in the workout
-    #[arg(short, long, value_name = "BODYWEIGHT", default_value = "true")]
-    bodyweight: bool,
-}
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
 
-// --------------------------------------------------
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
+    },
 
-// Shuffle a vector in place
-fn shuffle_vector<T>(vec: &mut Vec<T>) {
-    let mut rng = thread_rng();
-    vec.shuffle(&mut rng);
-}
+    /// Generate a fresh workout with the options of a past run, and other exercises
+    Regenerate {
+        /// Day of the run, e.g. 2024-06-01 [default: the latest run]
+        #[arg(long, value_name = "DATE")]
+        date: Option<NaiveDate>,
 
-// --------------------------------------------------
+        /// Path to the workouts directory, to regenerate the runs saved there only
+        #[arg(short, long, value_name = "WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
 
-// Remove a random element from a vector
-fn remove_random<T>(vec: &mut Vec<T>) -> Option<T> {
-    if vec.is_empty() {
-        None
-    } else {
-        let index = thread_rng().gen_range(0..vec.len());
-        Some(vec.swap_remove(index))
-    }
-}
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
 
-// --------------------------------------------------
+        /// Path to the state directory, when kept apart from the library
+        #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+        state_dir: Option<PathBuf>,
+    },
 
-// For pretty printing the exercise names
-fn to_title_case(input: &str) -> String {
-    input
-        .replace("__", " - ")
-        .replace('_', " ")
-        .split_whitespace()
-        .map(|word| {
-            let mut c = word.chars();
-            match c.next() {
-                None => String::new(),
-                Some(first) => first.to_uppercase().collect::<String>() + c.as_str(),
-            }
-        })
-        .collect::<Vec<String>>()
-        .join(" ")
-}
+    /// Generate, print and save the workout of today's weekday in the split of the user config
+    Today {
+        /// Path to the user config file [default: <CONFIG_DIR>/config.toml]
+        #[arg(long, value_name = "CONFIG_FILE", env = "WODGEN_CONFIG")]
+        config: Option<PathBuf>,
 
-// --------------------------------------------------
+        /// Other generate options, e.g. `--level advanced`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
 
-// Filter exercises by type
-fn filter_by_type(e: &Exercise, t: &ExerciseType) -> bool {
-    e.exercise_type == *t
-}
+    /// Manage the images and videos referenced by the exercise library
+    Media {
+        #[command(subcommand)]
+        command: MediaCommand,
+    },
 
-// Filter exercises by level
-fn filter_by_level(e: &Exercise, l: &ExerciseLevel) -> bool {
-    match l {
-        ExerciseLevel::Beginner => e.exercise_level == ExerciseLevel::Beginner,
-        ExerciseLevel::Intermediate => {
-            e.exercise_level == ExerciseLevel::Intermediate
-            // e.exercise_level == ExerciseLevel::Beginner
-            //     || e.exercise_level == ExerciseLevel::Intermediate
-        }
-        ExerciseLevel::Advanced => {
-            e.exercise_level == ExerciseLevel::Intermediate
-                || e.exercise_level == ExerciseLevel::Advanced
-        }
-    }
-}
+    /// Export saved workouts for other devices and clients
+    Export {
+        #[command(subcommand)]
+        command: ExportCommand,
+    },
 
-// Filter exercises by category
-fn filter_by_category(e: &Exercise, g: u32, l: &ExerciseLevel, t: &ExerciseType) -> bool {
-    match g {
-        0 => match l {
-            ExerciseLevel::Beginner => e.exercise_category == ExerciseCategory::Secondary,
-            _ => e.exercise_category == ExerciseCategory::Primary,
-        },
-        1 => {
-            e.exercise_category == ExerciseCategory::Primary
-                || e.exercise_category == ExerciseCategory::Secondary
-        }
-        2 => match t {
-            ExerciseType::Core => e.exercise_category == ExerciseCategory::Secondary,
-            _ => {
-                e.exercise_category == ExerciseCategory::Secondary
-                    || e.exercise_category == ExerciseCategory::Accessory
-            }
-        },
-        3.. => match t {
-            ExerciseType::Core => e.exercise_category == ExerciseCategory::Secondary,
-            _ => e.exercise_category == ExerciseCategory::Accessory,
-        },
-    }
-}
+    /// Run a chat bot sending the athletes of a roster their workouts
+    Bot {
+        #[command(subcommand)]
+        command: BotCommand,
+    },
 
-// --------------------------------------------------
+    /// Generate a saved workout again from the run that saved it, and show how
+    /// the picks differ
+    Replay {
+        /// Workout file to replay [default: the latest workout]
+        file: Option<PathBuf>,
 
-// Initialize the simplelog logger
-fn init_logger() {
-    CombinedLogger::init(vec![TermLogger::new(
-        LevelFilter::Info,
-        Config::default(),
-        TerminalMode::Mixed,
-        ColorChoice::Auto,
-    )])
-    .unwrap();
-}
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
 
-// --------------------------------------------------
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
 
-// Map exercise types to their corresponding file paths
-fn map_file_paths(exercise_library_dir: &PathBuf) -> HashMap<ExerciseType, PathBuf> {
-    [
-        (
-            ExerciseType::Cooldown,
-            exercise_library_dir.join(COOLDOWN_FILE),
-        ),
-        (ExerciseType::Core, exercise_library_dir.join(CORE_FILE)),
-        (ExerciseType::Legs, exercise_library_dir.join(LEGS_FILE)),
-        (ExerciseType::Pull, exercise_library_dir.join(PULL_FILE)),
-        (ExerciseType::Push, exercise_library_dir.join(PUSH_FILE)),
-    ]
-    .iter()
-    .cloned()
-    .collect::<HashMap<_, _>>()
-}
+        /// Path to the state directory, when kept apart from the library
+        #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+        state_dir: Option<PathBuf>,
 
-// --------------------------------------------------
+        /// Print why each exercise was picked and why the others were skipped
+        #[arg(long)]
+        explain: bool,
+    },
 
-// Load exercises from a CSV file
-fn load_exercises(file_path: &PathBuf) -> Result<Vec<Exercise>> {
-    let exercises = read_csv::<Exercise>(file_path.to_str().unwrap())?;
-    info!("Loaded {} exercises from {:?}", exercises.len(), file_path);
-    Ok(exercises)
-}
+    /// Generate many workouts without saving anything, and report how often
+    /// each exercise is picked, the slots left unfilled and the variety
+    Simulate {
+        /// Number of workouts to generate
+        #[arg(long, value_name = "RUNS", default_value_t = 100,
+              value_parser = clap::value_parser!(u32).range(1..))]
+        runs: u32,
 
-// --------------------------------------------------
+        /// File to append the seeds of the failed runs to, with their options,
+        /// e.g. tests/fuzz_seeds.txt
+        #[arg(long, value_name = "FILE")]
+        save_failing: Option<PathBuf>,
 
-// Load snoozed exercises from a CSV file
-fn load_snoozed_exercises(snoozed_file_path: &PathBuf) -> Result<Vec<SnoozedExercise>> {
-    let now = Utc::now();
-    let snoozed_exercises: Vec<SnoozedExercise> =
-        read_csv::<SnoozedExercise>(snoozed_file_path.to_str().unwrap())?
-            .into_iter()
-            .filter(|e| now.signed_duration_since(e.timestamp).num_days() < SNOOZE_PERIOD)
-            .collect();
-    info!("Loaded {} snoozed exercises", snoozed_exercises.len());
-    Ok(snoozed_exercises)
-}
+        /// Generate options, e.g. `-t push core -g 3`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
 
-// --------------------------------------------------
+    /// Print a share code for a saved workout that anyone can import
+    Share {
+        /// Workout file to share [default: the latest workout]
+        file: Option<PathBuf>,
 
-// Load relevant exercises for the specified exercise types
-fn load_relevant_exercises(
-    exercise_types: &[ExerciseType],
-    file_paths: &HashMap<ExerciseType, PathBuf>,
-) -> Result<Vec<Exercise>> {
-    let mut relevant_exercises = Vec::new();
-    for t in exercise_types {
-        if let Some(file_path) = file_paths.get(t) {
-            let exercises = read_csv::<Exercise>(file_path.to_str().unwrap())?;
-            info!("Loaded {} exercises for type {:?}", exercises.len(), t);
-            relevant_exercises.extend(exercises);
-        }
-    }
-    info!("Loaded {} exercises", relevant_exercises.len());
-    Ok(relevant_exercises)
-}
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
+    },
 
-// --------------------------------------------------
+    /// Show the exercises added, removed and changed between two saved workouts
+    Diff {
+        /// The older workout file
+        old: PathBuf,
 
-// Filter exercises based on bodyweight flag and snoozed exercises
-fn filter_exercises(
-    relevant_exercises: &mut Vec<Exercise>,
-    bodyweight: bool,
-    snoozed_exercises: &[SnoozedExercise],
-) {
-    if bodyweight {
-        relevant_exercises.retain(|e| e.bodyweight);
-        info!(
-            "Filtered out non-bodyweight exercises, {} exercies remaining",
-            relevant_exercises.len()
-        );
-    }
+        /// The newer workout file
+        new: PathBuf,
+    },
 
-    snoozed_exercises.iter().for_each(|snoozed| {
-        relevant_exercises.retain(|e| e.name != snoozed.name);
-    });
-    info!(
-        "Filtered out snoozed exercises, {} exercises remaining",
-        relevant_exercises.len()
-    );
+    /// Save a workout shared with `wodgen share` to the workouts directory
+    ImportShare {
+        /// Share code printed by `wodgen share`, or a wodgen://workout URI
+        code: String,
 
-    shuffle_vector(relevant_exercises);
-    info!("Shuffled relevant exercises");
-}
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
 
-// --------------------------------------------------
+        /// Language of the saved workout headers
+        #[arg(
+            long,
+            value_name = "LANG",
+            env = "WODGEN_LANG",
+            default_value = "en",
+            value_parser = clap::builder::EnumValueParser::<Lang>::new(),
+        )]
+        lang: Lang,
+    },
 
-// Generate a workout
-fn generate_workout(
-    relevant_exercises: &mut Vec<Exercise>,
-    exercise_types: &[ExerciseType],
-    exercise_level: &ExerciseLevel,
-    num_groups: u32,
-    snoozed_exercises: &mut Vec<SnoozedExercise>,
-) -> Vec<WorkoutExercise> {
-    let mut workout = Vec::<WorkoutExercise>::new();
+    /// Add a note to an exercise of a saved workout, e.g. how it went
+    Annotate {
+        /// The workout file to annotate
+        workout: PathBuf,
 
-    // Skill block placeholder
-    workout.push(WorkoutExercise {
-        group: 1,
-        name: String::from("Skill Block"),
-        sets: String::new(),
-        distance: String::new(),
-        time: String::new(),
-        reps: String::new(),
-        goal: String::new(),
-        video: String::new(),
-    });
+        /// Name of the exercise in the workout, e.g. "Pull-up - Regular"
+        exercise: String,
 
-    // Strength training block
-    for group in 0..num_groups {
-        info!("Generating group {}", group + 1);
-        let mut exercises_to_remove = Vec::new();
-        for t in exercise_types {
-            info!("Picking exercise of type {:?}", t);
-            let exercise = relevant_exercises
-                .iter()
-                .filter(|e| filter_by_type(e, t))
-                .filter(|e| filter_by_level(e, exercise_level))
-                .filter(|e| filter_by_category(e, group, exercise_level, t))
-                .next()
-                .cloned();
-
-            if let Some(exercise) = exercise {
-                info!("Picked exercise {:?}", exercise);
-                exercises_to_remove.push(exercise.name.clone());
-                snoozed_exercises.push(SnoozedExercise {
-                    name: exercise.name.clone(),
-                    timestamp: Utc::now(),
-                });
-                let workout_exercise = WorkoutExercise::from_exercise(group + 2, &exercise);
-                workout.push(workout_exercise);
-            }
-        }
-        relevant_exercises.retain(|e| !exercises_to_remove.contains(&e.name));
-    }
+        /// The note to add, appended to any existing notes
+        note: String,
+    },
 
-    workout
-}
+    /// Save the exercise library, snoozes, workout history and user config to a .tar.gz file
+    Backup {
+        /// The backup file to write, e.g. wodgen.tar.gz
+        file: PathBuf,
 
-// --------------------------------------------------
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
 
-// Add a cooldown exercise to the workout
-fn add_cooldown_exercise(
-    workout: &mut Vec<WorkoutExercise>,
-    cooldown_exercises: &mut Vec<Exercise>,
-    snoozed_exercises: &mut Vec<SnoozedExercise>,
-    num_groups: u32,
-) {
-    let cooldown_exercise = remove_random(cooldown_exercises).unwrap();
-    snoozed_exercises.push(SnoozedExercise {
-        name: cooldown_exercise.name.clone(),
-        timestamp: Utc::now(),
-    });
-    let workout_exercise = WorkoutExercise::from_exercise(num_groups + 2, &cooldown_exercise);
-    workout.push(workout_exercise);
-    info!(
-        "Added cooldown exercise {} to workout",
-        cooldown_exercise.name
-    );
-}
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
 
-// --------------------------------------------------
+        /// Path to the state directory, when kept apart from the library
+        #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+        state_dir: Option<PathBuf>,
 
-// Save the workout to a CSV file
-fn save_workout(workouts_dir: &PathBuf, workout: Vec<WorkoutExercise>) -> Result<()> {
-    let date = Local::now().format("%Y_%m_%d").to_string();
-    let file_name = workouts_dir.join(format!("{}.csv", date));
-    write_csv(file_name.to_str().unwrap(), workout)?;
-    info!("Saved workout to {}", file_name.to_str().unwrap());
-    Ok(())
-}
+        /// Path to the user config file, the directory holding it is backed up [default: <CONFIG_DIR>/config.toml]
+        #[arg(long, value_name = "CONFIG_FILE", env = "WODGEN_CONFIG")]
+        config: Option<PathBuf>,
+    },
 
-// --------------------------------------------------
+    /// Restore a backup made with `wodgen backup`, replacing the files it contains
+    Restore {
+        /// The backup file to read
+        file: PathBuf,
 
-// Update the snoozed exercises CSV file
-fn update_snoozed_exercises(
-    snoozed_file_path: &PathBuf,
-    snoozed_exercises: Vec<SnoozedExercise>,
-) -> Result<()> {
-    write_csv(snoozed_file_path.to_str().unwrap(), snoozed_exercises)?;
-    info!("Updated snoozed exercises");
-    Ok(())
-}
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
 
-// --------------------------------------------------
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
 
-// Main function
-fn main() -> Result<()> {
-    // Initialize the logger
-    init_logger();
+        /// Path to the state directory, when kept apart from the library
+        #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+        state_dir: Option<PathBuf>,
 
-    let args = Args::parse();
+        /// Path to the user config file, the directory holding it is restored [default: <CONFIG_DIR>/config.toml]
+        #[arg(long, value_name = "CONFIG_FILE", env = "WODGEN_CONFIG")]
+        config: Option<PathBuf>,
+    },
 
-    let exercise_types = args.types;
-    info!("Exercise types: {:?}", exercise_types);
-    let exercise_level = args.level;
-    info!("Exercise level: {:?}", exercise_level);
-    let num_groups = args.groups;
-    info!("Number of groups: {:?}", num_groups);
-    let bodyweight = args.bodyweight;
-    info!("Bodyweight: {:?}", bodyweight);
+    /// Merge the snoozes and workout history with a remote, then push them back
+    Sync {
+        /// Where the state is kept: an http(s):// WebDAV URL or a directory, S3
+        /// buckets and Git repositories are not supported [default: remote under [sync] in the user config]
+        #[arg(long, value_name = "URL")]
+        remote: Option<String>,
 
-    // Map exercise types to their corresponding file paths
-    let file_paths = map_file_paths(&args.exercise_library_dir);
+        /// Path to the user config file, for the remote and its credentials [default: <CONFIG_DIR>/config.toml]
+        #[arg(long, value_name = "CONFIG_FILE", env = "WODGEN_CONFIG")]
+        config: Option<PathBuf>,
 
-    let cooldown_file_path = file_paths.get(&ExerciseType::Cooldown).unwrap();
-    let snoozed_file_path = args.exercise_library_dir.join(SNOOZED_FILE);
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
 
-    // Load exercises
-    let mut cooldown_exercises = load_exercises(cooldown_file_path)?;
-    let mut snoozed_exercises = load_snoozed_exercises(&snoozed_file_path)?;
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
 
-    // Filter out snoozed exercises from cooldown exercises
-    cooldown_exercises.retain(|e| {
-        !snoozed_exercises
-            .iter()
-            .any(|snoozed| snoozed.name == e.name)
-    });
+        /// Path to the state directory, when kept apart from the library
+        #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+        state_dir: Option<PathBuf>,
+    },
 
-    let mut relevant_exercises = load_relevant_exercises(&exercise_types, &file_paths)?;
+    /// Convert an exercise library file between CSV, TOML and JSON
+    Convert {
+        /// The library file to read, e.g. push.csv
+        input: PathBuf,
 
-    // Filter exercises
-    filter_exercises(&mut relevant_exercises, bodyweight, &snoozed_exercises);
+        /// The library file to write, its extension sets the format, e.g. push.toml
+        output: PathBuf,
+    },
 
-    // Generate workout
-    let mut workout = generate_workout(
-        &mut relevant_exercises,
-        &exercise_types,
-        &exercise_level,
-        num_groups,
-        &mut snoozed_exercises,
-    );
+    /// Summarize a month or week of training from the saved workouts
+    Report {
+        /// Month to report on, e.g. 2024-06 [default: the current month]
+        #[arg(long, value_name = "MONTH", conflicts_with = "week")]
+        month: Option<String>,
 
-    // Add cooldown exercise
-    add_cooldown_exercise(
-        &mut workout,
-        &mut cooldown_exercises,
-        &mut snoozed_exercises,
-        num_groups,
-    );
+        /// ISO week to report on, e.g. 2024-W23
+        #[arg(long, value_name = "WEEK")]
+        week: Option<String>,
 
-    // Save the workout to a CSV file
-    if !args.workouts_dir.exists() {
-        std::fs::create_dir_all(&args.workouts_dir)?;
-    }
-    save_workout(&args.workouts_dir, workout)?;
+        /// Format of the report
+        #[arg(short, long, value_name = "FORMAT", default_value = "markdown")]
+        format: report::ReportFormat,
 
-    // Update snoozed exercises
-    update_snoozed_exercises(&snoozed_file_path, snoozed_exercises)?;
+        /// How the progress charts are embedded in HTML reports
+        #[arg(long, value_name = "CHARTS", default_value = "svg")]
+        charts: report::ChartFormat,
 
-    Ok(())
-}
+        /// File to write the report to [default: print it]
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
 
-// --------------------------------------------------
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
 
-    fn create_test_exercises() -> Vec<Exercise> {
-        vec![
-            Exercise {
-                name: String::from("Push Up"),
-                exercise_type: ExerciseType::Push,
-                exercise_category: ExerciseCategory::Primary,
-                exercise_level: ExerciseLevel::Beginner,
-                exercise_programming: ExerciseProgramming::Reps,
-                bodyweight: true,
-                goal: Some(String::from("Strength")),
-                video: String::from("push_up.mp4"),
-            },
-            Exercise {
-                name: String::from("Pull Up"),
-                exercise_type: ExerciseType::Pull,
-                exercise_category: ExerciseCategory::Primary,
-                exercise_level: ExerciseLevel::Intermediate,
-                exercise_programming: ExerciseProgramming::Reps,
-                bodyweight: true,
-                goal: Some(String::from("Strength")),
-                video: String::from("pull_up.mp4"),
-            },
-            Exercise {
-                name: String::from("Squat"),
-                exercise_type: ExerciseType::Legs,
-                exercise_category: ExerciseCategory::Primary,
-                exercise_level: ExerciseLevel::Advanced,
-                exercise_programming: ExerciseProgramming::Reps,
-                bodyweight: false,
-                goal: Some(String::from("Strength")),
-                video: String::from("squat.mp4"),
-            },
-            Exercise {
-                name: String::from("Plank"),
-                exercise_type: ExerciseType::Core,
-                exercise_category: ExerciseCategory::Secondary,
-                exercise_level: ExerciseLevel::Beginner,
-                exercise_programming: ExerciseProgramming::Time,
-                bodyweight: true,
-                goal: Some(String::from("Endurance")),
-                video: String::from("plank.mp4"),
-            },
-        ]
-    }
+        /// Unit system for distance records
+        #[arg(
+            long,
+            value_name = "UNITS",
+            env = "WODGEN_UNITS",
+            default_value = "metric",
+            value_parser = clap::builder::EnumValueParser::<Units>::new(),
+        )]
+        units: Units,
+    },
 
-    // --------------------------------------------------
+    /// List the exercises locked by their prerequisites and what unlocks them
+    Goals {
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
 
-    #[test]
-    fn test_filter_by_type() {
-        let exercises = create_test_exercises();
-        let push_exercises: Vec<&Exercise> = exercises
-            .iter()
-            .filter(|e| filter_by_type(e, &ExerciseType::Push))
-            .collect();
-        assert_eq!(push_exercises.len(), 1);
-        assert_eq!(push_exercises[0].name, "Push Up");
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
 
-        let pull_exercises: Vec<&Exercise> = exercises
-            .iter()
-            .filter(|e| filter_by_type(e, &ExerciseType::Pull))
-            .collect();
-        assert_eq!(pull_exercises.len(), 1);
-        assert_eq!(pull_exercises[0].name, "Pull Up");
-    }
+        /// Unit system for distance targets
+        #[arg(
+            long,
+            value_name = "UNITS",
+            env = "WODGEN_UNITS",
+            default_value = "metric",
+            value_parser = clap::builder::EnumValueParser::<Units>::new(),
+        )]
+        units: Units,
+    },
 
-    // --------------------------------------------------
+    /// Mark the workout of a day done, logged or not, for the adherence and
+    /// the recovery
+    Done {
+        /// Day of the workout, e.g. 2024-06-01 [default: today]
+        #[arg(value_name = "DATE")]
+        date: Option<NaiveDate>,
 
-    #[test]
-    fn test_filter_by_level() {
-        let exercises = create_test_exercises();
-        let beginner_exercises: Vec<&Exercise> = exercises
-            .iter()
+        /// Note on the session, e.g. "cut short, sore knee"
+        #[arg(long, value_name = "NOTE", default_value = "")]
+        note: String,
+
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
+    },
+
+    /// Generate and save a benchmark, a fixed named workout of benchmarks.csv,
+    /// with the scores of its earlier sessions, or list the benchmarks
+    Benchmark {
+        /// Name of the benchmark, e.g. murph; lists the benchmarks when left out
+        name: Option<String>,
+
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
+
+        /// Language of the benchmark
+        #[arg(
+            long,
+            value_name = "LANG",
+            env = "WODGEN_LANG",
+            default_value = "en",
+            value_parser = clap::builder::EnumValueParser::<Lang>::new(),
+        )]
+        lang: Lang,
+
+        /// Unit system for distance prescriptions and scores
+        #[arg(
+            long,
+            value_name = "UNITS",
+            env = "WODGEN_UNITS",
+            default_value = "metric",
+            value_parser = clap::builder::EnumValueParser::<Units>::new(),
+        )]
+        units: Units,
+    },
+
+    /// Generate and save a max-effort test day of the testable exercises, to
+    /// benchmark them and set records
+    TestDay {
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
+
+        /// Path to the user config file, for the retest interval [default: <CONFIG_DIR>/config.toml]
+        #[arg(long, value_name = "CONFIG_FILE", env = "WODGEN_CONFIG")]
+        config: Option<PathBuf>,
+
+        /// Language of the test day
+        #[arg(
+            long,
+            value_name = "LANG",
+            env = "WODGEN_LANG",
+            default_value = "en",
+            value_parser = clap::builder::EnumValueParser::<Lang>::new(),
+        )]
+        lang: Lang,
+
+        /// Unit system for distance prescriptions
+        #[arg(
+            long,
+            value_name = "UNITS",
+            env = "WODGEN_UNITS",
+            default_value = "metric",
+            value_parser = clap::builder::EnumValueParser::<Units>::new(),
+        )]
+        units: Units,
+    },
+
+    /// Set the goal the workouts build toward, e.g. `wodgen goal set first_muscle_up`
+    Goal {
+        #[command(subcommand)]
+        command: GoalCommand,
+    },
+
+    /// Show the current streak, the weekly adherence and the sessions missed,
+    /// and the progress toward the goal
+    Status {
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
+
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+
+        /// Path to the state directory, when kept apart from the library
+        #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+        state_dir: Option<PathBuf>,
+
+        /// Path to the user config file, for the retest interval and the weekly targets [default: <CONFIG_DIR>/config.toml]
+        #[arg(long, value_name = "CONFIG_FILE", env = "WODGEN_CONFIG")]
+        config: Option<PathBuf>,
+
+        /// Unit system for distance targets
+        #[arg(
+            long,
+            value_name = "UNITS",
+            env = "WODGEN_UNITS",
+            default_value = "metric",
+            value_parser = clap::builder::EnumValueParser::<Units>::new(),
+        )]
+        units: Units,
+    },
+
+    /// Chart the volume, the adherence or the progress of an exercise per week
+    Chart {
+        /// What to chart
+        metric: chart::Metric,
+
+        /// Name of the exercise whose progress to chart, e.g. "Pull-up - Regular"
+        #[arg(long, value_name = "EXERCISE", required_if_eq("metric", "progress"))]
+        exercise: Option<String>,
+
+        /// Number of weeks to chart, up to the current one
+        #[arg(long, value_name = "WEEKS", default_value_t = 12,
+              value_parser = clap::value_parser!(u32).range(1..))]
+        weeks: u32,
+
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
+
+        /// Unit system for distances
+        #[arg(
+            long,
+            value_name = "UNITS",
+            env = "WODGEN_UNITS",
+            default_value = "metric",
+            value_parser = clap::builder::EnumValueParser::<Units>::new(),
+        )]
+        units: Units,
+    },
+
+    /// Print a calendar heatmap of the training days, or of the days an
+    /// exercise or exercise type was trained
+    Heatmap {
+        /// Exercise type or exercise to follow, e.g. "pull" or "Pull-up - Regular"
+        filter: Option<String>,
+
+        /// Number of weeks to show, up to the current one
+        #[arg(long, value_name = "WEEKS", default_value_t = 52,
+              value_parser = clap::value_parser!(u32).range(1..))]
+        weeks: u32,
+
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
+
+        /// Path to the exercise library directory, for the exercises of a type [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+    },
+
+    /// Manage the equipment at hand, which exercises and loads are fitted to
+    Equipment {
+        #[command(subcommand)]
+        command: EquipmentCommand,
+    },
+
+    /// Manage the training maxes that set the loads of weighted exercises
+    Tm {
+        #[command(subcommand)]
+        command: TmCommand,
+    },
+
+    /// Log how you slept and feel, and how hard the last session was, to set
+    /// the readiness of the next sessions with `--readiness auto`
+    Checkin {
+        /// Sleep quality, from 1 (poor) to 5 (great)
+        #[arg(long, value_name = "1-5", value_parser = clap::value_parser!(u8).range(1..=5))]
+        sleep: u8,
+
+        /// Muscle soreness, from 1 (none) to 5 (very sore)
+        #[arg(long, value_name = "1-5", value_parser = clap::value_parser!(u8).range(1..=5))]
+        soreness: u8,
+
+        /// Motivation to train, from 1 (none) to 5 (eager)
+        #[arg(long, value_name = "1-5", value_parser = clap::value_parser!(u8).range(1..=5))]
+        motivation: u8,
+
+        /// Session RPE of the last session, from 1 (very easy) to 10 (maximal)
+        #[arg(long, value_name = "1-10", value_parser = clap::value_parser!(u8).range(1..=10))]
+        rpe: Option<u8>,
+
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+
+        /// Path to the state directory, when kept apart from the library
+        #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+        state_dir: Option<PathBuf>,
+    },
+
+    /// Calibrate the level of each exercise type from a few capability tests,
+    /// for `--calibrated`, or print the calibrated levels
+    Calibrate {
+        /// Most strict pull-ups in one set, calibrates the pull exercises
+        #[arg(long, value_name = "REPS")]
+        pull_ups: Option<u32>,
+
+        /// Most push-ups in one set, calibrates the push exercises
+        #[arg(long, value_name = "REPS")]
+        push_ups: Option<u32>,
+
+        /// Most bodyweight squats in one set, calibrates the legs exercises
+        #[arg(long, value_name = "REPS")]
+        squats: Option<u32>,
+
+        /// Longest plank hold in seconds, calibrates the core exercises
+        #[arg(long, value_name = "SECONDS")]
+        plank: Option<u32>,
+
+        /// CSV file of logged numbers to calibrate from, with `test` and
+        /// `value` columns, e.g. `pull_ups,8`
+        #[arg(long, value_name = "FILE")]
+        import: Option<PathBuf>,
+
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+
+        /// Path to the state directory, when kept apart from the library
+        #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+        state_dir: Option<PathBuf>,
+    },
+
+    /// List ranked alternatives to an exercise, e.g. when a station is occupied
+    Substitute {
+        /// Name of the exercise to replace, e.g. "Pull-up - Regular"
+        exercise: String,
+
+        /// Why the exercise needs replacing, narrows down the alternatives
+        #[arg(long, value_name = "REASON")]
+        reason: Option<substitute::Reason>,
+
+        /// Maximum number of alternatives to list
+        #[arg(short = 'n', long, value_name = "LIMIT", default_value_t = 5)]
+        limit: usize,
+
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+    },
+
+    /// Print the taxonomy of movement patterns under the exercise types, with
+    /// how many exercises of the library each node holds
+    Taxonomy {
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+    },
+
+    /// Look into the snoozed exercises and the snoozes that ended
+    Snooze {
+        #[command(subcommand)]
+        command: SnoozeCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SnoozeCommand {
+    /// Print when an exercise was snoozed, to see when it was last blocked
+    History {
+        /// Name of the exercise, e.g. "pull_up" or "Pull Up"
+        exercise: String,
+
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+
+        /// Path to the state directory, when kept apart from the library
+        #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+        state_dir: Option<PathBuf>,
+
+        /// Path to the snoozed exercises file [default: <STATE_DIR>/snoozed.csv]
+        #[arg(long, value_name = "SNOOZED_FILE", env = "WODGEN_SNOOZED_FILE")]
+        snoozed_file: Option<PathBuf>,
+
+        /// Path to the user config file, for the snooze periods [default: <CONFIG_DIR>/config.toml]
+        #[arg(long, value_name = "CONFIG_FILE", env = "WODGEN_CONFIG")]
+        config: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum GoalCommand {
+    /// Set the goal, an exercise to unlock named as in the library, optionally
+    /// with `first_`, e.g. `wodgen goal set first_muscle_up --by 2024-12-01`
+    Set {
+        /// Name of the goal, e.g. "first_muscle_up" or "Muscle Up"
+        name: String,
+
+        /// Day to reach the goal by, e.g. 2024-12-01
+        #[arg(long, value_name = "DATE")]
+        by: Option<NaiveDate>,
+
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+
+        /// Path to the state directory, when kept apart from the library
+        #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+        state_dir: Option<PathBuf>,
+    },
+
+    /// Drop the goal, the workouts no longer build toward it
+    Clear {
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+
+        /// Path to the state directory, when kept apart from the library
+        #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+        state_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum TmCommand {
+    /// Set the training max of a weighted exercise, e.g. `wodgen tm set squat 120`
+    Set {
+        /// Name of the exercise, e.g. "back_squat" or "Back Squat"
+        exercise: String,
+
+        /// The training max, in kg unless suffixed with lb, e.g. 120 or 265lb
+        weight: units::Weight,
+
+        /// Progression scheme of the prescriptions
+        #[arg(long, value_name = "SCHEME", default_value = "531")]
+        scheme: training_max::Scheme,
+
+        /// Load added every session (linear) or every cycle (5/3/1)
+        #[arg(long, value_name = "WEIGHT", default_value = "2.5kg")]
+        increment: units::Weight,
+
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+
+        /// Path to the state directory, when kept apart from the library
+        #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+        state_dir: Option<PathBuf>,
+    },
+
+    /// List the training maxes
+    List {
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+
+        /// Path to the state directory, when kept apart from the library
+        #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+        state_dir: Option<PathBuf>,
+
+        /// Unit system of the listed weights
+        #[arg(
+            long,
+            value_name = "UNITS",
+            env = "WODGEN_UNITS",
+            default_value = "metric",
+            value_parser = clap::builder::EnumValueParser::<Units>::new(),
+        )]
+        units: Units,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum EquipmentCommand {
+    /// Add equipment, e.g. `wodgen equipment add rings "40kg dumbbells x2"`
+    Add {
+        /// Equipment to add: an optional weight, a name and an optional count
+        #[arg(required = true)]
+        items: Vec<equipment::Equipment>,
+
+        /// Path to the user config file, the registry is kept next to it [default: <CONFIG_DIR>/config.toml]
+        #[arg(long, value_name = "CONFIG_FILE", env = "WODGEN_CONFIG")]
+        config: Option<PathBuf>,
+    },
+
+    /// Remove equipment, every weight of it unless one is given
+    Remove {
+        /// Equipment to remove, e.g. "dumbbells" or "40kg dumbbells"
+        #[arg(required = true)]
+        items: Vec<equipment::Equipment>,
+
+        /// Path to the user config file, the registry is kept next to it [default: <CONFIG_DIR>/config.toml]
+        #[arg(long, value_name = "CONFIG_FILE", env = "WODGEN_CONFIG")]
+        config: Option<PathBuf>,
+    },
+
+    /// List the equipment
+    List {
+        /// Path to the user config file, the registry is kept next to it [default: <CONFIG_DIR>/config.toml]
+        #[arg(long, value_name = "CONFIG_FILE", env = "WODGEN_CONFIG")]
+        config: Option<PathBuf>,
+
+        /// Unit system of the listed weights
+        #[arg(
+            long,
+            value_name = "UNITS",
+            env = "WODGEN_UNITS",
+            default_value = "metric",
+            value_parser = clap::builder::EnumValueParser::<Units>::new(),
+        )]
+        units: Units,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum MediaCommand {
+    /// Download remote images and videos into the media cache for offline use
+    Fetch {
+        /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+        #[arg(
+            short,
+            long,
+            value_name = "EXERCISE_LIBRARY_DIR",
+            env = "WODGEN_LIBRARY_DIR"
+        )]
+        exercise_library_dir: Option<PathBuf>,
+
+        /// Path to the media cache directory [default: <DATA_DIR>/media]
+        #[arg(long, value_name = "MEDIA_DIR", env = "WODGEN_MEDIA_DIR")]
+        media_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ExportCommand {
+    /// Zip the workout of a day as CSV, HTML and PDF with its thumbnails and a manifest
+    Bundle {
+        /// Day of the workout, e.g. 2024-06-01
+        date: NaiveDate,
+
+        /// The bundle file to write [default: <WORKOUT>.zip]
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
+
+        /// Path to the media cache directory [default: <DATA_DIR>/media]
+        #[arg(long, value_name = "MEDIA_DIR", env = "WODGEN_MEDIA_DIR")]
+        media_dir: Option<PathBuf>,
+    },
+
+    /// Export the interval blocks of the workout of a day for bike and rowing
+    /// ergometers, as a Zwift workout or an ERG file
+    Intervals {
+        /// Day of the workout, e.g. 2024-06-01
+        date: NaiveDate,
+
+        /// Format of the file
+        #[arg(
+            short,
+            long,
+            value_name = "FORMAT",
+            default_value = "zwo",
+            value_parser = clap::builder::EnumValueParser::<ergometer::ErgFormat>::new(),
+        )]
+        format: ergometer::ErgFormat,
+
+        /// The file to write [default: <WORKOUT>.zwo or <WORKOUT>.erg]
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Seconds of rest after each interval of the groups held in time
+        #[arg(long, value_name = "SECONDS", default_value_t = 30)]
+        rest: u32,
+
+        /// Functional threshold power the watts of ERG files are shares of
+        #[arg(long, value_name = "WATTS", env = "WODGEN_FTP", default_value_t = 200)]
+        ftp: u32,
+
+        /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+        #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+        workouts_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum BotCommand {
+    /// Answer /wod with a workout and record the logs sent back, in Telegram
+    Telegram {
+        /// Token of the bot, given by BotFather
+        #[arg(long, value_name = "TOKEN", env = "WODGEN_TELEGRAM_TOKEN")]
+        token: String,
+
+        /// Base URL of the Bot API
+        #[arg(long, value_name = "URL", env = "WODGEN_TELEGRAM_API", default_value = bot::TELEGRAM_API)]
+        api: String,
+
+        /// Generate options, with the roster of the athletes, e.g. `--roster roster.toml`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+// Command line arguments struct for generating a workout, saved with every
+// run so it can be regenerated. Options missing from an older run file take
+// their default values
+#[derive(Debug, Clone, Default, clap::Args, Serialize, Deserialize)]
+#[serde(default)]
+struct Args {
+    /// Exercise types to include in the workout, e.g., core, legs, pull, push,
+    /// each optionally qualified with any, a category or a movement pattern,
+    /// e.g., pull:any, legs:accessory, core:anti-rotation
+    #[arg(
+        short,
+        long,
+        value_name = "TYPES",
+        env = "WODGEN_TYPES",
+        required_unless_present_any = ["roster", "template", "constraints"],
+        num_args = 1..,
+        value_delimiter = ','
+    )]
+    types: Vec<template::Slot>,
+
+    /// Number of super-sets to include in the workout, at most 8
+    #[arg(
+        short,
+        long,
+        value_name = "GROUPS",
+        env = "WODGEN_GROUPS",
+        default_value = "2",
+        value_parser = clap::value_parser!(u32).range(1..=MAX_GROUPS as i64),
+    )]
+    groups: u32,
+
+    /// Lower the number of groups to the most the exercises left after the
+    /// filters can fill, instead of failing
+    #[arg(long, env = "WODGEN_AUTO_GROUPS")]
+    auto_groups: bool,
+
+    /// Time budget of the session in minutes, failing when the estimated time
+    /// of the workout goes past it, with the time of each group
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        env = "WODGEN_DURATION",
+        value_parser = clap::value_parser!(u32).range(1..),
+    )]
+    duration: Option<u32>,
+
+    /// Only warn when the workout goes past --duration, instead of failing
+    #[arg(long, env = "WODGEN_ALLOW_OVER_BUDGET")]
+    allow_over_budget: bool,
+
+    /// Categories the groups pick from in turn: standard goes from the main
+    /// lifts to the accessories, pyramid up to the main lifts in the middle
+    /// group and back down [default: that of the template, or standard]
+    #[arg(
+        long,
+        value_name = "STRATEGY",
+        env = "WODGEN_CATEGORY_STRATEGY",
+        value_parser = clap::builder::EnumValueParser::<category::CategoryStrategy>::new(),
+    )]
+    category_strategy: Option<category::CategoryStrategy>,
+
+    /// Level of difficulty for the workout
+    #[arg(
+        short,
+        long,
+        value_name = "LEVEL",
+        env = "WODGEN_LEVEL",
+        default_value = "intermediate",
+        value_parser = clap::builder::EnumValueParser::<ExerciseLevel>::new(),
+    )]
+    level: ExerciseLevel,
+
+    /// Train the exercise types calibrated with `wodgen calibrate` at their
+    /// levels, the others at --level
+    #[arg(long, env = "WODGEN_CALIBRATED")]
+    calibrated: bool,
+
+    /// Path to the exercise library directory [default: <DATA_DIR>/exercise_library]
+    #[arg(
+        short,
+        long,
+        value_name = "EXERCISE_LIBRARY_DIR",
+        env = "WODGEN_LIBRARY_DIR"
+    )]
+    exercise_library_dir: Option<PathBuf>,
+
+    /// Path to the workouts directory [default: <DATA_DIR>/workouts]
+    #[arg(short, long, value_name = "WORKOUTS_DIR", env = "WODGEN_WORKOUTS_DIR")]
+    workouts_dir: Option<PathBuf>,
+
+    /// Path to the snoozed exercises file [default: <STATE_DIR>/snoozed.csv]
+    #[arg(long, value_name = "SNOOZED_FILE", env = "WODGEN_SNOOZED_FILE")]
+    snoozed_file: Option<PathBuf>,
+
+    /// Directory for the state wodgen updates, e.g. the snoozed exercises, to keep
+    /// the library read-only [default: <EXERCISE_LIBRARY_DIR>]
+    #[arg(long, value_name = "STATE_DIR", env = "WODGEN_STATE_DIR")]
+    state_dir: Option<PathBuf>,
+
+    /// Whether to include only bodyweight exercises in the workout
+    #[arg(
+        short,
+        long,
+        value_name = "BODYWEIGHT",
+        env = "WODGEN_BODYWEIGHT",
+        default_value = "true",
+        action = clap::ArgAction::Set
+    )]
+    bodyweight: bool,
+
+    /// Language of the generated workout
+    #[arg(
+        long,
+        value_name = "LANG",
+        env = "WODGEN_LANG",
+        default_value = "en",
+        value_parser = clap::builder::EnumValueParser::<Lang>::new(),
+    )]
+    lang: Lang,
+
+    /// Unit system for distance prescriptions
+    #[arg(
+        long,
+        value_name = "UNITS",
+        env = "WODGEN_UNITS",
+        default_value = "metric",
+        value_parser = clap::builder::EnumValueParser::<Units>::new(),
+    )]
+    units: Units,
+
+    /// Formats to save the workout in
+    #[arg(
+        short,
+        long,
+        value_name = "FORMAT",
+        env = "WODGEN_FORMAT",
+        default_value = "csv",
+        num_args = 1..,
+        value_delimiter = ',',
+        value_parser = clap::builder::EnumValueParser::<Format>::new(),
+    )]
+    format: Vec<Format>,
+
+    /// Path to the media cache directory [default: <DATA_DIR>/media]
+    #[arg(long, value_name = "MEDIA_DIR", env = "WODGEN_MEDIA_DIR")]
+    media_dir: Option<PathBuf>,
+
+    /// Path to the user config file [default: <CONFIG_DIR>/config.toml]
+    #[arg(long, value_name = "CONFIG_FILE", env = "WODGEN_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Names of exercises to leave out of the workout, as written in the library
+    #[arg(
+        short = 'x',
+        long,
+        value_name = "EXERCISES",
+        env = "WODGEN_EXCLUDE",
+        num_args = 1..,
+        value_delimiter = ','
+    )]
+    exclude: Vec<String>,
+
+    /// Names of exercises to put in every workout, as written in the library.
+    /// They are never left out for being snoozed and skip the category rotation
+    #[arg(
+        long,
+        value_name = "EXERCISES",
+        env = "WODGEN_PIN",
+        num_args = 1..,
+        value_delimiter = ','
+    )]
+    pin: Vec<String>,
+
+    /// Sore areas to go easy on, exercise types, nodes of the taxonomy or
+    /// muscles, e.g. legs,shoulders. Their exercises are left out unless
+    /// nothing else of their type is left
+    #[arg(
+        long,
+        value_name = "AREAS",
+        env = "WODGEN_SORE",
+        num_args = 1..,
+        value_delimiter = ','
+    )]
+    sore: Vec<String>,
+
+    /// Path to a TOML roster, generates one workout per athlete
+    #[arg(long, value_name = "ROSTER", env = "WODGEN_ROSTER")]
+    roster: Option<PathBuf>,
+
+    /// Generate a class workout at --level with substitutes for the other levels
+    #[arg(long, env = "WODGEN_CLASS")]
+    class: bool,
+
+    /// Note for the whole session, e.g. "focus on scapular control"
+    #[arg(long, value_name = "NOTE", env = "WODGEN_NOTE")]
+    note: Option<String>,
+
+    /// Also print the workout to the terminal, as Markdown
+    #[arg(long, env = "WODGEN_PRINT")]
+    print: bool,
+
+    /// Do not print the summary of the generated workout
+    #[arg(short, long, env = "WODGEN_QUIET")]
+    quiet: bool,
+
+    /// Print the result as a JSON object on stdout, the logs going to stderr
+    #[arg(long, env = "WODGEN_JSON")]
+    json: bool,
+
+    /// Print the workout on one line on stdout, then a tab and its wodgen://
+    /// URI, the logs going to stderr
+    #[arg(long, env = "WODGEN_COMPACT", conflicts_with_all = ["json", "print"])]
+    compact: bool,
+
+    /// Path to a TOML template of the workout blocks, replaces --groups
+    #[arg(long, value_name = "TEMPLATE", env = "WODGEN_TEMPLATE")]
+    template: Option<PathBuf>,
+
+    /// Path to a TOML file bundling the constraints of the run: types, groups,
+    /// duration, counts, excludes, tags and equipment
+    #[arg(long, value_name = "CONSTRAINTS", env = "WODGEN_CONSTRAINTS")]
+    constraints: Option<PathBuf>,
+
+    /// Review each exercise before the workout is saved: accept, reroll or skip it
+    #[arg(long, env = "WODGEN_INTERACTIVE")]
+    interactive: bool,
+
+    /// Print why each exercise was picked and why the others were skipped
+    #[arg(long, env = "WODGEN_EXPLAIN")]
+    explain: bool,
+
+    /// Print which exercises are new and which repeat the last session of the
+    /// same types
+    #[arg(long, env = "WODGEN_PREVIEW_DIFF")]
+    preview_diff: bool,
+
+    /// Modify no file: print the workout instead of saving it, and snooze nothing
+    #[arg(long, env = "WODGEN_READ_ONLY")]
+    read_only: bool,
+
+    /// Name each session from its content, e.g. "Sunday Shoulder Smoker", in
+    /// the output headers and the file names
+    #[arg(long, env = "WODGEN_NAMED_FILES")]
+    named_files: bool,
+
+    /// Command run for every slot with its candidates as JSON on stdin, to
+    /// pick or veto exercises, e.g. "python3 plugins/grip.py"
+    #[arg(long, value_name = "COMMAND", env = "WODGEN_PLUGIN")]
+    plugin: Option<String>,
+
+    /// Addresses to email the workout to, through the SMTP server of the user config
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        env = "WODGEN_EMAIL",
+        num_args = 1..,
+        value_delimiter = ','
+    )]
+    email: Vec<String>,
+
+    /// Save the session as a task, or an event, in the CalDAV calendar of the user config
+    #[arg(long, env = "WODGEN_CALDAV")]
+    caldav: bool,
+
+    /// Number of candidate workouts to generate, the best scoring one is kept
+    #[arg(
+        long,
+        value_name = "ATTEMPTS",
+        env = "WODGEN_ATTEMPTS",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..),
+    )]
+    attempts: u32,
+
+    /// Seed of the random picks, to repeat a workout [default: random]
+    #[arg(long, value_name = "SEED", env = "WODGEN_SEED")]
+    seed: Option<u64>,
+
+    /// Extra selection weight of exercises never done, shrinking with every saved workout they appear in, so the whole library gets used. 0 picks uniformly
+    #[arg(
+        long,
+        value_name = "BONUS",
+        env = "WODGEN_NOVELTY_BONUS",
+        default_value_t = 1.0
+    )]
+    novelty_bonus: f64,
+
+    /// How picked exercises are kept from repeating: hard leaves them out until
+    /// their snooze ends, decay draws them less often the more recently picked
+    #[arg(
+        long,
+        value_name = "MODE",
+        env = "WODGEN_SNOOZE_MODE",
+        default_value = "hard",
+        value_parser = clap::builder::EnumValueParser::<snooze::SnoozeMode>::new(),
+    )]
+    snooze_mode: snooze::SnoozeMode,
+
+    /// What the super-sets train for, sets their rounds and the work of each round
+    #[arg(
+        long,
+        value_name = "FOCUS",
+        env = "WODGEN_FOCUS",
+        default_value = "hypertrophy",
+        value_parser = clap::builder::EnumValueParser::<rounds::Focus>::new(),
+    )]
+    focus: rounds::Focus,
+
+    /// How ready for training you are, low takes a round off every super-set
+    /// and high adds one. Auto reads it from the check-ins of the last week
+    #[arg(
+        long,
+        value_name = "READINESS",
+        env = "WODGEN_READINESS",
+        default_value = "normal",
+        value_parser = clap::builder::EnumValueParser::<wellness::Readiness>::new(),
+    )]
+    readiness: wellness::Readiness,
+
+    /// Number of exercises of each type per group, e.g. push=2,core=1 or push=1-2
+    /// for a random count, one for unlisted types
+    #[arg(
+        long,
+        value_name = "TYPE=COUNT",
+        env = "WODGEN_PER_GROUP",
+        num_args = 1..,
+        value_delimiter = ','
+    )]
+    per_group: Vec<template::TypeCount>,
+
+    /// Append a high-rep burnout block of accessory exercises of this type
+    /// before the cooldown
+    #[arg(
+        long,
+        value_name = "TYPE",
+        env = "WODGEN_FINISHER",
+        value_parser = clap::builder::EnumValueParser::<ExerciseType>::new(),
+    )]
+    finisher: Option<ExerciseType>,
+
+    /// Fill the skill block with a 10-minute EMOM alternating two skill drills
+    #[arg(long, env = "WODGEN_EMOM")]
+    emom: bool,
+
+    /// Number of training partners, alternates the sets between them and splits
+    /// the finisher
+    #[arg(
+        long,
+        value_name = "PARTNERS",
+        env = "WODGEN_PARTNERS",
+        value_parser = clap::value_parser!(u32).range(2..),
+    )]
+    partners: Option<u32>,
+
+    /// Workout for a hotel room: bodyweight only, no equipment, no jumping and
+    /// at most two groups
+    #[arg(long, env = "WODGEN_TRAVEL")]
+    travel: bool,
+
+    /// Workout for kids or true novices: beginner exercises without high skill
+    /// or load, at most two groups and playful block names
+    #[arg(long, env = "WODGEN_KIDS", conflicts_with = "travel")]
+    kids: bool,
+
+    /// Name of a profile adapting the workout, built in (travel, kids, pregnancy,
+    /// postpartum, senior) or defined in <EXERCISE_LIBRARY_DIR>/profiles.toml
+    #[arg(
+        long,
+        value_name = "PROFILE",
+        env = "WODGEN_PROFILE",
+        conflicts_with_all = ["travel", "kids"]
+    )]
+    profile: Option<String>,
+
+    // Generating for `wodgen simulate`, which prints nothing of the workout
+    #[arg(skip)]
+    #[serde(skip)]
+    simulated: bool,
+
+    // Replaying a run with `wodgen replay`, generating as at the time it was
+    // generated rather than now
+    #[arg(skip)]
+    #[serde(skip)]
+    generated_at: Option<DateTime<Utc>>,
+}
+
+// --------------------------------------------------
+
+// Shuffle a vector in place
+fn shuffle_vector<T>(vec: &mut [T]) {
+    random::with_rng(|rng| vec.shuffle(rng));
+}
+
+// --------------------------------------------------
+
+// Remove a random element from a vector
+fn remove_random<T>(vec: &mut Vec<T>) -> Option<T> {
+    if vec.is_empty() {
+        None
+    } else {
+        let index = random::with_rng(|rng| rng.gen_range(0..vec.len()));
+        Some(vec.swap_remove(index))
+    }
+}
+
+// --------------------------------------------------
+
+// Filter exercises by type
+fn filter_by_type(e: &Exercise, t: &ExerciseType) -> bool {
+    e.exercise_type == *t
+}
+
+// Filter exercises by level: an athlete trains at their level and below, so
+// Intermediate also picks Beginner exercises and Advanced picks all of them
+fn filter_by_level(e: &Exercise, l: &ExerciseLevel) -> bool {
+    e.exercise_level <= *l
+}
+
+// Filter exercises by category
+fn filter_by_category(e: &Exercise, g: u32, l: &ExerciseLevel, t: &ExerciseType) -> bool {
+    rotation_categories(g, l, t).contains(&e.exercise_category)
+}
+
+// The categories the rotation picks from in a group: the main lifts first,
+// down to the accessories
+fn rotation_categories(g: u32, l: &ExerciseLevel, t: &ExerciseType) -> &'static [ExerciseCategory] {
+    match g {
+        0 => match l {
+            ExerciseLevel::Beginner => &[ExerciseCategory::Secondary],
+            _ => &[ExerciseCategory::Primary],
+        },
+        1 => &[ExerciseCategory::Primary, ExerciseCategory::Secondary],
+        2 => match t {
+            ExerciseType::Core => &[ExerciseCategory::Secondary],
+            _ => &[ExerciseCategory::Secondary, ExerciseCategory::Accessory],
+        },
+        3.. => match t {
+            ExerciseType::Core => &[ExerciseCategory::Secondary],
+            _ => &[ExerciseCategory::Accessory],
+        },
+    }
+}
+
+// --------------------------------------------------
+
+// Initialize the simplelog logger, keeping the warnings for the result of the
+// run. The logs go to stderr when stdout is for the JSON or compact result
+fn init_logger(stderr: bool) -> Result<(), WodgenError> {
+    let mode = if stderr {
+        TerminalMode::Stderr
+    } else {
+        TerminalMode::Mixed
+    };
+    CombinedLogger::init(vec![
+        TermLogger::new(
+            LevelFilter::Info,
+            Config::default(),
+            mode,
+            ColorChoice::Auto,
+        ),
+        Box::new(outcome::WarningCollector),
+    ])?;
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Map exercise types to their corresponding file paths in any library format, a
+// single exercises file holding every type takes precedence over the per-type files
+fn map_file_paths(exercise_library_dir: &Path) -> HashMap<ExerciseType, PathBuf> {
+    let exercises_file = library::find_file(&exercise_library_dir.join(EXERCISES_FILE));
+    if exercises_file.is_file() {
+        return ExerciseType::value_variants()
+            .iter()
+            .map(|t| (t.clone(), exercises_file.clone()))
+            .collect();
+    }
+    [
+        (ExerciseType::Cooldown, COOLDOWN_FILE),
+        (ExerciseType::Core, CORE_FILE),
+        (ExerciseType::Legs, LEGS_FILE),
+        (ExerciseType::Pull, PULL_FILE),
+        (ExerciseType::Push, PUSH_FILE),
+    ]
+    .into_iter()
+    .map(|(t, file)| (t, library::find_file(&exercise_library_dir.join(file))))
+    .collect::<HashMap<_, _>>()
+}
+
+// --------------------------------------------------
+
+// Load snoozed exercises from a CSV file, a missing file means nothing is snoozed yet.
+// Each snooze lasts the days the policy sets for the type and category of its
+// library exercise, longer in the decay mode. The snoozes that ended are
+// returned apart, for the snooze history
+fn load_snoozed_exercises(
+    snoozed_file_path: &Path,
+    policy: &snooze::SnoozePolicy,
+    mode: snooze::SnoozeMode,
+    library: &[Exercise],
+    now: DateTime<Utc>,
+) -> Result<(Vec<SnoozedExercise>, Vec<snooze::ArchivedSnooze>)> {
+    if !snoozed_file_path.exists() {
+        info!("No snoozed exercises file at {:?}", snoozed_file_path);
+        return Ok((Vec::new(), Vec::new()));
+    }
+    let snoozed_exercises = read_csv::<SnoozedExercise>(path_to_str(snoozed_file_path)?)?;
+    let (snoozed_exercises, ended) = policy.compact(snoozed_exercises, mode, library, now);
+    info!(
+        "Loaded {} snoozed exercises, {} snoozes ended",
+        snoozed_exercises.len(),
+        ended.len()
+    );
+    Ok((snoozed_exercises, ended))
+}
+
+// --------------------------------------------------
+
+// Load relevant exercises for the specified exercise types, reading each file
+// once and keeping only the rows of those types
+fn load_relevant_exercises(
+    exercise_types: &[ExerciseType],
+    file_paths: &HashMap<ExerciseType, PathBuf>,
+) -> Result<Vec<Exercise>> {
+    let mut loaded_files: Vec<&PathBuf> = Vec::new();
+    let mut relevant_exercises = Vec::new();
+    for t in exercise_types {
+        let file_path = file_paths
+            .get(t)
+            .ok_or_else(|| WodgenError::MissingExerciseFile(t.clone()))?;
+        if loaded_files.contains(&file_path) {
+            continue;
+        }
+        loaded_files.push(file_path);
+        let exercises: Vec<Exercise> = library::read_exercises(file_path)?
+            .into_iter()
+            .filter(|e| exercise_types.contains(&e.exercise_type))
+            .collect();
+        info!("Loaded {} exercises from {:?}", exercises.len(), file_path);
+        relevant_exercises.extend(exercises);
+    }
+    info!("Loaded {} exercises", relevant_exercises.len());
+    Ok(relevant_exercises)
+}
+
+// --------------------------------------------------
+
+// Filter out the exercises the profile does not allow
+fn filter_by_profile(
+    exercises: &mut Vec<Exercise>,
+    profile: Option<&profile::Profile>,
+) -> Vec<String> {
+    let Some(profile) = profile else {
+        return Vec::new();
+    };
+    let removed = names_where(exercises, |e| !profile.allows(e));
+    exercises.retain(|e| profile.allows(e));
+    info!(
+        "Filtered exercises for the {} profile, {} exercises remaining",
+        profile.name,
+        exercises.len()
+    );
+    removed
+}
+
+// Names of the exercises matching a predicate
+fn names_where(exercises: &[Exercise], predicate: impl Fn(&Exercise) -> bool) -> Vec<String> {
+    exercises
+        .iter()
+        .filter(|e| predicate(e))
+        .map(|e| e.name.clone())
+        .collect()
+}
+
+// --------------------------------------------------
+
+// Filter exercises based on their active and bodyweight flags, excluded and snoozed exercises,
+// recording what each filter left out. Pinned exercises are kept even when
+// snoozed
+fn filter_exercises(
+    relevant_exercises: &mut Vec<Exercise>,
+    bodyweight: bool,
+    exclude: &[String],
+    snoozed_exercises: &[SnoozedExercise],
+    pinned: &[String],
+) -> explain::Audit {
+    let mut audit = explain::Audit::default();
+    audit.record(
+        explain::Skip::Retired,
+        names_where(relevant_exercises, |e| !e.active),
+    );
+    relevant_exercises.retain(|e| e.active);
+
+    if bodyweight {
+        audit.record(
+            explain::Skip::NotBodyweight,
+            names_where(relevant_exercises, |e| !e.bodyweight),
+        );
+        relevant_exercises.retain(|e| e.bodyweight);
+        info!(
+            "Filtered out non-bodyweight exercises, {} exercies remaining",
+            relevant_exercises.len()
+        );
+    }
+
+    if !exclude.is_empty() {
+        audit.record(
+            explain::Skip::Excluded,
+            names_where(relevant_exercises, |e| exclude.contains(&e.name)),
+        );
+        relevant_exercises.retain(|e| !exclude.contains(&e.name));
+        info!(
+            "Filtered out excluded exercises, {} exercises remaining",
+            relevant_exercises.len()
+        );
+    }
+
+    let is_snoozed = |e: &Exercise| {
+        !pinned.contains(&e.name) && snoozed_exercises.iter().any(|s| s.name == e.name)
+    };
+    audit.record(
+        explain::Skip::Snoozed,
+        names_where(relevant_exercises, is_snoozed),
+    );
+    relevant_exercises.retain(|e| !is_snoozed(e));
+    info!(
+        "Filtered out snoozed exercises, {} exercises remaining",
+        relevant_exercises.len()
+    );
+
+    shuffle_vector(relevant_exercises);
+    info!("Shuffled relevant exercises");
+    audit
+}
+
+// --------------------------------------------------
+
+// Generate a workout, block by block, along with the slots left without an
+// exercise. A pinned exercise is picked in the first group of its type,
+// whatever its category, and the plugin has the last word on every pick
+#[allow(clippy::too_many_arguments)]
+fn generate_workout(
+    relevant_exercises: &mut Vec<Exercise>,
+    pinned: &[String],
+    template: &Template,
+    levels: &calibration::Levels,
+    snoozed_exercises: &mut Vec<SnoozedExercise>,
+    plugin: Option<&plugin::Plugin>,
+    lang: Lang,
+    units: Units,
+) -> Result<(Vec<WorkoutExercise>, Vec<explain::Pick>, Vec<String>)> {
+    let mut workout = Vec::<WorkoutExercise>::new();
+    let mut picks: Vec<explain::Pick> = Vec::new();
+    let mut unfilled = Vec::new();
+
+    // Skill block placeholder
+    workout.push(WorkoutExercise {
+        group: 1,
+        name: String::from(lang.text(Text::SkillBlock)),
+        ..Default::default()
+    });
+
+    // Strength training blocks, the category rotation runs across all of them
+    let mut group = 0;
+    let mut selected: Vec<(Option<&str>, String, Vec<String>)> = Vec::new();
+    for (block_index, block) in template.blocks.iter().enumerate() {
+        if let Some(condition) = &block.when {
+            let selected: Vec<template::Selected> = selected
+                .iter()
+                .map(|(block, label, tags)| template::Selected {
+                    block: *block,
+                    label: label.clone(),
+                    tags,
+                })
+                .collect();
+            if !condition.holds(&selected) {
+                info!(
+                    "Skipping block {:?}, its condition does not hold",
+                    block.name
+                );
+                continue;
+            }
+        }
+        for _ in 0..block.groups {
+            info!("Generating group {}", group + 1);
+            let mut exercises_to_remove = Vec::new();
+            for slot in block.types() {
+                let t = &slot.exercise_type;
+                for _ in 0..random::with_rng(|rng| block.count(t, rng)) {
+                    info!("Picking exercise for slot {}", slot);
+                    let candidates = relevant_exercises
+                        .iter()
+                        .filter(|e| slot.accepts(e))
+                        .filter(|e| levels.allows(e))
+                        .filter(|e| block.accepts(e))
+                        .filter(|e| !exercises_to_remove.contains(&e.name));
+                    let mut eligible: Vec<&Exercise> = candidates
+                        .clone()
+                        .filter(|e| pinned.contains(&e.name))
+                        .collect();
+                    eligible.extend(candidates.filter(|e| {
+                        !pinned.contains(&e.name)
+                            && slot.fits(e, group, levels.of(t), &template.rotation())
+                    }));
+                    let index = match plugin {
+                        Some(plugin) => plugin.choose(&plugin::Request {
+                            version: plugin::VERSION,
+                            slot: slot.to_string(),
+                            group,
+                            block: block.name.as_deref(),
+                            level: &levels.session,
+                            selected: &picks.iter().map(|p| p.name.clone()).collect::<Vec<_>>(),
+                            candidates: eligible.clone(),
+                        })?,
+                        None => (!eligible.is_empty()).then_some(0),
+                    };
+                    let exercise = index.map(|index| eligible[index].clone());
+
+                    if let Some(exercise) = exercise {
+                        info!("Picked exercise {:?}", exercise);
+                        exercises_to_remove.push(exercise.name.clone());
+                        snoozed_exercises.push(SnoozedExercise {
+                            name: exercise.name.clone(),
+                            timestamp: Utc::now(),
+                        });
+                        let label = format!(
+                            "{}{}",
+                            notation::letter(group + 2),
+                            exercises_to_remove.len()
+                        );
+                        selected.push((block.name.as_deref(), label, exercise.tags.clone()));
+                        picks.push(explain::Pick {
+                            group,
+                            block: block_index,
+                            slot: slot.clone(),
+                            name: exercise.name.clone(),
+                        });
+                        let workout_exercise =
+                            WorkoutExercise::from_exercise(group + 2, &exercise, units);
+                        workout.push(workout_exercise);
+                    } else {
+                        info!("No exercise left for slot {}", slot);
+                        unfilled.push(slot.to_string());
+                    }
+                }
+            }
+            relevant_exercises.retain(|e| !exercises_to_remove.contains(&e.name));
+            group += 1;
+        }
+    }
+
+    Ok((workout, picks, unfilled))
+}
+
+// --------------------------------------------------
+
+// Generate candidate workouts from fresh shuffles of the pool and keep the one
+// with the best score, along with the snoozed exercises it leads to. Rarely
+// done exercises tend to be shuffled first, recently picked ones last as their
+// recency weighs, and the preferred exercises ahead of the others.
+#[allow(clippy::too_many_arguments)]
+fn generate_best_workout(
+    relevant_exercises: &[Exercise],
+    pinned: &[String],
+    preferred: &[String],
+    template: &Template,
+    levels: &calibration::Levels,
+    snoozed_exercises: &mut Vec<SnoozedExercise>,
+    history: &history::History,
+    attempts: u32,
+    novelty_bonus: f64,
+    recency: impl Fn(&Exercise) -> f64,
+    plugin: Option<&plugin::Plugin>,
+    lang: Lang,
+    units: Units,
+) -> Result<(Vec<WorkoutExercise>, Vec<explain::Pick>, Vec<String>)> {
+    let attempt = |number: u32| {
+        let mut pool = relevant_exercises.to_vec();
+        random::with_rng(|rng| novelty::shuffle(&mut pool, history, novelty_bonus, &recency, rng));
+        pool.sort_by_key(|e| !preferred.contains(&e.name));
+        let mut snoozed = snoozed_exercises.clone();
+        let (workout, picks, unfilled) = generate_workout(
+            &mut pool,
+            pinned,
+            template,
+            levels,
+            &mut snoozed,
+            plugin,
+            lang,
+            units,
+        )?;
+        let selected: Vec<&Exercise> = workout
+            .iter()
+            .filter_map(|w| {
+                relevant_exercises
+                    .iter()
+                    .find(|e| display::exercise_name(e) == w.name)
+            })
+            .collect();
+        let score = score::score(&selected, levels, history);
+        info!("Attempt {} scored {}", number, score);
+        Ok::<_, anyhow::Error>((score, workout, picks, snoozed, unfilled))
+    };
+
+    let mut best = attempt(1)?;
+    for number in 2..=attempts {
+        let candidate = attempt(number)?;
+        if candidate.0.total() > best.0.total() {
+            best = candidate;
+        }
+    }
+    let (score, workout, picks, snoozed, unfilled) = best;
+    if attempts > 1 {
+        info!("Kept the workout scoring {}", score);
+    }
+    *snoozed_exercises = snoozed;
+    Ok((workout, picks, unfilled))
+}
+
+// --------------------------------------------------
+
+// Pick a substitute for an exercise at another level, preferring the same
+// progression chain, then the same category, then just the same type
+fn find_substitute<'a>(
+    exercise: &Exercise,
+    level: &ExerciseLevel,
+    pool: &'a [Exercise],
+    taken: &[String],
+) -> Option<&'a Exercise> {
+    let candidates: Vec<&Exercise> = pool
+        .iter()
+        .filter(|e| e.exercise_type == exercise.exercise_type)
+        .filter(|e| e.exercise_level == *level)
+        .filter(|e| !taken.contains(&e.name))
+        .collect();
+    let same_progression = candidates
+        .iter()
+        .find(|e| exercise.progression.is_some() && e.progression == exercise.progression);
+    let same_category = candidates
+        .iter()
+        .find(|e| e.exercise_category == exercise.exercise_category);
+    same_progression
+        .or(same_category)
+        .or(candidates.first())
+        .copied()
+}
+
+// --------------------------------------------------
+
+// Add substitutes for the other levels to each exercise of a class workout, so
+// one sheet serves a mixed-level class
+fn add_class_scaling(
+    workout: &mut [WorkoutExercise],
+    pool: &[Exercise],
+    class_level: &ExerciseLevel,
+    lang: Lang,
+) {
+    let mut taken: Vec<String> = pool
+        .iter()
+        .filter(|e| workout.iter().any(|w| w.name == display::exercise_name(e)))
+        .map(|e| e.name.clone())
+        .collect();
+
+    for workout_exercise in workout.iter_mut() {
+        let Some(exercise) = pool
+            .iter()
+            .find(|e| display::exercise_name(e) == workout_exercise.name)
+        else {
+            continue;
+        };
+        let mut options = Vec::new();
+        for (level, text) in [
+            (ExerciseLevel::Beginner, Text::Beginner),
+            (ExerciseLevel::Intermediate, Text::Intermediate),
+            (ExerciseLevel::Advanced, Text::Advanced),
+        ] {
+            if level == *class_level {
+                continue;
+            }
+            if let Some(substitute) = find_substitute(exercise, &level, pool, &taken) {
+                info!(
+                    "Picked {:?} substitute {} for {}",
+                    level, substitute.name, exercise.name
+                );
+                taken.push(substitute.name.clone());
+                options.push(format!(
+                    "{}: {}",
+                    lang.text(text),
+                    display::exercise_name(substitute)
+                ));
+            }
+        }
+        workout_exercise.scaling = options.join("; ");
+    }
+}
+
+// --------------------------------------------------
+
+// Tell partners how to share each exercise: they take turns on the sets, and
+// split the work of the finisher. The cooldown is done together.
+fn add_partner_notes(
+    workout: &mut [WorkoutExercise],
+    partners: u32,
+    finisher_group: Option<u32>,
+    cooldown_group: u32,
+    lang: Lang,
+) {
+    for workout_exercise in workout.iter_mut() {
+        if workout_exercise.group == 0
+            || workout_exercise.group == cooldown_group
+            || workout_exercise.name == lang.text(Text::SkillBlock)
+        {
+            continue;
+        }
+        let text = if Some(workout_exercise.group) == finisher_group {
+            Text::Shared
+        } else {
+            Text::YouGoIGo
+        };
+        let partner_note = format!(
+            "{} ({} {})",
+            lang.text(text),
+            partners,
+            lang.text(Text::Partners)
+        );
+        workout_exercise.notes = if workout_exercise.notes.is_empty() {
+            partner_note
+        } else {
+            format!("{}; {}", workout_exercise.notes, partner_note)
+        };
+    }
+}
+
+// --------------------------------------------------
+
+// Swap the block names in the workout for playful ones
+fn use_playful_names(workout: &mut [WorkoutExercise], lang: Lang) {
+    for workout_exercise in workout.iter_mut() {
+        if workout_exercise.name == lang.text(Text::SkillBlock) {
+            workout_exercise.name = String::from(lang.text(Text::Playground));
+        }
+        if let Some(rest) = workout_exercise
+            .notes
+            .strip_prefix(lang.text(Text::Finisher))
+        {
+            workout_exercise.notes = format!("{}{}", lang.text(Text::GrandFinale), rest);
+        }
+    }
+}
+
+// --------------------------------------------------
+
+// Add a burnout block of one or two accessory exercises done for as many reps as
+// possible, returns whether any exercise was available
+fn add_finisher(
+    workout: &mut Vec<WorkoutExercise>,
+    finisher_exercises: &mut Vec<Exercise>,
+    snoozed_exercises: &mut Vec<SnoozedExercise>,
+    group: u32,
+    lang: Lang,
+    units: Units,
+) -> bool {
+    finisher_exercises.retain(|e| {
+        e.exercise_category == ExerciseCategory::Accessory
+            && !workout.iter().any(|w| w.name == display::exercise_name(e))
+    });
+    let count = random::with_rng(|rng| rng.gen_range(1..=2));
+    let mut added = false;
+    for _ in 0..count {
+        let Some(exercise) = remove_random(finisher_exercises) else {
+            break;
+        };
+        snoozed_exercises.push(SnoozedExercise {
+            name: exercise.name.clone(),
+            timestamp: Utc::now(),
+        });
+        let mut workout_exercise = WorkoutExercise::from_exercise(group, &exercise, units);
+        workout_exercise.sets = String::from("1");
+        for prescription in [
+            &mut workout_exercise.distance,
+            &mut workout_exercise.time,
+            &mut workout_exercise.reps,
+        ] {
+            if prescription == "X" {
+                *prescription = String::from("AMRAP");
+            }
+        }
+        workout_exercise.notes = [lang.text(Text::Finisher), &workout_exercise.notes]
+            .iter()
+            .filter(|note| !note.is_empty())
+            .copied()
+            .collect::<Vec<&str>>()
+            .join(": ");
+        workout.push(workout_exercise);
+        info!("Added finisher exercise {} to workout", exercise.name);
+        added = true;
+    }
+    added
+}
+
+// --------------------------------------------------
+
+// The areas trained by a workout: the types and the muscles of its exercises,
+// in lowercase
+fn trained_areas(workout: &[WorkoutExercise], library: &[Exercise]) -> Vec<String> {
+    let mut areas = Vec::new();
+    for exercise in workout.iter().filter(|e| e.group > 0).filter_map(|w| {
+        library.iter().find(|e| {
+            e.exercise_type != ExerciseType::Cooldown && display::exercise_name(e) == w.name
+        })
+    }) {
+        let exercise_type = exercise
+            .exercise_type
+            .to_possible_value()
+            .map(|v| v.get_name().to_string());
+        for area in exercise_type
+            .into_iter()
+            .chain(exercise.muscles.iter().cloned())
+        {
+            let area = area.trim().to_lowercase();
+            if !areas.contains(&area) {
+                areas.push(area);
+            }
+        }
+    }
+    areas
+}
+
+// Whether a cooldown exercise stretches any of the trained areas
+fn stretches_trained(exercise: &Exercise, trained: &[String]) -> bool {
+    exercise
+        .stretches
+        .iter()
+        .any(|area| trained.contains(&area.trim().to_lowercase()))
+}
+
+// Add the cooldown pieces to the workout, each the first of the shuffled
+// exercises ranked highest, e.g. the pinned ones then those stretching a
+// trained area, and held for the time of the cooldown unless its goal says
+fn add_cooldown_exercises(
+    workout: &mut Vec<WorkoutExercise>,
+    cooldown_exercises: &mut Vec<Exercise>,
+    rank: impl Fn(&Exercise) -> u32,
+    cooldown: rounds::Cooldown,
+    snoozed_exercises: &mut Vec<SnoozedExercise>,
+    num_groups: u32,
+    units: Units,
+) -> Result<(), WodgenError> {
+    for piece in 0..cooldown.pieces {
+        let top = cooldown_exercises.iter().map(&rank).max();
+        let Some(index) = cooldown_exercises.iter().position(|e| Some(rank(e)) == top) else {
+            if piece == 0 {
+                return Err(WodgenError::EmptyPool(ExerciseType::Cooldown));
+            }
+            warn!("Only {} cooldown exercises left", piece);
+            break;
+        };
+        let cooldown_exercise = cooldown_exercises.remove(index);
+        snoozed_exercises.push(SnoozedExercise {
+            name: cooldown_exercise.name.clone(),
+            timestamp: Utc::now(),
+        });
+
+        let mut workout_exercise =
+            WorkoutExercise::from_exercise(num_groups + 2, &cooldown_exercise, units);
+        if cooldown_exercise.exercise_programming != ExerciseProgramming::Time
+            || workout_exercise.goal.is_empty()
+        {
+            workout_exercise.distance.clear();
+            workout_exercise.reps.clear();
+            workout_exercise.time = String::from("X");
+            workout_exercise.goal = Duration::from_seconds(cooldown.seconds).render();
+        }
+        workout.push(workout_exercise);
+        info!(
+            "Added cooldown exercise {} to workout",
+            cooldown_exercise.name
+        );
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Save the workout in each of the requested formats, in files named after
+// `stem` under the `title` header, returning the files written. The CSV and
+// JSON files are read back as the history and keep the untranslated names,
+// the others are written from the `localized` rows
+#[allow(clippy::too_many_arguments)]
+fn save_workout(
+    workouts_dir: &Path,
+    stem: &str,
+    title: &str,
+    workout: &[WorkoutExercise],
+    localized: &[WorkoutExercise],
+    lang: Lang,
+    formats: &[Format],
+    media_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for format in formats {
+        let file_path = workouts_dir.join(format!("{}.{}", stem, format.extension()));
+        let file_name = path_to_str(&file_path)?;
+        match format {
+            Format::Csv => fs::write(file_name, export::render_csv(workout, lang)?)
+                .with_context(|| format!("Failed to write file: {}", file_name))?,
+            Format::Markdown => fs::write(
+                file_name,
+                export::render_markdown(localized, title, lang, media_dir),
+            )
+            .with_context(|| format!("Failed to write file: {}", file_name))?,
+            Format::Html => fs::write(
+                file_name,
+                export::render_html(localized, title, lang, media_dir),
+            )
+            .with_context(|| format!("Failed to write file: {}", file_name))?,
+            Format::Json => fs::write(file_name, export::render_json(workout, title, lang)?)
+                .with_context(|| format!("Failed to write file: {}", file_name))?,
+            Format::Pdf => fs::write(file_name, export::render_pdf(localized, title, lang))
+                .with_context(|| format!("Failed to write file: {}", file_name))?,
+            // Written to the vault by save_note, which knows the run
+            Format::Obsidian => continue,
+        }
+        info!("Saved workout to {}", file_name);
+        files.push(file_path);
+    }
+    Ok(files)
+}
+
+// Save the workout as a note in a folder of the Obsidian vault
+fn save_note(
+    vault: &Path,
+    stem: &str,
+    title: &str,
+    localized: &[WorkoutExercise],
+    lang: Lang,
+    media_dir: &Path,
+    frontmatter: &export::Frontmatter,
+) -> Result<PathBuf> {
+    fs::create_dir_all(vault)
+        .with_context(|| format!("Failed to create the vault folder {:?}", vault))?;
+    let file_path = vault.join(format!("{}.{}", stem, Format::Obsidian.extension()));
+    fs::write(
+        &file_path,
+        export::render_obsidian(localized, title, lang, media_dir, frontmatter),
+    )
+    .with_context(|| format!("Failed to write file: {:?}", file_path))?;
+    info!("Saved the note of the workout to {:?}", file_path);
+    Ok(file_path)
+}
+
+// --------------------------------------------------
+
+// Save the workout in the formats asked for, and as a note of the Obsidian
+// vault, the workouts directory unless the config says, returning the files
+// written
+#[allow(clippy::too_many_arguments)]
+fn save_files(
+    obsidian: &export::Obsidian,
+    workouts_dir: &Path,
+    stem: &str,
+    title: &str,
+    workout: &[WorkoutExercise],
+    localized: &[WorkoutExercise],
+    lang: Lang,
+    formats: &[Format],
+    media_dir: &Path,
+    frontmatter: &export::Frontmatter,
+) -> Result<Vec<PathBuf>> {
+    if !workouts_dir.exists() {
+        std::fs::create_dir_all(workouts_dir)?;
+    }
+    let mut files = save_workout(
+        workouts_dir,
+        stem,
+        title,
+        workout,
+        localized,
+        lang,
+        formats,
+        media_dir,
+    )?;
+    if formats.contains(&Format::Obsidian) {
+        let vault = obsidian.vault.as_deref().unwrap_or(workouts_dir);
+        files.push(save_note(
+            vault,
+            stem,
+            title,
+            localized,
+            lang,
+            media_dir,
+            frontmatter,
+        )?);
+    }
+    Ok(files)
+}
+
+// Weigh the estimated time of each group against the time budget: logged when
+// the workout fits, warned about when it does not and that is allowed, an
+// error otherwise
+fn check_budget(paces: &[pacing::Pace], budget: u32, allow_over_budget: bool) -> Result<()> {
+    let breakdown = pacing::render(paces, budget);
+    let total = pacing::total(paces);
+    if total <= budget * 60 {
+        info!("Estimated time:\n{}", breakdown);
+    } else if allow_over_budget {
+        warn!(
+            "The workout takes {}, past the {} min budget:\n{}",
+            Duration::from_seconds(total).render(),
+            budget,
+            breakdown
+        );
+    } else {
+        return Err(WodgenError::OverBudget(
+            Duration::from_seconds(total).render(),
+            budget,
+            breakdown,
+        )
+        .into());
+    }
+    Ok(())
+}
+
+// Email the workout to the addresses given, through the server of the config
+// or the environment
+fn email_workout(
+    smtp: &email::SmtpConfig,
+    to: &[String],
+    localized: &[WorkoutExercise],
+    title: &str,
+    lang: Lang,
+) -> Result<()> {
+    let smtp = smtp.clone().with_env(|name| std::env::var(name).ok())?;
+    email::send_workout(&smtp, to, localized, title, lang)
+}
+
+// Save the session to the calendar of the config or the environment, under an
+// id from the file names of the workout so a run saved to the same files
+// replaces it
+fn save_to_calendar(
+    caldav: &caldav::CalDavConfig,
+    stem: &str,
+    localized: &[WorkoutExercise],
+    title: &str,
+    lang: Lang,
+) -> Result<()> {
+    let caldav = caldav.clone().with_env(|name| std::env::var(name).ok());
+    let uid = format!("wodgen-{}", stem);
+    let ics = caldav::render_ics(
+        localized,
+        title,
+        lang,
+        Local::now().date_naive(),
+        &uid,
+        caldav.component,
+        Utc::now(),
+    );
+    caldav::save(&caldav, &uid, &ics)
+}
+
+// --------------------------------------------------
+
+// One line about a generated workout for the terminal, e.g. `Workout
+// 2024_06_01: push, core, 7 exercises, ~45 min, saved to ..., 6 exercises
+// snoozed`, with the estimated minutes of the workout
+fn summary(
+    title: &str,
+    types: &[ExerciseType],
+    workout: &[WorkoutExercise],
+    minutes: u32,
+    files: &[PathBuf],
+    snoozed: usize,
+) -> String {
+    let types: Vec<String> = types
+        .iter()
+        .filter_map(|t| t.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    let exercises = workout
+        .iter()
+        .filter(|e| e.group > 1 && !notation::is_warm_up(e))
+        .count();
+    let files: Vec<String> = files.iter().map(|f| f.display().to_string()).collect();
+    let saved = if files.is_empty() {
+        String::from("not saved")
+    } else {
+        format!("saved to {}", files.join(", "))
+    };
+    format!(
+        "Workout {}: {}, {} exercises, ~{} min, {}, {} exercises snoozed",
+        title,
+        types.join(", "),
+        exercises,
+        minutes,
+        saved,
+        snoozed
+    )
+}
+
+// --------------------------------------------------
+
+// Update the snoozed exercises CSV file
+fn update_snoozed_exercises(
+    snoozed_file_path: &Path,
+    mut snoozed_exercises: Vec<SnoozedExercise>,
+) -> Result<()> {
+    if let Some(state_dir) = snoozed_file_path.parent() {
+        fs::create_dir_all(state_dir)?;
+    }
+    // Sort the rows so that the file only changes where the snoozes do
+    snoozed_exercises.sort_by(|a, b| a.name.cmp(&b.name).then(a.timestamp.cmp(&b.timestamp)));
+    write_csv(path_to_str(snoozed_file_path)?, snoozed_exercises)?;
+    info!("Updated snoozed exercises");
+    Ok(())
+}
+
+// Write to the state directory, only warning when it is read-only, e.g. a
+// library mounted from a network share, so the workout is still generated
+fn write_state(what: &str, write: impl FnOnce() -> Result<()>) -> Result<()> {
+    match write() {
+        Err(e) if outcome::is_read_only(&e) => {
+            warn!(
+                "{} not updated, the state directory is read-only: {:#}",
+                what, e
+            );
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+// --------------------------------------------------
+
+// Warn when the library changed since a workout was generated, as the same
+// options may then pick other exercises
+fn warn_if_library_changed(
+    run: &run::Run,
+    file_paths: &HashMap<ExerciseType, PathBuf>,
+) -> Result<()> {
+    if library::content_hash(file_paths)? != run.library_sha256 {
+        warn!(
+            "The exercise library changed since {:?} was generated, the picks may differ",
+            run.workout_file
+        );
+    }
+    Ok(())
+}
+
+// Warn about the goal, the calibrations and the scripts changed since a run,
+// replayed as they are now
+fn warn_if_state_changed(run: &run::Run, state_dir: &Path) -> Result<()> {
+    let changed = |path: &Path| {
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| DateTime::<Utc>::from(modified) > run.generated_at)
+    };
+    let config_file = run
+        .args
+        .config
+        .clone()
+        .map_or_else(paths::default_config_file, Ok)?;
+    let scripts_dir = script::scripts_dir(&config_file);
+    let mut state = vec![state_dir.join(goal::GOAL_FILE), scripts_dir.clone()];
+    if run.args.calibrated {
+        state.push(state_dir.join(calibration::CALIBRATION_FILE));
+    }
+    if scripts_dir.is_dir() {
+        for entry in fs::read_dir(&scripts_dir)? {
+            state.push(entry?.path());
+        }
+    }
+    for path in state.iter().filter(|path| changed(path)) {
+        warn!(
+            "{:?} changed since {:?} was generated, the picks may differ",
+            path, run.workout_file
+        );
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Find the most recently saved workout in the workouts directory
+fn latest_workout(workouts_dir: &Path) -> Result<PathBuf> {
+    history::saved_workouts(workouts_dir)?
+        .pop()
+        .map(|(_, path)| path)
+        .ok_or_else(|| WodgenError::NoWorkouts(workouts_dir.to_path_buf()).into())
+}
+
+// --------------------------------------------------
+
+// Replay the run that saved a workout: its options, seed and snoozed
+// exercises, with the workouts saved before it as the history. Prints
+// the replayed workout and how its picks differ from the saved ones
+fn replay_workout(
+    file: Option<PathBuf>,
+    workouts_dir: Option<PathBuf>,
+    exercise_library_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+    explain: bool,
+) -> Result<()> {
+    let file = match file {
+        Some(file) => file,
+        None => latest_workout(&workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?)?,
+    };
+    let state_dir = match state_dir {
+        Some(dir) => dir,
+        None => exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?,
+    };
+    let saved_run = run::load_run_of(&state_dir, &file)?;
+    info!(
+        "Replaying {:?} with seed {}, generated on {}",
+        file, saved_run.seed, saved_run.generated_at
+    );
+    if saved_run.wodgen != env!("CARGO_PKG_VERSION") {
+        warn!(
+            "Generated by wodgen {}, replayed with {}, the picks may differ",
+            saved_run.wodgen,
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+    let exercise_library_dir = saved_run
+        .args
+        .exercise_library_dir
+        .clone()
+        .map_or_else(paths::default_library_dir, Ok)?;
+    warn_if_library_changed(&saved_run, &map_file_paths(&exercise_library_dir))?;
+    let run_state_dir = saved_run
+        .args
+        .state_dir
+        .clone()
+        .unwrap_or_else(|| exercise_library_dir.clone());
+    warn_if_state_changed(&saved_run, &run_state_dir)?;
+
+    // The history as it was, the workouts of the days before and the sessions
+    // marked done by then
+    let replay_dir = std::env::temp_dir().join(format!("wodgen_replay_{}", std::process::id()));
+    let history_dir = replay_dir.join("workouts");
+    fs::create_dir_all(&history_dir)?;
+    let date = history::workout_date(&file, fs::metadata(&file)?.modified()?);
+    for (modified, path) in history::saved_workouts(file.parent().unwrap_or(Path::new(".")))? {
+        if let Some(file_name) = path.file_name() {
+            if history::workout_date(&path, modified) < date {
+                fs::copy(&path, history_dir.join(file_name))?;
+            }
+        }
+    }
+    let completions: Vec<completion::Completion> =
+        completion::load_completions(file.parent().unwrap_or(Path::new(".")))?
+            .into_iter()
+            .filter(|c| c.completed_at <= saved_run.generated_at)
+            .collect();
+    if !completions.is_empty() {
+        write_csv(
+            path_to_str(&history_dir.join(completion::COMPLETIONS_FILE))?,
+            completions,
+        )?;
+    }
+
+    // The snoozes as they were, at the time of the run
+    let snoozed_file = replay_dir.join(SNOOZED_FILE);
+    write_csv(path_to_str(&snoozed_file)?, &saved_run.snoozed)?;
+    let replayed = generate(Args {
+        seed: Some(saved_run.seed),
+        exercise_library_dir: Some(exercise_library_dir),
+        workouts_dir: Some(history_dir),
+        snoozed_file: Some(snoozed_file),
+        generated_at: Some(saved_run.generated_at),
+        read_only: true,
+        explain,
+        quiet: true,
+        print: false,
+        json: false,
+        compact: false,
+        interactive: false,
+        roster: None,
+        ..saved_run.args
+    });
+    fs::remove_dir_all(&replay_dir)?;
+    let replayed = replayed?.workout;
+
+    // Compare the picks only, the saved workout may have been logged since
+    let picks = |workout: &[WorkoutExercise]| -> Vec<WorkoutExercise> {
+        workout
+            .iter()
+            .filter(|e| e.group > 0)
+            .map(|e| WorkoutExercise {
+                group: e.group,
+                name: e.name.clone(),
+                ..Default::default()
+            })
+            .collect()
+    };
+    let saved = picks(&history::load_workout(&file)?);
+    let replayed = picks(&replayed);
+    print!("{}", diff::render(&diff::diff(&saved, &replayed)));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Print a share code for a saved workout
+fn share_workout(file: Option<PathBuf>, workouts_dir: Option<PathBuf>) -> Result<()> {
+    let file = match file {
+        Some(file) => file,
+        None => latest_workout(&workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?)?,
+    };
+    let title = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let workout = history::load_workout(&file)?;
+    println!("{}", share::encode(&title, &workout)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Save a shared workout next to the generated ones
+fn import_share(code: &str, workouts_dir: Option<PathBuf>, lang: Lang) -> Result<()> {
+    let (title, workout) = share::decode(code)?;
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+    fs::create_dir_all(&workouts_dir)?;
+
+    // Keep the file name from escaping the workouts directory
+    let title: String = title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "-_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let file_name = workouts_dir.join(format!("{}_shared.csv", title.trim_start_matches('.')));
+    let file_name = path_to_str(&file_name)?;
+    fs::write(file_name, export::render_csv(&workout, lang)?)
+        .with_context(|| format!("Failed to write file: {}", file_name))?;
+    info!("Saved shared workout to {}", file_name);
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Print the differences between two saved workouts
+fn diff_workouts(old: &Path, new: &Path) -> Result<()> {
+    let old = history::load_workout(old)?;
+    let new = history::load_workout(new)?;
+    print!("{}", diff::render(&diff::diff(&old, &new)));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Load a saved workout with its language, and its title when saved as JSON.
+// The language of a CSV workout is read from its headers
+fn load_workout_with_lang(
+    file_path: &Path,
+) -> Result<(Option<String>, Lang, Vec<WorkoutExercise>)> {
+    if file_path.extension().is_some_and(|e| e == "json") {
+        let file = history::load_json_workout(file_path)?;
+        return Ok((Some(file.title), file.lang, file.exercises));
+    }
+    let lang = read_headers(path_to_str(file_path)?)?
+        .first()
+        .and_then(|header| Lang::from_header(header))
+        .unwrap_or_default();
+    Ok((None, lang, history::load_workout(file_path)?))
+}
+
+// --------------------------------------------------
+
+// Append a note to an exercise of a saved workout, keeping the workout format
+// and language
+fn annotate_workout(file_path: &Path, exercise: &str, note: &str) -> Result<()> {
+    let file_name = path_to_str(file_path)?;
+    let (title, lang, mut workout) = load_workout_with_lang(file_path)?;
+
+    let name = exercise.trim().to_lowercase();
+    let workout_exercise = workout
+        .iter_mut()
+        .find(|e| {
+            let e = e.name.to_lowercase();
+            e == name || e == to_title_case(&name).to_lowercase()
+        })
+        .ok_or_else(|| {
+            WodgenError::ExerciseNotInWorkout(exercise.to_string(), file_path.to_path_buf())
+        })?;
+    if !workout_exercise.notes.is_empty() {
+        workout_exercise.notes.push_str("; ");
+    }
+    workout_exercise.notes.push_str(note);
+
+    write_workout_file(file_path, title.as_deref(), lang, &workout)?;
+    info!("Annotated {} in {}", exercise, file_name);
+    Ok(())
+}
+
+// Write back a saved workout in its format, JSON when it has a title
+fn write_workout_file(
+    file_path: &Path,
+    title: Option<&str>,
+    lang: Lang,
+    workout: &[WorkoutExercise],
+) -> Result<()> {
+    let file_name = path_to_str(file_path)?;
+    let content = match title {
+        Some(title) => export::render_json(workout, title, lang)?.into_bytes(),
+        None => export::render_csv(workout, lang)?,
+    };
+    fs::write(file_name, content).with_context(|| format!("Failed to write file: {}", file_name))
+}
+
+// --------------------------------------------------
+
+// Names of the state directories inside a backup
+const BACKUP_LIBRARY_DIR: &str = "exercise_library";
+const BACKUP_WORKOUTS_DIR: &str = "workouts";
+const BACKUP_STATE_DIR: &str = "state";
+const BACKUP_CONFIG_DIR: &str = "config";
+
+// The directory of the user config file, holding the equipment registry and
+// the scripts next to it
+fn config_dir(config: Option<PathBuf>) -> Result<PathBuf> {
+    let config_file = config.map_or_else(paths::default_config_file, Ok)?;
+    Ok(config_file
+        .parent()
+        .map_or_else(PathBuf::new, Path::to_path_buf))
+}
+
+// Back up the exercise library, the workouts, the config directory and the
+// state directory, the snoozes live in the library unless a state directory
+// is given
+fn backup_state(
+    file: &Path,
+    exercise_library_dir: Option<PathBuf>,
+    workouts_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+    config: Option<PathBuf>,
+) -> Result<()> {
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?;
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+    let config_dir = config_dir(config)?;
+    let mut dirs = vec![
+        (BACKUP_LIBRARY_DIR, exercise_library_dir.as_path()),
+        (BACKUP_WORKOUTS_DIR, workouts_dir.as_path()),
+        (BACKUP_CONFIG_DIR, config_dir.as_path()),
+    ];
+    if let Some(state_dir) = &state_dir {
+        dirs.push((BACKUP_STATE_DIR, state_dir));
+    }
+    backup::backup(file, &dirs)?;
+    info!("Saved backup to {:?}", file);
+    Ok(())
+}
+
+// Restore a backup, into the data directory unless told otherwise
+fn restore_state(
+    file: &Path,
+    exercise_library_dir: Option<PathBuf>,
+    workouts_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+    config: Option<PathBuf>,
+) -> Result<()> {
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::data_library_dir, Ok)?;
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+    let config_dir = config_dir(config)?;
+    // A backup of a separate state directory goes back into the library by default
+    let state_dir = state_dir.unwrap_or_else(|| exercise_library_dir.clone());
+    let restored = backup::restore(
+        file,
+        &[
+            (BACKUP_LIBRARY_DIR, &exercise_library_dir),
+            (BACKUP_WORKOUTS_DIR, &workouts_dir),
+            (BACKUP_STATE_DIR, &state_dir),
+            (BACKUP_CONFIG_DIR, &config_dir),
+        ],
+    )?;
+    info!("Restored {} files from {:?}", restored, file);
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Sync the snoozes and workouts with a remote, the snoozes live in the library
+// unless a state directory is given
+fn sync_state(
+    remote: Option<String>,
+    config: Option<PathBuf>,
+    exercise_library_dir: Option<PathBuf>,
+    workouts_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+) -> Result<()> {
+    let user_config = config::load_config(&config.map_or_else(paths::default_config_file, Ok)?)?;
+    let sync_config = user_config.sync.with_env(|name| std::env::var(name).ok());
+    let remote = remote
+        .or_else(|| sync_config.remote.clone())
+        .ok_or(WodgenError::NoSyncRemote)?;
+    let remote = sync::Remote::parse(&remote)?.with_credentials(&sync_config);
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+    let state_dir = match state_dir {
+        Some(dir) => dir,
+        None => exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?,
+    };
+    let report = sync::sync(&remote, &workouts_dir, &state_dir)?;
+    info!(
+        "Pulled {} workouts, merged {} snooze files, pushed {} files",
+        report.pulled, report.merged, report.pushed
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Convert an exercise library file to the format of the output file
+fn convert_library(input: &Path, output: &Path) -> Result<()> {
+    let exercises = library::read_exercises(input)?;
+    let count = exercises.len();
+    library::write_exercises(output, exercises)?;
+    info!(
+        "Converted {} exercises from {:?} to {:?}",
+        count, input, output
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Print the best alternatives to an exercise from the whole library
+fn substitute_exercise(
+    name: &str,
+    reason: Option<substitute::Reason>,
+    limit: usize,
+    exercise_library_dir: Option<PathBuf>,
+) -> Result<()> {
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?;
+    let file_paths = map_file_paths(&exercise_library_dir);
+    let exercises = load_relevant_exercises(ExerciseType::value_variants(), &file_paths)?;
+
+    let target = substitute::find_exercise(name, &exercises)
+        .ok_or_else(|| WodgenError::UnknownExercise(name.to_string()))?;
+    let active: Vec<Exercise> = exercises.iter().filter(|e| e.active).cloned().collect();
+    let suggestions = substitute::suggest(target, &active, reason);
+    if suggestions.is_empty() {
+        println!(
+            "No alternatives found for {}",
+            display::exercise_name(target)
+        );
+    }
+    for (rank, suggestion) in suggestions.iter().take(limit).enumerate() {
+        println!(
+            "{}. {} ({:?}, score {})",
+            rank + 1,
+            display::exercise_name(suggestion.exercise),
+            suggestion.exercise.exercise_level,
+            suggestion.score
+        );
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Print the taxonomy of the library, with the exercises of each node
+fn show_taxonomy(exercise_library_dir: Option<PathBuf>) -> Result<()> {
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?;
+    let file_paths = map_file_paths(&exercise_library_dir);
+    let exercises = load_relevant_exercises(ExerciseType::value_variants(), &file_paths)?;
+    let taxonomy = taxonomy::load_taxonomy(&exercise_library_dir)?;
+    print!("{}", taxonomy.render(&exercises));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Print the snoozes of an exercise, the active one and those in the history
+fn show_snooze_history(
+    exercise: &str,
+    exercise_library_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+    snoozed_file: Option<PathBuf>,
+    config: Option<PathBuf>,
+) -> Result<()> {
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?;
+    let state_dir = state_dir.unwrap_or_else(|| exercise_library_dir.clone());
+    let snoozed_file = snoozed_file.unwrap_or_else(|| state_dir.join(SNOOZED_FILE));
+    let user_config = config::load_config(&config.map_or_else(paths::default_config_file, Ok)?)?;
+    let library = load_relevant_exercises(
+        ExerciseType::value_variants(),
+        &map_file_paths(&exercise_library_dir),
+    )?;
+    // Exercises that left the library are still found by their library name
+    let library_exercise = substitute::find_exercise(exercise, &library);
+    let name = library_exercise.map_or_else(|| exercise.trim().to_lowercase(), |e| e.name.clone());
+
+    let mut snoozes = snooze::load_history(&snooze::history_file(&snoozed_file))?;
+    snoozes.retain(|snooze| snooze.name == name);
+    if snoozed_file.exists() {
+        let days = user_config.snooze.days(library_exercise);
+        snoozes.extend(
+            read_csv::<SnoozedExercise>(path_to_str(&snoozed_file)?)?
+                .into_iter()
+                .filter(|snooze| snooze.name == name)
+                .map(|snooze| snooze::ArchivedSnooze {
+                    until: snooze.timestamp + chrono::Duration::days(days),
+                    name: snooze.name,
+                    timestamp: snooze.timestamp,
+                }),
+        );
+    }
+    print!(
+        "{}",
+        snooze::render_history(
+            &display::library_name(&name, &library),
+            &snoozes,
+            Utc::now()
+        )
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Print or save the report of a month or week of training
+#[allow(clippy::too_many_arguments)]
+fn report_training(
+    month: Option<String>,
+    week: Option<String>,
+    format: report::ReportFormat,
+    charts: report::ChartFormat,
+    output: Option<PathBuf>,
+    exercise_library_dir: Option<PathBuf>,
+    workouts_dir: Option<PathBuf>,
+    units: Units,
+) -> Result<()> {
+    let period = match (month, week) {
+        (_, Some(week)) => report::Period::week(&week)?,
+        (Some(month), None) => report::Period::month(&month)?,
+        (None, None) => report::Period::this_month(),
+    };
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?;
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+
+    let file_paths = map_file_paths(&exercise_library_dir);
+    let exercises = load_relevant_exercises(ExerciseType::value_variants(), &file_paths)?;
+    let workouts = history::dated_workouts(&workouts_dir)?;
+    let done = completion::done_dates(&workouts_dir)?;
+    let report = report::Report::build(period, &workouts, &done, &exercises, units);
+    let content = report.render(format, charts)?;
+    match output {
+        Some(path) => {
+            fs::write(&path, content)
+                .with_context(|| format!("Failed to write file: {:?}", path))?;
+            info!("Saved the report for {} to {:?}", report.period.label, path);
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Generate a workout with the options of a past run, leaving out the
+// exercises of its workout but the pinned ones
+fn regenerate(
+    date: Option<NaiveDate>,
+    workouts_dir: Option<PathBuf>,
+    exercise_library_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+) -> Result<()> {
+    let state_dir = match state_dir {
+        Some(dir) => dir,
+        None => exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?,
+    };
+    let (day, saved_run) = run::load_run(&state_dir, date, workouts_dir.as_deref())?;
+    info!("Regenerating the run of {}", day);
+    let mut args = saved_run.args.clone();
+    let exercise_library_dir = args
+        .exercise_library_dir
+        .clone()
+        .map_or_else(paths::default_library_dir, Ok)?;
+
+    if saved_run.workout_file.is_file() {
+        let workout_path = &saved_run.workout_file;
+        let workout = history::load_workout(workout_path)?;
+        let file_paths = map_file_paths(&exercise_library_dir);
+        warn_if_library_changed(&saved_run, &file_paths)?;
+        for exercise in load_relevant_exercises(ExerciseType::value_variants(), &file_paths)? {
+            let name = display::exercise_name(&exercise);
+            if workout.iter().any(|e| e.group > 0 && e.name == name)
+                && !args.exclude.contains(&exercise.name)
+                && !args.pin.contains(&exercise.name)
+            {
+                args.exclude.push(exercise.name);
+            }
+        }
+        info!(
+            "Regenerating {:?} without its exercises, {} excluded",
+            workout_path,
+            args.exclude.len()
+        );
+    }
+    run_generate(args)
+}
+
+// --------------------------------------------------
+
+// Generate the workout of today's weekday in the split, with the other
+// generate options as given
+fn generate_today(config: Option<PathBuf>, args: Vec<String>) -> Result<()> {
+    let config_file = config.map_or_else(paths::default_config_file, Ok)?;
+    let user_config = config::load_config(&config_file)?;
+    let weekday = Local::now().weekday();
+    let day = user_config
+        .split
+        .day(weekday)
+        .ok_or_else(|| WodgenError::RestDay(weekday.to_string()))?;
+    let types: Vec<String> = day
+        .types
+        .iter()
+        .filter_map(|t| t.to_possible_value())
+        .map(|t| t.get_name().to_string())
+        .collect();
+    info!("Split of {}: {}", weekday, types.join(", "));
+
+    // The day sets the defaults, the options given after it win
+    let mut argv = vec![
+        String::from("wodgen"),
+        String::from("--print"),
+        String::from("--config"),
+        path_to_str(&config_file)?.to_string(),
+        String::from("-t"),
+        types.join(","),
+    ];
+    // The groups fitting the duration are the most the day gets, fewer when
+    // the library cannot fill them
+    if let (Some(groups), Some(duration)) = (day.groups(), day.duration) {
+        argv.extend([
+            String::from("-g"),
+            groups.to_string(),
+            String::from("--auto-groups"),
+            String::from("--duration"),
+            duration.to_string(),
+        ]);
+    }
+    if let Some(level) = day.level.as_ref().and_then(|l| l.to_possible_value()) {
+        argv.extend([String::from("-l"), level.get_name().to_string()]);
+    }
+    if let Some(focus) = &day.focus {
+        argv.extend([String::from("--note"), focus.clone()]);
+        // A focus such as "strength" also sets the rounds
+        if let Some(value) = rounds::Focus::from_str(focus.trim(), true)
+            .ok()
+            .and_then(|f| f.to_possible_value())
+        {
+            argv.extend([String::from("--focus"), value.get_name().to_string()]);
+        }
+    }
+    argv.extend(args);
+    let cli = Cli::try_parse_from(argv).unwrap_or_else(|e| e.exit());
+
+    let exercise_library_dir = match &cli.args.exercise_library_dir {
+        Some(dir) => dir.clone(),
+        None => paths::default_library_dir()?,
+    };
+    let exercises = load_relevant_exercises(
+        ExerciseType::value_variants(),
+        &map_file_paths(&exercise_library_dir),
+    )?;
+    user_config.split.validate(&exercises)?;
+    run_generate(cli.args)
+}
+
+// --------------------------------------------------
+
+// Whether wodgen runs for the first time in a terminal, with neither a config
+// nor a library of its own, and no directories given. Never in read-only
+// runs, the setup writes files
+fn is_first_run(args: &Args) -> bool {
+    !args.read_only
+        && args.exercise_library_dir.is_none()
+        && args.config.is_none()
+        && std::io::stdin().is_terminal()
+        && paths::default_config_file().is_ok_and(|file| !file.exists())
+        && paths::data_library_dir().is_ok_and(|dir| !dir.exists())
+}
+
+// Ask about the training, write the config and set up the library from the
+// answers, then generate the workout of the first training day
+fn run_setup(
+    config: Option<PathBuf>,
+    exercise_library_dir: Option<PathBuf>,
+    workouts_dir: Option<PathBuf>,
+) -> Result<()> {
+    let config_file = config.map_or_else(paths::default_config_file, Ok)?;
+    if config_file.exists() {
+        return Err(WodgenError::ConfigExists(config_file).into());
+    }
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::data_library_dir, Ok)?;
+    let bundled = paths::bundled_library_dir();
+
+    let answers = wizard::interview(
+        &mut std::io::stdin().lock(),
+        &mut std::io::stdout(),
+        bundled.is_some(),
+    )?;
+    wizard::scaffold(
+        &answers,
+        &config_file,
+        &exercise_library_dir,
+        bundled.as_deref(),
+    )?;
+    if !exercise_library_dir.is_dir() {
+        return Ok(());
+    }
+
+    let (day, types) = answers.sessions()[0];
+    info!("Generating the workout of {}, the first training day", day);
+    let mut argv = vec![
+        String::from("wodgen"),
+        String::from("--print"),
+        String::from("--config"),
+        path_to_str(&config_file)?.to_string(),
+        String::from("-e"),
+        path_to_str(&exercise_library_dir)?.to_string(),
+        String::from("-t"),
+        types.join(","),
+    ];
+    for (option, value) in [
+        ("-l", answers.level.to_possible_value()),
+        ("--focus", answers.focus.to_possible_value()),
+    ] {
+        if let Some(value) = value {
+            argv.extend([String::from(option), value.get_name().to_string()]);
+        }
+    }
+    if let Some(workouts_dir) = &workouts_dir {
+        argv.extend([String::from("-w"), path_to_str(workouts_dir)?.to_string()]);
+    }
+    let cli = Cli::try_parse_from(argv).unwrap_or_else(|e| e.exit());
+    run_generate(cli.args)
+}
+
+// --------------------------------------------------
+
+// Generate workouts one seed after the other, without saving or printing
+// them, and print the statistics of the picks. The logs are muted meanwhile
+fn simulate_runs(runs: u32, save_failing: Option<PathBuf>, args: Vec<String>) -> Result<()> {
+    let options = args.join(" ");
+    let argv = [String::from("wodgen")].into_iter().chain(args);
+    let args = Cli::try_parse_from(argv).unwrap_or_else(|e| e.exit()).args;
+    let seed = args.seed.unwrap_or_else(rand::random);
+    info!("Simulating {} runs from seed {}", runs, seed);
+
+    let exercise_library_dir = match &args.exercise_library_dir {
+        Some(dir) => dir.clone(),
+        None => paths::default_library_dir()?,
+    };
+    let library = load_relevant_exercises(
+        ExerciseType::value_variants(),
+        &map_file_paths(&exercise_library_dir),
+    )?;
+
+    let max_level = log::max_level();
+    log::set_max_level(LevelFilter::Off);
+    let seeds: Vec<u64> = (0..u64::from(runs))
+        .map(|run| seed.wrapping_add(run))
+        .collect();
+    let simulated: Vec<simulate::Run> = seeds
+        .iter()
+        .map(|seed| {
+            let generated = generate(Args {
+                seed: Some(*seed),
+                exercise_library_dir: Some(exercise_library_dir.clone()),
+                read_only: true,
+                simulated: true,
+                quiet: true,
+                print: false,
+                json: false,
+                compact: false,
+                interactive: false,
+                explain: false,
+                preview_diff: false,
+                roster: None,
+                ..args.clone()
+            });
+            match generated {
+                Ok(generated) => simulate::Run::Generated {
+                    exercises: generated
+                        .workout
+                        .iter()
+                        .filter(|w| w.group > 1)
+                        .filter(|w| {
+                            library.iter().any(|e| {
+                                e.exercise_type != ExerciseType::Cooldown
+                                    && display::exercise_name(e) == w.name
+                            })
+                        })
+                        .map(|w| w.name.clone())
+                        .collect(),
+                    unfilled: generated.unfilled,
+                },
+                Err(error) => simulate::Run::Failed(error.to_string()),
+            }
+        })
+        .collect();
+    log::set_max_level(max_level);
+    if let Some(file_path) = save_failing {
+        let failing: Vec<u64> = seeds
+            .iter()
+            .zip(&simulated)
+            .filter(|(_, run)| matches!(run, simulate::Run::Failed(_)))
+            .map(|(seed, _)| *seed)
+            .collect();
+        simulate::save_seeds(&file_path, &failing, &options)?;
+    }
+
+    // The exercises of the types trained in the runs, to find those never picked
+    let types: Vec<&ExerciseType> = library
+        .iter()
+        .filter(|e| {
+            simulated.iter().any(|run| match run {
+                simulate::Run::Generated { exercises, .. } => {
+                    exercises.contains(&display::exercise_name(e))
+                }
+                simulate::Run::Failed(_) => false,
+            })
+        })
+        .map(|e| &e.exercise_type)
+        .collect();
+    let pool: Vec<String> = library
+        .iter()
+        .filter(|e| e.active && e.exercise_type != ExerciseType::Cooldown)
+        .filter(|e| types.contains(&&e.exercise_type))
+        .map(display::exercise_name)
+        .collect();
+    print!("{}", simulate::render(&simulated, &pool));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Print the exercises locked by their prerequisites, with the best sets logged
+// toward them
+fn show_goals(
+    exercise_library_dir: Option<PathBuf>,
+    workouts_dir: Option<PathBuf>,
+    units: Units,
+) -> Result<()> {
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?;
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+    let file_paths = map_file_paths(&exercise_library_dir);
+    let library = load_relevant_exercises(ExerciseType::value_variants(), &file_paths)?;
+    let history = history::History::load(&workouts_dir)?;
+    print!("{}", prerequisite::render_goals(&library, &history, units));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Mark the saved workout of a day done in the index of the workouts directory
+fn mark_done(date: Option<NaiveDate>, note: String, workouts_dir: Option<PathBuf>) -> Result<()> {
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+    let date = date.unwrap_or_else(|| Local::now().date_naive());
+    if !history::dated_workouts(&workouts_dir)?
+        .iter()
+        .any(|(day, _)| *day == date)
+    {
+        return Err(WodgenError::NoWorkoutOn(date.to_string(), workouts_dir).into());
+    }
+    completion::mark_done(&workouts_dir, date, &note)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Print the streak, adherence and missed sessions from the saved workouts, when
+// the next test day is due, then the progress toward the goal when one is set
+fn show_status(
+    workouts_dir: Option<PathBuf>,
+    exercise_library_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+    config: Option<PathBuf>,
+    units: Units,
+) -> Result<()> {
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+    let workouts = history::dated_workouts(&workouts_dir)?;
+    let done = completion::done_dates(&workouts_dir)?;
+    let today = Local::now().date_naive();
+    let status = status::Status::compute(&workouts, &done, today);
+    print!("{}", status.render());
+    let config_file = config.map_or_else(paths::default_config_file, Ok)?;
+    let user_config = config::load_config(&config_file)?;
+    if let Some(last) = test_day::last_test_day(&workouts_dir)? {
+        let due = user_config.test_day.due(last);
+        if due <= today {
+            println!("Test day due since {}, run wodgen test-day", due);
+        } else {
+            println!("Next test day: {}", due);
+        }
+    }
+
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?;
+    let goal_file = state_file(
+        Some(exercise_library_dir.clone()),
+        state_dir,
+        goal::GOAL_FILE,
+    )?;
+    let goal = goal::load_goal(&goal_file)?;
+    if goal.is_none() && user_config.targets.is_empty() {
+        return Ok(());
+    }
+    let file_paths = map_file_paths(&exercise_library_dir);
+    let library = load_relevant_exercises(ExerciseType::value_variants(), &file_paths)?;
+    if !user_config.targets.is_empty() {
+        let trained: Vec<(NaiveDate, Vec<WorkoutExercise>)> = workouts
+            .into_iter()
+            .filter(|(date, w)| !completion::is_skipped(*date, w, &done, today))
+            .collect();
+        println!("\nWeekly targets:");
+        for target in volume::progress(&trained, &library, &user_config.targets, today) {
+            println!("  {}", target.render());
+        }
+    }
+    if let Some(goal) = goal {
+        let history = history::History::load(&workouts_dir)?;
+        print!("\n{}", goal.render(&library, &history, today, units));
+    }
+    Ok(())
+}
+
+// Print a chart of the last weeks of training
+fn show_chart(
+    metric: chart::Metric,
+    exercise: Option<String>,
+    weeks: u32,
+    workouts_dir: Option<PathBuf>,
+    units: Units,
+) -> Result<()> {
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+    let workouts = history::dated_workouts(&workouts_dir)?;
+    let done = completion::done_dates(&workouts_dir)?;
+    let chart = chart::Chart::build(
+        metric,
+        exercise.as_deref(),
+        &workouts,
+        &done,
+        weeks,
+        Local::now().date_naive(),
+        units,
+    );
+    print!("{}", chart.render());
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Print the heatmap of the training days, or of an exercise type or exercise
+fn show_heatmap(
+    filter: Option<String>,
+    weeks: u32,
+    workouts_dir: Option<PathBuf>,
+    exercise_library_dir: Option<PathBuf>,
+) -> Result<()> {
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+    let workouts = history::dated_workouts(&workouts_dir)?;
+    let today = Local::now().date_naive();
+
+    let heatmap = match filter.as_deref() {
+        None => heatmap::Heatmap::build(
+            String::from("Training days"),
+            &workouts,
+            weeks,
+            today,
+            |_| true,
+        ),
+        Some(filter) => match ExerciseType::from_str(filter.trim(), true) {
+            // The rows of a type are those named after its library exercises
+            Ok(exercise_type) => {
+                let exercise_library_dir =
+                    exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?;
+                let names: Vec<String> = load_relevant_exercises(
+                    std::slice::from_ref(&exercise_type),
+                    &map_file_paths(&exercise_library_dir),
+                )?
+                .iter()
+                .map(display::exercise_name)
+                .collect();
+                heatmap::Heatmap::build(
+                    format!("{:?} exercises", exercise_type),
+                    &workouts,
+                    weeks,
+                    today,
+                    |e| names.contains(&e.name),
+                )
+            }
+            Err(_) => {
+                heatmap::Heatmap::build(to_title_case(filter), &workouts, weeks, today, |e| {
+                    chart::is_exercise(e, filter)
+                })
+            }
+        },
+    };
+    print!("{}", heatmap.render());
+    let active_days = heatmap.active_days();
+    println!(
+        "{} active day{} in the last {} weeks",
+        active_days,
+        if active_days == 1 { "" } else { "s" },
+        weeks
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// The equipment registry next to the user config file
+fn equipment_file(config: Option<PathBuf>) -> Result<PathBuf> {
+    let config_file = config.map_or_else(paths::default_config_file, Ok)?;
+    Ok(equipment::registry_file(&config_file))
+}
+
+// Print the equipment registry
+fn list_equipment(config: Option<PathBuf>, units: Units) -> Result<()> {
+    for item in equipment::load_equipment(&equipment_file(config)?)? {
+        println!("{}", item.render(units));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// A file in the state directory, which defaults to the library
+fn state_file(
+    exercise_library_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+    file_name: &str,
+) -> Result<PathBuf> {
+    let state_dir = match state_dir {
+        Some(dir) => dir,
+        None => exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?,
+    };
+    Ok(state_dir.join(file_name))
+}
+
+// Save the session of a benchmark and print it, with the scores of the
+// earlier sessions, or list the benchmarks when none is named
+fn generate_benchmark(
+    name: Option<String>,
+    exercise_library_dir: Option<PathBuf>,
+    workouts_dir: Option<PathBuf>,
+    lang: Lang,
+    units: Units,
+) -> Result<()> {
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?;
+    let rows = benchmark::load_benchmarks(&exercise_library_dir)?;
+    let Some(name) = name else {
+        let names = benchmark::names(&rows);
+        if names.is_empty() {
+            println!(
+                "No benchmarks, add them to {:?}",
+                exercise_library_dir.join(benchmark::BENCHMARKS_FILE)
+            );
+        }
+        for name in names {
+            println!("{}", name);
+        }
+        return Ok(());
+    };
+
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+    let file_paths = map_file_paths(&exercise_library_dir);
+    let library = load_relevant_exercises(ExerciseType::value_variants(), &file_paths)?;
+    let workout = benchmark::build(&name, &rows, &library, units)?;
+    let sessions = benchmark::sessions(&workouts_dir, &name)?;
+
+    let date = Local::now().format(lang.date_format()).to_string();
+    let stem = benchmark::stem(&date, &name);
+    let title = format!("{} {}", date, name.trim());
+    fs::create_dir_all(&workouts_dir)?;
+    let media_dir = paths::default_media_dir()?;
+    save_workout(
+        &workouts_dir,
+        &stem,
+        &title,
+        &workout,
+        &workout,
+        lang,
+        &[Format::Csv],
+        &media_dir,
+    )?;
+    print!(
+        "{}",
+        export::render_markdown(&workout, &title, lang, &media_dir)
+    );
+    print!("\n{}", benchmark::render_scores(&sessions, units));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Save a test day of the testable exercises and print it, with the day the
+// retest is due
+fn generate_test_day(
+    exercise_library_dir: Option<PathBuf>,
+    workouts_dir: Option<PathBuf>,
+    config: Option<PathBuf>,
+    lang: Lang,
+    units: Units,
+) -> Result<()> {
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?;
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+    let config_file = config.map_or_else(paths::default_config_file, Ok)?;
+    let user_config = config::load_config(&config_file)?;
+    let file_paths = map_file_paths(&exercise_library_dir);
+    let library = load_relevant_exercises(ExerciseType::value_variants(), &file_paths)?;
+    let workout = test_day::build(&library, lang, units);
+    if workout.is_empty() {
+        return Err(WodgenError::NoTestableExercises.into());
+    }
+
+    let today = Local::now().date_naive();
+    let stem = format!(
+        "{}{}",
+        today.format(lang.date_format()),
+        test_day::TEST_DAY_SUFFIX
+    );
+    let title = format!(
+        "{} ({})",
+        today.format(lang.date_format()),
+        lang.text(Text::TestDay)
+    );
+    fs::create_dir_all(&workouts_dir)?;
+    let media_dir = paths::default_media_dir()?;
+    save_workout(
+        &workouts_dir,
+        &stem,
+        &title,
+        &workout,
+        &workout,
+        lang,
+        &[Format::Csv],
+        &media_dir,
+    )?;
+    print!(
+        "{}",
+        export::render_markdown(&workout, &title, lang, &media_dir)
+    );
+    println!(
+        "\nLog the results in {:?}, the retest is due on {}",
+        workouts_dir.join(format!("{}.csv", stem)),
+        user_config.test_day.due(today)
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Set the goal to the exercise of the library it is named after
+fn set_goal(
+    name: &str,
+    by: Option<NaiveDate>,
+    exercise_library_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+) -> Result<()> {
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?;
+    let file_paths = map_file_paths(&exercise_library_dir);
+    let exercises = load_relevant_exercises(ExerciseType::value_variants(), &file_paths)?;
+    let exercise = goal::find_goal_exercise(name, &exercises)
+        .ok_or_else(|| WodgenError::UnknownExercise(name.to_string()))?;
+    let chain = goal::chain(&exercise.name, &exercises);
+    goal::set_goal(
+        &state_file(Some(exercise_library_dir), state_dir, goal::GOAL_FILE)?,
+        goal::Goal {
+            name: name.trim().to_string(),
+            exercise: exercise.name.clone(),
+            by,
+            set_at: Utc::now(),
+        },
+    )?;
+    println!(
+        "Building toward {}: {}",
+        display::exercise_name(exercise),
+        chain
+            .iter()
+            .skip(1)
+            .map(|name| display::library_name(name, &exercises))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    Ok(())
+}
+
+// Drop the goal
+fn clear_goal(exercise_library_dir: Option<PathBuf>, state_dir: Option<PathBuf>) -> Result<()> {
+    let file_path = state_file(exercise_library_dir, state_dir, goal::GOAL_FILE)?;
+    if file_path.exists() {
+        fs::remove_file(&file_path)?;
+        info!("Cleared the goal in {:?}", file_path);
+    }
+    Ok(())
+}
+
+// Set the training max of a weighted exercise of the library
+fn set_training_max(
+    name: &str,
+    weight: units::Weight,
+    scheme: training_max::Scheme,
+    increment: units::Weight,
+    exercise_library_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+) -> Result<()> {
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?;
+    let file_paths = map_file_paths(&exercise_library_dir);
+    let exercises = load_relevant_exercises(ExerciseType::value_variants(), &file_paths)?;
+    let exercise = substitute::find_exercise(name, &exercises)
+        .ok_or_else(|| WodgenError::UnknownExercise(name.to_string()))?;
+    if exercise.bodyweight {
+        return Err(WodgenError::BodyweightTrainingMax(exercise.name.clone()).into());
+    }
+    training_max::set_training_max(
+        &state_file(
+            Some(exercise_library_dir),
+            state_dir,
+            training_max::TRAINING_MAXES_FILE,
+        )?,
+        training_max::TrainingMax {
+            name: exercise.name.clone(),
+            training_max_kg: weight.kilograms(),
+            scheme,
+            increment_kg: increment.kilograms(),
+            set_at: Utc::now(),
+        },
+    )
+}
+
+// Print the training maxes
+fn list_training_maxes(
+    exercise_library_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+    units: Units,
+) -> Result<()> {
+    let file_path = state_file(
+        exercise_library_dir,
+        state_dir,
+        training_max::TRAINING_MAXES_FILE,
+    )?;
+    for tm in training_max::load_training_maxes(&file_path)? {
+        println!(
+            "{}: {} ({:?}, +{}, set on {})",
+            to_title_case(&tm.name),
+            units::Weight::from_kilograms(tm.training_max_kg).render(units),
+            tm.scheme,
+            units::Weight::from_kilograms(tm.increment_kg).render(units),
+            tm.set_at.date_naive()
+        );
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Log a wellness check-in and print the readiness it leads to
+fn check_in(
+    check_in: wellness::CheckIn,
+    exercise_library_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+) -> Result<()> {
+    let file_path = state_file(exercise_library_dir, state_dir, wellness::WELLNESS_FILE)?;
+    let score = check_in.score();
+    wellness::add_check_in(&file_path, check_in)?;
+    let check_ins = wellness::load_check_ins(&file_path)?;
+    if let Some(rolling) = wellness::rolling_score(&check_ins, Utc::now()) {
+        println!(
+            "Wellness {:.0}/100, {:.0}/100 over the last week: {:?} readiness",
+            score,
+            rolling,
+            wellness::Readiness::of_score(rolling)
+        );
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Calibrate the levels of the exercise types from the logged numbers, then the
+// answers, and print the calibrated levels
+fn calibrate(
+    answers: Vec<calibration::LoggedNumber>,
+    import: Option<PathBuf>,
+    exercise_library_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+) -> Result<()> {
+    let file_path = state_file(
+        exercise_library_dir,
+        state_dir,
+        calibration::CALIBRATION_FILE,
+    )?;
+    let mut numbers = match import {
+        Some(import) => calibration::import_numbers(&import)?,
+        None => Vec::new(),
+    };
+    numbers.extend(answers);
+    let calibrations = if numbers.is_empty() {
+        calibration::load_calibrations(&file_path)?
+    } else {
+        calibration::calibrate(&file_path, &numbers)?
+    };
+    for c in calibrations {
+        println!(
+            "{:?}: {:?} ({}, on {})",
+            c.exercise_type,
+            c.exercise_level,
+            c.test.describe(c.value),
+            c.calibrated_at.date_naive()
+        );
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Zip the latest workout saved for a day with its thumbnails
+fn export_bundle(
+    date: NaiveDate,
+    output: Option<PathBuf>,
+    workouts_dir: Option<PathBuf>,
+    media_dir: Option<PathBuf>,
+) -> Result<()> {
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+    let media_dir = media_dir.map_or_else(paths::default_media_dir, Ok)?;
+    let file_path = workout_on(&workouts_dir, date)?;
+    let (title, lang, workout) = load_workout_with_lang(&file_path)?;
+    let stem = file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let title = title.unwrap_or_else(|| stem.clone());
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("{}.zip", stem)));
+
+    let manifest = bundle::write_bundle(&output, &title, date, lang, &workout, &media_dir)?;
+    println!(
+        "Bundled workout {} into {}: {} exercises, {} files",
+        title,
+        output.display(),
+        manifest.exercises,
+        manifest.files.len() + 1
+    );
+    Ok(())
+}
+
+// The latest workout saved for a day
+fn workout_on(workouts_dir: &Path, date: NaiveDate) -> Result<PathBuf> {
+    Ok(history::saved_workouts(workouts_dir)?
+        .into_iter()
+        .filter(|(modified, path)| history::workout_date(path, *modified) == date)
+        .map(|(_, path)| path)
+        .next_back()
+        .ok_or_else(|| WodgenError::NoWorkoutOn(date.to_string(), workouts_dir.to_path_buf()))?)
+}
+
+// Write the interval blocks of the workout of a day to a file ergometer apps
+// load
+fn export_intervals(
+    date: NaiveDate,
+    format: ergometer::ErgFormat,
+    output: Option<PathBuf>,
+    rest: u32,
+    ftp: u32,
+    workouts_dir: Option<PathBuf>,
+) -> Result<()> {
+    let workouts_dir = workouts_dir.map_or_else(paths::default_workouts_dir, Ok)?;
+    let file_path = workout_on(&workouts_dir, date)?;
+    let (title, lang, workout) = load_workout_with_lang(&file_path)?;
+    let stem = file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let title = title.unwrap_or_else(|| stem.clone());
+    let blocks = ergometer::blocks(&workout, rest);
+    if blocks.is_empty() {
+        return Err(WodgenError::NoIntervals(file_path).into());
+    }
+    let output =
+        output.unwrap_or_else(|| PathBuf::from(format!("{}.{}", stem, format.extension())));
+    let content = match format {
+        ergometer::ErgFormat::Zwo => ergometer::render_zwo(&blocks, &title, lang),
+        ergometer::ErgFormat::Erg => ergometer::render_erg(&blocks, &title, lang, ftp),
+    };
+    fs::write(&output, content).with_context(|| format!("Failed to write file: {:?}", output))?;
+    let labels: Vec<&str> = blocks.iter().map(|b| b.label.as_str()).collect();
+    println!(
+        "Exported the intervals of {} ({}) to {}",
+        title,
+        labels.join(", "),
+        output.display()
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Download the remote media referenced anywhere in the exercise library
+fn fetch_media(exercise_library_dir: Option<PathBuf>, media_dir: Option<PathBuf>) -> Result<()> {
+    let exercise_library_dir = exercise_library_dir.map_or_else(paths::default_library_dir, Ok)?;
+    let media_dir = media_dir.map_or_else(paths::default_media_dir, Ok)?;
+
+    let file_paths = map_file_paths(&exercise_library_dir);
+    let exercises = load_relevant_exercises(ExerciseType::value_variants(), &file_paths)?;
+    let (downloaded, failed) = media::fetch(&exercises, &media_dir)?;
+    info!(
+        "Downloaded {} media files to {:?}, {} failed",
+        downloaded, media_dir, failed
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+
+// Generate a single workout, or one per athlete when a roster is given
+fn run_generate(args: Args) -> Result<()> {
+    match args.roster.clone() {
+        Some(roster_path) => generate_for_roster(args, &roster_path),
+        None => generate(args).map(|_| ()),
+    }
+}
+
+// --------------------------------------------------
+
+// A generated workout, with the slots of the template left without an exercise
+struct Generated {
+    workout: Vec<WorkoutExercise>,
+    unfilled: Vec<String>,
+}
+
+// Generate a workout and save it, returning it
+fn generate(args: Args) -> Result<Generated> {
+    let run_args = args.clone();
+
+    // The constraints file takes precedence over the options, and is read again
+    // when the run is regenerated
+    let constraints = args
+        .constraints
+        .as_deref()
+        .map(constraints::load_constraints)
+        .transpose()?
+        .unwrap_or_default();
+    let args = constraints.apply(args)?;
+
+    // Seed the random picks, saved with the workout to replay them
+    let seed = args.seed.unwrap_or_else(rand::random);
+    random::reseed(seed);
+    info!("Seed: {}", seed);
+    let exercise_types = args.types;
+    info!("Exercise types: {:?}", exercise_types);
+
+    // Resolve the directories, falling back to the platform defaults
+    let exercise_library_dir = match args.exercise_library_dir {
+        Some(dir) => dir,
+        None => paths::default_library_dir()?,
+    };
+    info!("Exercise library: {:?}", exercise_library_dir);
+
+    // --travel and --kids are shortcuts for their profiles
+    let profile_name = match &args.profile {
+        Some(name) => Some(name.as_str()),
+        None if args.travel => Some("travel"),
+        None if args.kids => Some("kids"),
+        None => None,
+    };
+    let profile = profile_name
+        .map(|name| profile::find_profile(name, &exercise_library_dir))
+        .transpose()?;
+    info!("Profile: {:?}", profile_name);
+    let exercise_level = profile
+        .as_ref()
+        .and_then(|p| p.level.clone())
+        .unwrap_or(args.level);
+    info!("Exercise level: {:?}", exercise_level);
+    let num_groups = args.groups;
+    info!("Number of groups: {:?}", num_groups);
+    // Set by a roster, the constraints or the split rather than --groups
+    if num_groups > MAX_GROUPS {
+        return Err(WodgenError::TooManyGroups(num_groups, MAX_GROUPS).into());
+    }
+    let bodyweight = args.bodyweight || profile.as_ref().is_some_and(|p| p.bodyweight);
+    info!("Bodyweight: {:?}", bodyweight);
+    info!("Language: {:?}", args.lang);
+    info!("Units: {:?}", args.units);
+    let workouts_dir = match args.workouts_dir {
+        Some(dir) => dir,
+        None => paths::default_workouts_dir()?,
+    };
+    let media_dir = args.media_dir.map_or_else(paths::default_media_dir, Ok)?;
+    let config_file = args.config.map_or_else(paths::default_config_file, Ok)?;
+    let mut user_config = config::load_config(&config_file)?;
+    let registry = match &constraints.equipment {
+        Some(equipment) => equipment.clone(),
+        None => equipment::load_equipment(&equipment::registry_file(&config_file))?,
+    };
+    equipment::extend_inventory(&mut user_config.inventory, &registry);
+    let scripts = script::load_scripts(&script::scripts_dir(&config_file))?;
+    let state_dir = args
+        .state_dir
+        .unwrap_or_else(|| exercise_library_dir.clone());
+    let snoozed_file_path = args
+        .snoozed_file
+        .unwrap_or_else(|| state_dir.join(SNOOZED_FILE));
+
+    // Train the calibrated exercise types at their levels
+    let calibrations = if args.calibrated {
+        calibration::load_calibrations(&state_dir.join(calibration::CALIBRATION_FILE))?
+    } else {
+        Vec::new()
+    };
+    for c in &calibrations {
+        info!(
+            "Calibrated level of {:?}: {:?}",
+            c.exercise_type, c.exercise_level
+        );
+    }
+    let levels = calibration::Levels::calibrated(exercise_level.clone(), &calibrations);
+
+    // Map exercise types to their corresponding file paths
+    let file_paths = map_file_paths(&exercise_library_dir);
+    let library_sha256 = library::content_hash(&file_paths)?;
+    info!("Exercise library hash: {}", library_sha256);
+
+    // Load exercises
+    let mut cooldown_exercises = load_relevant_exercises(&[ExerciseType::Cooldown], &file_paths)?;
+    let library_exercises = load_relevant_exercises(ExerciseType::value_variants(), &file_paths)?;
+    // A replayed run happens at the time it was generated
+    let now = args.generated_at.unwrap_or_else(Utc::now);
+    let today = now.with_timezone(&Local).date_naive();
+    let (mut snoozed_exercises, mut ended_snoozes) = load_snoozed_exercises(
+        &snoozed_file_path,
+        &user_config.snooze,
+        args.snooze_mode,
+        &library_exercises,
+        now,
+    )?;
+    info!("Snooze mode: {:?}", args.snooze_mode);
+
+    // Release the snoozes of the workouts not done within the days the policy
+    // gives them, their exercises were never trained
+    let done = completion::done_dates(&workouts_dir)?;
+    let dated_workouts = history::dated_workouts(&workouts_dir)?;
+    if let Some(days) = user_config.snooze.release {
+        let skipped: Vec<(NaiveDate, Vec<WorkoutExercise>)> = dated_workouts
+            .iter()
+            .filter(|(date, w)| {
+                completion::is_skipped(*date, w, &done, today - chrono::Duration::days(days))
+            })
+            .cloned()
+            .collect();
+        let released = snooze::release(&mut snoozed_exercises, &skipped, &library_exercises, now);
+        if !released.is_empty() {
+            info!(
+                "Released {} snoozes of workouts not done within {} days",
+                released.len(),
+                days
+            );
+        }
+        ended_snoozes.extend(released);
+    }
+
+    // The decay mode draws the snoozed exercises less often instead of leaving
+    // them out, weighing them as they were before this workout, and the
+    // scripts scale the weights of the exercises they like more or less
+    let decay = args.snooze_mode == snooze::SnoozeMode::Decay;
+    let snoozed_before = snoozed_exercises.clone();
+    let weights = scripts.weights(&library_exercises)?;
+    // The exercises on the way to the goal are drawn more often
+    let goal_chain = goal::load_goal(&state_dir.join(goal::GOAL_FILE))?
+        .map_or_else(Vec::new, |g| goal::chain(&g.exercise, &library_exercises));
+    let recency = |e: &Exercise| {
+        let mut weight = weights.get(&e.name).copied().unwrap_or(1.0);
+        if goal_chain.contains(&e.name) {
+            weight *= goal::GOAL_WEIGHT;
+        }
+        if decay {
+            weight * user_config.snooze.recency(e, &snoozed_before, now)
+        } else {
+            weight
+        }
+    };
+    let hard_snoozed: &[SnoozedExercise] = if decay { &[] } else { &snoozed_before };
+
+    // Filter out excluded and snoozed exercises from cooldown exercises, but
+    // for the pinned ones
+    cooldown_exercises.retain(|e| {
+        !args.exclude.contains(&e.name)
+            && (args.pin.contains(&e.name)
+                || !hard_snoozed.iter().any(|snoozed| snoozed.name == e.name))
+    });
+    filter_by_profile(&mut cooldown_exercises, profile.as_ref());
+    cooldown_exercises.retain(|e| e.active && constraints.allows(e));
+    equipment::filter_by_equipment(&mut cooldown_exercises, &registry);
+    random::with_rng(|rng| {
+        novelty::shuffle(
+            &mut cooldown_exercises,
+            &history::History::default(),
+            0.0,
+            recency,
+            rng,
+        )
+    });
+
+    // The template defaults to --groups super-sets of --types
+    let mut template = match &args.template {
+        Some(template_path) => template::load_template(template_path)?,
+        None => Template::from_types(&exercise_types, num_groups),
+    };
+    template.fill_types(&exercise_types)?;
+    template.fill_counts(&args.per_group);
+    if args.category_strategy.is_some() {
+        template.category_strategy = args.category_strategy;
+    }
+    info!("Category strategy: {:?}", template.rotation().strategy);
+    template.fill_tags(&constraints.tags);
+    let taxonomy = taxonomy::load_taxonomy(&exercise_library_dir)?;
+    taxonomy.resolve(&mut template);
+    if let Some(profile) = &profile {
+        profile.cap_groups(&mut template);
+    }
+
+    let mut relevant_exercises = load_relevant_exercises(&template.types(), &file_paths)?;
+    let history = history::History::load(&workouts_dir)?;
+
+    // Keep the unfiltered exercises around to explain the picks
+    let library = if args.explain {
+        relevant_exercises.clone()
+    } else {
+        Vec::new()
+    };
+    let initially_snoozed: Vec<String> = hard_snoozed
+        .iter()
+        .map(|e| e.name.clone())
+        .filter(|name| !args.pin.contains(name))
+        .collect();
+
+    // Filter exercises
+    let mut audit = filter_exercises(
+        &mut relevant_exercises,
+        bodyweight,
+        &args.exclude,
+        hard_snoozed,
+        &args.pin,
+    );
+    let excluded_tags = names_where(&relevant_exercises, |e| !constraints.allows(e));
+    relevant_exercises.retain(|e| constraints.allows(e));
+    audit.record(explain::Skip::Excluded, excluded_tags);
+    let unequipped = equipment::filter_by_equipment(&mut relevant_exercises, &registry);
+    audit.record(explain::Skip::Unequipped, unequipped.clone());
+    let locked = prerequisite::filter_locked(&mut relevant_exercises, &history, &library_exercises);
+    audit.record(explain::Skip::Locked, locked.clone());
+    // Profiles that substitute pick from the whole pool and swap what they rule
+    // out afterwards, the others never see it
+    let substitute = profile.as_ref().is_some_and(|p| p.substitute);
+    if !substitute {
+        let removed = filter_by_profile(&mut relevant_exercises, profile.as_ref());
+        audit.record(explain::Skip::Profile, removed);
+    }
+
+    // Steer the picks away from the muscles that reached their MRV this week
+    // and toward those below their MV, leaving out the sessions skipped
+    let trained: Vec<(NaiveDate, Vec<WorkoutExercise>)> = dated_workouts
+        .iter()
+        .filter(|(date, w)| !completion::is_skipped(*date, w, &done, today))
+        .cloned()
+        .collect();
+    let weekly_sets = volume::weekly_sets(&trained, &library_exercises, today);
+    let flags = volume::check(&weekly_sets, &library_exercises, &user_config.volume);
+    for flag in &flags {
+        match flag.imbalance {
+            volume::Imbalance::BelowMv => warn!(
+                "{} sets of {} this week, below its MV of {}",
+                flag.sets, flag.muscle, flag.landmark.mv
+            ),
+            volume::Imbalance::AtMrv => warn!(
+                "{} sets of {} this week, at its MRV of {}, leaving out its exercises",
+                flag.sets, flag.muscle, flag.landmark.mrv
+            ),
+        }
+    }
+    let rebalance = volume::rebalance(&mut relevant_exercises, &flags);
+    audit.record(explain::Skip::Rested, rebalance.rested.clone());
+    // Then toward the areas behind their weekly targets
+    let targets = volume::progress(&trained, &library_exercises, &user_config.targets, today);
+    for target in targets.iter().filter(|t| !t.is_met()) {
+        info!("Behind the weekly target of {}", target.render());
+    }
+    let mut preferred = rebalance.preferred.clone();
+    for name in volume::close_gaps(&relevant_exercises, &targets) {
+        if !preferred.contains(&name) {
+            preferred.push(name);
+        }
+    }
+
+    // Go easy on the sore areas
+    let sore = soreness::normalize(&args.sore);
+    let sore_rested = soreness::rest_sore_areas(&mut relevant_exercises, &sore, &taxonomy);
+    audit.record(explain::Skip::Sore, sore_rested.clone());
+    // Exercises above the level stay in the pool for the class scaling, but
+    // are never picked
+    audit.record(
+        explain::Skip::Level,
+        names_where(&relevant_exercises, |e| !levels.allows(e)),
+    );
+
+    // Fail before picking anything when the pool cannot fill the template, or
+    // drop the groups it cannot fill
+    if args.auto_groups {
+        let budget = args.duration.map(|minutes| preflight::Budget {
+            minutes,
+            focus: args.focus,
+            level: &exercise_level,
+        });
+        preflight::fit_groups(
+            &mut template,
+            &relevant_exercises,
+            &args.pin,
+            &levels,
+            budget.as_ref(),
+        )?;
+    } else {
+        preflight::check(&template, &relevant_exercises, &args.pin, &levels)?;
+    }
+
+    // Generate workout
+    let plugin = args.plugin.as_deref().and_then(plugin::Plugin::new);
+    let (mut workout, mut picks, unfilled) = generate_best_workout(
+        &relevant_exercises,
+        &args.pin,
+        &preferred,
+        &template,
+        &levels,
+        &mut snoozed_exercises,
+        &history,
+        args.attempts,
+        args.novelty_bonus,
+        recency,
+        plugin.as_ref(),
+        args.lang,
+        args.units,
+    )?;
+    if let Some(profile) = profile.as_ref().filter(|_| substitute) {
+        profile.substitute_picks(
+            &mut workout,
+            &mut picks,
+            &relevant_exercises,
+            &levels,
+            &mut snoozed_exercises,
+            args.units,
+        );
+        filter_by_profile(&mut relevant_exercises, Some(profile));
+    }
+    let num_groups = workout.iter().map(|e| e.group).max().unwrap_or(1) - 1;
+    let strength_groups = num_groups;
+    for name in &args.pin {
+        if !picks.iter().any(|p| &p.name == name)
+            && !cooldown_exercises.iter().any(|e| &e.name == name)
+        {
+            warn!(
+                "Pinned exercise {} is not in the workout, it was filtered out or its type is not trained",
+                name
+            );
+        }
+    }
+
+    // Let the user review the picks, only the exercises they keep get snoozed
+    if args.interactive {
+        let picked: Vec<String> = picks.iter().map(|p| p.name.clone()).collect();
+        let review = interactive::Review {
+            pool: &relevant_exercises,
+            template: &template,
+            levels: &levels,
+            units: args.units,
+        };
+        review.run(
+            &mut std::io::stdin().lock(),
+            &mut std::io::stdout(),
+            &mut workout,
+            &mut picks,
+        )?;
+        snoozed_exercises
+            .retain(|s| !picked.contains(&s.name) || picks.iter().any(|p| p.name == s.name));
+        for pick in picks.iter().filter(|p| !picked.contains(&p.name)) {
+            snoozed_exercises.push(SnoozedExercise {
+                name: pick.name.clone(),
+                timestamp: Utc::now(),
+            });
+        }
+    }
+
+    if args.explain {
+        let filters = explain::Filters {
+            levels: &levels,
+            bodyweight,
+            exclude: &args.exclude,
+            snoozed: &initially_snoozed,
+            profile: profile.as_ref(),
+            rested: &rebalance.rested,
+            sore: &sore_rested,
+            unequipped: &unequipped,
+            locked: &locked,
+        };
+        print!(
+            "{}",
+            explain::render(&picks, &library, &template, &filters, &audit)
+        );
+    }
+
+    // Add the scaling options for the other levels
+    if args.class {
+        add_class_scaling(
+            &mut workout,
+            &relevant_exercises,
+            &exercise_level,
+            args.lang,
+        );
+    }
+
+    // Replace the skill block placeholder with a structured practice
+    if args.emom {
+        let rows = skill::emom(
+            &relevant_exercises,
+            &levels,
+            &workout,
+            &goal_chain,
+            &mut snoozed_exercises,
+            args.lang,
+            args.units,
+        );
+        workout.splice(0..1, rows);
+    }
+
+    // Add the burnout block between the strength groups and the cooldown
+    let mut num_groups = num_groups;
+    let mut finisher_group = None;
+    if let Some(finisher_type) = &args.finisher {
+        let mut finisher_exercises =
+            load_relevant_exercises(std::slice::from_ref(finisher_type), &file_paths)?;
+        filter_exercises(
+            &mut finisher_exercises,
+            bodyweight,
+            &args.exclude,
+            &snoozed_exercises,
+            &args.pin,
+        );
+        filter_by_profile(&mut finisher_exercises, profile.as_ref());
+        finisher_exercises.retain(|e| levels.allows(e));
+        if add_finisher(
+            &mut workout,
+            &mut finisher_exercises,
+            &mut snoozed_exercises,
+            num_groups + 2,
+            args.lang,
+            args.units,
+        ) {
+            finisher_group = Some(num_groups + 2);
+            num_groups += 1;
+        } else {
+            warn!(
+                "No {:?} accessory exercises left for the finisher",
+                finisher_type
+            );
+        }
+    }
+
+    // Add the cooldown, pinned exercises first, then those stretching the
+    // trained or sore areas
+    let mut trained = trained_areas(&workout, &library_exercises);
+    for area in &sore {
+        if !trained.contains(area) {
+            trained.push(area.clone());
+        }
+    }
+    info!("Trained and sore areas: {:?}", trained);
+    add_cooldown_exercises(
+        &mut workout,
+        &mut cooldown_exercises,
+        |e| {
+            if args.pin.contains(&e.name) {
+                2
+            } else {
+                u32::from(stretches_trained(e, &trained))
+            }
+        },
+        args.focus.cooldown(num_groups),
+        &mut snoozed_exercises,
+        num_groups,
+        args.units,
+    )?;
+
+    if let Some(partners) = args.partners {
+        add_partner_notes(
+            &mut workout,
+            partners,
+            finisher_group,
+            num_groups + 2,
+            args.lang,
+        );
+    }
+
+    if profile.as_ref().is_some_and(|p| p.playful) {
+        use_playful_names(&mut workout, args.lang);
+    }
+
+    // Prescribe the loads of the weighted exercises from their training maxes,
+    let training_maxes =
+        training_max::load_training_maxes(&state_dir.join(training_max::TRAINING_MAXES_FILE))?;
+    // and ramp up to the primary ones with warm-up sets
+    let mut warm_ups = Vec::new();
+    for (index, e) in workout.iter_mut().enumerate().filter(|(_, e)| e.group > 0) {
+        let Some(tm) = training_maxes
+            .iter()
+            .find(|tm| display::library_name(&tm.name, &library_exercises) == e.name)
+        else {
+            continue;
+        };
+        let exercise = library_exercises
+            .iter()
+            .find(|exercise| exercise.name == tm.name);
+        let implement = exercise.map_or(plates::Implement::Barbell, plates::Implement::of);
+        let sessions = tm.sessions(&e.name, &dated_workouts);
+        let (sets, prescription) =
+            tm.prescribe(sessions, &user_config.inventory, implement, args.units);
+        if exercise.is_some_and(|e| e.exercise_category == ExerciseCategory::Primary) {
+            let rows: Vec<WorkoutExercise> = tm
+                .warm_up(sessions, &user_config.inventory, implement, args.units)
+                .into_iter()
+                .map(|goal| WorkoutExercise {
+                    group: e.group,
+                    name: String::from(args.lang.text(Text::WarmUp)),
+                    sets: String::from("1"),
+                    goal,
+                    kind: notation::RowKind::WarmUp,
+                    ..Default::default()
+                })
+                .collect();
+            warm_ups.push((index, rows));
+        }
+        info!("Prescribed {} for {}", prescription, e.name);
+        e.sets = sets;
+        e.notes = [prescription, e.notes.clone()]
+            .iter()
+            .filter(|note| !note.is_empty())
+            .cloned()
+            .collect::<Vec<String>>()
+            .join("; ");
+    }
+    for (index, rows) in warm_ups.into_iter().rev() {
+        workout.splice(index..index, rows);
+    }
+
+    // Recommend the rounds of the strength groups and what they add up to, at
+    // the readiness of the athlete, leaving out the check-ins after a replayed
+    // run
+    let mut check_ins = wellness::load_check_ins(&state_dir.join(wellness::WELLNESS_FILE))?;
+    check_ins.retain(|c| c.checked_in_at <= now);
+    let readiness = args.readiness.resolve(&check_ins, now);
+    info!("Readiness: {:?}", readiness);
+    rounds::prescribe(
+        &mut workout,
+        2..strength_groups + 2,
+        args.focus,
+        &exercise_level,
+        readiness,
+        args.lang,
+    );
+
+    // Let the scripts have the last word on the prescriptions
+    for e in &mut workout {
+        if let Some(exercise) = library_exercises
+            .iter()
+            .find(|exercise| display::exercise_name(exercise) == e.name)
+        {
+            scripts.prescribe(exercise, e)?;
+        }
+    }
+
+    // Estimate the energy spent from the MET values and the body mass
+    if let Some(body_mass_kg) = user_config.body_mass_kg {
+        match energy::estimate_kcal(&workout, &library_exercises, body_mass_kg) {
+            Some(kcal) => {
+                info!("Estimated energy: {:.0} kcal", kcal);
+                workout.insert(
+                    0,
+                    WorkoutExercise {
+                        group: 0,
+                        name: String::from(args.lang.text(Text::Energy)),
+                        notes: format!("~{:.0} kcal", kcal),
+                        ..Default::default()
+                    },
+                );
+            }
+            None => warn!("No exercise in the workout has a MET value to estimate the energy"),
+        }
+    }
+
+    // Note the sore areas, kept with the session for later analysis
+    if !sore.is_empty() {
+        workout.insert(
+            0,
+            WorkoutExercise {
+                group: 0,
+                name: String::from(args.lang.text(Text::Sore)),
+                notes: sore.join(", "),
+                ..Default::default()
+            },
+        );
+    }
+
+    // Put the session note above the exercises
+    if let Some(note) = args.note {
+        workout.insert(
+            0,
+            WorkoutExercise {
+                group: 0,
+                name: String::from(args.lang.text(Text::Note)),
+                notes: note,
+                ..Default::default()
+            },
+        );
+    }
+
+    // Render the template variables in the goals and notes from the workout history
+    for e in &mut workout {
+        e.goal = history.render(&e.goal, &e.name, args.units);
+        e.notes = history.render(&e.notes, &e.name, args.units);
+    }
+
+    // Hint at beating the last performance of the repeated exercises, but for
+    // those run by a training max and the cooldown
+    for e in workout.iter_mut().filter(|e| {
+        (2..num_groups + 2).contains(&e.group)
+            && !notation::is_warm_up(e)
+            && !training_maxes
+                .iter()
+                .any(|tm| display::library_name(&tm.name, &library_exercises) == e.name)
+    }) {
+        if let Some(hint) = history.overload_hint(e, args.lang, args.units) {
+            e.goal = if e.goal.is_empty() {
+                hint
+            } else {
+                format!("{} ({})", e.goal, hint)
+            };
+        }
+    }
+
+    // Check the workout against the rules of the scripts
+    for problem in scripts.validate(&workout)? {
+        warn!("{}", problem);
+    }
+
+    // Estimate the time of each group, weighed against the time budget
+    let paces = pacing::paces(&workout, args.focus);
+    let minutes = (pacing::total(&paces) + 30) / 60;
+    if let Some(budget) = args.duration {
+        check_budget(&paces, budget, args.allow_over_budget)?;
+    }
+
+    // Name the session from its content when asked, in the headers and maybe
+    // the file names
+    let date = today.format(args.lang.date_format()).to_string();
+    let name = (user_config.naming.enabled || args.named_files)
+        .then(|| naming::session_name(&user_config.naming, today, &template.types(), &workout));
+    let title = name
+        .as_ref()
+        .map_or_else(|| date.clone(), |name| format!("{} {}", date, name));
+    let stem = match &name {
+        Some(name) if args.named_files => format!("{}_{}", date, naming::slug(name)),
+        _ => date.clone(),
+    };
+
+    // Translate the exercise names of the output people read, the saved state
+    // keeping the untranslated ones
+    let aliases = display::load_aliases(&exercise_library_dir, args.lang, &library_exercises)?;
+    let localized = display::localize(&workout, &aliases);
+
+    // Save the workout to a CSV file, unless no file may be modified
+    let files = if args.read_only {
+        info!("Read-only, the workout is not saved");
+        Vec::new()
+    } else {
+        let frontmatter = export::Frontmatter {
+            date: today,
+            types: template
+                .types()
+                .iter()
+                .filter_map(|t| t.to_possible_value())
+                .map(|value| value.get_name().to_string())
+                .collect(),
+            tags: user_config.obsidian.tags(),
+            minutes,
+        };
+        save_files(
+            &user_config.obsidian,
+            &workouts_dir,
+            &stem,
+            &title,
+            &workout,
+            &localized,
+            args.lang,
+            &args.format,
+            &media_dir,
+            &frontmatter,
+        )?
+    };
+    // Keep how the workout was generated with it, to regenerate or replay the
+    // run
+    if let Some(file) = files.first() {
+        write_state("Run", || {
+            run::save_run(
+                &state_dir,
+                run::Run {
+                    workout_file: file.clone(),
+                    wodgen: String::from(env!("CARGO_PKG_VERSION")),
+                    generated_at: now,
+                    seed,
+                    library_sha256,
+                    snoozed: snoozed_before.clone(),
+                    args: Args {
+                        exercise_library_dir: Some(exercise_library_dir.clone()),
+                        workouts_dir: Some(workouts_dir.clone()),
+                        ..run_args
+                    },
+                },
+            )
+        })?;
+    }
+    if args.print || (args.read_only && !args.compact && !args.simulated) {
+        print!(
+            "{}",
+            export::render_markdown(&localized, &title, args.lang, &media_dir)
+        );
+    }
+    if args.preview_diff {
+        print!(
+            "{}",
+            diff::render_preview(&workout, &dated_workouts, &library_exercises)
+        );
+    }
+
+    // Update snoozed exercises
+    let newly_snoozed = if args.read_only {
+        0
+    } else {
+        snoozed_exercises.len().saturating_sub(snoozed_before.len())
+    };
+    if !args.read_only {
+        // Archive the ended snoozes first, the active file keeps them until then
+        write_state("Snoozed exercises", || {
+            snooze::archive(&snooze::history_file(&snoozed_file_path), &ended_snoozes)?;
+            update_snoozed_exercises(&snoozed_file_path, snoozed_exercises)
+        })?;
+    }
+
+    if !args.email.is_empty() && args.read_only {
+        info!("Read-only, the workout is not emailed");
+    } else if !args.email.is_empty() {
+        email_workout(
+            &user_config.smtp,
+            &args.email,
+            &localized,
+            &title,
+            args.lang,
+        )?;
+    }
+    if args.caldav && args.read_only {
+        info!("Read-only, the session is not saved to the calendar");
+    } else if args.caldav {
+        save_to_calendar(&user_config.caldav, &stem, &localized, &title, args.lang)?;
+    }
+    if args.json {
+        let exercises = workout
+            .iter()
+            .filter(|e| e.group > 1 && !notation::is_warm_up(e))
+            .count();
+        outcome::Outcome::new(date, files, exercises, newly_snoozed).print()?;
+    } else if args.compact {
+        println!(
+            "{}\t{}",
+            export::render_compact(&localized, &title, args.lang),
+            share::uri(&title, &workout)?
+        );
+    } else if !args.quiet {
+        println!(
+            "{}",
+            summary(
+                &title,
+                &template.types(),
+                &workout,
+                minutes,
+                &files,
+                newly_snoozed
+            )
+        );
+    }
+    Ok(Generated { workout, unfilled })
+}
+
+// --------------------------------------------------
+
+// Generate one workout per athlete in a roster
+//
+// Each athlete gets their own workouts directory and snoozed exercises file, so
+// what one athlete did never depletes the pool of another.
+fn generate_for_roster(args: Args, roster_path: &Path) -> Result<()> {
+    let roster = roster::load_roster(roster_path)?;
+    info!(
+        "Loaded {} athletes from {:?}",
+        roster.athletes.len(),
+        roster_path
+    );
+
+    for athlete in roster.athletes {
+        info!("Generating workout for {}", athlete.name);
+        generate(athlete_args(&args, athlete)?)?;
+    }
+    Ok(())
+}
+
+// The generate options of an athlete of a roster: theirs over those of the
+// command line, with workouts and snoozed exercises of their own
+fn athlete_args(args: &Args, athlete: roster::Athlete) -> Result<Args> {
+    let exercise_library_dir = args
+        .exercise_library_dir
+        .clone()
+        .map_or_else(paths::default_library_dir, Ok)?;
+    let workouts_dir = args
+        .workouts_dir
+        .clone()
+        .map_or_else(paths::default_workouts_dir, Ok)?;
+    let state_dir = args
+        .state_dir
+        .clone()
+        .unwrap_or_else(|| exercise_library_dir.clone());
+
+    let slug = athlete.slug();
+    let types = athlete.types.map_or_else(
+        || args.types.clone(),
+        |types| types.into_iter().map(template::Slot::from).collect(),
+    );
+    if types.is_empty() {
+        return Err(WodgenError::NoTypes(athlete.name).into());
+    }
+    let mut exclude = args.exclude.clone();
+    exclude.extend(athlete.exclude);
+
+    Ok(Args {
+        types,
+        level: athlete.level.unwrap_or(args.level.clone()),
+        groups: athlete.groups.unwrap_or(args.groups),
+        bodyweight: athlete.bodyweight.unwrap_or(args.bodyweight),
+        exclude,
+        exercise_library_dir: Some(exercise_library_dir),
+        workouts_dir: Some(workouts_dir.join(&slug)),
+        snoozed_file: Some(state_dir.join(format!("snoozed_{}.csv", slug))),
+        email: athlete
+            .email
+            .map_or_else(|| args.email.clone(), |email| vec![email]),
+        roster: None,
+        ..args.clone()
+    })
+}
+
+// Run the Telegram bot: the athlete of the roster chatting with it gets a
+// workout with /wod and logs the latest one by replying
+fn run_telegram_bot(token: &str, api: &str, args: Vec<String>) -> Result<()> {
+    let argv = [String::from("wodgen")].into_iter().chain(args);
+    let args = Cli::try_parse_from(argv).unwrap_or_else(|e| e.exit()).args;
+    let roster_path = args.roster.clone().ok_or(WodgenError::NoRoster)?;
+
+    bot::Telegram::new(api, token).run(|chat_id, text| {
+        // Read for every message, so athletes can be added while the bot runs
+        let roster = roster::load_roster(&roster_path)?;
+        let Some(athlete) = roster
+            .athletes
+            .into_iter()
+            .find(|athlete| athlete.telegram == Some(chat_id))
+        else {
+            return Ok(bot::help(chat_id));
+        };
+        let request = bot::parse(text);
+        if request == bot::Request::Help {
+            return Ok(bot::help(chat_id));
+        }
+        let athlete_args = athlete_args(&args, athlete)?;
+        let workouts_dir = athlete_args.workouts_dir.clone().unwrap_or_default();
+        if request == bot::Request::Wod {
+            generate(Args {
+                quiet: true,
+                print: false,
+                json: false,
+                compact: false,
+                interactive: false,
+                ..athlete_args
+            })?;
+        }
+
+        let file_path = latest_workout(&workouts_dir)?;
+        let (title, lang, mut workout) = load_workout_with_lang(&file_path)?;
+        if let bot::Request::Log(entries) = request {
+            let replies = bot::log(&mut workout, &entries);
+            write_workout_file(&file_path, title.as_deref(), lang, &workout)?;
+            return Ok(replies.join("\n"));
+        }
+        let title = title.unwrap_or_else(|| {
+            file_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+        Ok(bot::render(&workout, &title, lang))
+    })
+}
+
+// --------------------------------------------------
+
+// Main function
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let (json, compact) = match &cli.command {
+        Some(Command::Generate(args)) => (args.json, args.compact),
+        Some(_) => (false, false),
+        None => (cli.args.json, cli.args.compact),
+    };
+    outcome::set_json(json);
+    csv_utils::set_delimiter(cli.csv_delimiter);
+    outcome::finish(run(cli, json || compact))
+}
+
+// Run the command given on the command line, logging to stderr to keep stdout
+// for the result
+fn run(cli: Cli, stderr: bool) -> Result<()> {
+    // Initialize the logger
+    init_logger(stderr)?;
+
+    match cli.command {
+        Some(Command::Media {
+            command:
+                MediaCommand::Fetch {
+                    exercise_library_dir,
+                    media_dir,
+                },
+        }) => fetch_media(exercise_library_dir, media_dir),
+        Some(Command::Export {
+            command:
+                ExportCommand::Bundle {
+                    date,
+                    output,
+                    workouts_dir,
+                    media_dir,
+                },
+        }) => export_bundle(date, output, workouts_dir, media_dir),
+        Some(Command::Export {
+            command:
+                ExportCommand::Intervals {
+                    date,
+                    format,
+                    output,
+                    rest,
+                    ftp,
+                    workouts_dir,
+                },
+        }) => export_intervals(date, format, output, rest, ftp, workouts_dir),
+        Some(Command::Bot {
+            command: BotCommand::Telegram { token, api, args },
+        }) => run_telegram_bot(&token, &api, args),
+        Some(Command::Replay {
+            file,
+            workouts_dir,
+            exercise_library_dir,
+            state_dir,
+            explain,
+        }) => replay_workout(file, workouts_dir, exercise_library_dir, state_dir, explain),
+        Some(Command::Share { file, workouts_dir }) => share_workout(file, workouts_dir),
+        Some(Command::Diff { old, new }) => diff_workouts(&old, &new),
+        Some(Command::ImportShare {
+            code,
+            workouts_dir,
+            lang,
+        }) => import_share(&code, workouts_dir, lang),
+        Some(Command::Annotate {
+            workout,
+            exercise,
+            note,
+        }) => annotate_workout(&workout, &exercise, &note),
+        Some(Command::Backup {
+            file,
+            exercise_library_dir,
+            workouts_dir,
+            state_dir,
+            config,
+        }) => backup_state(&file, exercise_library_dir, workouts_dir, state_dir, config),
+        Some(Command::Restore {
+            file,
+            exercise_library_dir,
+            workouts_dir,
+            state_dir,
+            config,
+        }) => restore_state(&file, exercise_library_dir, workouts_dir, state_dir, config),
+        Some(Command::Sync {
+            remote,
+            config,
+            exercise_library_dir,
+            workouts_dir,
+            state_dir,
+        }) => sync_state(
+            remote,
+            config,
+            exercise_library_dir,
+            workouts_dir,
+            state_dir,
+        ),
+        Some(Command::Convert { input, output }) => convert_library(&input, &output),
+        Some(Command::Substitute {
+            exercise,
+            reason,
+            limit,
+            exercise_library_dir,
+        }) => substitute_exercise(&exercise, reason, limit, exercise_library_dir),
+        Some(Command::Taxonomy {
+            exercise_library_dir,
+        }) => show_taxonomy(exercise_library_dir),
+        Some(Command::Snooze {
+            command:
+                SnoozeCommand::History {
+                    exercise,
+                    exercise_library_dir,
+                    state_dir,
+                    snoozed_file,
+                    config,
+                },
+        }) => show_snooze_history(
+            &exercise,
+            exercise_library_dir,
+            state_dir,
+            snoozed_file,
+            config,
+        ),
+        Some(Command::Report {
+            month,
+            week,
+            format,
+            charts,
+            output,
+            exercise_library_dir,
+            workouts_dir,
+            units,
+        }) => report_training(
+            month,
+            week,
+            format,
+            charts,
+            output,
+            exercise_library_dir,
+            workouts_dir,
+            units,
+        ),
+        Some(Command::Today { config, args }) => generate_today(config, args),
+        Some(Command::Regenerate {
+            date,
+            workouts_dir,
+            exercise_library_dir,
+            state_dir,
+        }) => regenerate(date, workouts_dir, exercise_library_dir, state_dir),
+        Some(Command::Goals {
+            exercise_library_dir,
+            workouts_dir,
+            units,
+        }) => show_goals(exercise_library_dir, workouts_dir, units),
+        Some(Command::Done {
+            date,
+            note,
+            workouts_dir,
+        }) => mark_done(date, note, workouts_dir),
+        Some(Command::Goal {
+            command:
+                GoalCommand::Set {
+                    name,
+                    by,
+                    exercise_library_dir,
+                    state_dir,
+                },
+        }) => set_goal(&name, by, exercise_library_dir, state_dir),
+        Some(Command::Goal {
+            command:
+                GoalCommand::Clear {
+                    exercise_library_dir,
+                    state_dir,
+                },
+        }) => clear_goal(exercise_library_dir, state_dir),
+        Some(Command::Benchmark {
+            name,
+            exercise_library_dir,
+            workouts_dir,
+            lang,
+            units,
+        }) => generate_benchmark(name, exercise_library_dir, workouts_dir, lang, units),
+        Some(Command::TestDay {
+            exercise_library_dir,
+            workouts_dir,
+            config,
+            lang,
+            units,
+        }) => generate_test_day(exercise_library_dir, workouts_dir, config, lang, units),
+        Some(Command::Status {
+            workouts_dir,
+            exercise_library_dir,
+            state_dir,
+            config,
+            units,
+        }) => show_status(workouts_dir, exercise_library_dir, state_dir, config, units),
+        Some(Command::Chart {
+            metric,
+            exercise,
+            weeks,
+            workouts_dir,
+            units,
+        }) => show_chart(metric, exercise, weeks, workouts_dir, units),
+        Some(Command::Heatmap {
+            filter,
+            weeks,
+            workouts_dir,
+            exercise_library_dir,
+        }) => show_heatmap(filter, weeks, workouts_dir, exercise_library_dir),
+        Some(Command::Equipment {
+            command: EquipmentCommand::Add { items, config },
+        }) => equipment_file(config).and_then(|file| equipment::add_equipment(&file, items)),
+        Some(Command::Equipment {
+            command: EquipmentCommand::Remove { items, config },
+        }) => equipment_file(config).and_then(|file| equipment::remove_equipment(&file, &items)),
+        Some(Command::Equipment {
+            command: EquipmentCommand::List { config, units },
+        }) => list_equipment(config, units),
+        Some(Command::Tm {
+            command:
+                TmCommand::Set {
+                    exercise,
+                    weight,
+                    scheme,
+                    increment,
+                    exercise_library_dir,
+                    state_dir,
+                },
+        }) => set_training_max(
+            &exercise,
+            weight,
+            scheme,
+            increment,
+            exercise_library_dir,
+            state_dir,
+        ),
+        Some(Command::Tm {
+            command:
+                TmCommand::List {
+                    exercise_library_dir,
+                    state_dir,
+                    units,
+                },
+        }) => list_training_maxes(exercise_library_dir, state_dir, units),
+        Some(Command::Checkin {
+            sleep,
+            soreness,
+            motivation,
+            rpe,
+            exercise_library_dir,
+            state_dir,
+        }) => check_in(
+            wellness::CheckIn {
+                checked_in_at: Utc::now(),
+                sleep,
+                soreness,
+                motivation,
+                rpe,
+            },
+            exercise_library_dir,
+            state_dir,
+        ),
+        Some(Command::Calibrate {
+            pull_ups,
+            push_ups,
+            squats,
+            plank,
+            import,
+            exercise_library_dir,
+            state_dir,
+        }) => calibrate(
+            [
+                (calibration::Test::PullUps, pull_ups),
+                (calibration::Test::PushUps, push_ups),
+                (calibration::Test::Squats, squats),
+                (calibration::Test::Plank, plank),
+            ]
+            .into_iter()
+            .filter_map(|(test, value)| {
+                value.map(|value| calibration::LoggedNumber { test, value })
+            })
+            .collect(),
+            import,
+            exercise_library_dir,
+            state_dir,
+        ),
+        Some(Command::Simulate {
+            runs,
+            save_failing,
+            args,
+        }) => simulate_runs(runs, save_failing, args),
+        Some(Command::Generate(args)) => run_generate(*args),
+        Some(Command::Setup {
+            config,
+            exercise_library_dir,
+            workouts_dir,
+        }) => run_setup(config, exercise_library_dir, workouts_dir),
+        None if is_first_run(&cli.args) => run_setup(None, None, cli.args.workouts_dir.clone()),
+        None => run_generate(cli.args),
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_exercises() -> Vec<Exercise> {
+        vec![
+            Exercise {
+                name: String::from("Push Up"),
+                exercise_level: ExerciseLevel::Beginner,
+                goal: Some(String::from("Strength")),
+                video: String::from("push_up.mp4"),
+                ..Default::default()
+            },
+            Exercise {
+                name: String::from("Pull Up"),
+                exercise_type: ExerciseType::Pull,
+                goal: Some(String::from("Strength")),
+                video: String::from("pull_up.mp4"),
+                ..Default::default()
+            },
+            Exercise {
+                name: String::from("Squat"),
+                exercise_type: ExerciseType::Legs,
+                exercise_level: ExerciseLevel::Advanced,
+                bodyweight: false,
+                goal: Some(String::from("Strength")),
+                video: String::from("squat.mp4"),
+                ..Default::default()
+            },
+            Exercise {
+                name: String::from("Plank"),
+                exercise_type: ExerciseType::Core,
+                exercise_category: ExerciseCategory::Secondary,
+                exercise_level: ExerciseLevel::Beginner,
+                exercise_programming: ExerciseProgramming::Time,
+                goal: Some(String::from("Endurance")),
+                video: String::from("plank.mp4"),
+                ..Default::default()
+            },
+        ]
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_filter_by_type() {
+        let exercises = create_test_exercises();
+        let push_exercises: Vec<&Exercise> = exercises
+            .iter()
+            .filter(|e| filter_by_type(e, &ExerciseType::Push))
+            .collect();
+        assert_eq!(push_exercises.len(), 1);
+        assert_eq!(push_exercises[0].name, "Push Up");
+
+        let pull_exercises: Vec<&Exercise> = exercises
+            .iter()
+            .filter(|e| filter_by_type(e, &ExerciseType::Pull))
+            .collect();
+        assert_eq!(pull_exercises.len(), 1);
+        assert_eq!(pull_exercises[0].name, "Pull Up");
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_filter_by_level() {
+        let exercises = create_test_exercises();
+        let beginner_exercises: Vec<&Exercise> = exercises
+            .iter()
             .filter(|e| filter_by_level(e, &ExerciseLevel::Beginner))
             .collect();
         assert_eq!(beginner_exercises.len(), 2);
@@ -724,4 +5391,232 @@ mod tests {
             .collect();
         assert_eq!(accessory_exercises.len(), 0);
     }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_add_cooldown_exercise_empty_pool() {
+        let mut workout = Vec::new();
+        let mut snoozed_exercises = Vec::new();
+        let result = add_cooldown_exercises(
+            &mut workout,
+            &mut Vec::new(),
+            |_| 0,
+            rounds::Focus::Hypertrophy.cooldown(2),
+            &mut snoozed_exercises,
+            2,
+            Units::Metric,
+        );
+        assert!(matches!(
+            result,
+            Err(WodgenError::EmptyPool(ExerciseType::Cooldown))
+        ));
+        assert!(workout.is_empty());
+        assert!(snoozed_exercises.is_empty());
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_pinned_exercise_skips_snooze_and_category() {
+        let mut pool = create_test_exercises();
+        let snoozed = [SnoozedExercise {
+            name: String::from("Plank"),
+            timestamp: Utc::now(),
+        }];
+        let pinned = [String::from("Plank")];
+        filter_exercises(&mut pool, true, &[], &snoozed, &pinned);
+        assert!(pool.iter().any(|e| e.name == "Plank"));
+
+        // A secondary exercise never fits the first group, unless pinned
+        let template = Template::from_types(&[ExerciseType::Core.into()], 1);
+        for (pinned, picked) in [(&[][..], 0), (&pinned[..], 1)] {
+            let (_, picks, _) = generate_workout(
+                &mut pool.clone(),
+                pinned,
+                &template,
+                &calibration::Levels::from(ExerciseLevel::Intermediate),
+                &mut Vec::new(),
+                None,
+                Lang::En,
+                Units::Metric,
+            )
+            .unwrap();
+            assert_eq!(picks.len(), picked);
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_cooldown_targets_trained_areas() {
+        let library = create_test_exercises();
+        let cooldown = |name: &str, stretches: &[&str]| Exercise {
+            name: String::from(name),
+            exercise_type: ExerciseType::Cooldown,
+            exercise_programming: ExerciseProgramming::Time,
+            stretches: stretches.iter().map(|s| s.to_string()).collect(),
+            prerequisites: Vec::new(),
+            testable: false,
+            ..library[3].clone()
+        };
+        let mut workout = vec![WorkoutExercise {
+            group: 2,
+            name: to_title_case("Pull Up"),
+            ..Default::default()
+        }];
+        let trained = trained_areas(&workout, &library);
+        assert_eq!(trained, ["pull"]);
+
+        for _ in 0..10 {
+            let mut pool = vec![
+                cooldown("couch_stretch", &["legs", "hip flexors"]),
+                cooldown("lat_stretch", &["Pull", "lats"]),
+            ];
+            add_cooldown_exercises(
+                &mut workout,
+                &mut pool,
+                |e| u32::from(stretches_trained(e, &trained)),
+                rounds::Focus::Hypertrophy.cooldown(1),
+                &mut Vec::new(),
+                1,
+                Units::Metric,
+            )
+            .unwrap();
+            assert_eq!(workout.pop().unwrap().name, "Lat Stretch");
+            assert_eq!(pool.len(), 1);
+        }
+        // Any cooldown exercise when none stretches a trained area, as many
+        // as the cooldown takes and timed
+        let mut pool = vec![
+            cooldown("couch_stretch", &["legs"]),
+            cooldown("childs_pose", &[]),
+            cooldown("pigeon_pose", &["glutes"]),
+        ];
+        add_cooldown_exercises(
+            &mut workout,
+            &mut pool,
+            |e| u32::from(stretches_trained(e, &trained)),
+            rounds::Focus::Strength.cooldown(3),
+            &mut Vec::new(),
+            1,
+            Units::Metric,
+        )
+        .unwrap();
+        assert_eq!(workout.len(), 3);
+        assert_eq!(pool.len(), 1);
+        assert!(workout[1..]
+            .iter()
+            .all(|e| e.group == 3 && e.time == "X" && !e.goal.is_empty()));
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_render_goal() {
+        assert_eq!(
+            render_goal("400m", &ExerciseProgramming::Distance, Units::Imperial),
+            "0.25mi"
+        );
+        assert_eq!(
+            render_goal("90", &ExerciseProgramming::Time, Units::Metric),
+            "1:30"
+        );
+        assert_eq!(
+            render_goal(
+                "as far as possible",
+                &ExerciseProgramming::Distance,
+                Units::Metric
+            ),
+            "as far as possible"
+        );
+        assert_eq!(
+            render_goal("10", &ExerciseProgramming::Reps, Units::Imperial),
+            "10"
+        );
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_find_substitute_prefers_progression() {
+        let mut exercises = create_test_exercises();
+        exercises[0].progression = Some(String::from("push_up"));
+        exercises.push(Exercise {
+            name: String::from("Dip"),
+            exercise_level: ExerciseLevel::Intermediate,
+            progression: Some(String::from("dip")),
+            ..exercises[0].clone()
+        });
+        exercises.push(Exercise {
+            name: String::from("Diamond Push Up"),
+            exercise_category: ExerciseCategory::Secondary,
+            exercise_level: ExerciseLevel::Intermediate,
+            ..exercises[0].clone()
+        });
+
+        let push_up = exercises[0].clone();
+        let substitute =
+            find_substitute(&push_up, &ExerciseLevel::Intermediate, &exercises, &[]).unwrap();
+        assert_eq!(substitute.name, "Diamond Push Up");
+
+        let taken = [String::from("Diamond Push Up")];
+        let substitute =
+            find_substitute(&push_up, &ExerciseLevel::Intermediate, &exercises, &taken).unwrap();
+        assert_eq!(substitute.name, "Dip");
+
+        assert!(find_substitute(&push_up, &ExerciseLevel::Advanced, &exercises, &[]).is_none());
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_load_from_single_exercises_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(
+            tmp.path().join(EXERCISES_FILE),
+            "name,exercise_type,exercise_category,exercise_level,exercise_programming,bodyweight,goal,video\n\
+             dip,Push,Primary,Beginner,Reps,true,,\n\
+             plank,Core,Primary,Beginner,Time,true,,\n\
+             squat,Legs,Primary,Beginner,Reps,true,,\n",
+        )
+        .unwrap();
+
+        let file_paths = map_file_paths(tmp.path());
+        assert_eq!(file_paths.len(), ExerciseType::value_variants().len());
+        let exercises =
+            load_relevant_exercises(&[ExerciseType::Push, ExerciseType::Legs], &file_paths)
+                .unwrap();
+        let names: Vec<&str> = exercises.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["dip", "squat"]);
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_partner_notes() {
+        let row = |group: u32, name: &str, notes: &str| WorkoutExercise {
+            group,
+            name: String::from(name),
+            notes: String::from(notes),
+            ..Default::default()
+        };
+        let mut workout = vec![
+            row(1, "Skill Block", ""),
+            row(2, "Dip", "elbows in"),
+            row(3, "Calves Raise", "Finisher"),
+            row(4, "Breathing", ""),
+        ];
+        add_partner_notes(&mut workout, 2, Some(3), 4, Lang::En);
+        let notes: Vec<&str> = workout.iter().map(|w| w.notes.as_str()).collect();
+        assert_eq!(
+            notes,
+            [
+                "",
+                "elbows in; You go, I go (2 partners)",
+                "Finisher; Shared, split the work (2 partners)",
+                ""
+            ]
+        );
+    }
 }
@@ -0,0 +1,156 @@
+use crate::Exercise;
+use anyhow::{Context, Result};
+use log::info;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+// --------------------------------------------------
+
+/// Returns whether a media reference is a remote URL rather than a local path.
+pub fn is_url(reference: &str) -> bool {
+    reference.starts_with("http://") || reference.starts_with("https://")
+}
+
+// --------------------------------------------------
+
+/// Returns the path a remote media reference is cached at.
+///
+/// The file name is the SHA-256 of the URL, keeping the extension of the URL so
+/// browsers and viewers can still tell the media type.
+pub fn cached_path(media_dir: &Path, url: &str) -> PathBuf {
+    let hash = Sha256::digest(url.as_bytes());
+    let hash: String = hash.iter().map(|b| format!("{:02x}", b)).collect();
+    let extension = url
+        .rsplit('/')
+        .next()
+        .and_then(|file_name| file_name.split(['?', '#']).next())
+        .and_then(|file_name| file_name.rsplit_once('.'))
+        .map(|(_, extension)| extension)
+        .filter(|extension| !extension.is_empty() && extension.len() <= 5);
+    match extension {
+        Some(extension) => media_dir.join(format!("{}.{}", hash, extension)),
+        None => media_dir.join(hash),
+    }
+}
+
+// --------------------------------------------------
+
+/// Resolves a media reference for an export, preferring the locally cached copy
+/// of remote media so sheets keep working offline.
+pub fn resolve(reference: &str, media_dir: &Path) -> String {
+    if !is_url(reference) {
+        return reference.to_string();
+    }
+    let cached = cached_path(media_dir, reference);
+    if cached.exists() {
+        cached.to_string_lossy().into_owned()
+    } else {
+        reference.to_string()
+    }
+}
+
+// --------------------------------------------------
+
+/// Downloads a single URL to a file, e.g. into the media cache.
+///
+/// # Errors
+///
+/// This function will return an error if the download or the file fails.
+pub fn download(url: &str, destination: &Path) -> Result<()> {
+    let response = ureq::get(url)
+        .call()
+        .with_context(|| format!("Failed to download {}", url))?;
+
+    // Write to a temporary file first so an interrupted download is not cached
+    let partial = destination.with_extension("part");
+    let mut file =
+        File::create(&partial).with_context(|| format!("Failed to create file: {:?}", partial))?;
+    std::io::copy(&mut response.into_body().into_reader(), &mut file)
+        .with_context(|| format!("Failed to download {}", url))?;
+    fs::rename(&partial, destination)
+        .with_context(|| format!("Failed to move {:?} to {:?}", partial, destination))?;
+    Ok(())
+}
+
+// --------------------------------------------------
+
+/// Downloads every remote image and video referenced by the exercises into the
+/// media cache, skipping media that is already cached.
+///
+/// Failed downloads are logged and skipped so one dead link does not stop the
+/// rest of the library from being cached.
+///
+/// # Returns
+///
+/// * `Result<(usize, usize)>` - The number of downloaded and failed media files.
+pub fn fetch(exercises: &[Exercise], media_dir: &Path) -> Result<(usize, usize)> {
+    fs::create_dir_all(media_dir)
+        .with_context(|| format!("Failed to create media directory: {:?}", media_dir))?;
+
+    let mut urls: Vec<&str> = exercises
+        .iter()
+        .flat_map(|e| [e.image.as_deref(), Some(e.video.as_str())])
+        .flatten()
+        .filter(|reference| is_url(reference))
+        .collect();
+    urls.sort_unstable();
+    urls.dedup();
+
+    let (mut downloaded, mut failed) = (0, 0);
+    for url in urls {
+        let destination = cached_path(media_dir, url);
+        if destination.exists() {
+            info!("Already cached {}", url);
+            continue;
+        }
+        match download(url, &destination) {
+            Ok(()) => {
+                info!("Cached {} at {:?}", url, destination);
+                downloaded += 1;
+            }
+            Err(e) => {
+                log::warn!("{:#}", e);
+                failed += 1;
+            }
+        }
+    }
+    Ok((downloaded, failed))
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_path_keeps_extension() {
+        let dir = Path::new("/cache");
+        let path = cached_path(dir, "https://example.com/img/push_up.jpg?size=small");
+        assert_eq!(path.extension().unwrap(), "jpg");
+        assert_eq!(
+            path,
+            cached_path(dir, "https://example.com/img/push_up.jpg?size=small")
+        );
+        assert_ne!(
+            path,
+            cached_path(dir, "https://example.com/img/pull_up.jpg")
+        );
+        assert!(cached_path(dir, "https://example.com/media")
+            .extension()
+            .is_none());
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_resolve_leaves_local_paths() {
+        let dir = Path::new("/cache");
+        assert_eq!(resolve("img/squat.png", dir), "img/squat.png");
+        assert_eq!(
+            resolve("https://example.com/squat.png", dir),
+            "https://example.com/squat.png"
+        );
+    }
+}
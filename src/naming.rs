@@ -0,0 +1,172 @@
+use crate::{ExerciseType, WorkoutExercise};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+// Words for the trained area of each type, and for sessions of many types
+const PUSH_WORDS: [&str; 5] = ["Shoulder", "Chest", "Press", "Push", "Triceps"];
+const PULL_WORDS: [&str; 5] = ["Back", "Pull", "Row", "Lat", "Biceps"];
+const LEGS_WORDS: [&str; 5] = ["Leg", "Squat", "Glute", "Quad", "Hamstring"];
+const CORE_WORDS: [&str; 4] = ["Core", "Ab", "Plank", "Trunk"];
+const FULL_BODY_WORDS: [&str; 3] = ["Full Body", "Total Body", "Mobility"];
+
+// Nouns closing the names, at least one for every initial of the weekdays and
+// the area words so names can alliterate
+const NOUNS: [&str; 26] = [
+    "Smoker",
+    "Scorcher",
+    "Sizzler",
+    "Shredder",
+    "Showdown",
+    "Burner",
+    "Blaster",
+    "Builder",
+    "Bash",
+    "Crusher",
+    "Cooker",
+    "Challenge",
+    "Grinder",
+    "Gauntlet",
+    "Party",
+    "Pump",
+    "Pounder",
+    "Rumble",
+    "Ripper",
+    "Roast",
+    "Torcher",
+    "Throwdown",
+    "Frenzy",
+    "Fest",
+    "Meltdown",
+    "Mayhem",
+];
+
+/// Fun names of the sessions, e.g. `Sunday Shoulder Smoker`, under `[naming]`
+/// in the user config.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Naming {
+    /// Names every session in the output headers
+    pub enabled: bool,
+    /// Nouns closing the names instead of the built-in ones
+    pub nouns: Vec<String>,
+}
+
+// --------------------------------------------------
+
+// Picks a word with the seed, among those starting with `initial` when there
+// are any, so names alliterate
+fn pick<'a>(words: &[&'a str], seed: u64, initial: char) -> &'a str {
+    let alliterating: Vec<&str> = words
+        .iter()
+        .copied()
+        .filter(|word| word.starts_with(initial))
+        .collect();
+    let words = if alliterating.is_empty() {
+        words.to_vec()
+    } else {
+        alliterating
+    };
+    words[(seed % words.len() as u64) as usize]
+}
+
+// The first letter of a word
+fn initial(word: &str) -> char {
+    word.chars().next().unwrap_or_default()
+}
+
+/// Names a session from its weekday, the area its types train and a noun,
+/// seeded from the exercises so the same workout always gets the same name.
+pub fn session_name(
+    naming: &Naming,
+    date: NaiveDate,
+    types: &[ExerciseType],
+    workout: &[WorkoutExercise],
+) -> String {
+    let mut hasher = Sha256::new();
+    for e in workout.iter().filter(|e| e.group > 0) {
+        hasher.update(e.name.as_bytes());
+        hasher.update(b"\n");
+    }
+    let digest = hasher.finalize();
+    let seed = |i: usize| u64::from_le_bytes(digest[i * 8..i * 8 + 8].try_into().unwrap());
+
+    let weekday = date.format("%A").to_string();
+    let trained: Vec<&ExerciseType> = types
+        .iter()
+        .filter(|t| **t != ExerciseType::Cooldown)
+        .collect();
+    let areas: &[&str] = match trained[..] {
+        [ExerciseType::Push] => &PUSH_WORDS,
+        [ExerciseType::Pull] => &PULL_WORDS,
+        [ExerciseType::Legs] => &LEGS_WORDS,
+        [ExerciseType::Core] => &CORE_WORDS,
+        _ => &FULL_BODY_WORDS,
+    };
+    let area = pick(areas, seed(0), initial(&weekday));
+
+    let custom: Vec<&str> = naming.nouns.iter().map(String::as_str).collect();
+    let nouns = if custom.is_empty() {
+        &NOUNS[..]
+    } else {
+        &custom
+    };
+    let noun = pick(nouns, seed(1), initial(area));
+    format!("{} {} {}", weekday, area, noun)
+}
+
+/// The name of a session for a file name, e.g. `sunday_shoulder_smoker`.
+pub fn slug(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workout(names: &[&str]) -> Vec<WorkoutExercise> {
+        names
+            .iter()
+            .map(|name| WorkoutExercise {
+                group: 2,
+                name: String::from(*name),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_session_name() {
+        let sunday = NaiveDate::from_ymd_opt(2024, 6, 2).unwrap();
+        let push = [ExerciseType::Push, ExerciseType::Cooldown];
+        let naming = Naming::default();
+        let name = session_name(&naming, sunday, &push, &workout(&["Dip", "Push Up"]));
+        // The weekday, the only push word starting with S, then an S noun
+        assert!(name.starts_with("Sunday Shoulder S"), "{}", name);
+        assert_eq!(
+            name,
+            session_name(&naming, sunday, &push, &workout(&["Dip", "Push Up"]))
+        );
+
+        let naming = Naming {
+            enabled: true,
+            nouns: vec![String::from("Smoker")],
+        };
+        let name = session_name(&naming, sunday, &push, &workout(&["Dip"]));
+        assert_eq!(name, "Sunday Shoulder Smoker");
+        assert_eq!(slug(&name), "sunday_shoulder_smoker");
+
+        let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+        let types = [ExerciseType::Push, ExerciseType::Legs];
+        let name = session_name(&naming, friday, &types, &workout(&["Squat"]));
+        assert_eq!(name, "Friday Full Body Smoker");
+    }
+}
@@ -0,0 +1,240 @@
+use crate::i18n::{Lang, Text};
+use crate::WorkoutExercise;
+use clap::ValueEnum;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// Number of letters labelling the groups before they take two letters
+const LETTERS: u32 = 26;
+
+/// Headers of the columns a saved workout holds after the translated ones,
+/// typed values that read back as they were written.
+pub const TYPED_HEADERS: [&str; 3] = ["programming", "kind", "interval"];
+
+/// Version of the workout schema since which the warm-up sets are told apart
+/// by their kind rather than by their name.
+pub const ROW_KINDS_VERSION: u32 = 2;
+
+// --------------------------------------------------
+
+// Enum for what a row of a workout is: an exercise, or a warm-up set of the
+// exercise after it
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowKind {
+    #[default]
+    Exercise,
+    WarmUp,
+}
+
+// --------------------------------------------------
+
+/// A row of a workout under its coach label, e.g. `B2` for the second exercise
+/// of the first strength group, serialized like a `WorkoutExercise` with the
+/// label in place of the group number.
+pub struct Row<'a> {
+    pub label: String,
+    pub exercise: &'a WorkoutExercise,
+}
+
+// --------------------------------------------------
+
+/// Returns the letter of a group: `A` for the skill block, then `B`, `C`, ...
+/// and `AA` past `Z`. Session notes, in group 0, have none.
+pub fn letter(group: u32) -> String {
+    let mut letters = String::new();
+    let mut n = group;
+    while n > 0 {
+        n -= 1;
+        letters.insert(0, char::from(b'A' + (n % LETTERS) as u8));
+        n /= LETTERS;
+    }
+    letters
+}
+
+/// Whether a row is a warm-up set of the exercise after it.
+pub fn is_warm_up(e: &WorkoutExercise) -> bool {
+    e.kind == RowKind::WarmUp
+}
+
+/// Marks the warm-up sets of a workout saved before the row kinds, which only
+/// their name, in the language of the workout, told apart.
+pub fn mark_warm_ups(workout: &mut [WorkoutExercise]) {
+    for e in workout {
+        if Lang::value_variants()
+            .iter()
+            .any(|lang| lang.text(Text::WarmUp) == e.name)
+        {
+            e.kind = RowKind::WarmUp;
+        }
+    }
+}
+
+/// Returns the label of every row of a workout, the letter of its group and
+/// its position in the group, e.g. `A1`, `B1`, `B2`, `C1`. Warm-up sets take
+/// the label of the exercise after them with a `w`, e.g. `B1w`, and session
+/// notes an empty label.
+pub fn labels<'a>(workout: impl IntoIterator<Item = &'a WorkoutExercise>) -> Vec<String> {
+    let mut positions: Vec<(u32, u32)> = Vec::new();
+    workout
+        .into_iter()
+        .map(|e| {
+            if e.group == 0 {
+                return String::new();
+            }
+            let index = match positions.iter().position(|(group, _)| *group == e.group) {
+                Some(index) => index,
+                None => {
+                    positions.push((e.group, 0));
+                    positions.len() - 1
+                }
+            };
+            let position = &mut positions[index].1;
+            if is_warm_up(e) {
+                return format!("{}{}w", letter(e.group), *position + 1);
+            }
+            *position += 1;
+            format!("{}{}", letter(e.group), position)
+        })
+        .collect()
+}
+
+/// Returns the headers of a workout saved as CSV: the columns in `lang`, then
+/// the typed columns.
+pub fn csv_headers(lang: Lang) -> Vec<&'static str> {
+    let mut headers = lang.workout_headers();
+    headers.extend(TYPED_HEADERS);
+    headers
+}
+
+/// Returns the rows of a workout under their labels, one at a time.
+pub fn rows(workout: &[WorkoutExercise]) -> impl Iterator<Item = Row<'_>> {
+    labels(workout)
+        .into_iter()
+        .zip(workout)
+        .map(|(label, exercise)| Row { label, exercise })
+}
+
+/// Parses a group label, `B2` or just `B`, into the group number and the
+/// position in the group if given, that of the exercise for a warm-up set. A plain number is a group number, as in
+/// workouts saved before the labels.
+pub fn parse_label(label: &str) -> Result<(u32, Option<u32>), String> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Ok((0, None));
+    }
+    if let Ok(group) = label.parse::<u32>() {
+        return Ok((group, None));
+    }
+    let digits = label.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    let letters = &label[..label.len() - digits.len()];
+    if letters.is_empty() {
+        return Err(format!("invalid group {:?}, expected e.g. B2", label));
+    }
+    let group = letters.chars().try_fold(0u32, |group, c| {
+        group
+            .checked_mul(LETTERS)
+            .and_then(|group| group.checked_add(c.to_ascii_uppercase() as u32 - 'A' as u32 + 1))
+            .ok_or_else(|| format!("invalid group {:?}, too many letters", label))
+    })?;
+    let digits = digits.strip_suffix(['w', 'W']).unwrap_or(digits);
+    let position = if digits.is_empty() {
+        None
+    } else {
+        Some(
+            digits
+                .parse::<u32>()
+                .map_err(|_| format!("invalid group {:?}, expected e.g. B2", label))?,
+        )
+    };
+    Ok((group, position))
+}
+
+/// Deserializes the group of a saved workout row, a label like `B2` or a group
+/// number.
+pub fn deserialize_group<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let label = String::deserialize(deserializer)?;
+    parse_label(&label)
+        .map(|(group, _)| group)
+        .map_err(serde::de::Error::custom)
+}
+
+/// Deserializes the kind of a saved workout row, an empty field being an
+/// exercise.
+pub fn deserialize_kind<'de, D>(deserializer: D) -> Result<RowKind, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<RowKind>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+// --------------------------------------------------
+
+impl Serialize for Row<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let e = self.exercise;
+        let mut row = serializer.serialize_struct("WorkoutExercise", 14)?;
+        row.serialize_field("group", &self.label)?;
+        row.serialize_field("name", &e.name)?;
+        row.serialize_field("sets", &e.sets)?;
+        row.serialize_field("distance", &e.distance)?;
+        row.serialize_field("time", &e.time)?;
+        row.serialize_field("reps", &e.reps)?;
+        row.serialize_field("goal", &e.goal)?;
+        row.serialize_field("image", &e.image)?;
+        row.serialize_field("video", &e.video)?;
+        row.serialize_field("scaling", &e.scaling)?;
+        row.serialize_field("notes", &e.notes)?;
+        row.serialize_field("programming", &e.programming)?;
+        row.serialize_field("kind", &e.kind)?;
+        row.serialize_field("interval", &e.interval)?;
+        row.end()
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels_round_trip() {
+        let workout: Vec<WorkoutExercise> = [0, 1, 2, 2, 3, 4]
+            .iter()
+            .map(|group| WorkoutExercise {
+                group: *group,
+                ..Default::default()
+            })
+            .collect();
+        assert_eq!(labels(&workout), ["", "A1", "B1", "B2", "C1", "D1"]);
+        let mut workout = workout;
+        workout.insert(
+            3,
+            WorkoutExercise {
+                group: 2,
+                name: String::from("Aufwärmsatz"),
+                ..Default::default()
+            },
+        );
+        assert_eq!(labels(&workout), ["", "A1", "B1", "B2", "B3", "C1", "D1"]);
+        // Saved before the kinds, the name in any language tells it apart
+        mark_warm_ups(&mut workout);
+        assert_eq!(workout[3].kind, RowKind::WarmUp);
+        assert_eq!(labels(&workout), ["", "A1", "B1", "B2w", "B2", "C1", "D1"]);
+        assert_eq!(letter(26), "Z");
+        assert_eq!(letter(27), "AA");
+
+        assert_eq!(parse_label("B2"), Ok((2, Some(2))));
+        assert_eq!(parse_label("c"), Ok((3, None)));
+        assert_eq!(parse_label("AA1"), Ok((27, Some(1))));
+        assert_eq!(parse_label("4"), Ok((4, None)));
+        assert_eq!(parse_label("B2w"), Ok((2, Some(2))));
+        assert_eq!(parse_label(""), Ok((0, None)));
+        assert!(parse_label("2B").is_err());
+        assert!(parse_label("B-1").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use crate::history::History;
+use crate::{display, Exercise};
+use rand::Rng;
+
+// --------------------------------------------------
+
+/// Returns the selection weight of an exercise that appeared in `appearances`
+/// saved workouts: 1 plus the bonus, shrinking as the exercise gets used. A
+/// negative bonus counts as 0.
+pub fn weight(appearances: usize, bonus: f64) -> f64 {
+    1.0 + bonus.max(0.0) / (1.0 + appearances as f64)
+}
+
+/// Shuffles the pool so that exercises rarely found in the saved workouts tend
+/// to come first, each one ahead of the others in proportion to its weight,
+/// scaled by `recency`, e.g. lower for recently picked exercises.
+///
+/// A bonus of 0 with a recency of 1 is a uniform shuffle.
+pub fn shuffle<R: Rng>(
+    pool: &mut [Exercise],
+    history: &History,
+    bonus: f64,
+    recency: impl Fn(&Exercise) -> f64,
+    rng: &mut R,
+) {
+    // Weighted random sampling without replacement: every exercise draws a key
+    // u^(1/weight) and the highest keys come first
+    let mut keyed: Vec<(f64, Exercise)> = pool
+        .iter()
+        .map(|e| {
+            let weight =
+                weight(history.appearances(&display::exercise_name(e)), bonus) * recency(e);
+            (rng.gen::<f64>().powf(1.0 / weight), e.clone())
+        })
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    for (slot, (_, exercise)) in pool.iter_mut().zip(keyed) {
+        *slot = exercise;
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WorkoutExercise;
+    use crate::{ExerciseLevel, ExerciseType};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::time::SystemTime;
+
+    fn exercise(name: &str) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type: ExerciseType::Pull,
+            exercise_level: ExerciseLevel::Beginner,
+            ..Default::default()
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_rare_exercises_come_first_more_often() {
+        assert_eq!(weight(0, 2.0), 3.0);
+        assert_eq!(weight(3, 2.0), 1.5);
+
+        let mut history = History::default();
+        for _ in 0..9 {
+            history.record(
+                SystemTime::now(),
+                &[WorkoutExercise {
+                    group: 2,
+                    name: String::from("Pull Up"),
+                    ..Default::default()
+                }],
+            );
+        }
+        let mut rng = StdRng::seed_from_u64(7);
+        let firsts = |bonus: f64, rng: &mut StdRng| {
+            (0..1000)
+                .filter(|_| {
+                    let mut pool = vec![exercise("pull_up"), exercise("ring_row")];
+                    shuffle(&mut pool, &history, bonus, |_| 1.0, rng);
+                    pool[0].name == "ring_row"
+                })
+                .count()
+        };
+        // Uniform without a bonus, the unused exercise leads with one
+        assert!((400..600).contains(&firsts(0.0, &mut rng)));
+        assert!(firsts(4.0, &mut rng) > 650);
+
+        // A recently picked exercise goes last more often
+        let lasts = (0..1000)
+            .filter(|_| {
+                let mut pool = vec![exercise("pull_up"), exercise("ring_row")];
+                let recency = |e: &Exercise| if e.name == "ring_row" { 0.1 } else { 1.0 };
+                shuffle(&mut pool, &History::default(), 0.0, recency, &mut rng);
+                pool[1].name == "ring_row"
+            })
+            .count();
+        assert!(lasts > 800);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::error::WodgenError;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+use simplelog::{Config, SharedLogger};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Exit status of a run that could not fill the workout, the pool of a type
+/// being empty.
+pub const EXIT_UNFILLED: u8 = 3;
+
+/// Exit status of a run that failed to read or write a file.
+pub const EXIT_IO: u8 = 4;
+
+/// Exit status of a workout generated with warnings, with `--json` only.
+pub const EXIT_WARNINGS: u8 = 10;
+
+// Warnings logged since the last result, reported in it
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// Whether the results are printed as JSON
+static JSON: AtomicBool = AtomicBool::new(false);
+
+// Whether a result printed as JSON had warnings
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+// --------------------------------------------------
+
+/// The result of generating a workout, printed as one JSON object with `--json`.
+#[derive(Debug, Serialize)]
+pub struct Outcome {
+    pub status: &'static str,
+    pub date: String,
+    pub files: Vec<PathBuf>,
+    pub exercises: usize,
+    pub snoozed: usize,
+    pub warnings: Vec<String>,
+}
+
+// The result of a failed run, printed as one JSON object with `--json`
+#[derive(Debug, Serialize)]
+struct Failure {
+    status: &'static str,
+    error: String,
+    exit_code: u8,
+}
+
+/// A logger keeping the warnings for the result of the run.
+pub struct WarningCollector;
+
+// --------------------------------------------------
+
+impl Outcome {
+    /// The result of a workout saved to `files`, with the warnings logged while
+    /// generating it.
+    pub fn new(date: String, files: Vec<PathBuf>, exercises: usize, snoozed: usize) -> Outcome {
+        let warnings = std::mem::take(&mut *WARNINGS.lock().unwrap_or_else(|e| e.into_inner()));
+        Outcome {
+            status: if warnings.is_empty() {
+                "ok"
+            } else {
+                "warnings"
+            },
+            date,
+            files,
+            exercises,
+            snoozed,
+            warnings,
+        }
+    }
+
+    /// Prints the result as one line of JSON on stdout.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the result cannot be serialized.
+    pub fn print(&self) -> serde_json::Result<()> {
+        if !self.warnings.is_empty() {
+            WARNED.store(true, Ordering::Relaxed);
+        }
+        println!("{}", serde_json::to_string(self)?);
+        Ok(())
+    }
+}
+
+impl Log for WarningCollector {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() == Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            if let Ok(mut warnings) = WARNINGS.lock() {
+                warnings.push(record.args().to_string());
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for WarningCollector {
+    fn level(&self) -> LevelFilter {
+        LevelFilter::Warn
+    }
+
+    fn config(&self) -> Option<&Config> {
+        None
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+// --------------------------------------------------
+
+/// Prints the results of the run as JSON from now on.
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+/// Whether the results of the run are printed as JSON.
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Returns the exit status of a failed run: whether the workout could not be
+/// filled, a file could not be read or written, or anything else.
+pub fn exit_code(error: &anyhow::Error) -> u8 {
+    let unfilled = error
+        .chain()
+        .any(|cause| matches!(cause.downcast_ref(), Some(WodgenError::EmptyPool(_))));
+    if unfilled {
+        EXIT_UNFILLED
+    } else if error.chain().any(|cause| cause.is::<std::io::Error>()) {
+        EXIT_IO
+    } else {
+        1
+    }
+}
+
+/// Returns whether an error is about writing to a read-only file or
+/// directory, e.g. of a library mounted from a network share.
+pub fn is_read_only(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        // The CSV writer keeps the IO error inside its own
+        let io = cause.downcast_ref::<std::io::Error>().or_else(|| {
+            match cause.downcast_ref::<csv::Error>().map(csv::Error::kind) {
+                Some(csv::ErrorKind::Io(io)) => Some(io),
+                _ => None,
+            }
+        });
+        io.is_some_and(|io| {
+            matches!(
+                io.kind(),
+                std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem
+            )
+        })
+    })
+}
+
+/// Reports how the run ended, on stderr and as JSON on stdout with `--json`,
+/// and returns its exit status.
+pub fn finish(result: anyhow::Result<()>) -> ExitCode {
+    match result {
+        Ok(()) if is_json() && WARNED.load(Ordering::Relaxed) => ExitCode::from(EXIT_WARNINGS),
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            let exit_code = exit_code(&error);
+            eprintln!("Error: {:?}", error);
+            if is_json() {
+                let failure = Failure {
+                    status: "error",
+                    error: format!("{:#}", error),
+                    exit_code,
+                };
+                if let Ok(json) = serde_json::to_string(&failure) {
+                    println!("{}", json);
+                }
+            }
+            ExitCode::from(exit_code)
+        }
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExerciseType;
+
+    #[test]
+    fn test_exit_codes() {
+        let unfilled = anyhow::Error::from(WodgenError::EmptyPool(ExerciseType::Core))
+            .context("Failed to generate");
+        assert_eq!(exit_code(&unfilled), EXIT_UNFILLED);
+        let io = anyhow::Error::from(std::io::Error::from(std::io::ErrorKind::NotFound))
+            .context("Failed to open file");
+        assert_eq!(exit_code(&io), EXIT_IO);
+        assert_eq!(exit_code(&anyhow::anyhow!("bad option")), 1);
+    }
+
+    #[test]
+    fn test_read_only_errors() {
+        let denied =
+            anyhow::Error::from(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+                .context("Failed to write file");
+        assert!(is_read_only(&denied));
+        let csv = anyhow::Error::from(csv::Error::from(std::io::Error::from(
+            std::io::ErrorKind::ReadOnlyFilesystem,
+        )));
+        assert!(is_read_only(&csv));
+        let missing = anyhow::Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(!is_read_only(&missing));
+        assert!(!is_read_only(&anyhow::anyhow!("bad option")));
+    }
+}
@@ -0,0 +1,226 @@
+use crate::notation;
+use crate::rounds::Focus;
+use crate::units::Duration;
+use crate::WorkoutExercise;
+
+// Seconds a rep takes
+const SECONDS_PER_REP: u32 = 3;
+
+// Seconds of a set whose goal gives neither reps nor a time, e.g. a distance
+const SECONDS_PER_SET: u32 = 45;
+
+// Seconds to move from an exercise of a round to the next
+const STATION_SECONDS: u32 = 15;
+
+// Seconds to set up a group before its first round
+const SETUP_SECONDS: u32 = 60;
+
+// --------------------------------------------------
+
+/// The estimated time of a group of a workout, in seconds: the work of its
+/// rounds, the rest between them, and the moves from an exercise to the next.
+#[derive(Debug, PartialEq)]
+pub struct Pace {
+    /// Label of the group, e.g. `B`
+    pub label: String,
+    pub work: u32,
+    pub rest: u32,
+    pub transitions: u32,
+}
+
+// --------------------------------------------------
+
+impl Pace {
+    /// Seconds the group takes in all.
+    pub fn total(&self) -> u32 {
+        self.work + self.rest + self.transitions
+    }
+
+    /// Renders the time of the group, e.g. `B: 3:00 work, 3:00 rest, 1:45
+    /// transitions, 7:45`.
+    pub fn render(&self) -> String {
+        format!(
+            "{}: {} work, {} rest, {} transitions, {}",
+            self.label,
+            Duration::from_seconds(self.work).render(),
+            Duration::from_seconds(self.rest).render(),
+            Duration::from_seconds(self.transitions).render(),
+            Duration::from_seconds(self.total()).render()
+        )
+    }
+}
+
+// --------------------------------------------------
+
+// Seconds of a set of a row: the time or the reps of its goal, those of the
+// focus when it has none
+fn set_seconds(row: &WorkoutExercise, focus: Focus) -> u32 {
+    let goal = row.goal.trim();
+    if !row.reps.is_empty() {
+        let reps = goal
+            .split(|c: char| !c.is_ascii_digit())
+            .next()
+            .and_then(|reps| reps.parse::<u32>().ok())
+            .unwrap_or(focus.reps());
+        reps * SECONDS_PER_REP
+    } else if !row.time.is_empty() {
+        goal.parse::<Duration>()
+            .map_or(focus.seconds(), |time| time.seconds())
+    } else {
+        SECONDS_PER_SET
+    }
+}
+
+/// Estimates the time of each group of a workout. A group goes through its
+/// rows for its rounds, resting between them as long as the focus says, or on
+/// the clock of its interval. Groups without rounds, such as the warm-up
+/// and the cooldown, are gone through once, and warm-up sets are done once
+/// before the rounds.
+pub fn paces(workout: &[WorkoutExercise], focus: Focus) -> Vec<Pace> {
+    let mut groups: Vec<u32> = workout
+        .iter()
+        .filter(|e| e.group > 0)
+        .map(|e| e.group)
+        .collect();
+    groups.dedup();
+    groups
+        .into_iter()
+        .map(|group| {
+            let rows: Vec<&WorkoutExercise> = workout.iter().filter(|e| e.group == group).collect();
+            let (warm_ups, rows): (Vec<&WorkoutExercise>, Vec<&WorkoutExercise>) =
+                rows.into_iter().partition(|e| notation::is_warm_up(e));
+            let rounds = rows
+                .iter()
+                .filter_map(|e| e.sets.trim().parse::<u32>().ok())
+                .max();
+            let stations = rows.len() as u32;
+            let mut pace = Pace {
+                label: notation::letter(group),
+                work: warm_ups.iter().map(|e| set_seconds(e, focus)).sum(),
+                rest: 0,
+                transitions: SETUP_SECONDS,
+            };
+            match (rounds, rows.iter().find_map(|e| e.interval)) {
+                (Some(rounds), Some(interval)) => {
+                    pace.work += rounds * stations * interval.work;
+                    pace.transitions += rounds * stations * interval.transition;
+                }
+                (rounds, _) => {
+                    let round: u32 = rows.iter().map(|e| set_seconds(e, focus)).sum();
+                    let rounds = rounds.unwrap_or(1).max(1);
+                    pace.work += rounds * round;
+                    pace.rest += (rounds - 1) * focus.rest();
+                    pace.transitions += rounds * stations.saturating_sub(1) * STATION_SECONDS;
+                }
+            }
+            pace
+        })
+        .collect()
+}
+
+/// Seconds the whole workout takes.
+pub fn total(paces: &[Pace]) -> u32 {
+    paces.iter().map(Pace::total).sum()
+}
+
+/// Renders the time of each group and that of the whole workout against the
+/// budget in minutes, e.g. `Total: 25:30 of the 30 min budget`.
+pub fn render(paces: &[Pace], budget: u32) -> String {
+    let mut lines: Vec<String> = paces
+        .iter()
+        .map(|pace| format!("  {}", pace.render()))
+        .collect();
+    lines.push(format!(
+        "  Total: {} of the {} min budget",
+        Duration::from_seconds(total(paces)).render(),
+        budget
+    ));
+    lines.join("\n")
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::RowKind;
+    use crate::rounds::Interval;
+
+    fn row(group: u32, sets: &str, time: &str, reps: &str, goal: &str) -> WorkoutExercise {
+        WorkoutExercise {
+            group,
+            sets: String::from(sets),
+            time: String::from(time),
+            reps: String::from(reps),
+            goal: String::from(goal),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_paces() {
+        let mut mixed = row(3, "2", "", "X", "8");
+        mixed.interval = Some(Interval {
+            work: 40,
+            transition: 20,
+        });
+        let workout = vec![
+            row(0, "", "", "", ""),
+            row(1, "", "", "", ""),
+            WorkoutExercise {
+                group: 2,
+                name: String::from("Warm-up"),
+                sets: String::from("1"),
+                goal: String::from("5 @ 40kg"),
+                kind: RowKind::WarmUp,
+                ..Default::default()
+            },
+            row(2, "3", "", "X", "10, slow"),
+            row(2, "3", "X", "", "30s"),
+            mixed,
+            row(3, "2", "X", "", ""),
+            row(4, "", "X", "", "1:00"),
+        ];
+        let paces = paces(&workout, Focus::Hypertrophy);
+        assert_eq!(
+            paces,
+            [
+                Pace {
+                    label: String::from("A"),
+                    work: 45,
+                    rest: 0,
+                    transitions: 60,
+                },
+                Pace {
+                    label: String::from("B"),
+                    work: 45 + 3 * (30 + 30),
+                    rest: 2 * 90,
+                    transitions: 60 + 3 * 15,
+                },
+                Pace {
+                    label: String::from("C"),
+                    work: 2 * 2 * 40,
+                    rest: 0,
+                    transitions: 60 + 2 * 2 * 20,
+                },
+                Pace {
+                    label: String::from("D"),
+                    work: 60,
+                    rest: 0,
+                    transitions: 60,
+                },
+            ]
+        );
+        assert_eq!(
+            paces[1].render(),
+            "B: 3:45 work, 3:00 rest, 1:45 transitions, 8:30"
+        );
+        assert_eq!(total(&paces), 105 + 510 + 300 + 120);
+        assert!(render(&paces, 15).ends_with("  Total: 17:15 of the 15 min budget"));
+        // Strength rests longer between the rounds
+        assert_eq!(
+            total(&super::paces(&workout[3..5], Focus::Strength)),
+            60 + 3 * (30 + 30) + 2 * 180 + 3 * 15
+        );
+    }
+}
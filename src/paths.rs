@@ -0,0 +1,81 @@
+use crate::error::WodgenError;
+use directories::ProjectDirs;
+use std::env;
+use std::path::PathBuf;
+
+// Directory names used inside the platform data directory
+const EXERCISE_LIBRARY_DIR: &str = "exercise_library";
+const WORKOUTS_DIR: &str = "workouts";
+const MEDIA_DIR: &str = "media";
+
+// File name of the user configuration inside the platform config directory
+const CONFIG_FILE: &str = "config.toml";
+
+// --------------------------------------------------
+
+/// Returns the platform-specific project directories for wodgen.
+///
+/// On Linux these follow the XDG base directory spec (`~/.local/share/wodgen`,
+/// `~/.config/wodgen`), on macOS `~/Library/Application Support/wodgen` and on
+/// Windows `%APPDATA%\wodgen`.
+pub fn project_dirs() -> Result<ProjectDirs, WodgenError> {
+    ProjectDirs::from("", "", "wodgen").ok_or(WodgenError::NoHomeDir)
+}
+
+// --------------------------------------------------
+
+/// Returns the exercise library that `build.rs` copies next to the executable,
+/// if there is one.
+pub fn bundled_library_dir() -> Option<PathBuf> {
+    let exe = env::current_exe().ok()?;
+    let dir = exe.parent()?.join(EXERCISE_LIBRARY_DIR);
+    dir.is_dir().then_some(dir)
+}
+
+// --------------------------------------------------
+
+/// Returns the exercise library directory inside the platform data directory,
+/// whether it has been set up or not.
+pub fn data_library_dir() -> Result<PathBuf, WodgenError> {
+    Ok(project_dirs()?.data_dir().join(EXERCISE_LIBRARY_DIR))
+}
+
+// --------------------------------------------------
+
+/// Resolves the default exercise library directory.
+///
+/// The library in the platform data directory is preferred. When it has not been
+/// set up yet, the library bundled next to the executable is used instead.
+///
+/// # Errors
+///
+/// Returns `WodgenError::LibraryNotFound` pointing at the data directory when
+/// neither library exists.
+pub fn default_library_dir() -> Result<PathBuf, WodgenError> {
+    let library_dir = data_library_dir()?;
+    if library_dir.is_dir() {
+        return Ok(library_dir);
+    }
+    bundled_library_dir().ok_or(WodgenError::LibraryNotFound(library_dir))
+}
+
+// --------------------------------------------------
+
+/// Resolves the default directory where generated workouts are saved.
+pub fn default_workouts_dir() -> Result<PathBuf, WodgenError> {
+    Ok(project_dirs()?.data_dir().join(WORKOUTS_DIR))
+}
+
+// --------------------------------------------------
+
+/// Resolves the default directory where remote exercise media is cached.
+pub fn default_media_dir() -> Result<PathBuf, WodgenError> {
+    Ok(project_dirs()?.data_dir().join(MEDIA_DIR))
+}
+
+// --------------------------------------------------
+
+/// Resolves the default user configuration file, e.g. `~/.config/wodgen/config.toml`.
+pub fn default_config_file() -> Result<PathBuf, WodgenError> {
+    Ok(project_dirs()?.config_dir().join(CONFIG_FILE))
+}
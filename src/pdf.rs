@@ -0,0 +1,207 @@
+// Size of an A4 page in points
+pub const PAGE_WIDTH: f32 = 595.0;
+pub const PAGE_HEIGHT: f32 = 842.0;
+
+// Characters of the Windows-1252 encoding of the standard fonts that are not
+// at their Unicode code point. Other characters are drawn as `?`
+const WIN_ANSI: [(char, u8); 9] = [
+    ('€', 0x80),
+    ('…', 0x85),
+    ('‘', 0x91),
+    ('’', 0x92),
+    ('“', 0x93),
+    ('”', 0x94),
+    ('•', 0x95),
+    ('–', 0x96),
+    ('—', 0x97),
+];
+
+// Fonts every PDF viewer has, regular and bold
+const FONTS: [&str; 2] = ["Helvetica", "Helvetica-Bold"];
+
+/// A PDF document of A4 pages holding lines of text in Helvetica.
+pub struct Document {
+    pages: Vec<Vec<u8>>,
+}
+
+// --------------------------------------------------
+
+/// Average width in points of a character of Helvetica at a font size, to
+/// fit texts in columns.
+pub fn char_width(size: f32) -> f32 {
+    size * 0.55
+}
+
+// Encodes a text as a PDF string in Windows-1252, escaping the delimiters
+fn encode_text(text: &str) -> Vec<u8> {
+    let mut bytes = vec![b'('];
+    for c in text.chars() {
+        let byte = match c {
+            '(' | ')' | '\\' => {
+                bytes.push(b'\\');
+                c as u8
+            }
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            _ => WIN_ANSI
+                .iter()
+                .find(|(special, _)| *special == c)
+                .map_or(b'?', |(_, byte)| *byte),
+        };
+        bytes.push(byte);
+    }
+    bytes.push(b')');
+    bytes
+}
+
+// --------------------------------------------------
+
+impl Default for Document {
+    fn default() -> Document {
+        Document::new()
+    }
+}
+
+impl Document {
+    /// A document with a blank first page.
+    pub fn new() -> Document {
+        Document {
+            pages: vec![Vec::new()],
+        }
+    }
+
+    /// Starts a new page, the next texts are drawn on it.
+    pub fn add_page(&mut self) {
+        self.pages.push(Vec::new());
+    }
+
+    /// Draws a line of text on the last page from the left of its baseline, in
+    /// points from the top left corner of the page.
+    pub fn text(&mut self, x: f32, y: f32, size: f32, bold: bool, text: &str) {
+        let page = self.pages.last_mut().expect("a document has a page");
+        page.extend(
+            format!(
+                "BT /F{} {} Tf {:.1} {:.1} Td ",
+                u8::from(bold) + 1,
+                size,
+                x,
+                PAGE_HEIGHT - y
+            )
+            .into_bytes(),
+        );
+        page.extend(encode_text(text));
+        page.extend(b" Tj ET\n");
+    }
+
+    /// Fills a black rectangle on the last page, in points from the top left
+    /// corner of the page to the top left corner of the rectangle.
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        let page = self.pages.last_mut().expect("a document has a page");
+        page.extend(
+            format!(
+                "{:.2} {:.2} {:.2} {:.2} re f\n",
+                x,
+                PAGE_HEIGHT - y - height,
+                width,
+                height
+            )
+            .into_bytes(),
+        );
+    }
+
+    /// Encodes the document as a PDF file.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut objects: Vec<Vec<u8>> = Vec::new();
+        let kids: Vec<String> = (0..self.pages.len())
+            .map(|i| format!("{} 0 R", 5 + 2 * i))
+            .collect();
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        objects.push(
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                self.pages.len()
+            )
+            .into_bytes(),
+        );
+        for font in FONTS {
+            objects.push(
+                format!(
+                    "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                    font
+                )
+                .into_bytes(),
+            );
+        }
+        for (i, page) in self.pages.iter().enumerate() {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    PAGE_WIDTH,
+                    PAGE_HEIGHT,
+                    6 + 2 * i
+                )
+                .into_bytes(),
+            );
+            let mut stream = format!("<< /Length {} >>\nstream\n", page.len()).into_bytes();
+            stream.extend(page);
+            stream.extend(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+            pdf.extend(object);
+            pdf.extend(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+        for offset in offsets {
+            pdf.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+        }
+        pdf.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .into_bytes(),
+        );
+        pdf
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_pdf() {
+        assert_eq!(encode_text("a (b) – é"), b"(a \\(b\\) \x96 \xe9)");
+        assert_eq!(encode_text("✓"), b"(?)");
+
+        let mut document = Document::new();
+        document.text(40.0, 40.0, 16.0, true, "Workout 2024_06_01");
+        document.add_page();
+        document.text(40.0, 40.0, 9.0, false, "Push Up");
+        document.rect(40.0, 50.0, 10.0, 2.0);
+        let pdf = document.encode();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains("BT /F2 16 Tf 40.0 802.0 Td (Workout 2024_06_01) Tj ET"));
+        assert!(text.contains("40.00 790.00 10.00 2.00 re f"));
+
+        // The cross-reference table points at every object
+        let xref = text.rfind("xref\n").unwrap();
+        for (i, line) in text[xref..].lines().skip(3).take(8).enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+}
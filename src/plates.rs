@@ -0,0 +1,202 @@
+use crate::units::{Units, Weight};
+use crate::Exercise;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+// Weight of the bar when the inventory lists plates but no bar
+const DEFAULT_BAR_KG: f64 = 20.0;
+
+// --------------------------------------------------
+
+/// The weights available to load, under `[inventory]` in the user config.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Inventory {
+    /// Weight of the barbell, 20kg by default
+    pub bar: Option<Weight>,
+    /// Plates, one entry per pair so a pair goes on each side of the bar
+    pub plates: Vec<Weight>,
+    /// Dumbbells, one entry per weight
+    pub dumbbells: Vec<Weight>,
+}
+
+// Enum for what the load of a weighted exercise is put on
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Implement {
+    Barbell,
+    Dumbbell,
+}
+
+/// A load that can be put together from the inventory.
+#[derive(Debug, PartialEq)]
+pub struct Load {
+    pub weight: Weight,
+    /// Plates on each side of the bar, heaviest first
+    pub per_side: Vec<Weight>,
+}
+
+// --------------------------------------------------
+
+impl Implement {
+    /// The implement of a weighted exercise, dumbbells when its equipment says
+    /// so and a barbell otherwise.
+    pub fn of(exercise: &Exercise) -> Implement {
+        if exercise
+            .equipment
+            .iter()
+            .any(|e| e.to_lowercase().starts_with("dumbbell"))
+        {
+            Implement::Dumbbell
+        } else {
+            Implement::Barbell
+        }
+    }
+}
+
+impl Load {
+    /// Renders the load with its plates, e.g. `77.5kg (per side: 25kg + 2.5kg)`.
+    pub fn render(&self, units: Units) -> String {
+        let weight = self.weight.render(units);
+        if self.per_side.is_empty() {
+            return weight;
+        }
+        let plates: Vec<String> = self.per_side.iter().map(|p| p.render(units)).collect();
+        format!("{} (per side: {})", weight, plates.join(" + "))
+    }
+}
+
+// --------------------------------------------------
+
+// A weight in grams, to compare sums of plates exactly
+fn grams(weight: Weight) -> i64 {
+    (weight.kilograms() * 1000.0).round() as i64
+}
+
+// Every weight a set of plates can add to one side, with the plates making it
+// up, found adding the heaviest plates first so each sum uses few plates
+fn per_side_sums(plates: &[Weight]) -> BTreeMap<i64, Vec<Weight>> {
+    let mut plates = plates.to_vec();
+    plates.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    let mut sums = BTreeMap::from([(0, Vec::new())]);
+    for plate in plates {
+        let loaded: Vec<(i64, Vec<Weight>)> = sums
+            .iter()
+            .map(|(sum, combination)| {
+                let mut combination = combination.clone();
+                combination.push(plate);
+                (sum + grams(plate), combination)
+            })
+            .collect();
+        for (sum, combination) in loaded {
+            sums.entry(sum).or_insert(combination);
+        }
+    }
+    sums
+}
+
+impl Inventory {
+    /// Rounds a load to the nearest one the inventory can make, the lighter one
+    /// on a tie.
+    ///
+    /// Barbell loads are built from the bar and a pair of plates per entry, and
+    /// dumbbell loads are picked from the dumbbells. Without any of these in the
+    /// inventory, loads are rounded to 2.5kg, or 5lb in imperial units.
+    pub fn load(&self, weight: Weight, implement: Implement, units: Units) -> Load {
+        let target = grams(weight);
+        match implement {
+            Implement::Dumbbell if !self.dumbbells.is_empty() => {
+                let dumbbell = self
+                    .dumbbells
+                    .iter()
+                    .min_by_key(|d| ((grams(**d) - target).abs(), grams(**d)))
+                    .copied()
+                    .unwrap_or(weight);
+                Load {
+                    weight: dumbbell,
+                    per_side: Vec::new(),
+                }
+            }
+            Implement::Barbell if !self.plates.is_empty() => {
+                let bar = self.bar.unwrap_or(Weight::from_kilograms(DEFAULT_BAR_KG));
+                let (sum, per_side) = per_side_sums(&self.plates)
+                    .into_iter()
+                    .min_by_key(|(sum, _)| ((grams(bar) + 2 * sum - target).abs(), *sum))
+                    .unwrap_or_default();
+                Load {
+                    weight: Weight::from_kilograms(bar.kilograms() + 2.0 * sum as f64 / 1000.0),
+                    per_side,
+                }
+            }
+            _ => Load {
+                weight: weight.round(units),
+                per_side: Vec::new(),
+            },
+        }
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inventory(plates: &[f64], dumbbells: &[f64]) -> Inventory {
+        let weights = |kgs: &[f64]| kgs.iter().map(|kg| Weight::from_kilograms(*kg)).collect();
+        Inventory {
+            bar: None,
+            plates: weights(plates),
+            dumbbells: weights(dumbbells),
+        }
+    }
+
+    fn kg(kilograms: f64) -> Weight {
+        Weight::from_kilograms(kilograms)
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_plate_math() {
+        let inventory = inventory(&[20.0, 10.0, 5.0, 2.5, 1.25], &[10.0, 12.5, 15.0]);
+        let render = |weight, implement| {
+            inventory
+                .load(kg(weight), implement, Units::Metric)
+                .render(Units::Metric)
+        };
+        assert_eq!(
+            render(80.0, Implement::Barbell),
+            "80kg (per side: 20kg + 10kg)"
+        );
+        assert_eq!(
+            render(84.0, Implement::Barbell),
+            "85kg (per side: 20kg + 10kg + 2.5kg)"
+        );
+        // Every plate on the bar is as heavy as it gets
+        assert_eq!(
+            render(150.0, Implement::Barbell),
+            "97.5kg (per side: 20kg + 10kg + 5kg + 2.5kg + 1.25kg)"
+        );
+        assert_eq!(render(10.0, Implement::Barbell), "20kg");
+        // The lighter dumbbell on a tie
+        assert_eq!(render(13.75, Implement::Dumbbell), "12.5kg");
+
+        let inventory: Inventory =
+            toml::from_str("bar = \"45lb\"\nplates = [\"45lb\", \"25lb\", \"25lb\"]").unwrap();
+        assert_eq!(
+            inventory
+                .load(kg(100.0), Implement::Barbell, Units::Imperial)
+                .render(Units::Imperial),
+            "235lb (per side: 45lb + 25lb + 25lb)"
+        );
+
+        let empty = Inventory::default();
+        assert_eq!(
+            empty.load(kg(101.0), Implement::Barbell, Units::Metric),
+            Load {
+                weight: kg(100.0),
+                per_side: Vec::new(),
+            }
+        );
+    }
+}
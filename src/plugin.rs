@@ -0,0 +1,189 @@
+use crate::{Exercise, ExerciseLevel};
+use anyhow::{bail, Context, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+/// Version of the JSON contract, sent with every request.
+pub const VERSION: u32 = 1;
+
+/// An external command choosing the exercise of each slot among the
+/// candidates, or vetoing some of them.
+#[derive(Debug, Clone)]
+pub struct Plugin {
+    program: String,
+    args: Vec<String>,
+}
+
+/// What the plugin is sent on stdin for every slot of the workout.
+#[derive(Debug, Serialize)]
+pub struct Request<'a> {
+    pub version: u32,
+    /// The slot to fill, as written in --types, e.g. `pull:any`
+    pub slot: String,
+    /// Index of the group among the strength groups, starting at 0
+    pub group: u32,
+    /// Name of the template block of the group, if it has one
+    pub block: Option<&'a str>,
+    pub level: &'a ExerciseLevel,
+    /// Names of the exercises picked so far, in order
+    pub selected: &'a [String],
+    /// The exercises that may fill the slot, in the order they would be
+    /// picked without the plugin
+    pub candidates: Vec<&'a Exercise>,
+}
+
+// What the plugin answers on stdout, nothing keeping the default pick
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Response {
+    // Name of the candidate to pick
+    pick: Option<String>,
+    // Names of the candidates never to pick for the slot
+    veto: Vec<String>,
+}
+
+// --------------------------------------------------
+
+impl Plugin {
+    /// A plugin running a command line, a program followed by its arguments
+    /// separated by whitespace, e.g. `python3 plugins/grip.py`.
+    pub fn new(command: &str) -> Option<Plugin> {
+        let mut words = command.split_whitespace().map(String::from);
+        Some(Plugin {
+            program: words.next()?,
+            args: words.collect(),
+        })
+    }
+
+    /// Asks the plugin which candidate fills the slot: the one it picks, or
+    /// else the first one it does not veto. None when it vetoes them all.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the plugin cannot be run, exits
+    /// with a failure, answers something else than the JSON contract or picks
+    /// an exercise that is not a candidate.
+    pub fn choose(&self, request: &Request) -> Result<Option<usize>> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run plugin: {:?}", self.program))?;
+        let mut json = serde_json::to_vec(request)?;
+        json.push(b'\n');
+        if let Some(mut stdin) = child.stdin.take() {
+            // A plugin may answer without reading the request
+            if let Err(error) = stdin.write_all(&json) {
+                if error.kind() != ErrorKind::BrokenPipe {
+                    return Err(error.into());
+                }
+            }
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            bail!(
+                "Plugin {:?} failed with {}: {}",
+                self.program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let response: Response = if stdout.trim().is_empty() {
+            Response::default()
+        } else {
+            serde_json::from_str(&stdout).with_context(|| {
+                format!("Failed to parse the answer of plugin {:?}", self.program)
+            })?
+        };
+        let position = |name: &str| request.candidates.iter().position(|e| e.name == name);
+        match response.pick {
+            Some(name) => match position(&name) {
+                Some(index) => {
+                    info!("Plugin picked {} for slot {}", name, request.slot);
+                    Ok(Some(index))
+                }
+                None => bail!(
+                    "Plugin {:?} picked {:?}, which is not a candidate for slot {}",
+                    self.program,
+                    name,
+                    request.slot
+                ),
+            },
+            None => {
+                if !response.veto.is_empty() {
+                    info!(
+                        "Plugin vetoed {:?} for slot {}",
+                        response.veto, request.slot
+                    );
+                }
+                Ok(request
+                    .candidates
+                    .iter()
+                    .position(|e| !response.veto.contains(&e.name)))
+            }
+        }
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExerciseType;
+    use std::fs;
+
+    fn exercise(name: &str) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type: ExerciseType::Pull,
+            exercise_level: ExerciseLevel::Beginner,
+            ..Default::default()
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_plugin_picks_and_vetoes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let candidates = [exercise("pull_up"), exercise("row"), exercise("chin_up")];
+        let request = Request {
+            version: VERSION,
+            slot: String::from("pull"),
+            group: 0,
+            block: None,
+            level: &ExerciseLevel::Intermediate,
+            selected: &[],
+            candidates: candidates.iter().collect(),
+        };
+        let choose = |answer: &str| {
+            let script = tmp.path().join("plugin.sh");
+            fs::write(&script, format!("cat > /dev/null\n{}\n", answer)).unwrap();
+            Plugin::new(&format!("sh {}", script.display()))
+                .unwrap()
+                .choose(&request)
+        };
+
+        assert_eq!(choose("").unwrap(), Some(0));
+        assert_eq!(choose(r#"echo '{"pick": "chin_up"}'"#).unwrap(), Some(2));
+        assert_eq!(choose(r#"echo '{"veto": ["pull_up"]}'"#).unwrap(), Some(1));
+        assert_eq!(
+            choose(r#"echo '{"veto": ["pull_up", "row", "chin_up"]}'"#).unwrap(),
+            None
+        );
+        assert!(choose(r#"echo '{"pick": "dip"}'"#).is_err());
+        assert!(choose(r#"echo '{"choice": "row"}'"#).is_err());
+        assert!(choose("echo oops >&2; exit 1")
+            .unwrap_err()
+            .to_string()
+            .contains("oops"));
+        assert!(Plugin::new("  ").is_none());
+    }
+}
@@ -0,0 +1,154 @@
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use std::io::Write;
+
+// Signature starting every PNG file
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// Pixels per dot of the font, whose glyphs are 3 dots wide and 5 high
+const DOT: usize = 2;
+
+// Glyphs of the font, one row of 3 dots per byte from the top, the leftmost
+// dot in the highest bit. Other characters are drawn as blanks
+const GLYPHS: [(char, [u8; 5]); 15] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    ('%', [0b101, 0b001, 0b010, 0b100, 0b101]),
+    ('W', [0b101, 0b101, 0b101, 0b111, 0b101]),
+];
+
+/// An RGB color.
+pub type Rgb = [u8; 3];
+
+/// An RGB image drawn with rectangles and a small pixel font, encoded as PNG.
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+// --------------------------------------------------
+
+/// Width in pixels of a text in the pixel font.
+pub fn text_width(text: &str) -> usize {
+    (text.chars().count() * 4).saturating_sub(1) * DOT
+}
+
+// Writes a chunk: its length, its type, its data and their checksum
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    png.extend(kind);
+    png.extend(data);
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    png.extend(crc.sum().to_be_bytes());
+}
+
+// --------------------------------------------------
+
+impl Canvas {
+    /// A canvas of the given size filled with a color.
+    pub fn new(width: usize, height: usize, background: Rgb) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: background.repeat(width * height),
+        }
+    }
+
+    /// Fills a rectangle, clipped to the canvas.
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Rgb) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                let i = (row * self.width + column) * 3;
+                self.pixels[i..i + 3].copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Draws a text in the pixel font from its top left corner. Only digits,
+    /// `-`, `:`, `.`, `%` and `W` are drawn, e.g. a day or an ISO week.
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str, color: Rgb) {
+        for (i, c) in text.chars().enumerate() {
+            let Some((_, rows)) = GLYPHS.iter().find(|(glyph, _)| *glyph == c) else {
+                continue;
+            };
+            for (row, dots) in rows.iter().enumerate() {
+                for dot in 0..3 {
+                    if dots & (0b100 >> dot) != 0 {
+                        self.fill_rect(x + (i * 4 + dot) * DOT, y + row * DOT, DOT, DOT, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Encodes the canvas as a PNG image.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the pixels cannot be compressed.
+    pub fn encode(&self) -> std::io::Result<Vec<u8>> {
+        let mut header = Vec::new();
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // 8 bits per channel, RGB, default compression, filter and no interlace
+        header.extend([8, 2, 0, 0, 0]);
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for row in self.pixels.chunks(self.width * 3) {
+            // Every scanline starts with its filter, none
+            encoder.write_all(&[0])?;
+            encoder.write_all(row)?;
+        }
+        let data = encoder.finish()?;
+
+        let mut png = SIGNATURE.to_vec();
+        write_chunk(&mut png, b"IHDR", &header);
+        write_chunk(&mut png, b"IDAT", &data);
+        write_chunk(&mut png, b"IEND", &[]);
+        Ok(png)
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_encode_png() {
+        let mut canvas = Canvas::new(4, 2, [255, 255, 255]);
+        canvas.fill_rect(1, 1, 10, 10, [0, 0, 255]);
+        let png = canvas.encode().unwrap();
+        assert!(png.starts_with(&SIGNATURE));
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+
+        // The image data holds every scanline after its filter byte
+        let length = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+        assert_eq!(&png[37..41], b"IDAT");
+        let mut pixels = Vec::new();
+        ZlibDecoder::new(&png[41..41 + length])
+            .read_to_end(&mut pixels)
+            .unwrap();
+        assert_eq!(pixels.len(), 2 * (1 + 4 * 3));
+        assert_eq!(&pixels[14..17], [255, 255, 255]);
+        assert_eq!(&pixels[17..20], [0, 0, 255]);
+        assert_eq!(text_width("W23"), 22);
+    }
+}
@@ -0,0 +1,402 @@
+use crate::calibration::Levels;
+use crate::category::Rotation;
+use crate::error::WodgenError;
+use crate::pacing;
+use crate::rounds::Focus;
+use crate::template::{Block, Qualifier, Slot, Template};
+use crate::units::Duration;
+use crate::{Exercise, ExerciseCategory, ExerciseLevel, WorkoutExercise};
+use log::info;
+
+// A slot of a group to fill, with the exercises of the pool that fit it
+struct Need {
+    requirement: Requirement,
+    eligible: Vec<usize>,
+}
+
+// What a slot asks for, e.g. `Push` exercises `at Accessory level`
+#[derive(Debug, PartialEq)]
+struct Requirement {
+    exercise_type: String,
+    qualifier: String,
+}
+
+/// The time budget of a session, for `--duration`: its minutes, and the focus
+/// and level setting the rounds of its groups.
+pub struct Budget<'a> {
+    pub minutes: u32,
+    pub focus: Focus,
+    pub level: &'a ExerciseLevel,
+}
+
+// --------------------------------------------------
+
+// The categories of a requirement, e.g. `Primary or Secondary`
+fn categories(categories: &[ExerciseCategory]) -> String {
+    categories
+        .iter()
+        .map(|c| format!("{:?}", c))
+        .collect::<Vec<String>>()
+        .join(" or ")
+}
+
+// Exercises a slot of a block picks in each group, the minimum of a count in
+// a range
+fn count(slot: &Slot, block: &Block) -> u32 {
+    block
+        .per_group
+        .iter()
+        .find(|c| c.exercise_type == slot.exercise_type)
+        .map_or(1, |c| c.min)
+}
+
+// Seconds a workout built from a template takes, estimated like the workout
+// itself: the skill block, each exercise of the groups done for the rounds and
+// reps of the focus, and the cooldown
+fn estimate(template: &Template, budget: &Budget) -> u32 {
+    let rounds = budget.focus.rounds(budget.level).to_string();
+    let mut rows = vec![WorkoutExercise {
+        group: 1,
+        ..Default::default()
+    }];
+    let mut group = 2;
+    for block in template.blocks.iter().filter(|b| b.when.is_none()) {
+        for _ in 0..block.groups {
+            for slot in block.types() {
+                for _ in 0..count(slot, block) {
+                    rows.push(WorkoutExercise {
+                        group,
+                        sets: rounds.clone(),
+                        reps: String::from("X"),
+                        ..Default::default()
+                    });
+                }
+            }
+            group += 1;
+        }
+    }
+    let cooldown = budget.focus.cooldown(group - 2);
+    for _ in 0..cooldown.pieces {
+        rows.push(WorkoutExercise {
+            group,
+            time: String::from("X"),
+            goal: Duration::from_seconds(cooldown.seconds).render(),
+            ..Default::default()
+        });
+    }
+    pacing::total(&pacing::paces(&rows, budget.focus))
+}
+
+// Drops the last group of the blocks without a condition, unless it is the
+// only one left
+fn drop_group(template: &mut Template, groups: &mut u32) -> bool {
+    let last = template
+        .blocks
+        .iter_mut()
+        .rev()
+        .find(|b| b.when.is_none() && b.groups > 0);
+    match last {
+        Some(block) if *groups > 1 => {
+            block.groups -= 1;
+            *groups -= 1;
+            true
+        }
+        _ => false,
+    }
+}
+
+// What a slot of a block asks for in a group
+fn requirement(
+    slot: &Slot,
+    block: &Block,
+    group: u32,
+    level: &ExerciseLevel,
+    rotation: &Rotation,
+) -> Requirement {
+    let mut qualifier = match &slot.qualifier {
+        Qualifier::Rotation => format!(
+            "at {} level",
+            categories(rotation.categories(group, level, &slot.exercise_type))
+        ),
+        Qualifier::Category(category) => {
+            format!("at {} level", categories(std::slice::from_ref(category)))
+        }
+        Qualifier::Any => String::from("of any category"),
+        Qualifier::Node(node) => format!("of the {} pattern", node),
+    };
+    if !block.tags.is_empty() {
+        qualifier.push_str(&format!(" tagged {}", block.tags.join(" or ")));
+    }
+    Requirement {
+        exercise_type: format!("{:?}", slot.exercise_type),
+        qualifier,
+    }
+}
+
+// Gives a slot an exercise, moving the slots holding the exercises it fits to
+// others. The exercises looked at are marked as seen
+fn assign(need: usize, needs: &[Need], owner: &mut [Option<usize>], seen: &mut [bool]) -> bool {
+    for &exercise in &needs[need].eligible {
+        if seen[exercise] {
+            continue;
+        }
+        seen[exercise] = true;
+        if owner[exercise].is_none_or(|other| assign(other, needs, owner, seen)) {
+            owner[exercise] = Some(need);
+            return true;
+        }
+    }
+    false
+}
+
+// Describes slots competing for too few exercises, e.g. `3 Push exercises at
+// Accessory level`
+fn describe(needs: &[&Need]) -> String {
+    let mut counts: Vec<(&Requirement, usize)> = Vec::new();
+    for need in needs {
+        match counts.iter_mut().find(|(r, _)| **r == need.requirement) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((&need.requirement, 1)),
+        }
+    }
+    counts
+        .iter()
+        .map(|(r, count)| {
+            let plural = if *count == 1 { "" } else { "s" };
+            format!(
+                "{} {} exercise{} {}",
+                count, r.exercise_type, plural, r.qualifier
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(" and ")
+}
+
+/// Checks that the pool can fill every slot of the template, each exercise
+/// picked once, before anything is picked. The blocks with a condition may not
+/// be included and are left out, and slots with a count in a range need their
+/// minimum only. Pinned exercises fit the slots of their type whatever their
+/// category.
+///
+/// # Errors
+///
+/// This function will return an error naming the slots the pool is too small
+/// for, and how many exercises they could pick from.
+pub fn check(
+    template: &Template,
+    pool: &[Exercise],
+    pinned: &[String],
+    levels: &Levels,
+) -> Result<(), WodgenError> {
+    let rotation = template.rotation();
+    let mut needs = Vec::new();
+    let mut group = 0;
+    for block in template.blocks.iter().filter(|b| b.when.is_none()) {
+        for _ in 0..block.groups {
+            for slot in block.types() {
+                let eligible: Vec<usize> = pool
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, e)| {
+                        slot.accepts(e)
+                            && levels.allows(e)
+                            && block.accepts(e)
+                            && (pinned.contains(&e.name)
+                                || slot.fits(e, group, levels.of(&slot.exercise_type), &rotation))
+                    })
+                    .map(|(i, _)| i)
+                    .collect();
+                for _ in 0..count(slot, block) {
+                    needs.push(Need {
+                        requirement: requirement(
+                            slot,
+                            block,
+                            group,
+                            levels.of(&slot.exercise_type),
+                            &rotation,
+                        ),
+                        eligible: eligible.clone(),
+                    });
+                }
+            }
+            group += 1;
+        }
+    }
+
+    // The slots get their exercises one by one, those before giving up theirs
+    // when they can take another. The first slot left without one competes
+    // with the slots holding every exercise it could take, and with the later
+    // slots left without one that could take only those
+    let mut owner = vec![None; pool.len()];
+    let mut shortfall: Option<(Vec<usize>, Vec<bool>)> = None;
+    for need in 0..needs.len() {
+        let mut seen = vec![false; pool.len()];
+        if assign(need, &needs, &mut owner, &mut seen) {
+            continue;
+        }
+        match &mut shortfall {
+            None => {
+                let mut competing: Vec<usize> = (0..pool.len())
+                    .filter(|exercise| seen[*exercise])
+                    .filter_map(|exercise| owner[exercise])
+                    .collect();
+                competing.push(need);
+                shortfall = Some((competing, seen));
+            }
+            Some((competing, taken)) => {
+                if needs[need].eligible.iter().all(|exercise| taken[*exercise]) {
+                    competing.push(need);
+                }
+            }
+        }
+    }
+    if let Some((mut competing, taken)) = shortfall {
+        competing.sort();
+        let available = taken.iter().filter(|taken| **taken).count();
+        let competing: Vec<&Need> = competing.iter().map(|i| &needs[*i]).collect();
+        return Err(WodgenError::PoolTooSmall(describe(&competing), available));
+    }
+    info!(
+        "The pool can fill the {} slots of the template",
+        needs.len()
+    );
+    Ok(())
+}
+
+/// Drops the groups the pool cannot fill from the end of the template, for
+/// `--auto-groups`, so the workout gets as many groups as it can up to those
+/// planned. With a time budget, the groups past it are dropped as well, their
+/// time estimated like that of the workout. At least one group is kept.
+///
+/// # Errors
+///
+/// This function will return an error if the pool cannot fill even one group.
+pub fn fit_groups(
+    template: &mut Template,
+    pool: &[Exercise],
+    pinned: &[String],
+    levels: &Levels,
+    budget: Option<&Budget>,
+) -> Result<(), WodgenError> {
+    let planned: u32 = template.blocks.iter().map(|b| b.groups).sum();
+    let mut groups = planned;
+    while let Err(error) = check(template, pool, pinned, levels) {
+        if !drop_group(template, &mut groups) {
+            return Err(error);
+        }
+    }
+    if groups < planned {
+        info!(
+            "The pool can fill {} of the {} groups, the others are dropped",
+            groups, planned
+        );
+    }
+    if let Some(budget) = budget {
+        let filled = groups;
+        while estimate(template, budget) > budget.minutes * 60 {
+            if !drop_group(template, &mut groups) {
+                break;
+            }
+        }
+        if groups < filled {
+            info!(
+                "The {} min budget fits {} of the {} groups, the others are dropped",
+                budget.minutes, groups, filled
+            );
+        }
+    }
+    template.blocks.retain(|b| b.groups > 0);
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::TypeCount;
+    use crate::ExerciseType;
+
+    fn exercise(name: &str, exercise_category: ExerciseCategory) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_category,
+            exercise_level: ExerciseLevel::Beginner,
+            ..Default::default()
+        }
+    }
+
+    fn template(groups: u32, per_group: &str) -> Template {
+        Template {
+            category_strategy: None,
+            blocks: vec![Block {
+                name: None,
+                types: Some(vec![Slot::from(ExerciseType::Push)]),
+                groups,
+                tags: Vec::new(),
+                when: None,
+                per_group: per_group
+                    .split_whitespace()
+                    .map(|c| TypeCount::try_from(c.to_string()).unwrap())
+                    .collect(),
+            }],
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_check_pool_size() {
+        let pool = vec![
+            exercise("dip", ExerciseCategory::Primary),
+            exercise("pike_push_up", ExerciseCategory::Secondary),
+            exercise("push_up", ExerciseCategory::Secondary),
+            exercise("diamond_push_up", ExerciseCategory::Accessory),
+        ];
+        let level = Levels::from(ExerciseLevel::Intermediate);
+        // Primary, then Primary or Secondary, then Secondary or Accessory
+        assert!(check(&template(3, ""), &pool, &[], &level).is_ok());
+        // The second group takes a Secondary exercise to leave the Accessory
+        // one to the fourth
+        assert!(check(&template(4, ""), &pool, &[], &level).is_ok());
+
+        let error = check(&template(5, ""), &pool, &[], &level).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Need 2 Push exercises at Accessory level, only 1 available, add exercises to the library, reduce --groups or pass --auto-groups"
+        );
+        let error = check(&template(7, ""), &pool, &[], &level).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Need 4 Push exercises at Accessory level, only 1 available"));
+
+        let error = check(&template(1, "push=2-3"), &pool, &[], &level).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Need 2 Push exercises at Primary level, only 1 available"));
+        // The groups past the fourth are dropped
+        let mut fitted = template(7, "");
+        fit_groups(&mut fitted, &pool, &[], &level, None).unwrap();
+        assert_eq!(fitted.blocks[0].groups, 4);
+        let mut empty = template(2, "");
+        assert!(fit_groups(&mut empty, &[], &[], &level, None).is_err());
+        // A group of one exercise takes 5:30 for hypertrophy: a minute to set
+        // up and 3 rounds of 10 reps, 90s apart. The skill block and the
+        // cooldown take 3:45
+        let budget = |minutes| Budget {
+            minutes,
+            focus: Focus::Hypertrophy,
+            level: &ExerciseLevel::Beginner,
+        };
+        let mut fitted = template(4, "");
+        fit_groups(&mut fitted, &pool, &[], &level, Some(&budget(15))).unwrap();
+        assert_eq!(fitted.blocks[0].groups, 2);
+        let mut fitted = template(4, "");
+        fit_groups(&mut fitted, &pool, &[], &level, Some(&budget(5))).unwrap();
+        assert_eq!(fitted.blocks[0].groups, 1);
+
+        // A pinned exercise fits whatever its category
+        let pinned = [String::from("push_up")];
+        assert!(check(&template(1, "push=2-3"), &pool, &pinned, &level).is_ok());
+    }
+}
@@ -0,0 +1,257 @@
+use crate::display;
+use crate::history::History;
+use crate::units::{Distance, Duration, Units};
+use crate::Exercise;
+use log::{info, warn};
+use std::str::FromStr;
+
+// --------------------------------------------------
+
+// Enum for what has to be logged for an exercise: reps, a time or a distance,
+// in a single set
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Target {
+    Reps(u32),
+    Time(Duration),
+    Distance(Distance),
+}
+
+/// An exercise and what has to be logged for it before an exercise needing it
+/// unlocks, e.g. `pull-up__regular: 10` for 10 pull-ups in a set.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Prerequisite {
+    /// Name of the exercise in the library, or the name it is shown under
+    pub exercise: String,
+    pub target: Target,
+}
+
+// --------------------------------------------------
+
+impl FromStr for Target {
+    type Err = String;
+
+    /// Parses reps from a bare number, e.g. `10`, then a time, e.g. `30s`, or a
+    /// distance, e.g. `1km`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(reps) = s.parse::<u32>() {
+            Ok(Target::Reps(reps))
+        } else if let Ok(time) = s.parse::<Duration>() {
+            Ok(Target::Time(time))
+        } else if let Ok(distance) = s.parse::<Distance>() {
+            Ok(Target::Distance(distance))
+        } else {
+            Err(format!("Invalid target {:?}, e.g. 10, 30s or 1km", s))
+        }
+    }
+}
+
+impl FromStr for Prerequisite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (exercise, target) = s
+            .rsplit_once(':')
+            .ok_or_else(|| format!("Invalid prerequisite {:?}, e.g. pull_up: 10", s))?;
+        if exercise.trim().is_empty() {
+            return Err(format!("Invalid prerequisite {:?}, e.g. pull_up: 10", s));
+        }
+        Ok(Prerequisite {
+            exercise: exercise.trim().to_string(),
+            target: target.parse()?,
+        })
+    }
+}
+
+impl Target {
+    fn render(&self, units: Units) -> String {
+        match self {
+            Target::Reps(1) => String::from("1 rep"),
+            Target::Reps(reps) => format!("{} reps", reps),
+            Target::Time(time) => time.render(),
+            Target::Distance(distance) => distance.render(units),
+        }
+    }
+}
+
+impl Prerequisite {
+    /// Parses the prerequisites of an exercise, leaving out the invalid ones
+    /// with a warning.
+    pub fn of(exercise: &Exercise) -> Vec<Prerequisite> {
+        exercise
+            .prerequisites
+            .iter()
+            .filter_map(|p| match p.parse() {
+                Ok(prerequisite) => Some(prerequisite),
+                Err(error) => {
+                    warn!("Ignoring a prerequisite of {}: {}", exercise.name, error);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    // The best set logged toward the target, none when nothing was
+    fn best(&self, history: &History, library: &[Exercise]) -> Option<Target> {
+        let (reps, time, distance) =
+            history.records(&display::library_name(&self.exercise, library));
+        match self.target {
+            Target::Reps(_) => reps.map(Target::Reps),
+            Target::Time(_) => time.map(Target::Time),
+            Target::Distance(_) => distance.map(Target::Distance),
+        }
+    }
+
+    /// Whether a set logged for the exercise reaches the target.
+    pub fn is_met(&self, history: &History, library: &[Exercise]) -> bool {
+        match (self.best(history, library), self.target) {
+            (Some(Target::Reps(best)), Target::Reps(target)) => best >= target,
+            (Some(Target::Time(best)), Target::Time(target)) => best >= target,
+            (Some(Target::Distance(best)), Target::Distance(target)) => best >= target,
+            _ => false,
+        }
+    }
+
+    /// Renders the target and the best set logged toward it, e.g.
+    /// `Pull Up: 10 reps (best: 7 reps)`.
+    pub fn render(&self, history: &History, library: &[Exercise], units: Units) -> String {
+        let best = self
+            .best(history, library)
+            .map_or_else(|| String::from("nothing logged"), |b| b.render(units));
+        format!(
+            "{}: {} (best: {}){}",
+            display::library_name(&self.exercise, library),
+            self.target.render(units),
+            best,
+            if self.is_met(history, library) {
+                ", done"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+// --------------------------------------------------
+
+/// Whether every prerequisite of an exercise is met in the history.
+pub fn is_unlocked(exercise: &Exercise, history: &History, library: &[Exercise]) -> bool {
+    Prerequisite::of(exercise)
+        .iter()
+        .all(|p| p.is_met(history, library))
+}
+
+/// Leaves out the exercises with a prerequisite not met yet, and returns their
+/// names.
+pub fn filter_locked(
+    exercises: &mut Vec<Exercise>,
+    history: &History,
+    library: &[Exercise],
+) -> Vec<String> {
+    let locked: Vec<String> = exercises
+        .iter()
+        .filter(|e| !is_unlocked(e, history, library))
+        .map(|e| e.name.clone())
+        .collect();
+    exercises.retain(|e| !locked.contains(&e.name));
+    if !locked.is_empty() {
+        info!(
+            "Left out {} exercises with prerequisites not met, {} exercises remaining",
+            locked.len(),
+            exercises.len()
+        );
+    }
+    locked
+}
+
+/// Renders the active exercises still locked, each with its prerequisites and
+/// the best sets logged toward them.
+pub fn render_goals(library: &[Exercise], history: &History, units: Units) -> String {
+    let locked: Vec<&Exercise> = library
+        .iter()
+        .filter(|e| e.active && !is_unlocked(e, history, library))
+        .collect();
+    if locked.is_empty() {
+        return String::from("Every exercise is unlocked\n");
+    }
+    let mut output = String::new();
+    for e in locked {
+        output.push_str(&format!("{} needs:\n", display::exercise_name(e)));
+        for p in Prerequisite::of(e) {
+            output.push_str(&format!("  {}\n", p.render(history, library, units)));
+        }
+    }
+    output
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WorkoutExercise;
+    use std::time::SystemTime;
+
+    fn exercise(name: &str, prerequisites: &[&str]) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type: crate::ExerciseType::Pull,
+            exercise_category: crate::ExerciseCategory::Primary,
+            exercise_level: crate::ExerciseLevel::Advanced,
+            exercise_programming: crate::ExerciseProgramming::Reps,
+            prerequisites: prerequisites.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_prerequisites() {
+        assert_eq!(
+            "pull_up: 10".parse::<Prerequisite>(),
+            Ok(Prerequisite {
+                exercise: String::from("pull_up"),
+                target: Target::Reps(10)
+            })
+        );
+        assert_eq!(
+            "l_sit:30s".parse::<Prerequisite>().unwrap().target,
+            Target::Time(Duration::from_seconds(30))
+        );
+        assert!("pull_up".parse::<Prerequisite>().is_err());
+        assert!(": 10".parse::<Prerequisite>().is_err());
+
+        let library = [
+            exercise("pull_up", &[]),
+            exercise("dip", &[]),
+            exercise("muscle_up", &["pull_up: 10", "dip: 10", "typo"]),
+        ];
+        let mut history = History::default();
+        let logged = |name: &str, reps: &str| WorkoutExercise {
+            group: 2,
+            name: String::from(name),
+            reps: String::from(reps),
+            ..Default::default()
+        };
+        history.record(
+            SystemTime::now(),
+            &[logged("Pull Up", "8/7"), logged("Dip", "12")],
+        );
+        let mut exercises = library.to_vec();
+        assert_eq!(
+            filter_locked(&mut exercises, &history, &library),
+            ["muscle_up"]
+        );
+        assert_eq!(exercises.len(), 2);
+        assert_eq!(
+            render_goals(&library, &history, Units::Metric),
+            "Muscle Up needs:\n  Pull Up: 10 reps (best: 8 reps)\n  Dip: 10 reps (best: 12 reps), done\n"
+        );
+
+        history.record(SystemTime::now(), &[logged("Pull Up", "10")]);
+        assert!(is_unlocked(&library[2], &history, &library));
+        assert_eq!(
+            render_goals(&library, &history, Units::Metric),
+            "Every exercise is unlocked\n"
+        );
+    }
+}
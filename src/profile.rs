@@ -0,0 +1,338 @@
+use crate::calibration::Levels;
+use crate::error::WodgenError;
+use crate::explain::Pick;
+use crate::roster::deserialize_value_enum;
+use crate::template::Template;
+use crate::units::Units;
+use crate::{display, Exercise, ExerciseLevel, SnoozedExercise, WorkoutExercise};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use log::info;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// File in the exercise library that defines or overrides profiles
+const PROFILES_FILE: &str = "profiles.toml";
+
+// --------------------------------------------------
+
+/// Restrictions applied on top of the command line options for a kind of
+/// session, e.g. a workout in a hotel room.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Name the profile is selected by, the key of its table in profiles.toml
+    #[serde(skip)]
+    pub name: String,
+    /// Only pick bodyweight exercises
+    pub bodyweight: bool,
+    /// Leave out exercises with any of these tags
+    pub exclude_tags: Vec<String>,
+    /// Cap on the number of strength groups, for a shorter session
+    pub max_groups: Option<u32>,
+    /// Level replacing --level
+    #[serde(deserialize_with = "deserialize_value_enum")]
+    pub level: Option<ExerciseLevel>,
+    /// Use playful block names in the output
+    pub playful: bool,
+    /// Replace the exercises left out with one from the same progression chain,
+    /// instead of letting the group pick from the rest of the pool
+    pub substitute: bool,
+}
+
+/// The layout of profiles.toml, profiles keyed by name.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfilesFile {
+    profiles: HashMap<String, Profile>,
+}
+
+// --------------------------------------------------
+
+impl Profile {
+    /// Returns the travel profile: bodyweight exercises that need no equipment
+    /// and make no noise, in a compact session of two groups.
+    pub fn travel() -> Profile {
+        Profile {
+            name: String::from("travel"),
+            bodyweight: true,
+            exclude_tags: vec![String::from("equipment"), String::from("jumping")],
+            max_groups: Some(2),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the kids profile, also suited to true novices: beginner
+    /// exercises without high skill or load, in a short session with playful
+    /// block names.
+    pub fn kids() -> Profile {
+        Profile {
+            name: String::from("kids"),
+            exclude_tags: vec![String::from("high-skill"), String::from("high-load")],
+            max_groups: Some(2),
+            level: Some(ExerciseLevel::Beginner),
+            playful: true,
+            ..Default::default()
+        }
+    }
+
+    // An adaptation profile that swaps the contraindicated exercises for safer
+    // ones of the same progression chain
+    fn adaptation(name: &str, exclude_tags: &[&str]) -> Profile {
+        Profile {
+            name: String::from(name),
+            exclude_tags: exclude_tags.iter().map(|tag| tag.to_string()).collect(),
+            substitute: true,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the profiles shipped with wodgen.
+    pub fn built_in() -> Vec<Profile> {
+        vec![
+            Profile::travel(),
+            Profile::kids(),
+            Profile::adaptation(
+                "pregnancy",
+                &["supine", "prone", "high-impact", "jumping", "high-load"],
+            ),
+            Profile::adaptation(
+                "postpartum",
+                &["high-impact", "jumping", "high-load", "core-pressure"],
+            ),
+            Profile::adaptation("senior", &["high-impact", "jumping", "high-skill"]),
+        ]
+    }
+
+    /// Whether the profile lets the exercise be picked.
+    pub fn allows(&self, exercise: &Exercise) -> bool {
+        (!self.bodyweight || exercise.bodyweight)
+            && !exercise
+                .tags
+                .iter()
+                .any(|tag| self.exclude_tags.contains(tag))
+    }
+
+    /// Shortens the template to at most `max_groups` groups, dropping the groups
+    /// of the last blocks first.
+    pub fn cap_groups(&self, template: &mut Template) {
+        let Some(max_groups) = self.max_groups else {
+            return;
+        };
+        let mut remaining = max_groups;
+        for block in &mut template.blocks {
+            block.groups = block.groups.min(remaining);
+            remaining -= block.groups;
+        }
+        template.blocks.retain(|block| block.groups > 0);
+        info!(
+            "Capped the workout to {} groups for the {} profile",
+            max_groups, self.name
+        );
+    }
+
+    // An allowed replacement for an exercise, preferring the same progression
+    // chain, then the same movement pattern, then the same category
+    fn replacement<'a>(
+        &self,
+        exercise: &Exercise,
+        pool: &'a [Exercise],
+        levels: &Levels,
+        taken: &[String],
+    ) -> Option<&'a Exercise> {
+        let candidates: Vec<&Exercise> = pool
+            .iter()
+            .filter(|e| e.exercise_type == exercise.exercise_type)
+            .filter(|e| self.allows(e) && levels.allows(e))
+            .filter(|e| !taken.contains(&e.name))
+            .collect();
+        let same = |field: fn(&Exercise) -> Option<&String>| {
+            candidates
+                .iter()
+                .find(|e| field(exercise).is_some() && field(e) == field(exercise))
+        };
+        same(|e| e.progression.as_ref())
+            .or_else(|| same(|e| e.pattern.as_ref()))
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .find(|e| e.exercise_category == exercise.exercise_category)
+            })
+            .copied()
+    }
+
+    /// Replaces each picked exercise the profile does not allow with an allowed
+    /// one from `pool`, dropping it when there is none. The snoozes follow the
+    /// replacements.
+    pub fn substitute_picks(
+        &self,
+        workout: &mut Vec<WorkoutExercise>,
+        picks: &mut Vec<Pick>,
+        pool: &[Exercise],
+        levels: &Levels,
+        snoozed_exercises: &mut Vec<SnoozedExercise>,
+        units: Units,
+    ) {
+        let mut taken: Vec<String> = picks.iter().map(|p| p.name.clone()).collect();
+        let mut index = 0;
+        while index < picks.len() {
+            let pick = &picks[index];
+            let Some(exercise) = pool.iter().find(|e| e.name == pick.name) else {
+                index += 1;
+                continue;
+            };
+            if self.allows(exercise) {
+                index += 1;
+                continue;
+            }
+            let group = pick.group + 2;
+            let row = workout
+                .iter()
+                .position(|w| w.group == group && w.name == display::exercise_name(exercise));
+            snoozed_exercises.retain(|s| s.name != exercise.name);
+            match (self.replacement(exercise, pool, levels, &taken), row) {
+                (Some(replacement), Some(row)) => {
+                    info!(
+                        "Replaced {} with {} for the {} profile",
+                        exercise.name, replacement.name, self.name
+                    );
+                    workout[row] = WorkoutExercise::from_exercise(group, replacement, units);
+                    snoozed_exercises.push(SnoozedExercise {
+                        name: replacement.name.clone(),
+                        timestamp: Utc::now(),
+                    });
+                    taken.push(replacement.name.clone());
+                    picks[index].name = replacement.name.clone();
+                    index += 1;
+                }
+                (_, row) => {
+                    info!(
+                        "Dropped {}, nothing replaces it for the {} profile",
+                        exercise.name, self.name
+                    );
+                    if let Some(row) = row {
+                        workout.remove(row);
+                    }
+                    picks.remove(index);
+                }
+            }
+        }
+    }
+}
+
+// --------------------------------------------------
+
+/// Finds a profile by name among the built-in profiles and those defined in the
+/// `profiles.toml` file of the exercise library, which take precedence.
+///
+/// # Errors
+///
+/// This function will return an error if profiles.toml cannot be parsed, or
+/// `WodgenError::UnknownProfile` if no profile has that name.
+pub fn find_profile(name: &str, exercise_library_dir: &Path) -> Result<Profile> {
+    let mut profiles = Profile::built_in();
+    let file_path = exercise_library_dir.join(PROFILES_FILE);
+    if file_path.is_file() {
+        let content = fs::read_to_string(&file_path)
+            .with_context(|| format!("Failed to open file: {:?}", file_path))?;
+        let file: ProfilesFile = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse profiles: {:?}", file_path))?;
+        for (name, mut profile) in file.profiles {
+            profiles.retain(|p| p.name != name);
+            profile.name = name;
+            profiles.push(profile);
+        }
+    }
+    match profiles.iter().position(|p| p.name == name) {
+        Some(index) => Ok(profiles.swap_remove(index)),
+        None => {
+            let mut names: Vec<String> = profiles.into_iter().map(|p| p.name).collect();
+            names.sort();
+            Err(WodgenError::UnknownProfile(name.to_string(), names.join(", ")).into())
+        }
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExerciseProgramming, ExerciseType};
+
+    fn exercise(name: &str, progression: &str, tags: &[&str]) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type: ExerciseType::Core,
+            exercise_level: ExerciseLevel::Beginner,
+            exercise_programming: ExerciseProgramming::Time,
+            progression: Some(String::from(progression)),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cap_groups() {
+        let mut template = Template::from_types(&[ExerciseType::Push.into()], 3);
+        template
+            .blocks
+            .extend(Template::from_types(&[ExerciseType::Core.into()], 1).blocks);
+        Profile::travel().cap_groups(&mut template);
+        assert_eq!(template.blocks.len(), 1);
+        assert_eq!(template.blocks[0].groups, 2);
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_substitute_from_progression_chain() {
+        let pool = vec![
+            exercise("hollow_hold", "hollow", &["supine"]),
+            exercise("bird_dog", "anti_extension", &[]),
+            exercise("hollow_hold_standing", "hollow", &[]),
+        ];
+        let profile = find_profile("pregnancy", Path::new("/nonexistent")).unwrap();
+        let mut workout = vec![WorkoutExercise::from_exercise(2, &pool[0], Units::Metric)];
+        let mut picks = vec![Pick {
+            group: 0,
+            block: 0,
+            slot: ExerciseType::Core.into(),
+            name: String::from("hollow_hold"),
+        }];
+        let mut snoozed = Vec::new();
+        profile.substitute_picks(
+            &mut workout,
+            &mut picks,
+            &pool,
+            &Levels::from(ExerciseLevel::Beginner),
+            &mut snoozed,
+            Units::Metric,
+        );
+        assert_eq!(workout[0].name, "Hollow Hold Standing");
+        assert_eq!(picks[0].name, "hollow_hold_standing");
+        assert_eq!(snoozed[0].name, "hollow_hold_standing");
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_profiles_file_overrides_built_in() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(
+            tmp.path().join(PROFILES_FILE),
+            r#"
+            [profiles.senior]
+            exclude_tags = ["overhead"]
+            level = "beginner"
+            "#,
+        )
+        .unwrap();
+        let senior = find_profile("senior", tmp.path()).unwrap();
+        assert_eq!(senior.exclude_tags, ["overhead"]);
+        assert_eq!(senior.level, Some(ExerciseLevel::Beginner));
+        assert!(find_profile("astronaut", tmp.path()).is_err());
+    }
+}
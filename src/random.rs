@@ -0,0 +1,37 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::cell::RefCell;
+
+thread_local! {
+    // Generator of every random pick, seeded from the OS until a run seeds it
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+// --------------------------------------------------
+
+/// Seeds the generator of the random picks, so the same seed repeats them.
+pub fn reseed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// Runs `f` with the generator of the random picks.
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_reseed_repeats_the_picks() {
+        let draw = || with_rng(|rng| (0..8).map(|_| rng.gen_range(0..100)).collect::<Vec<u32>>());
+        reseed(42);
+        let first = draw();
+        reseed(42);
+        assert_eq!(draw(), first);
+    }
+}
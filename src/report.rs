@@ -0,0 +1,516 @@
+use crate::chart::{week_start, Chart, Point};
+use crate::error::WodgenError;
+use crate::export::{escape_html, escape_markdown};
+use crate::history::{best_value, logged_sets};
+use crate::units::{Distance, Duration, Units};
+use crate::{display, Exercise, WorkoutExercise};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{Datelike, Local, Months, NaiveDate};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// Number of muscles listed under the most trained ones
+const TOP_MUSCLES: usize = 5;
+
+// --------------------------------------------------
+
+// Enum for the formats a report can be rendered in
+#[derive(Debug, PartialEq, Clone, Copy, clap::ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+// Enum for how the charts of an HTML report are embedded: as SVG drawings, as
+// PNG images for mail clients and viewers without SVG, or not at all
+#[derive(Debug, Default, PartialEq, Clone, Copy, clap::ValueEnum)]
+pub enum ChartFormat {
+    #[default]
+    Svg,
+    Png,
+    None,
+}
+
+/// The days a report covers, a calendar month or an ISO week.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Period {
+    /// Name of the period in the report title, e.g. `2024-06` or `2024-W23`
+    pub label: String,
+    start: NaiveDate,
+    // First day after the period
+    end: NaiveDate,
+}
+
+/// A personal record set during the period.
+#[derive(Debug, PartialEq)]
+pub struct Record {
+    pub date: NaiveDate,
+    pub exercise: String,
+    pub value: String,
+}
+
+/// The summary of the training done during a period.
+#[derive(Debug)]
+pub struct Report {
+    pub period: Period,
+    /// Workouts saved for the period
+    pub planned: usize,
+    /// Workouts with at least one value logged
+    pub completed: usize,
+    /// Sets logged per exercise type, most first
+    pub sets_by_type: Vec<(String, usize)>,
+    /// Sets logged per muscle, most first
+    pub sets_by_muscle: Vec<(String, usize)>,
+    /// Sets logged in each workout of the period, oldest first
+    pub sets_by_session: Vec<(NaiveDate, usize)>,
+    pub records: Vec<Record>,
+}
+
+// A titled table of the report
+struct Section {
+    title: &'static str,
+    headers: &'static [&'static str],
+    rows: Vec<Vec<String>>,
+}
+
+// --------------------------------------------------
+
+impl Period {
+    /// Returns the calendar month of the given day.
+    pub fn month_of(date: NaiveDate) -> Period {
+        let start = date.with_day(1).unwrap_or(date);
+        Period {
+            label: start.format("%Y-%m").to_string(),
+            start,
+            end: start + Months::new(1),
+        }
+    }
+
+    /// Returns the current calendar month.
+    pub fn this_month() -> Period {
+        Period::month_of(Local::now().date_naive())
+    }
+
+    /// Parses a calendar month, e.g. `2024-06`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WodgenError::InvalidPeriod` if the month is not written as `YYYY-MM`.
+    pub fn month(month: &str) -> Result<Period, WodgenError> {
+        NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+            .map(Period::month_of)
+            .map_err(|_| WodgenError::InvalidPeriod(month.to_string()))
+    }
+
+    /// Parses an ISO week, e.g. `2024-W23`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WodgenError::InvalidPeriod` if the week is not written as `YYYY-Www`.
+    pub fn week(week: &str) -> Result<Period, WodgenError> {
+        let start = NaiveDate::parse_from_str(&format!("{}-1", week), "%G-W%V-%u")
+            .map_err(|_| WodgenError::InvalidPeriod(week.to_string()))?;
+        Ok(Period {
+            label: start.format("%G-W%V").to_string(),
+            start,
+            end: start + chrono::Duration::days(7),
+        })
+    }
+
+    /// Whether the day falls in the period.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date < self.end
+    }
+}
+
+// --------------------------------------------------
+
+// The best values logged for an exercise so far
+#[derive(Debug, Default)]
+struct Best {
+    reps: Option<u32>,
+    time: Option<Duration>,
+    distance: Option<Distance>,
+}
+
+// Keep a new best value, returning it when it beats an earlier one
+fn beat<T: FromStr + PartialOrd + Copy>(best: &mut Option<T>, cell: &str) -> Option<T> {
+    let value = best_value::<T>(cell)?;
+    match *best {
+        Some(previous) if value <= previous => None,
+        previous => {
+            *best = Some(value);
+            previous.map(|_| value)
+        }
+    }
+}
+
+// Sort tallies with the largest first, then by name
+fn ranked(tally: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut ranked: Vec<(String, usize)> = tally.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+impl Report {
+    /// Builds the report of a period from the saved workouts, oldest first, and
+    /// the exercise library used to look up the type and muscles of each
+    /// exercise.
+    ///
+    /// A record is set when a logged value beats the best one logged for the
+    /// exercise before, so workouts before the period count toward the bests.
+    /// The sessions marked `done` count as completed, logged or not.
+    pub fn build(
+        period: Period,
+        workouts: &[(NaiveDate, Vec<WorkoutExercise>)],
+        done: &[NaiveDate],
+        exercises: &[Exercise],
+        units: Units,
+    ) -> Report {
+        let library: HashMap<String, &Exercise> = exercises
+            .iter()
+            .map(|e| (display::exercise_name(e), e))
+            .collect();
+        let mut bests: HashMap<&str, Best> = HashMap::new();
+        let mut report = Report {
+            period,
+            planned: 0,
+            completed: 0,
+            sets_by_type: Vec::new(),
+            sets_by_muscle: Vec::new(),
+            sets_by_session: Vec::new(),
+            records: Vec::new(),
+        };
+        let mut sets_by_type = HashMap::new();
+        let mut sets_by_muscle = HashMap::new();
+
+        for (date, workout) in workouts {
+            let in_period = report.period.contains(*date);
+            let mut completed = false;
+            let mut session_sets = 0;
+            for e in workout.iter().filter(|e| e.group > 0) {
+                let best = bests.entry(e.name.as_str()).or_default();
+                let records = [
+                    beat(&mut best.reps, &e.reps).map(|r| r.to_string()),
+                    beat(&mut best.time, &e.time).map(|t| t.render()),
+                    beat(&mut best.distance, &e.distance).map(|d| d.render(units)),
+                ];
+                if !in_period {
+                    continue;
+                }
+                report
+                    .records
+                    .extend(records.into_iter().flatten().map(|value| Record {
+                        date: *date,
+                        exercise: e.name.clone(),
+                        value,
+                    }));
+
+                let sets = logged_sets(e);
+                if sets == 0 {
+                    continue;
+                }
+                completed = true;
+                session_sets += sets;
+                if let Some(exercise) = library.get(&e.name) {
+                    *sets_by_type
+                        .entry(format!("{:?}", exercise.exercise_type))
+                        .or_insert(0) += sets;
+                    for muscle in &exercise.muscles {
+                        *sets_by_muscle.entry(muscle.clone()).or_insert(0) += sets;
+                    }
+                }
+            }
+            if in_period {
+                report.planned += 1;
+                report.completed += usize::from(completed || done.contains(date));
+                report.sets_by_session.push((*date, session_sets));
+            }
+        }
+
+        report.sets_by_type = ranked(sets_by_type);
+        report.sets_by_muscle = ranked(sets_by_muscle);
+        report.sets_by_muscle.truncate(TOP_MUSCLES);
+        report
+    }
+
+    /// Share of the planned workouts that were completed, in percent.
+    pub fn adherence(&self) -> u32 {
+        if self.planned == 0 {
+            return 0;
+        }
+        (self.completed * 100 / self.planned) as u32
+    }
+
+    // The report as titled tables of rows
+    fn sections(&self) -> Vec<Section> {
+        let tally = |rows: &[(String, usize)]| {
+            rows.iter()
+                .map(|(name, sets)| vec![name.clone(), sets.to_string()])
+                .collect()
+        };
+        vec![
+            Section {
+                title: "Volume by Type",
+                headers: &["Type", "Sets"],
+                rows: tally(&self.sets_by_type),
+            },
+            Section {
+                title: "Most Trained Muscles",
+                headers: &["Muscle", "Sets"],
+                rows: tally(&self.sets_by_muscle),
+            },
+            Section {
+                title: "Personal Records",
+                headers: &["Date", "Exercise", "Record"],
+                rows: self
+                    .records
+                    .iter()
+                    .map(|r| vec![r.date.to_string(), r.exercise.clone(), r.value.clone()])
+                    .collect(),
+            },
+        ]
+    }
+
+    /// Charts of the progress over the period: the sets logged in each
+    /// session, and in each week.
+    pub fn charts(&self) -> Vec<Chart> {
+        let sessions = self
+            .sets_by_session
+            .iter()
+            .map(|(date, sets)| Point {
+                label: date.format("%m-%d").to_string(),
+                value: Some(*sets as f64),
+                text: sets.to_string(),
+            })
+            .collect();
+        let mut weeks: Vec<Point> = Vec::new();
+        for (date, sets) in &self.sets_by_session {
+            let label = week_start(*date).format("W%V").to_string();
+            match weeks.last_mut() {
+                Some(week) if week.label == label => {
+                    let total = week.value.unwrap_or(0.0) + *sets as f64;
+                    week.value = Some(total);
+                    week.text = total.to_string();
+                }
+                _ => weeks.push(Point {
+                    label,
+                    value: Some(*sets as f64),
+                    text: sets.to_string(),
+                }),
+            }
+        }
+        vec![
+            Chart {
+                title: String::from("Sets per Session"),
+                points: sessions,
+            },
+            Chart {
+                title: String::from("Sets per Week"),
+                points: weeks,
+            },
+        ]
+    }
+
+    // The sessions line at the top of the report
+    fn summary(&self) -> String {
+        format!(
+            "Sessions completed: {} of {} planned ({}% adherence)",
+            self.completed,
+            self.planned,
+            self.adherence()
+        )
+    }
+
+    /// Renders the report as a Markdown document.
+    pub fn render_markdown(&self) -> String {
+        let mut output = format!("# Report {}\n\n{}\n", self.period.label, self.summary());
+        for section in self.sections() {
+            output.push_str(&format!("\n## {}\n\n", section.title));
+            if section.rows.is_empty() {
+                output.push_str("Nothing logged.\n");
+                continue;
+            }
+            output.push_str(&format!("| {} |\n", section.headers.join(" | ")));
+            output.push_str(&format!("|{}\n", " --- |".repeat(section.headers.len())));
+            for row in section.rows {
+                let cells: Vec<String> = row.iter().map(|c| escape_markdown(c)).collect();
+                output.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+        }
+        output
+    }
+
+    /// Renders the report as a standalone HTML page, with its charts embedded
+    /// in the given format.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a PNG chart cannot be encoded.
+    pub fn render_html(&self, charts: ChartFormat) -> std::io::Result<String> {
+        let title = format!("Report {}", escape_html(&self.period.label));
+        let mut output = String::new();
+        output.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        output.push_str(&format!("<title>{}</title>\n", title));
+        output.push_str("<style>\nbody { font-family: sans-serif; }\ntable { border-collapse: collapse; }\nth, td { border: 1px solid #ccc; padding: 4px 8px; }\n</style>\n");
+        output.push_str("</head>\n<body>\n");
+        output.push_str(&format!("<h1>{}</h1>\n<p>{}</p>\n", title, self.summary()));
+        if charts != ChartFormat::None && !self.sets_by_session.is_empty() {
+            output.push_str("<h2>Progress</h2>\n");
+            for chart in self.charts() {
+                output.push_str(&format!("<h3>{}</h3>\n", chart.title));
+                match charts {
+                    ChartFormat::Svg => output.push_str(&chart.render_svg()),
+                    _ => output.push_str(&format!(
+                        "<img alt=\"{}\" src=\"data:image/png;base64,{}\">\n",
+                        chart.title,
+                        STANDARD.encode(chart.render_png()?)
+                    )),
+                }
+            }
+        }
+        for section in self.sections() {
+            output.push_str(&format!("<h2>{}</h2>\n", section.title));
+            if section.rows.is_empty() {
+                output.push_str("<p>Nothing logged.</p>\n");
+                continue;
+            }
+            output.push_str("<table>\n<tr>");
+            for header in section.headers {
+                output.push_str(&format!("<th>{}</th>", header));
+            }
+            output.push_str("</tr>\n");
+            for row in section.rows {
+                output.push_str("<tr>");
+                for cell in &row {
+                    output.push_str(&format!("<td>{}</td>", escape_html(cell)));
+                }
+                output.push_str("</tr>\n");
+            }
+            output.push_str("</table>\n");
+        }
+        output.push_str("</body>\n</html>\n");
+        Ok(output)
+    }
+
+    /// Renders the report in the given format, the charts in HTML only.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a PNG chart cannot be encoded.
+    pub fn render(&self, format: ReportFormat, charts: ChartFormat) -> std::io::Result<String> {
+        match format {
+            ReportFormat::Markdown => Ok(self.render_markdown()),
+            ReportFormat::Html => self.render_html(charts),
+        }
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExerciseLevel, ExerciseType};
+
+    fn exercise(name: &str, exercise_type: ExerciseType, muscles: &[&str]) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type,
+            exercise_level: ExerciseLevel::Beginner,
+            muscles: muscles.iter().map(|m| m.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn logged(name: &str, reps: &str) -> WorkoutExercise {
+        WorkoutExercise {
+            group: 2,
+            name: String::from(name),
+            reps: String::from(reps),
+            ..Default::default()
+        }
+    }
+
+    fn date(day: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap()
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_parse_periods() {
+        let june = Period::month("2024-06").unwrap();
+        assert!(june.contains(date("2024-06-30")));
+        assert!(!june.contains(date("2024-07-01")));
+        let week = Period::week("2024-W23").unwrap();
+        assert_eq!(week.label, "2024-W23");
+        assert!(week.contains(date("2024-06-03")));
+        assert!(!week.contains(date("2024-06-10")));
+        assert!(Period::month("June").is_err());
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_build_report() {
+        let exercises = vec![
+            exercise("pull_up", ExerciseType::Pull, &["lats", "biceps"]),
+            exercise("dip", ExerciseType::Push, &["chest"]),
+        ];
+        let workouts = vec![
+            (date("2024-05-28"), vec![logged("Pull Up", "10")]),
+            (
+                date("2024-06-03"),
+                vec![logged("Pull Up", "11/10/9"), logged("Dip", "12")],
+            ),
+            (date("2024-06-10"), vec![logged("Pull Up", "X")]),
+        ];
+        let report = Report::build(
+            Period::month("2024-06").unwrap(),
+            &workouts,
+            &[],
+            &exercises,
+            Units::Metric,
+        );
+
+        assert_eq!((report.completed, report.planned), (1, 2));
+        assert_eq!(report.adherence(), 50);
+        let marked = Report::build(
+            Period::month("2024-06").unwrap(),
+            &workouts,
+            &[date("2024-06-10")],
+            &exercises,
+            Units::Metric,
+        );
+        assert_eq!(marked.adherence(), 100);
+        assert_eq!(report.sets_by_type[0], (String::from("Pull"), 3));
+        assert_eq!(report.sets_by_muscle[0], (String::from("biceps"), 3));
+        assert_eq!(
+            report.records,
+            [Record {
+                date: date("2024-06-03"),
+                exercise: String::from("Pull Up"),
+                value: String::from("11"),
+            }]
+        );
+        assert!(report.render_markdown().contains("| Pull Up | 11 |"));
+
+        assert_eq!(
+            report.sets_by_session,
+            [(date("2024-06-03"), 4), (date("2024-06-10"), 0)]
+        );
+        let charts = report.charts();
+        assert_eq!(charts[0].points[0].label, "06-03");
+        assert_eq!(charts[1].points[1].label, "W24");
+        let html = report.render_html(ChartFormat::Svg).unwrap();
+        assert!(html.contains("<h3>Sets per Week</h3>\n<svg "));
+        let html = report.render_html(ChartFormat::Png).unwrap();
+        assert!(html.contains("src=\"data:image/png;base64,iVBORw0KGgo"));
+        assert!(!report
+            .render_html(ChartFormat::None)
+            .unwrap()
+            .contains("Progress"));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{ExerciseLevel, ExerciseType};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::Path;
+
+/// A group of athletes to generate workouts for in one run.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Roster {
+    pub athletes: Vec<Athlete>,
+}
+
+/// An athlete in a roster, unset fields fall back to the command line options.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Athlete {
+    pub name: String,
+    #[serde(default, deserialize_with = "deserialize_value_enum")]
+    pub level: Option<ExerciseLevel>,
+    #[serde(default, deserialize_with = "deserialize_value_enums")]
+    pub types: Option<Vec<ExerciseType>>,
+    pub groups: Option<u32>,
+    pub bodyweight: Option<bool>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Address the workout of the athlete is emailed to
+    pub email: Option<String>,
+    /// Id of the Telegram chat with the athlete, for `wodgen bot telegram`
+    pub telegram: Option<i64>,
+}
+
+// --------------------------------------------------
+
+/// Parses a value the same way clap parses it on the command line, e.g. "beginner".
+pub fn parse_value_enum<T: ValueEnum, E: serde::de::Error>(value: &str) -> Result<T, E> {
+    T::from_str(value, true).map_err(|_| {
+        let possible_values: Vec<String> = T::value_variants()
+            .iter()
+            .filter_map(|v| v.to_possible_value())
+            .map(|v| v.get_name().to_string())
+            .collect();
+        E::custom(format!(
+            "invalid value {:?}, possible values: {}",
+            value,
+            possible_values.join(", ")
+        ))
+    })
+}
+
+pub fn deserialize_value_enum<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ValueEnum,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| parse_value_enum(&value))
+        .transpose()
+}
+
+pub fn deserialize_value_enums<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ValueEnum,
+{
+    Option::<Vec<String>>::deserialize(deserializer)?
+        .map(|values| values.iter().map(|value| parse_value_enum(value)).collect())
+        .transpose()
+}
+
+// --------------------------------------------------
+
+impl Athlete {
+    /// Returns the athlete name made safe for use in file and directory names.
+    pub fn slug(&self) -> String {
+        self.name
+            .trim()
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect()
+    }
+}
+
+// --------------------------------------------------
+
+/// Loads a roster from a TOML file.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or is not a
+/// valid roster, e.g. because an athlete has an unknown level.
+pub fn load_roster(file_path: &Path) -> Result<Roster> {
+    let content = fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to open file: {:?}", file_path))?;
+    let roster: Roster = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse roster: {:?}", file_path))?;
+    Ok(roster)
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_roster() {
+        let roster: Roster = toml::from_str(
+            r#"
+            [[athletes]]
+            name = "Alice B."
+            level = "beginner"
+            types = ["push", "core"]
+            exclude = ["dip__regular"]
+
+            [[athletes]]
+            name = "bob"
+            groups = 3
+            bodyweight = false
+            "#,
+        )
+        .unwrap();
+        assert_eq!(roster.athletes.len(), 2);
+        assert_eq!(roster.athletes[0].slug(), "alice_b_");
+        assert_eq!(roster.athletes[0].level, Some(ExerciseLevel::Beginner));
+        assert_eq!(
+            roster.athletes[0].types,
+            Some(vec![ExerciseType::Push, ExerciseType::Core])
+        );
+        assert_eq!(roster.athletes[1].level, None);
+        assert_eq!(roster.athletes[1].groups, Some(3));
+
+        let invalid = toml::from_str::<Roster>("[[athletes]]\nname = \"c\"\nlevel = \"pro\"\n");
+        assert!(invalid.unwrap_err().to_string().contains("possible values"));
+    }
+}
@@ -0,0 +1,491 @@
+use crate::i18n::{Lang, Text};
+use crate::notation;
+use crate::units::Duration;
+use crate::wellness::Readiness;
+use crate::{ExerciseLevel, WorkoutExercise};
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Version of the workout schema since which the clock of a group is saved on
+/// its rows rather than only written in its notes.
+pub const INTERVALS_VERSION: u32 = 3;
+
+// --------------------------------------------------
+
+// Enum for what the strength groups train for, setting their rounds and the
+// work of each round
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Focus {
+    Strength,
+    #[default]
+    Hypertrophy,
+    Endurance,
+}
+
+/// The work of a group: its rounds and what they add up to.
+#[derive(Debug, PartialEq)]
+pub struct Volume {
+    pub rounds: u32,
+    /// Reps of all the rounds, for the exercises programmed in reps
+    pub reps: u32,
+    /// Seconds of all the rounds, for the exercises programmed in time
+    pub seconds: u32,
+}
+
+/// The clock of a group mixing exercises in reps and in time, run as a circuit
+/// of stations: the seconds of work at each one and those to move to the next.
+/// Saved on the rows of the group as `work/transition`, e.g. `40s/20s`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Interval {
+    pub work: u32,
+    pub transition: u32,
+}
+
+/// The cooldown of a session: how many exercises and how long each one lasts.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Cooldown {
+    pub pieces: u32,
+    pub seconds: u32,
+}
+
+// --------------------------------------------------
+
+impl Focus {
+    /// Recommended rounds of a group: more for strength and for advanced
+    /// athletes, fewer for endurance and for beginners.
+    pub fn rounds(&self, level: &ExerciseLevel) -> u32 {
+        let level = match level {
+            ExerciseLevel::Beginner => 0,
+            ExerciseLevel::Intermediate => 1,
+            ExerciseLevel::Advanced => 2,
+        };
+        match self {
+            Focus::Strength => 3 + level,
+            Focus::Hypertrophy => 2 + level.max(1),
+            Focus::Endurance => 2 + level.min(1),
+        }
+    }
+
+    /// Reps of a round when the goal does not say.
+    pub fn reps(&self) -> u32 {
+        match self {
+            Focus::Strength => 5,
+            Focus::Hypertrophy => 10,
+            Focus::Endurance => 15,
+        }
+    }
+
+    /// Seconds of a round when the goal does not say.
+    pub fn seconds(&self) -> u32 {
+        match self {
+            Focus::Strength => 20,
+            Focus::Hypertrophy => 30,
+            Focus::Endurance => 45,
+        }
+    }
+
+    /// Seconds of rest between the rounds of a group, longer the heavier the
+    /// work.
+    pub fn rest(&self) -> u32 {
+        match self {
+            Focus::Strength => 180,
+            Focus::Hypertrophy => 90,
+            Focus::Endurance => 45,
+        }
+    }
+
+    // Seconds to move to the next station of a circuit, shorter the lighter
+    // the work
+    fn transition(&self) -> u32 {
+        match self {
+            Focus::Strength => 30,
+            Focus::Hypertrophy => 20,
+            Focus::Endurance => 15,
+        }
+    }
+
+    /// The cooldown after `groups` groups, the finisher included: one exercise
+    /// up to 2 groups, two for 3 and three past that, one more for endurance.
+    /// Each lasts longer after heavier work.
+    pub fn cooldown(&self, groups: u32) -> Cooldown {
+        let pieces = match groups {
+            0..=2 => 1,
+            3 => 2,
+            _ => 3,
+        };
+        match self {
+            Focus::Strength => Cooldown {
+                pieces,
+                seconds: 90,
+            },
+            Focus::Hypertrophy => Cooldown {
+                pieces,
+                seconds: 60,
+            },
+            Focus::Endurance => Cooldown {
+                pieces: (pieces + 1).min(3),
+                seconds: 45,
+            },
+        }
+    }
+}
+
+impl Volume {
+    /// Adds up the work of the rows of a group over its rounds, those of the
+    /// focus adjusted to the readiness, taking the reps and times of their
+    /// goals, or those of the focus when a goal has none. Distance exercises
+    /// are left out.
+    pub fn of(
+        rows: &[&WorkoutExercise],
+        focus: Focus,
+        level: &ExerciseLevel,
+        readiness: Readiness,
+    ) -> Volume {
+        let rounds = readiness.rounds(focus.rounds(level));
+        let mut volume = Volume {
+            rounds,
+            reps: 0,
+            seconds: 0,
+        };
+        for row in rows {
+            if !row.reps.is_empty() {
+                volume.reps += rounds * row.goal.trim().parse().unwrap_or(focus.reps());
+            } else if !row.time.is_empty() {
+                volume.seconds += rounds
+                    * row
+                        .goal
+                        .parse::<Duration>()
+                        .map_or(focus.seconds(), |d| d.seconds());
+            }
+        }
+        volume
+    }
+}
+
+impl Interval {
+    /// The clock of a group when its rows mix exercises in reps and in time,
+    /// none otherwise: every station lasts the longest time of the goals, or
+    /// that of the focus, the reps being done within it, followed by the
+    /// transition of the focus.
+    pub fn of(rows: &[&WorkoutExercise], focus: Focus) -> Option<Interval> {
+        let timed: Vec<&&WorkoutExercise> =
+            rows.iter().filter(|row| !row.time.is_empty()).collect();
+        if timed.is_empty() || !rows.iter().any(|row| !row.reps.is_empty()) {
+            return None;
+        }
+        let work = timed
+            .iter()
+            .filter_map(|row| row.goal.parse::<Duration>().ok())
+            .map(|d| d.seconds())
+            .max()
+            .unwrap_or(focus.seconds());
+        Some(Interval {
+            work,
+            transition: focus.transition(),
+        })
+    }
+
+    /// Parses the clock written in the notes of a group, e.g. `40s on / 20s
+    /// transition`, in the language of the workout.
+    pub fn parse(notes: &str, lang: Lang) -> Option<Interval> {
+        notes.split([',', ';', '(']).find_map(|part| {
+            let (on, off) = part.trim().split_once(" / ")?;
+            let work = on.strip_suffix(lang.text(Text::On))?.trim();
+            let transition = off.strip_suffix(lang.text(Text::Transition))?.trim();
+            Some(Interval {
+                work: work.parse::<Duration>().ok()?.seconds(),
+                transition: transition.parse::<Duration>().ok()?.seconds(),
+            })
+        })
+    }
+
+    /// Renders the clock, e.g. `40s on / 20s transition`.
+    pub fn render(&self, lang: Lang) -> String {
+        format!(
+            "{} {} / {} {}",
+            Duration::from_seconds(self.work).render(),
+            lang.text(Text::On),
+            Duration::from_seconds(self.transition).render(),
+            lang.text(Text::Transition)
+        )
+    }
+}
+
+impl Serialize for Interval {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!(
+            "{}/{}",
+            Duration::from_seconds(self.work).render(),
+            Duration::from_seconds(self.transition).render()
+        ))
+    }
+}
+
+impl<'de> Deserialize<'de> for Interval {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Interval, D::Error> {
+        let clock = String::deserialize(deserializer)?;
+        let (work, transition) = clock
+            .split_once('/')
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid interval {:?}", clock)))?;
+        let seconds = |part: &str| {
+            part.trim()
+                .parse::<Duration>()
+                .map(|d| d.seconds())
+                .map_err(serde::de::Error::custom)
+        };
+        Ok(Interval {
+            work: seconds(work)?,
+            transition: seconds(transition)?,
+        })
+    }
+}
+
+// --------------------------------------------------
+
+/// Sets the clock of the groups of a workout saved before the intervals, which
+/// only their notes, in the language of the workout, held.
+pub fn mark_intervals(workout: &mut [WorkoutExercise]) {
+    let clocks: Vec<(u32, Interval)> = workout
+        .iter()
+        .filter_map(|e| {
+            Lang::value_variants()
+                .iter()
+                .find_map(|lang| Interval::parse(&e.notes, *lang))
+                .map(|interval| (e.group, interval))
+        })
+        .collect();
+    for e in workout {
+        if let Some((_, interval)) = clocks.iter().find(|(group, _)| *group == e.group) {
+            if !notation::is_warm_up(e) {
+                e.interval = Some(*interval);
+            }
+        }
+    }
+}
+
+/// Fills in the sets of the rows of the given groups with their rounds, at the
+/// readiness of the athlete, and notes the rounds and the total work on the
+/// first row of each group, e.g. `3 rounds of B1+B2 (total: 30 reps, 1:30)`.
+/// Groups mixing reps and time run on the clock of their interval instead,
+/// set on each of their rows and noted as well, e.g. `3 rounds of B1+B2, 40s
+/// on / 20s transition (total: 6:00)`.
+/// Rows with sets already, such as those prescribed from a training max, and
+/// warm-up sets are left out.
+pub fn prescribe(
+    workout: &mut [WorkoutExercise],
+    groups: impl IntoIterator<Item = u32>,
+    focus: Focus,
+    level: &ExerciseLevel,
+    readiness: Readiness,
+    lang: Lang,
+) {
+    let labels = notation::labels(workout.iter());
+    for group in groups {
+        let indices: Vec<usize> = (0..workout.len())
+            .filter(|i| {
+                let e = &workout[*i];
+                e.group == group && e.sets.is_empty() && !notation::is_warm_up(e)
+            })
+            .collect();
+        let Some(first) = indices.first().copied() else {
+            continue;
+        };
+        let rows: Vec<&WorkoutExercise> = indices.iter().map(|i| &workout[*i]).collect();
+        let volume = Volume::of(&rows, focus, level, readiness);
+        let interval = Interval::of(&rows, focus);
+
+        let mut totals = Vec::new();
+        match interval {
+            // Every round goes through the stations on the clock
+            Some(interval) => totals.push(
+                Duration::from_seconds(
+                    volume.rounds * rows.len() as u32 * (interval.work + interval.transition),
+                )
+                .render(),
+            ),
+            None => {
+                if volume.reps > 0 {
+                    totals.push(format!("{} {}", volume.reps, lang.text(Text::Reps)));
+                }
+                if volume.seconds > 0 {
+                    totals.push(Duration::from_seconds(volume.seconds).render());
+                }
+            }
+        }
+        let group_labels: Vec<&str> = indices.iter().map(|i| labels[*i].as_str()).collect();
+        let mut note = format!(
+            "{} {} {}",
+            volume.rounds,
+            lang.text(Text::RoundsOf),
+            group_labels.join("+")
+        );
+        if let Some(interval) = interval {
+            note = format!("{}, {}", note, interval.render(lang));
+        }
+        if !totals.is_empty() {
+            note = format!(
+                "{} ({}: {})",
+                note,
+                lang.text(Text::Total),
+                totals.join(", ")
+            );
+        }
+
+        for i in &indices {
+            workout[*i].sets = volume.rounds.to_string();
+            workout[*i].interval = interval;
+        }
+        let notes = &mut workout[first].notes;
+        *notes = if notes.is_empty() {
+            note
+        } else {
+            format!("{}; {}", note, notes)
+        };
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::RowKind;
+
+    fn row(group: u32, reps: &str, time: &str, goal: &str) -> WorkoutExercise {
+        WorkoutExercise {
+            group,
+            reps: String::from(reps),
+            time: String::from(time),
+            goal: String::from(goal),
+            ..Default::default()
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_rounds_and_total_work_per_group() {
+        assert_eq!(Focus::Strength.rounds(&ExerciseLevel::Advanced), 5);
+        assert_eq!(Focus::Hypertrophy.rounds(&ExerciseLevel::Beginner), 3);
+        assert_eq!(Focus::Endurance.rounds(&ExerciseLevel::Advanced), 3);
+        assert_eq!(Focus::Hypertrophy.cooldown(2).pieces, 1);
+        assert_eq!(Focus::Strength.cooldown(4).pieces, 3);
+        assert_eq!(
+            Focus::Endurance.cooldown(3),
+            Cooldown {
+                pieces: 3,
+                seconds: 45
+            }
+        );
+
+        let mut workout = vec![
+            row(1, "", "", ""),
+            row(2, "X", "", "8"),
+            row(2, "", "X", "1:00"),
+            WorkoutExercise {
+                group: 2,
+                name: String::from("Warm-up"),
+                goal: String::from("5 @ 40kg"),
+                kind: RowKind::WarmUp,
+                ..Default::default()
+            },
+            row(3, "X", "", "beat {pr_reps}"),
+            row(4, "", "X", ""),
+        ];
+        workout[5].sets = String::from("1");
+        workout[1].notes = String::from("elbows in");
+        prescribe(
+            &mut workout,
+            2..=4,
+            Focus::Hypertrophy,
+            &ExerciseLevel::Intermediate,
+            Readiness::Normal,
+            Lang::En,
+        );
+        assert_eq!(workout[0].sets, "");
+        assert_eq!(workout[1].sets, "3");
+        assert_eq!(workout[2].sets, "3");
+        assert_eq!(
+            workout[1].notes,
+            "3 rounds of B1+B2, 1:00 on / 20s transition (total: 8:00); elbows in"
+        );
+        assert_eq!(workout[2].interval.map(|i| i.work), Some(60));
+        assert_eq!(workout[3].sets, "");
+        assert_eq!(workout[3].interval, None);
+        assert_eq!(workout[4].notes, "3 rounds of C1 (total: 30 reps)");
+        assert_eq!(workout[5].sets, "1");
+        assert_eq!(workout[5].notes, "");
+        assert_eq!(
+            Volume::of(
+                &[],
+                Focus::Strength,
+                &ExerciseLevel::Beginner,
+                Readiness::Low
+            )
+            .rounds,
+            2
+        );
+    }
+
+    #[test]
+    fn test_mixed_groups_run_on_the_clock() {
+        let (reps, time) = (row(2, "X", "", "8"), row(2, "", "X", ""));
+        assert_eq!(Interval::of(&[&reps, &reps], Focus::Strength), None);
+        assert_eq!(Interval::of(&[&time], Focus::Strength), None);
+        let interval = Interval::of(&[&reps, &time], Focus::Hypertrophy).unwrap();
+        assert_eq!(
+            interval,
+            Interval {
+                work: 30,
+                transition: 20
+            }
+        );
+        assert_eq!(interval.render(Lang::En), "30s on / 20s transition");
+        assert_eq!(
+            Interval::of(&[&reps, &row(2, "", "X", "40s")], Focus::Endurance)
+                .unwrap()
+                .render(Lang::De),
+            "40s Arbeit / 15s Wechsel"
+        );
+        assert_eq!(
+            Interval::parse(
+                "3 rounds of B1+B2, 1:00 on / 20s transition (total: 8:00)",
+                Lang::En
+            ),
+            Some(Interval {
+                work: 60,
+                transition: 20
+            })
+        );
+        assert_eq!(
+            Interval::parse("40s Arbeit / 15s Wechsel", Lang::De),
+            Some(Interval {
+                work: 40,
+                transition: 15
+            })
+        );
+        assert_eq!(Interval::parse("3 rounds of B1+B2", Lang::En), None);
+
+        let mut workout = vec![row(2, "X", "", "8"), row(2, "", "X", "")];
+        workout[0].notes = String::from("3 Runden von B1+B2, 40s Arbeit / 15s Wechsel");
+        mark_intervals(&mut workout);
+        assert_eq!(
+            workout[1].interval,
+            Some(Interval {
+                work: 40,
+                transition: 15
+            })
+        );
+        assert_eq!(
+            serde_json::to_string(&workout[1].interval).unwrap(),
+            "\"40s/15s\""
+        );
+        assert_eq!(
+            serde_json::from_str::<Interval>("\"1:00/20s\"").unwrap(),
+            Interval {
+                work: 60,
+                transition: 20
+            }
+        );
+    }
+}
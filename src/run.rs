@@ -0,0 +1,226 @@
+use crate::error::WodgenError;
+use crate::{Args, SnoozedExercise};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory in the state directory holding the runs that saved a workout, one
+/// file per day, e.g. `runs/2024-06-01.toml`.
+pub const RUNS_DIR: &str = "runs";
+
+// Format of the day in the names of the run files
+const RUN_DATE_FORMAT: &str = "%Y-%m-%d";
+
+// --------------------------------------------------
+
+/// How a workout was generated, saved with it to generate it again with other
+/// exercises, or to replay the run and find out why it picked these ones.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Run {
+    /// The saved workout file
+    pub workout_file: PathBuf,
+    /// Version of wodgen that generated the workout
+    pub wodgen: String,
+    pub generated_at: DateTime<Utc>,
+    /// Seed of the random picks
+    pub seed: u64,
+    /// SHA-256 of the exercise files of the library
+    pub library_sha256: String,
+    /// Exercises snoozed at the time with when their snoozes started, left out
+    /// of the picks or drawn less often in the decay mode
+    pub snoozed: Vec<SnoozedExercise>,
+    /// Options of the run
+    pub args: Args,
+}
+
+/// The runs of a day, one per workout file so the athletes of a roster each
+/// keep theirs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RunsFile {
+    runs: Vec<Run>,
+}
+
+// --------------------------------------------------
+
+// The runs file of a day
+fn runs_file(state_dir: &Path, date: NaiveDate) -> PathBuf {
+    state_dir
+        .join(RUNS_DIR)
+        .join(format!("{}.toml", date.format(RUN_DATE_FORMAT)))
+}
+
+// Load a runs file, empty when it does not exist
+fn load_runs(file_path: &Path) -> Result<RunsFile> {
+    if !file_path.is_file() {
+        return Ok(RunsFile::default());
+    }
+    let content = fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to open file: {:?}", file_path))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse the runs: {:?}", file_path))
+}
+
+// The days with a runs file, the latest first
+fn run_days(state_dir: &Path) -> Result<Vec<NaiveDate>> {
+    let runs_dir = state_dir.join(RUNS_DIR);
+    let mut days = Vec::new();
+    if runs_dir.is_dir() {
+        for entry in fs::read_dir(&runs_dir)
+            .with_context(|| format!("Failed to read directory: {:?}", runs_dir))?
+        {
+            let path = entry?.path();
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            if let Ok(day) = NaiveDate::parse_from_str(&stem, RUN_DATE_FORMAT) {
+                days.push(day);
+            }
+        }
+    }
+    days.sort();
+    days.reverse();
+    Ok(days)
+}
+
+// The same workout file whatever the format it was saved in, and however its
+// directory is written
+fn same_workout(a: &Path, b: &Path) -> bool {
+    let dir = |file: &Path| {
+        let dir = match file.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
+    };
+    a.file_stem() == b.file_stem() && dir(a) == dir(b)
+}
+
+/// Saves a run under the day it was generated, replacing that of an earlier
+/// workout saved to the same file that day.
+///
+/// # Errors
+///
+/// This function will return an error if the runs file cannot be read or
+/// written.
+pub fn save_run(state_dir: &Path, run: Run) -> Result<()> {
+    let file_path = runs_file(
+        state_dir,
+        run.generated_at.with_timezone(&chrono::Local).date_naive(),
+    );
+    let mut runs = load_runs(&file_path)?;
+    runs.runs
+        .retain(|other| !same_workout(&other.workout_file, &run.workout_file));
+    runs.runs.push(run);
+    if let Some(runs_dir) = file_path.parent() {
+        fs::create_dir_all(runs_dir)?;
+    }
+    let content = toml::to_string(&runs).context("Failed to serialize the runs")?;
+    fs::write(&file_path, content)
+        .with_context(|| format!("Failed to write file: {:?}", file_path))?;
+    info!("Saved the run to {:?}", file_path);
+    Ok(())
+}
+
+/// Finds the latest run of a day, or of any day, with its date. Only the runs
+/// into `workouts_dir` are considered when it is given.
+///
+/// # Errors
+///
+/// This function will return an error if there is no such run or a runs file
+/// cannot be parsed.
+pub fn load_run(
+    state_dir: &Path,
+    date: Option<NaiveDate>,
+    workouts_dir: Option<&Path>,
+) -> Result<(NaiveDate, Run)> {
+    for day in run_days(state_dir)?
+        .into_iter()
+        .filter(|day| date.is_none_or(|date| *day == date))
+    {
+        let runs = load_runs(&runs_file(state_dir, day))?;
+        if let Some(run) = runs.runs.into_iter().rev().find(|run| {
+            workouts_dir.is_none_or(|dir| run.args.workouts_dir.as_deref() == Some(dir))
+        }) {
+            return Ok((day, run));
+        }
+    }
+    let day = date.map_or_else(|| String::from("any day"), |date| date.to_string());
+    Err(WodgenError::NoRun(day, state_dir.join(RUNS_DIR)).into())
+}
+
+/// Finds the run that saved a workout, whatever the format of the file given.
+///
+/// # Errors
+///
+/// This function will return an error if no run saved the workout or a runs
+/// file cannot be parsed.
+pub fn load_run_of(state_dir: &Path, workout_file: &Path) -> Result<Run> {
+    for day in run_days(state_dir)? {
+        let runs = load_runs(&runs_file(state_dir, day))?;
+        if let Some(run) = runs
+            .runs
+            .into_iter()
+            .find(|run| same_workout(&run.workout_file, workout_file))
+        {
+            return Ok(run);
+        }
+    }
+    Err(WodgenError::NoRunOf(workout_file.to_path_buf()).into())
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load_runs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let run = |workouts_dir: &str, seed| Run {
+            workout_file: tmp
+                .path()
+                .join(workouts_dir)
+                .join("2024_06_01_sunday_pull_party.csv"),
+            wodgen: String::from("0.1.0"),
+            generated_at: Utc::now(),
+            seed,
+            library_sha256: String::from("2997c347"),
+            snoozed: vec![SnoozedExercise {
+                name: String::from("dip__regular"),
+                timestamp: Utc::now(),
+            }],
+            args: Args {
+                groups: 3,
+                exclude: vec![String::from("plank")],
+                workouts_dir: Some(tmp.path().join(workouts_dir)),
+                ..Default::default()
+            },
+        };
+        let state_dir = tmp.path().join("state");
+        save_run(&state_dir, run("alice", 1)).unwrap();
+        save_run(&state_dir, run("bob", 2)).unwrap();
+        save_run(&state_dir, run("alice", 42)).unwrap();
+        assert_eq!(run_days(&state_dir).unwrap().len(), 1);
+
+        // Found from any format of the workout
+        let workout_file = tmp.path().join("alice/2024_06_01_sunday_pull_party.md");
+        let loaded = load_run_of(&state_dir, &workout_file).unwrap();
+        assert_eq!(loaded.seed, 42);
+        assert_eq!(loaded.snoozed[0].name, "dip__regular");
+        assert_eq!(loaded.args.groups, 3);
+        assert_eq!(loaded.args.exclude, ["plank"]);
+        let workout_file = tmp.path().join("bob/2024_06_01_sunday_pull_party.csv");
+        assert_eq!(load_run_of(&state_dir, &workout_file).unwrap().seed, 2);
+
+        // The latest run, or the latest into a workouts directory
+        assert_eq!(load_run(&state_dir, None, None).unwrap().1.seed, 42);
+        let bob = tmp.path().join("bob");
+        assert_eq!(load_run(&state_dir, None, Some(&bob)).unwrap().1.seed, 2);
+
+        let error = load_run_of(&state_dir, &tmp.path().join("alice/2024_06_02.csv"));
+        assert!(error.unwrap_err().to_string().contains("No saved run"));
+        let error = load_run(&state_dir, NaiveDate::from_ymd_opt(2024, 6, 2), None);
+        assert!(error.unwrap_err().to_string().contains("No run found"));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::calibration::Levels;
+use crate::history::History;
+use crate::{display, Exercise};
+use std::fmt;
+
+// Exercises done in this many of the latest workouts count as repeated
+const RECENT_WORKOUTS: usize = 3;
+
+// --------------------------------------------------
+
+/// The quality of a generated workout, each part between 0 and 1.
+#[derive(Debug, PartialEq)]
+pub struct Score {
+    /// How evenly the workout spreads across movement patterns and muscles
+    pub balance: f64,
+    /// Share of exercises at exactly the requested level
+    pub level: f64,
+    /// Share of exercises not done in the latest workouts
+    pub variety: f64,
+}
+
+impl Score {
+    /// Returns the sum of the parts, weighted equally.
+    pub fn total(&self) -> f64 {
+        self.balance + self.level + self.variety
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.2} (balance {:.2}, level {:.2}, variety {:.2})",
+            self.total(),
+            self.balance,
+            self.level,
+            self.variety
+        )
+    }
+}
+
+// --------------------------------------------------
+
+// Share of distinct values among all the values, 1 when there are none
+fn distinct_share<'a>(values: impl Iterator<Item = &'a String>) -> f64 {
+    let values: Vec<&String> = values.collect();
+    if values.is_empty() {
+        return 1.0;
+    }
+    let mut distinct = values.clone();
+    distinct.sort();
+    distinct.dedup();
+    distinct.len() as f64 / values.len() as f64
+}
+
+// Share of exercises matching a predicate, 1 when there are none
+fn share(exercises: &[&Exercise], predicate: impl Fn(&Exercise) -> bool) -> f64 {
+    if exercises.is_empty() {
+        return 1.0;
+    }
+    exercises.iter().filter(|e| predicate(e)).count() as f64 / exercises.len() as f64
+}
+
+// --------------------------------------------------
+
+/// Scores the exercises selected for a workout.
+///
+/// Exercises without pattern or muscles metadata do not affect the balance, so
+/// a library without metadata is scored on level and variety alone.
+pub fn score(exercises: &[&Exercise], levels: &Levels, history: &History) -> Score {
+    let patterns = distinct_share(exercises.iter().filter_map(|e| e.pattern.as_ref()));
+    let muscles = distinct_share(exercises.iter().flat_map(|e| e.muscles.iter()));
+    Score {
+        balance: (patterns + muscles) / 2.0,
+        level: share(exercises, |e| {
+            e.exercise_level == *levels.of(&e.exercise_type)
+        }),
+        variety: share(exercises, |e| {
+            !history.is_recent(&display::exercise_name(e), RECENT_WORKOUTS)
+        }),
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExerciseLevel, ExerciseType};
+
+    fn exercise(name: &str, level: ExerciseLevel, pattern: &str) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type: ExerciseType::Pull,
+            exercise_level: level,
+            pattern: Some(String::from(pattern)),
+            muscles: vec![String::from("lats")],
+            ..Default::default()
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_score() {
+        let pull_up = exercise("pull_up", ExerciseLevel::Intermediate, "vertical_pull");
+        let chin_up = exercise("chin_up", ExerciseLevel::Beginner, "vertical_pull");
+        let row = exercise("row", ExerciseLevel::Intermediate, "horizontal_pull");
+        let history = History::default();
+
+        let same_pattern = score(
+            &[&pull_up, &chin_up],
+            &Levels::from(ExerciseLevel::Intermediate),
+            &history,
+        );
+        assert_eq!(
+            same_pattern,
+            Score {
+                balance: 0.5,
+                level: 0.5,
+                variety: 1.0
+            }
+        );
+        let balanced = score(
+            &[&pull_up, &row],
+            &Levels::from(ExerciseLevel::Intermediate),
+            &history,
+        );
+        assert!(balanced.total() > same_pattern.total());
+        assert_eq!(
+            balanced.to_string(),
+            "2.75 (balance 0.75, level 1.00, variety 1.00)"
+        );
+    }
+}
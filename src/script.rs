@@ -0,0 +1,280 @@
+use crate::{Exercise, WorkoutExercise};
+use anyhow::{anyhow, Context, Result};
+use log::info;
+use rhai::{Array, Dynamic, Engine, FuncArgs, Map, Scope, AST};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Directory next to the user config holding the scripts
+const SCRIPTS_DIR: &str = "scripts";
+
+// Extension of the script files
+const EXTENSION: &str = "rhai";
+
+// Operations a call may run before it is stopped, e.g. in an endless loop
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// The Rhai scripts of the user, each defining any of the hooks called while
+/// generating a workout: `weight(exercise)`, `prescribe(exercise, row)` and
+/// `validate(workout)`.
+pub struct Scripts {
+    engine: Engine,
+    scripts: Vec<(PathBuf, AST)>,
+}
+
+// --------------------------------------------------
+
+/// Returns the directory of the scripts, next to the user config file.
+pub fn scripts_dir(config_file: &Path) -> PathBuf {
+    config_file.with_file_name(SCRIPTS_DIR)
+}
+
+/// Compiles the `.rhai` files of a directory, in the order of their names,
+/// none when the directory does not exist.
+///
+/// # Errors
+///
+/// This function will return an error if a script cannot be read or compiled.
+pub fn load_scripts(dir: &Path) -> Result<Scripts> {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    let mut files = Vec::new();
+    if dir.is_dir() {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == EXTENSION) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+
+    let mut scripts = Vec::new();
+    for path in files {
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to open file: {:?}", path))?;
+        let ast = engine
+            .compile(&source)
+            .map_err(|e| anyhow!("Failed to compile script {:?}: {}", path, e))?;
+        info!("Loaded script {:?}", path);
+        scripts.push((path, ast));
+    }
+    Ok(Scripts { engine, scripts })
+}
+
+// An exercise as a script sees it, a map of the columns of the library
+fn to_dynamic(exercise: &Exercise) -> Result<Dynamic> {
+    rhai::serde::to_dynamic(exercise).map_err(|e| anyhow!("{}", e))
+}
+
+// A workout row as a script sees it, a map of its columns
+fn row_to_map(row: &WorkoutExercise) -> Map {
+    let mut map = Map::new();
+    map.insert("group".into(), Dynamic::from(i64::from(row.group)));
+    map.insert("name".into(), row.name.clone().into());
+    for (column, value) in [
+        ("sets", &row.sets),
+        ("reps", &row.reps),
+        ("time", &row.time),
+        ("distance", &row.distance),
+        ("goal", &row.goal),
+        ("notes", &row.notes),
+    ] {
+        map.insert(column.into(), value.clone().into());
+    }
+    map
+}
+
+// --------------------------------------------------
+
+impl Scripts {
+    // The scripts defining a hook with that many parameters
+    fn defining<'a>(
+        &'a self,
+        hook: &'a str,
+        arity: usize,
+    ) -> impl Iterator<Item = &'a (PathBuf, AST)> {
+        self.scripts.iter().filter(move |(_, ast)| {
+            ast.iter_functions()
+                .any(|f| f.name == hook && f.params.len() == arity)
+        })
+    }
+
+    // Calls a hook of a script
+    fn call(
+        &self,
+        (path, ast): &(PathBuf, AST),
+        hook: &str,
+        args: impl FuncArgs,
+    ) -> Result<Dynamic> {
+        self.engine
+            .call_fn::<Dynamic>(&mut Scope::new(), ast, hook, args)
+            .map_err(|e| anyhow!("Script {:?} failed in {}: {}", path, hook, e))
+    }
+
+    /// Selection weights of the exercises, the product of those returned by
+    /// `weight(exercise)`, 1 when no script defines it. A weight of 0 puts the
+    /// exercise last when the pool is shuffled.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a script fails or returns
+    /// something else than a number.
+    pub fn weights(&self, exercises: &[Exercise]) -> Result<HashMap<String, f64>> {
+        let mut weights = HashMap::new();
+        for exercise in exercises {
+            let mut weight = 1.0;
+            let dynamic = to_dynamic(exercise)?;
+            for script in self.defining("weight", 1) {
+                let result = self.call(script, "weight", (dynamic.clone(),))?;
+                let factor = result
+                    .as_float()
+                    .or_else(|_| result.as_int().map(|i| i as f64))
+                    .map_err(|t| anyhow!("weight returned a {} for {}", t, exercise.name))?;
+                weight *= factor.max(0.0);
+            }
+            if weight != 1.0 {
+                info!("Script weight of {}: {}", exercise.name, weight);
+                weights.insert(exercise.name.clone(), weight);
+            }
+        }
+        Ok(weights)
+    }
+
+    /// Lets `prescribe(exercise, row)` change the sets, reps, time, distance,
+    /// goal and notes of the row of an exercise, from the map it returns.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a script fails.
+    pub fn prescribe(&self, exercise: &Exercise, row: &mut WorkoutExercise) -> Result<()> {
+        let exercise = to_dynamic(exercise)?;
+        for script in self.defining("prescribe", 2) {
+            let result = self.call(script, "prescribe", (exercise.clone(), row_to_map(row)))?;
+            // Anything but a map keeps the row as it is
+            let Some(map) = result.try_cast::<Map>() else {
+                continue;
+            };
+            for (column, cell) in [
+                ("sets", &mut row.sets),
+                ("reps", &mut row.reps),
+                ("time", &mut row.time),
+                ("distance", &mut row.distance),
+                ("goal", &mut row.goal),
+                ("notes", &mut row.notes),
+            ] {
+                if let Some(value) = map.get(column) {
+                    *cell = value.to_string();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the workout with `validate(workout)`, which gets the rows as an
+    /// array of maps and returns the problems found, a message or an array of
+    /// them.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a script fails, e.g. throws to
+    /// stop the run.
+    pub fn validate(&self, workout: &[WorkoutExercise]) -> Result<Vec<String>> {
+        let rows: Array = workout
+            .iter()
+            .map(|row| Dynamic::from_map(row_to_map(row)))
+            .collect();
+        let mut problems = Vec::new();
+        for script in self.defining("validate", 1) {
+            let result = self.call(script, "validate", (rows.clone(),))?;
+            if result.is_array() {
+                problems.extend(result.cast::<Array>().iter().map(|m| m.to_string()));
+            } else if !result.is_unit() {
+                problems.push(result.to_string());
+            }
+        }
+        Ok(problems)
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExerciseCategory, ExerciseLevel, ExerciseType, Units};
+
+    fn exercise(name: &str, exercise_category: ExerciseCategory, tags: &[&str]) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type: ExerciseType::Pull,
+            exercise_category,
+            exercise_level: ExerciseLevel::Beginner,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_script_hooks() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(load_scripts(&tmp.path().join("scripts"))
+            .unwrap()
+            .scripts
+            .is_empty());
+
+        fs::write(
+            tmp.path().join("a.rhai"),
+            r#"
+            fn weight(exercise) { if "grip" in exercise.tags { 0.5 } else { 1 } }
+            fn prescribe(exercise, row) {
+                if exercise.exercise_category == "Primary" { row.sets = 5; row.notes = "heavy"; }
+                row
+            }
+            fn validate(workout) { if workout.len() < 3 { "Too short" } }
+            "#,
+        )
+        .unwrap();
+        fs::write(tmp.path().join("b.rhai"), "fn weight(exercise) { 2.0 }").unwrap();
+        fs::write(tmp.path().join("notes.txt"), "not a script").unwrap();
+        let scripts = load_scripts(tmp.path()).unwrap();
+        assert_eq!(scripts.scripts.len(), 2);
+
+        let pull_up = exercise("pull_up", ExerciseCategory::Primary, &["grip"]);
+        let row = exercise("row", ExerciseCategory::Accessory, &[]);
+        let weights = scripts.weights(&[pull_up.clone(), row.clone()]).unwrap();
+        // Halved and doubled, the weight of pull_up is back to 1
+        assert_eq!(weights.get("pull_up"), None);
+        assert_eq!(weights.get("row"), Some(&2.0));
+
+        let mut workout = vec![
+            WorkoutExercise::from_exercise(2, &pull_up, Units::Metric),
+            WorkoutExercise::from_exercise(2, &row, Units::Metric),
+        ];
+        let sets = workout[1].sets.clone();
+        scripts.prescribe(&pull_up, &mut workout[0]).unwrap();
+        scripts.prescribe(&row, &mut workout[1]).unwrap();
+        assert_eq!(
+            (&workout[0].sets[..], &workout[0].notes[..]),
+            ("5", "heavy")
+        );
+        assert_eq!(workout[1].sets, sets);
+        assert_eq!(scripts.validate(&workout).unwrap(), ["Too short"]);
+
+        fs::write(
+            tmp.path().join("c.rhai"),
+            "fn validate(workout) { throw \"no\"; }",
+        )
+        .unwrap();
+        let error = load_scripts(tmp.path())
+            .unwrap()
+            .validate(&workout)
+            .unwrap_err();
+        assert!(error.to_string().contains("c.rhai"));
+        fs::write(tmp.path().join("d.rhai"), "fn weight(exercise) {").unwrap();
+        assert!(load_scripts(tmp.path()).is_err());
+    }
+}
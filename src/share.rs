@@ -0,0 +1,173 @@
+use crate::WorkoutExercise;
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use csv::{ReaderBuilder, WriterBuilder};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+// Prefix of share codes, bumped whenever the encoding changes
+const SHARE_PREFIX: &str = "wod1.";
+
+/// Scheme and path of workout URIs, followed by their query, e.g.
+/// `wodgen://workout?v=1&title=2024_06_01&code=wod1...`.
+pub const URI_PREFIX: &str = "wodgen://workout?";
+
+// Version of the query of workout URIs, bumped whenever a parameter changes
+// meaning or goes away
+const URI_VERSION: u32 = 1;
+
+// --------------------------------------------------
+
+// Percent-encodes a query value, every byte but the unreserved characters
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// --------------------------------------------------
+
+/// Encodes a workout into a compact, URL-safe share code.
+///
+/// The code is the workout title followed by its rows as header-less CSV,
+/// deflated and base64url encoded, so it fits in a chat message or a link.
+pub fn encode(title: &str, workout: &[WorkoutExercise]) -> Result<String> {
+    let mut wtr = WriterBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_writer(Vec::new());
+    wtr.write_record([title])?;
+    for e in workout {
+        wtr.serialize(e)?;
+    }
+    let csv = wtr
+        .into_inner()
+        .context("Failed to serialize the workout")?;
+
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&csv)?;
+    let compressed = encoder.finish()?;
+    Ok(format!(
+        "{}{}",
+        SHARE_PREFIX,
+        URL_SAFE_NO_PAD.encode(compressed)
+    ))
+}
+
+/// Encodes a workout into a `wodgen://workout` URI for apps and automations,
+/// with the version of the query, the title and the share code of the workout.
+/// Parameters are only ever added, so clients can rely on these.
+pub fn uri(title: &str, workout: &[WorkoutExercise]) -> Result<String> {
+    Ok(format!(
+        "{}v={}&title={}&code={}",
+        URI_PREFIX,
+        URI_VERSION,
+        percent_encode(title),
+        encode(title, workout)?
+    ))
+}
+
+// --------------------------------------------------
+
+/// Decodes a share code produced by `encode`, or the URI of `uri` holding one,
+/// back into the workout title and rows.
+///
+/// # Errors
+///
+/// This function will return an error if the code was not produced by a
+/// compatible version of wodgen or was truncated while being copied.
+pub fn decode(code: &str) -> Result<(String, Vec<WorkoutExercise>)> {
+    let code = code.trim();
+    let code = match code.strip_prefix(URI_PREFIX) {
+        Some(query) => query
+            .split('&')
+            .find_map(|parameter| parameter.strip_prefix("code="))
+            .unwrap_or_default(),
+        None => code,
+    };
+    let Some(payload) = code.strip_prefix(SHARE_PREFIX) else {
+        bail!(
+            "Not a wodgen share code, it should start with {:?}",
+            SHARE_PREFIX
+        );
+    };
+    let compressed = URL_SAFE_NO_PAD
+        .decode(payload)
+        .context("Invalid share code, make sure it was copied completely")?;
+    let mut csv = Vec::new();
+    DeflateDecoder::new(compressed.as_slice())
+        .read_to_end(&mut csv)
+        .context("Invalid share code, make sure it was copied completely")?;
+
+    let mut rdr = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(csv.as_slice());
+    let mut records = rdr.records();
+    let title = match records.next() {
+        Some(record) => record?.get(0).unwrap_or_default().to_string(),
+        None => bail!("Invalid share code, it contains no workout"),
+    };
+    let workout = records
+        .map(|record| Ok(record?.deserialize(None)?))
+        .collect::<Result<Vec<WorkoutExercise>>>()
+        .context("Invalid share code, it contains a malformed exercise")?;
+    Ok((title, workout))
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExerciseProgramming;
+
+    #[test]
+    fn test_share_round_trip() {
+        let workout = vec![WorkoutExercise {
+            group: 2,
+            name: String::from("Pull-up - Regular"),
+            reps: String::from("X"),
+            goal: String::from("10"),
+            video: String::from("video/pull/pull-up_regular.mp4"),
+            programming: Some(ExerciseProgramming::Reps),
+            ..Default::default()
+        }];
+        let code = encode("2024_06_01", &workout).unwrap();
+        assert!(code.starts_with(SHARE_PREFIX));
+        assert!(code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)));
+
+        let (title, decoded) = decode(&code).unwrap();
+        assert_eq!(title, "2024_06_01");
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].name, "Pull-up - Regular");
+        assert_eq!(decoded[0].goal, "10");
+
+        let uri = uri("2024_06_01 Sunday Pull Party", &workout).unwrap();
+        assert!(uri.starts_with(
+            "wodgen://workout?v=1&title=2024_06_01%20Sunday%20Pull%20Party&code=wod1."
+        ));
+        let (title, decoded) = decode(&uri).unwrap();
+        assert_eq!(title, "2024_06_01 Sunday Pull Party");
+        assert_eq!(decoded, workout);
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_decode_rejects_garbage() {
+        assert!(decode("hello").is_err());
+        assert!(decode("wod1.!!!").is_err());
+    }
+}
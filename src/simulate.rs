@@ -0,0 +1,207 @@
+use anyhow::{Context, Result};
+use log::info;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+// --------------------------------------------------
+
+// Enum for the outcome of a simulated run: the strength exercises it picked and
+// the slots it left without one, or why it failed
+#[derive(Debug)]
+pub enum Run {
+    Generated {
+        exercises: Vec<String>,
+        unfilled: Vec<String>,
+    },
+    Failed(String),
+}
+
+// --------------------------------------------------
+
+// A count and its noun, e.g. `1 run` or `3 runs`
+fn plural(n: usize, noun: &str) -> String {
+    format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" })
+}
+
+// Counts the values, the most frequent first and in order of appearance among
+// equals
+fn count<'a>(values: impl Iterator<Item = &'a String>) -> Vec<(&'a String, usize)> {
+    let mut counts: Vec<(&String, usize)> = Vec::new();
+    let mut index: HashMap<&String, usize> = HashMap::new();
+    for value in values {
+        match index.get(value) {
+            Some(i) => counts[*i].1 += 1,
+            None => {
+                index.insert(value, counts.len());
+                counts.push((value, 1));
+            }
+        }
+    }
+    counts.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+    counts
+}
+
+/// The average share of the exercises of a run not picked by the run before,
+/// 1 with fewer than two generated runs.
+pub fn variety(runs: &[Run]) -> f64 {
+    let generated: Vec<&Vec<String>> = runs
+        .iter()
+        .filter_map(|run| match run {
+            Run::Generated { exercises, .. } => Some(exercises),
+            Run::Failed(_) => None,
+        })
+        .collect();
+    let shares: Vec<f64> = generated
+        .windows(2)
+        .filter(|pair| !pair[1].is_empty())
+        .map(|pair| {
+            let new = pair[1].iter().filter(|e| !pair[0].contains(e)).count();
+            new as f64 / pair[1].len() as f64
+        })
+        .collect();
+    if shares.is_empty() {
+        return 1.0;
+    }
+    shares.iter().sum::<f64>() / shares.len() as f64
+}
+
+/// Renders the statistics of the simulated runs for library authors: how often
+/// each exercise was picked, those of the pool never picked, the slots left
+/// without an exercise and the reasons runs failed.
+pub fn render(runs: &[Run], pool: &[String]) -> String {
+    let failures = count(runs.iter().filter_map(|run| match run {
+        Run::Failed(error) => Some(error),
+        Run::Generated { .. } => None,
+    }));
+    let failed: usize = failures.iter().map(|(_, n)| n).sum();
+    let mut output = format!(
+        "{} runs, {} generated, {} failed\n",
+        runs.len(),
+        runs.len() - failed,
+        failed
+    );
+    output.push_str(&format!(
+        "Average variety: {:.2} (share of the exercises not in the run before)\n",
+        variety(runs)
+    ));
+
+    let picked = count(runs.iter().flat_map(|run| match run {
+        Run::Generated { exercises, .. } => exercises.as_slice(),
+        Run::Failed(_) => &[],
+    }));
+    if !picked.is_empty() {
+        output.push_str("\nSelection frequency:\n");
+        for (name, n) in &picked {
+            output.push_str(&format!(
+                "  {}: {} ({:.0}%)\n",
+                name,
+                plural(*n, "run"),
+                100.0 * *n as f64 / runs.len() as f64
+            ));
+        }
+    }
+    let never: Vec<&str> = pool
+        .iter()
+        .filter(|name| !picked.iter().any(|(picked, _)| picked == name))
+        .map(String::as_str)
+        .collect();
+    if !never.is_empty() {
+        output.push_str(&format!("\nNever picked: {}\n", never.join(", ")));
+    }
+
+    let unfilled = count(runs.iter().flat_map(|run| match run {
+        Run::Generated { unfilled, .. } => unfilled.as_slice(),
+        Run::Failed(_) => &[],
+    }));
+    if !unfilled.is_empty() {
+        output.push_str("\nUnfilled slots:\n");
+        for (slot, n) in &unfilled {
+            output.push_str(&format!("  {}: {}\n", slot, plural(*n, "time")));
+        }
+    }
+    if !failures.is_empty() {
+        output.push_str("\nFailures:\n");
+        for (error, n) in &failures {
+            output.push_str(&format!("  {}: {}\n", error, plural(*n, "run")));
+        }
+    }
+    output
+}
+
+/// Appends the seeds of failed runs to a file, one per line followed by the
+/// options of the runs, for the fuzz tests to replay them.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be written.
+pub fn save_seeds(file_path: &Path, seeds: &[u64], options: &str) -> Result<()> {
+    if seeds.is_empty() {
+        return Ok(());
+    }
+    if let Some(dir) = file_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file_path)
+        .with_context(|| format!("Failed to open file: {:?}", file_path))?;
+    for seed in seeds {
+        writeln!(file, "{} {}", seed, options)?;
+    }
+    info!("Saved {} failing seeds to {:?}", seeds.len(), file_path);
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generated(exercises: &[&str], unfilled: &[&str]) -> Run {
+        Run::Generated {
+            exercises: exercises.iter().map(|e| e.to_string()).collect(),
+            unfilled: unfilled.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_render_stats() {
+        let runs = vec![
+            generated(&["Dip - Regular", "Pike Push Up"], &[]),
+            generated(&["Dip - Regular", "Push Up"], &["push:accessory"]),
+            Run::Failed(String::from("Need 2 Push exercises")),
+            generated(&["Dip - Regular", "Push Up"], &["push:accessory"]),
+        ];
+        // Half new, then nothing new
+        assert_eq!(variety(&runs), 0.25);
+        let pool = [
+            "Dip - Regular",
+            "Push Up",
+            "Pike Push Up",
+            "Diamond Push Up",
+        ]
+        .map(String::from);
+        assert_eq!(
+            render(&runs, &pool),
+            "4 runs, 3 generated, 1 failed\n\
+             Average variety: 0.25 (share of the exercises not in the run before)\n\
+             \n\
+             Selection frequency:\n\
+             \x20 Dip - Regular: 3 runs (75%)\n\
+             \x20 Push Up: 2 runs (50%)\n\
+             \x20 Pike Push Up: 1 run (25%)\n\
+             \n\
+             Never picked: Diamond Push Up\n\
+             \n\
+             Unfilled slots:\n\
+             \x20 push:accessory: 2 times\n\
+             \n\
+             Failures:\n\
+             \x20 Need 2 Push exercises: 1 run\n"
+        );
+    }
+}
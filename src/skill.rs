@@ -0,0 +1,183 @@
+use crate::calibration::Levels;
+use crate::i18n::{Lang, Text};
+use crate::random;
+use crate::units::Units;
+use crate::{display, Exercise, ExerciseCategory};
+use crate::{SnoozedExercise, WorkoutExercise};
+use chrono::Utc;
+use log::info;
+use rand::seq::SliceRandom;
+
+// Length of the skill practice in minutes, split evenly between the two drills
+const EMOM_MINUTES: u32 = 10;
+
+// Tag marking the exercises meant for skill practice
+const SKILL_TAG: &str = "skill";
+
+// --------------------------------------------------
+
+// Whether an exercise can be practised as a skill drill, those on the way to
+// the goal come first, then the tagged ones
+fn drill_rank(exercise: &Exercise, goal: &[String]) -> Option<u8> {
+    if goal.contains(&exercise.name) {
+        Some(0)
+    } else if exercise.tags.iter().any(|tag| tag == SKILL_TAG) {
+        Some(1)
+    } else if exercise.exercise_category != ExerciseCategory::Accessory {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+// Pick two drills, of different types when the pool allows it
+fn pick_drills<'a>(
+    pool: &'a [Exercise],
+    levels: &Levels,
+    workout: &[WorkoutExercise],
+    goal: &[String],
+) -> Vec<&'a Exercise> {
+    let mut candidates: Vec<&Exercise> = pool
+        .iter()
+        .filter(|e| levels.allows(e))
+        .filter(|e| !workout.iter().any(|w| w.name == display::exercise_name(e)))
+        .filter(|e| drill_rank(e, goal).is_some())
+        .collect();
+    random::with_rng(|rng| candidates.shuffle(rng));
+    candidates.sort_by_key(|e| drill_rank(e, goal));
+
+    let Some(first) = candidates.first().copied() else {
+        return Vec::new();
+    };
+    let second = candidates
+        .iter()
+        .find(|e| e.exercise_type != first.exercise_type)
+        .or_else(|| candidates.get(1))
+        .copied();
+    std::iter::once(first).chain(second).collect()
+}
+
+// --------------------------------------------------
+
+/// Builds an EMOM skill block: every minute on the minute for `EMOM_MINUTES`
+/// minutes, alternating two skill drills picked from the pool.
+///
+/// The exercises of the prerequisite chain of the goal, library names in
+/// `goal`, are preferred, then those tagged `skill`, then any primary or
+/// secondary exercise at the level. The drills are snoozed like the other picks.
+///
+/// # Returns
+///
+/// The rows of the skill block, a header with the scheme followed by one row per
+/// drill, or only the plain skill block row when no drill is available.
+pub fn emom(
+    pool: &[Exercise],
+    levels: &Levels,
+    workout: &[WorkoutExercise],
+    goal: &[String],
+    snoozed_exercises: &mut Vec<SnoozedExercise>,
+    lang: Lang,
+    units: Units,
+) -> Vec<WorkoutExercise> {
+    let drills = pick_drills(pool, levels, workout, goal);
+    let mut rows = vec![WorkoutExercise {
+        group: 1,
+        name: String::from(lang.text(Text::SkillBlock)),
+        ..Default::default()
+    }];
+    if drills.is_empty() {
+        return rows;
+    }
+
+    rows[0].time = format!("{}:00", EMOM_MINUTES);
+    rows[0].notes = format!("EMOM {}'", EMOM_MINUTES);
+    for (index, drill) in drills.iter().enumerate() {
+        let minutes: Vec<String> = (1..=EMOM_MINUTES)
+            .skip(index)
+            .step_by(drills.len())
+            .map(|minute| minute.to_string())
+            .collect();
+        let mut row = WorkoutExercise::from_exercise(1, drill, units);
+        row.sets = minutes.len().to_string();
+        row.notes = [
+            format!("{} {}", lang.text(Text::Minutes), minutes.join(", ")),
+            row.notes,
+        ]
+        .iter()
+        .filter(|note| !note.is_empty())
+        .cloned()
+        .collect::<Vec<String>>()
+        .join(": ");
+        snoozed_exercises.push(SnoozedExercise {
+            name: drill.name.clone(),
+            timestamp: Utc::now(),
+        });
+        info!("Added skill drill {} to workout", drill.name);
+        rows.push(row);
+    }
+    rows
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExerciseLevel, ExerciseProgramming, ExerciseType};
+
+    fn exercise(name: &str, exercise_type: ExerciseType, tags: &[&str]) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type,
+            exercise_level: ExerciseLevel::Beginner,
+            exercise_programming: ExerciseProgramming::Time,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_emom_alternates_two_drills() {
+        let pool = vec![
+            exercise("dip", ExerciseType::Push, &[]),
+            exercise("handstand", ExerciseType::Push, &["skill"]),
+            exercise("l_sit", ExerciseType::Core, &["skill"]),
+            exercise("plank", ExerciseType::Core, &[]),
+        ];
+        let taken = [WorkoutExercise::from_exercise(2, &pool[2], Units::Metric)];
+        let mut snoozed = Vec::new();
+        let rows = emom(
+            &pool,
+            &Levels::from(ExerciseLevel::Beginner),
+            &taken,
+            &[],
+            &mut snoozed,
+            Lang::En,
+            Units::Metric,
+        );
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].name, "Skill Block");
+        assert_eq!(rows[0].time, "10:00");
+        assert_eq!(rows[1].name, "Handstand");
+        assert_eq!(rows[1].sets, "5");
+        assert_eq!(rows[1].notes, "Minutes 1, 3, 5, 7, 9");
+        assert_eq!(rows[2].name, "Plank");
+        assert_eq!(rows[2].notes, "Minutes 2, 4, 6, 8, 10");
+        assert_eq!(snoozed.len(), 2);
+
+        // The way to the goal comes first
+        let rows = emom(
+            &pool,
+            &Levels::from(ExerciseLevel::Beginner),
+            &taken,
+            &[String::from("dip")],
+            &mut snoozed,
+            Lang::En,
+            Units::Metric,
+        );
+        assert_eq!(rows[1].name, "Dip");
+    }
+}
@@ -0,0 +1,406 @@
+use crate::csv_utils::{append_csv, read_csv};
+use crate::display;
+use crate::error::path_to_str;
+use crate::{
+    Exercise, ExerciseCategory, ExerciseType, SnoozedExercise, WorkoutExercise, SNOOZE_PERIOD,
+};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+// Periods a decaying snooze is remembered for, its weight is back to 95% by then
+const DECAY_PERIODS: i64 = 3;
+
+// Weight of an exercise snoozed just now, above 0 so it keeps a place in the
+// draw when nothing else fits
+const MIN_WEIGHT: f64 = 0.01;
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+// Prefix of the snooze history files, next to the snoozed exercises files.
+// Not `snoozed`, so syncing never merges a history as a list of snoozes
+const HISTORY_PREFIX: &str = "snooze_history";
+
+// --------------------------------------------------
+
+// Enum for how picked exercises are kept from coming back: left out of the
+// pool until their snooze ends, or drawn less often the more recently picked
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+pub enum SnoozeMode {
+    #[default]
+    Hard,
+    Decay,
+}
+
+/// How many days picked exercises stay snoozed, under `[snooze]` in the user
+/// config: `days` for every exercise, overridden per category and, taking
+/// precedence, per type. Unset periods fall back to 7 days. `release` gives the
+/// days a workout has to be done in before the snoozes of its exercises are
+/// released, never when unset.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SnoozePolicy {
+    pub days: Option<i64>,
+    pub cooldown: Option<i64>,
+    pub core: Option<i64>,
+    pub legs: Option<i64>,
+    pub pull: Option<i64>,
+    pub push: Option<i64>,
+    pub primary: Option<i64>,
+    pub secondary: Option<i64>,
+    pub accessory: Option<i64>,
+    pub release: Option<i64>,
+}
+
+/// A snooze that ended, moved from the snoozed exercises file to the snooze
+/// history: when the exercise was snoozed, and until when.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedSnooze {
+    pub name: String,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub until: DateTime<Utc>,
+}
+
+// --------------------------------------------------
+
+impl SnoozePolicy {
+    /// Days an exercise stays snoozed once picked, those of every exercise
+    /// when it is not in the library anymore.
+    pub fn days(&self, exercise: Option<&Exercise>) -> i64 {
+        let by_type = exercise.and_then(|e| match e.exercise_type {
+            ExerciseType::Cooldown => self.cooldown,
+            ExerciseType::Core => self.core,
+            ExerciseType::Legs => self.legs,
+            ExerciseType::Pull => self.pull,
+            ExerciseType::Push => self.push,
+        });
+        let by_category = exercise.and_then(|e| match e.exercise_category {
+            ExerciseCategory::Primary => self.primary,
+            ExerciseCategory::Secondary => self.secondary,
+            ExerciseCategory::Accessory => self.accessory,
+        });
+        by_type
+            .or(by_category)
+            .or(self.days)
+            .unwrap_or(SNOOZE_PERIOD)
+    }
+
+    /// Selection weight of an exercise in the decay mode, from 1 for an
+    /// exercise not snoozed down to almost 0 for one picked just now, decaying
+    /// exponentially with the days of its snooze period.
+    pub fn recency(
+        &self,
+        exercise: &Exercise,
+        snoozed: &[SnoozedExercise],
+        now: DateTime<Utc>,
+    ) -> f64 {
+        snoozed
+            .iter()
+            .filter(|s| s.name == exercise.name)
+            .map(|s| s.timestamp)
+            .max()
+            .map_or(1.0, |timestamp| {
+                let age =
+                    now.signed_duration_since(timestamp).num_seconds() as f64 / SECONDS_PER_DAY;
+                decay(age, self.days(Some(exercise)))
+            })
+    }
+
+    /// Splits the snoozes into the active ones, the latest of each exercise
+    /// while `mode` remembers it, and the ended ones, expired or superseded by
+    /// a later snooze of their exercise, for the history.
+    pub fn compact(
+        &self,
+        mut snoozed: Vec<SnoozedExercise>,
+        mode: SnoozeMode,
+        library: &[Exercise],
+        now: DateTime<Utc>,
+    ) -> (Vec<SnoozedExercise>, Vec<ArchivedSnooze>) {
+        // Latest first, so the first snooze of an exercise is the one to keep
+        snoozed.sort_by_key(|snooze| Reverse(snooze.timestamp));
+        let mut seen = HashSet::new();
+        let (mut active, mut ended) = (Vec::new(), Vec::new());
+        for snooze in snoozed {
+            let days = self.days(library.iter().find(|e| e.name == snooze.name));
+            let latest = seen.insert(snooze.name.clone());
+            if latest && now.signed_duration_since(snooze.timestamp).num_days() < mode.window(days)
+            {
+                active.push(snooze);
+            } else {
+                ended.push(ArchivedSnooze {
+                    until: snooze.timestamp + Duration::days(days),
+                    name: snooze.name,
+                    timestamp: snooze.timestamp,
+                });
+            }
+        }
+        ended.reverse();
+        (active, ended)
+    }
+}
+
+impl SnoozeMode {
+    /// Days a snooze of `days` is remembered: its period, or a few periods in
+    /// the decay mode, past which the exercise is drawn as if never picked.
+    pub fn window(&self, days: i64) -> i64 {
+        match self {
+            SnoozeMode::Hard => days,
+            SnoozeMode::Decay => days * DECAY_PERIODS,
+        }
+    }
+}
+
+// --------------------------------------------------
+
+/// Weight of an exercise snoozed `age` days ago for `days` days: `1 - e^(-age/days)`,
+/// 63% after one period, never below 1%.
+pub fn decay(age: f64, days: i64) -> f64 {
+    if days <= 0 {
+        return 1.0;
+    }
+    (1.0 - (-age.max(0.0) / days as f64).exp()).max(MIN_WEIGHT)
+}
+
+/// Releases the snoozes that the skipped workouts led to, those of their
+/// exercises set on their days, as the exercises were never trained. Returns
+/// the released snoozes, ending now, for the history.
+pub fn release(
+    snoozed: &mut Vec<SnoozedExercise>,
+    skipped: &[(NaiveDate, Vec<WorkoutExercise>)],
+    library: &[Exercise],
+    now: DateTime<Utc>,
+) -> Vec<ArchivedSnooze> {
+    let is_skipped = |snooze: &SnoozedExercise| {
+        let day = snooze.timestamp.with_timezone(&Local).date_naive();
+        let name = display::library_name(&snooze.name, library);
+        skipped
+            .iter()
+            .any(|(date, workout)| *date == day && workout.iter().any(|e| e.name == name))
+    };
+    let (released, kept): (Vec<SnoozedExercise>, Vec<SnoozedExercise>) =
+        snoozed.drain(..).partition(is_skipped);
+    *snoozed = kept;
+    released
+        .into_iter()
+        .map(|snooze| ArchivedSnooze {
+            name: snooze.name,
+            timestamp: snooze.timestamp,
+            until: now,
+        })
+        .collect()
+}
+
+/// Returns the snooze history next to a snoozed exercises file, e.g.
+/// `snooze_history_alice.csv` for `snoozed_alice.csv`.
+pub fn history_file(snoozed_file: &Path) -> PathBuf {
+    let file_name = snoozed_file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let history = match file_name.strip_prefix("snoozed") {
+        Some(rest) => format!("{}{}", HISTORY_PREFIX, rest),
+        None => format!("{}_{}", HISTORY_PREFIX, file_name),
+    };
+    snoozed_file.with_file_name(history)
+}
+
+/// Loads a snooze history, empty when there is no such file yet.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or parsed.
+pub fn load_history(file_path: &Path) -> Result<Vec<ArchivedSnooze>> {
+    if !file_path.exists() {
+        return Ok(Vec::new());
+    }
+    read_csv(path_to_str(file_path)?)
+}
+
+/// Appends ended snoozes to a snooze history.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be written.
+pub fn archive(file_path: &Path, ended: &[ArchivedSnooze]) -> Result<()> {
+    if ended.is_empty() {
+        return Ok(());
+    }
+    append_csv(path_to_str(file_path)?, ended)
+}
+
+/// Renders the snoozes of an exercise, shown as `name`, latest first, one per
+/// line with its dates and whether it is still on.
+pub fn render_history(name: &str, snoozes: &[ArchivedSnooze], now: DateTime<Utc>) -> String {
+    let mut snoozes: Vec<&ArchivedSnooze> = snoozes.iter().collect();
+    snoozes.sort_by_key(|snooze| Reverse(snooze.timestamp));
+    let Some(last) = snoozes.first() else {
+        return format!("{} was never snoozed\n", name);
+    };
+    let date = |timestamp: DateTime<Utc>| timestamp.with_timezone(&Local).format("%Y-%m-%d");
+    let mut output = format!(
+        "{} was last blocked {} {}, snoozed {} time{}\n",
+        name,
+        if last.until > now { "until" } else { "on" },
+        date(last.until),
+        snoozes.len(),
+        if snoozes.len() == 1 { "" } else { "s" }
+    );
+    for snooze in snoozes {
+        output.push_str(&format!(
+            "{} to {}{}\n",
+            date(snooze.timestamp),
+            date(snooze.until),
+            if snooze.until > now { " (snoozed)" } else { "" }
+        ));
+    }
+    output
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_takes_precedence_over_category() {
+        let policy: SnoozePolicy =
+            toml::from_str("days = 6\ncooldown = 3\ncore = 2\nprimary = 10\naccessory = 5")
+                .unwrap();
+        let exercise = |exercise_type, exercise_category| Exercise {
+            name: String::from("plank"),
+            exercise_type,
+            exercise_category,
+            exercise_level: crate::ExerciseLevel::Beginner,
+            exercise_programming: crate::ExerciseProgramming::Time,
+            ..Default::default()
+        };
+        let days = |t, c| policy.days(Some(&exercise(t, c)));
+        assert_eq!(days(ExerciseType::Core, ExerciseCategory::Primary), 2);
+        assert_eq!(days(ExerciseType::Push, ExerciseCategory::Primary), 10);
+        assert_eq!(days(ExerciseType::Legs, ExerciseCategory::Secondary), 6);
+        assert_eq!(policy.days(None), 6);
+        assert_eq!(SnoozePolicy::default().days(None), SNOOZE_PERIOD);
+        assert!(toml::from_str::<SnoozePolicy>("warmup = 1").is_err());
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_recency_decays() {
+        assert_eq!(decay(0.0, 7), MIN_WEIGHT);
+        assert!((decay(7.0, 7) - 0.632).abs() < 0.001);
+        assert!(decay(21.0, 7) > 0.95);
+        assert!(decay(3.0, 7) < decay(3.0, 2));
+        assert_eq!(decay(1.0, 0), 1.0);
+        assert_eq!(SnoozeMode::Decay.window(7), 21);
+        assert_eq!(SnoozeMode::Hard.window(7), 7);
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_compact_archives_ended_snoozes() {
+        let now = Utc::now();
+        let snoozed = |name: &str, days_ago: i64| SnoozedExercise {
+            name: String::from(name),
+            timestamp: now - Duration::days(days_ago),
+        };
+        let policy = SnoozePolicy::default();
+        let (active, ended) = policy.compact(
+            vec![
+                snoozed("squat", 10),
+                snoozed("dip", 2),
+                snoozed("dip", 5),
+                snoozed("plank", 12),
+            ],
+            SnoozeMode::Hard,
+            &[],
+            now,
+        );
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].timestamp, now - Duration::days(2));
+        // The expired snoozes and the one superseded by a later snooze, oldest first
+        let names: Vec<&str> = ended.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["plank", "squat", "dip"]);
+        assert_eq!(ended[1].until, now - Duration::days(3));
+
+        // The decay mode remembers the snoozes for longer
+        let (active, ended) =
+            policy.compact(vec![snoozed("squat", 10)], SnoozeMode::Decay, &[], now);
+        assert_eq!((active.len(), ended.len()), (1, 0));
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_release_skipped_workouts() {
+        let now = Utc::now();
+        let day = |days_ago: i64| (now - Duration::days(days_ago)).with_timezone(&Local);
+        let snoozed = |name: &str, days_ago: i64| SnoozedExercise {
+            name: String::from(name),
+            timestamp: day(days_ago).with_timezone(&Utc),
+        };
+        let row = |name: &str| WorkoutExercise {
+            group: 1,
+            name: String::from(name),
+            ..Default::default()
+        };
+        let mut snoozes = vec![
+            snoozed("pull_up", 4),
+            snoozed("dip", 4),
+            snoozed("pull_up", 1),
+        ];
+        let skipped = [(day(4).date_naive(), vec![row("Pull Up"), row("Squat")])];
+        let released = release(&mut snoozes, &skipped, &[], now);
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].name, "pull_up");
+        assert_eq!(released[0].until, now);
+        // The snoozes of other days and exercises stay
+        assert_eq!(snoozes.len(), 2);
+        assert_eq!(snoozes[0].name, "dip");
+        assert_eq!(snoozes[1].timestamp, day(1).with_timezone(&Utc));
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_snooze_history() {
+        assert_eq!(
+            history_file(Path::new("state/snoozed.csv")),
+            Path::new("state/snooze_history.csv")
+        );
+        assert_eq!(
+            history_file(Path::new("snoozed_alice.csv")),
+            Path::new("snooze_history_alice.csv")
+        );
+        assert_eq!(
+            history_file(Path::new("blocked.csv")),
+            Path::new("snooze_history_blocked.csv")
+        );
+
+        let now = Utc::now();
+        let snooze = |days_ago: i64| ArchivedSnooze {
+            name: String::from("pull_up"),
+            timestamp: now - Duration::days(days_ago),
+            until: now - Duration::days(days_ago - 7),
+        };
+        assert_eq!(
+            render_history("Pull Up", &[], now),
+            "Pull Up was never snoozed\n"
+        );
+        let output = render_history("Pull Up", &[snooze(20), snooze(3)], now);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("Pull Up was last blocked until "));
+        assert!(lines[0].ends_with(", snoozed 2 times"));
+        assert!(lines[1].ends_with(" (snoozed)"));
+        assert!(!lines[2].ends_with(" (snoozed)"));
+        let output = render_history("Pull Up", &[snooze(20)], now);
+        assert!(output.starts_with("Pull Up was last blocked on "));
+    }
+}
@@ -0,0 +1,136 @@
+use crate::taxonomy::Taxonomy;
+use crate::Exercise;
+use log::info;
+
+// --------------------------------------------------
+
+// Whether an exercise trains one of the sore areas, its type, a node of the
+// taxonomy above its movement pattern or one of its muscles, all lowercase
+fn trains_sore_area(exercise: &Exercise, sore: &[String], taxonomy: &Taxonomy) -> bool {
+    let exercise_type = format!("{:?}", exercise.exercise_type).to_lowercase();
+    sore.contains(&exercise_type)
+        || exercise
+            .pattern
+            .as_deref()
+            .is_some_and(|pattern| sore.iter().any(|area| taxonomy.contains(area, pattern)))
+        || exercise
+            .muscles
+            .iter()
+            .any(|muscle| sore.contains(&muscle.trim().to_lowercase()))
+}
+
+/// Normalizes the sore areas given on the command line, e.g. `Legs` or
+/// ` shoulders `, to match the types and muscles of the library.
+pub fn normalize(sore: &[String]) -> Vec<String> {
+    sore.iter()
+        .map(|area| area.trim().to_lowercase())
+        .filter(|area| !area.is_empty())
+        .collect()
+}
+
+/// Leaves out the exercises training a sore area, an exercise type, a node of
+/// the taxonomy or a muscle of the `muscles` column, unless nothing else of their type is left, in
+/// which case they stay in the pool to keep the workout whole.
+///
+/// # Returns
+///
+/// The names of the exercises left out.
+pub fn rest_sore_areas(
+    pool: &mut Vec<Exercise>,
+    sore: &[String],
+    taxonomy: &Taxonomy,
+) -> Vec<String> {
+    if sore.is_empty() {
+        return Vec::new();
+    }
+    let rested: Vec<String> = pool
+        .iter()
+        .filter(|e| {
+            trains_sore_area(e, sore, taxonomy)
+                && pool.iter().any(|o| {
+                    o.exercise_type == e.exercise_type && !trains_sore_area(o, sore, taxonomy)
+                })
+        })
+        .map(|e| e.name.clone())
+        .collect();
+    pool.retain(|e| !rested.contains(&e.name));
+    info!(
+        "Left out {} exercises training the sore areas {:?}, {} exercises remaining",
+        rested.len(),
+        sore,
+        pool.len()
+    );
+    rested
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExerciseLevel, ExerciseType};
+
+    fn exercise(
+        name: &str,
+        exercise_type: ExerciseType,
+        muscles: &[&str],
+        pattern: Option<&str>,
+    ) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type,
+            exercise_level: ExerciseLevel::Beginner,
+            pattern: pattern.map(String::from),
+            muscles: muscles.iter().map(|m| m.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_rest_sore_areas() {
+        let sore = normalize(&[String::from(" Shoulders"), String::from("LEGS")]);
+        assert_eq!(sore, ["shoulders", "legs"]);
+
+        let taxonomy = Taxonomy::default();
+        let mut pool = vec![
+            exercise(
+                "pike_push_up",
+                ExerciseType::Push,
+                &["Shoulders", "triceps"],
+                None,
+            ),
+            exercise("push_up", ExerciseType::Push, &["chest"], None),
+            exercise("squat", ExerciseType::Legs, &["quads"], None),
+            exercise("plank", ExerciseType::Core, &["abs"], None),
+        ];
+        let rested = rest_sore_areas(&mut pool, &sore, &taxonomy);
+        // The only legs exercise stays, sore or not
+        assert_eq!(rested, ["pike_push_up"]);
+        assert_eq!(pool.len(), 3);
+        assert!(rest_sore_areas(&mut pool, &[], &taxonomy).is_empty());
+
+        // A node of the taxonomy rests its whole subtree
+        let mut pool = vec![
+            exercise(
+                "pallof_press",
+                ExerciseType::Core,
+                &[],
+                Some("anti_rotation"),
+            ),
+            exercise(
+                "hollow_hold",
+                ExerciseType::Core,
+                &[],
+                Some("anti_extension"),
+            ),
+        ];
+        let rested = rest_sore_areas(
+            &mut pool,
+            &normalize(&[String::from("Rotation")]),
+            &taxonomy,
+        );
+        assert_eq!(rested, ["pallof_press"]);
+    }
+}
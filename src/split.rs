@@ -0,0 +1,224 @@
+use crate::error::WodgenError;
+use crate::roster::{deserialize_value_enum, deserialize_value_enums, parse_value_enum};
+use crate::{Exercise, ExerciseLevel, ExerciseType, MAX_GROUPS};
+use chrono::Weekday;
+use log::warn;
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+
+// Minutes a strength group takes, to turn the duration of a day into groups
+const MINUTES_PER_GROUP: u32 = 15;
+
+// --------------------------------------------------
+
+/// The session of a weekday in the split.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Day {
+    #[serde(deserialize_with = "deserialize_types")]
+    pub types: Vec<ExerciseType>,
+    /// What the session is about, shown as its note, e.g. "heavy pulls"
+    pub focus: Option<String>,
+    /// Length of the session in minutes, sets the number of groups and the
+    /// time budget
+    pub duration: Option<u32>,
+    /// Level of the session, e.g. "beginner"
+    #[serde(default, deserialize_with = "deserialize_value_enum")]
+    pub level: Option<ExerciseLevel>,
+}
+
+/// The sessions of each weekday, under `[split]` in the user config. Days left
+/// out are rest days.
+///
+/// A day is either a list of exercise types, e.g. `mon = ["push", "core"]`, or
+/// a table with the types, a focus, a duration and a level.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Split {
+    #[serde(deserialize_with = "deserialize_day")]
+    pub mon: Option<Day>,
+    #[serde(deserialize_with = "deserialize_day")]
+    pub tue: Option<Day>,
+    #[serde(deserialize_with = "deserialize_day")]
+    pub wed: Option<Day>,
+    #[serde(deserialize_with = "deserialize_day")]
+    pub thu: Option<Day>,
+    #[serde(deserialize_with = "deserialize_day")]
+    pub fri: Option<Day>,
+    #[serde(deserialize_with = "deserialize_day")]
+    pub sat: Option<Day>,
+    #[serde(deserialize_with = "deserialize_day")]
+    pub sun: Option<Day>,
+}
+
+// --------------------------------------------------
+
+fn deserialize_types<'de, D>(deserializer: D) -> Result<Vec<ExerciseType>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(deserialize_value_enums(deserializer)?.unwrap_or_default())
+}
+
+fn deserialize_day<'de, D>(deserializer: D) -> Result<Option<Day>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(DayVisitor).map(Some)
+}
+
+// Visitor accepting both a list of types and a table
+struct DayVisitor;
+
+impl<'de> Visitor<'de> for DayVisitor {
+    type Value = Day;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .write_str("a list of exercise types or a table with types, focus, duration and level")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Day, A::Error> {
+        let mut types = Vec::new();
+        while let Some(value) = seq.next_element::<String>()? {
+            types.push(parse_value_enum(&value)?);
+        }
+        Ok(Day {
+            types,
+            focus: None,
+            duration: None,
+            level: None,
+        })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Day, A::Error> {
+        Day::deserialize(de::value::MapAccessDeserializer::new(map))
+    }
+}
+
+// --------------------------------------------------
+
+/// Number of strength groups fitting a session of `minutes`, at least one and
+/// at most `MAX_GROUPS`.
+pub fn groups_for(minutes: u32) -> u32 {
+    (minutes / MINUTES_PER_GROUP).clamp(1, MAX_GROUPS)
+}
+
+// --------------------------------------------------
+
+impl Day {
+    /// Number of strength groups fitting the duration, none for a session
+    /// without one or of no minutes.
+    pub fn groups(&self) -> Option<u32> {
+        self.duration.filter(|d| *d > 0).map(groups_for)
+    }
+}
+
+impl Split {
+    // The days with their weekday, Monday first
+    fn days(&self) -> [(Weekday, &Option<Day>); 7] {
+        [
+            (Weekday::Mon, &self.mon),
+            (Weekday::Tue, &self.tue),
+            (Weekday::Wed, &self.wed),
+            (Weekday::Thu, &self.thu),
+            (Weekday::Fri, &self.fri),
+            (Weekday::Sat, &self.sat),
+            (Weekday::Sun, &self.sun),
+        ]
+    }
+
+    /// Returns the session of a weekday, none on a rest day.
+    pub fn day(&self, weekday: Weekday) -> Option<&Day> {
+        self.days()
+            .into_iter()
+            .find(|(day, _)| *day == weekday)
+            .and_then(|(_, day)| day.as_ref())
+            .filter(|day| !day.types.is_empty())
+    }
+
+    /// Checks that the library has exercises of every type of the split and
+    /// that every duration fits at least one group, so a mistake shows up on
+    /// any day rather than on the day it is planned for. A duration past the
+    /// most groups is only warned about, the session getting `MAX_GROUPS`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error naming the first day of no minutes
+    /// or with a type the library has no exercise of.
+    pub fn validate(&self, exercises: &[Exercise]) -> Result<(), WodgenError> {
+        for (weekday, day) in self.days() {
+            let Some(day) = day else {
+                continue;
+            };
+            match day.duration {
+                Some(0) => return Err(WodgenError::SplitNoMinutes(weekday.to_string())),
+                Some(duration) if duration / MINUTES_PER_GROUP > MAX_GROUPS => warn!(
+                    "The {} minutes of {} fit more than {} groups, the session gets {}",
+                    duration, weekday, MAX_GROUPS, MAX_GROUPS
+                ),
+                _ => {}
+            }
+            if let Some(missing) = day
+                .types
+                .iter()
+                .find(|t| !exercises.iter().any(|e| e.exercise_type == **t))
+            {
+                return Err(WodgenError::SplitTypeMissing(
+                    weekday.to_string(),
+                    missing.clone(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_days() {
+        let split: Split = toml::from_str(
+            r#"
+            mon = ["push", "core"]
+            fri = []
+
+            [wed]
+            types = ["Pull", "legs"]
+            focus = "heavy pulls"
+            duration = 50
+            level = "advanced"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            split.day(Weekday::Mon).map(|day| &day.types[..]),
+            Some(&[ExerciseType::Push, ExerciseType::Core][..])
+        );
+        assert_eq!(
+            split.day(Weekday::Wed),
+            Some(&Day {
+                types: vec![ExerciseType::Pull, ExerciseType::Legs],
+                focus: Some(String::from("heavy pulls")),
+                duration: Some(50),
+                level: Some(ExerciseLevel::Advanced),
+            })
+        );
+        assert_eq!(split.day(Weekday::Wed).unwrap().groups(), Some(3));
+        assert_eq!(groups_for(10), 1);
+        assert_eq!(groups_for(240), MAX_GROUPS);
+        assert_eq!(split.day(Weekday::Mon).unwrap().groups(), None);
+        assert_eq!(split.day(Weekday::Fri), None);
+        assert_eq!(split.day(Weekday::Sun), None);
+
+        assert!(toml::from_str::<Split>("mon = [\"arms\"]").is_err());
+        assert!(toml::from_str::<Split>("monday = [\"push\"]").is_err());
+        assert!(toml::from_str::<Split>("[mon]\nfocus = \"grip\"").is_err());
+        assert!(toml::from_str::<Split>("[mon]\ntypes = [\"push\"]\nlength = 30").is_err());
+    }
+}
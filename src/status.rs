@@ -0,0 +1,237 @@
+use crate::completion::is_done;
+use crate::history::logged_sets;
+use crate::WorkoutExercise;
+use chrono::{Datelike, NaiveDate};
+
+// Number of weeks, counting the current one, the adherence is shown for
+const ADHERENCE_WEEKS: u64 = 4;
+
+// Sessions planned this many days back and not logged are pointed out
+const MISSED_DAYS: i64 = 7;
+
+// A break longer than this many days since the last completed session earns a nudge
+const BREAK_DAYS: i64 = 7;
+
+// --------------------------------------------------
+
+/// The share of planned sessions completed in an ISO week.
+#[derive(Debug, PartialEq)]
+pub struct WeekAdherence {
+    /// The week, e.g. `2024-W23`
+    pub week: String,
+    pub planned: usize,
+    pub completed: usize,
+}
+
+/// Motivation feedback from the saved workouts: the streak, the adherence of
+/// the latest weeks and the sessions missed.
+#[derive(Debug)]
+pub struct Status {
+    /// Sessions completed in a row, counting back from the latest one
+    pub streak: usize,
+    /// Adherence of the latest weeks, oldest first
+    pub weeks: Vec<WeekAdherence>,
+    /// Days of the latest sessions planned but not logged, oldest first
+    pub missed: Vec<NaiveDate>,
+    /// Day of the latest completed session
+    pub last_completed: Option<NaiveDate>,
+    today: NaiveDate,
+}
+
+// --------------------------------------------------
+
+/// Whether any reps, time or distance was logged in a workout.
+pub fn is_completed(workout: &[WorkoutExercise]) -> bool {
+    workout
+        .iter()
+        .filter(|e| e.group > 0)
+        .any(|e| logged_sets(e) > 0)
+}
+
+impl WeekAdherence {
+    /// Share of the planned sessions that were completed, in percent.
+    pub fn percent(&self) -> usize {
+        if self.planned == 0 {
+            return 0;
+        }
+        self.completed * 100 / self.planned
+    }
+}
+
+impl Status {
+    /// Computes the status from the saved workouts, oldest first, and the days
+    /// of the sessions marked done.
+    ///
+    /// A session planned for today does not break the streak nor count as
+    /// missed until the day is over.
+    pub fn compute(
+        workouts: &[(NaiveDate, Vec<WorkoutExercise>)],
+        done: &[NaiveDate],
+        today: NaiveDate,
+    ) -> Status {
+        let sessions: Vec<(NaiveDate, bool)> = workouts
+            .iter()
+            .filter(|(date, _)| *date <= today)
+            .map(|(date, workout)| (*date, is_done(*date, workout, done)))
+            .collect();
+
+        let streak = sessions
+            .iter()
+            .rev()
+            .skip_while(|(date, completed)| *date == today && !completed)
+            .take_while(|(_, completed)| *completed)
+            .count();
+
+        let this_week =
+            today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+        let weeks = (0..ADHERENCE_WEEKS)
+            .rev()
+            .map(|weeks_ago| {
+                let start = this_week - chrono::Duration::weeks(weeks_ago as i64);
+                let end = start + chrono::Duration::weeks(1);
+                let in_week: Vec<&(NaiveDate, bool)> = sessions
+                    .iter()
+                    .filter(|(date, _)| start <= *date && *date < end)
+                    .collect();
+                WeekAdherence {
+                    week: start.format("%G-W%V").to_string(),
+                    planned: in_week.len(),
+                    completed: in_week.iter().filter(|(_, completed)| *completed).count(),
+                }
+            })
+            .collect();
+
+        let missed = sessions
+            .iter()
+            .filter(|(date, completed)| {
+                !completed && *date < today && (today - *date).num_days() <= MISSED_DAYS
+            })
+            .map(|(date, _)| *date)
+            .collect();
+
+        let last_completed = sessions
+            .iter()
+            .rev()
+            .find(|(_, completed)| *completed)
+            .map(|(date, _)| *date);
+
+        Status {
+            streak,
+            weeks,
+            missed,
+            last_completed,
+            today,
+        }
+    }
+
+    /// Renders the status as plain text for the terminal.
+    pub fn render(&self) -> String {
+        let mut output = format!(
+            "Current streak: {} session{} completed in a row\n",
+            self.streak,
+            if self.streak == 1 { "" } else { "s" }
+        );
+        output.push_str("\nWeekly adherence:\n");
+        for week in &self.weeks {
+            output.push_str(&format!(
+                "  {}: {} of {} planned ({}%)\n",
+                week.week,
+                week.completed,
+                week.planned,
+                week.percent()
+            ));
+        }
+
+        let mut nudges = Vec::new();
+        for date in &self.missed {
+            nudges.push(format!(
+                "The session of {} was not logged, log it in the workout file, mark it with wodgen done {} or plan a new one",
+                date, date
+            ));
+        }
+        match self.last_completed {
+            Some(date) if (self.today - date).num_days() > BREAK_DAYS => nudges.push(format!(
+                "No session completed in the last {} days, time to get back to it",
+                (self.today - date).num_days()
+            )),
+            None => nudges.push(String::from(
+                "No session completed yet, generate a workout and log how it went",
+            )),
+            _ => (),
+        }
+        if !nudges.is_empty() {
+            output.push('\n');
+            for nudge in nudges {
+                output.push_str(&format!("- {}\n", nudge));
+            }
+        }
+        output
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(day: &str, reps: &str) -> (NaiveDate, Vec<WorkoutExercise>) {
+        let workout = vec![WorkoutExercise {
+            group: 2,
+            name: String::from("Dip"),
+            reps: String::from(reps),
+            ..Default::default()
+        }];
+        (date(day), workout)
+    }
+
+    fn date(day: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap()
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_streak_and_adherence() {
+        let workouts = vec![
+            session("2024-06-03", "10"),
+            session("2024-06-05", "X"),
+            session("2024-06-10", "12"),
+            session("2024-06-12", "10/8"),
+            session("2024-06-14", "X"),
+        ];
+        let status = Status::compute(&workouts, &[], date("2024-06-14"));
+
+        assert_eq!(status.streak, 2);
+        assert!(status.missed.is_empty());
+        assert_eq!(status.last_completed, Some(date("2024-06-12")));
+        assert_eq!(
+            status.weeks[2..],
+            [
+                WeekAdherence {
+                    week: String::from("2024-W23"),
+                    planned: 2,
+                    completed: 1,
+                },
+                WeekAdherence {
+                    week: String::from("2024-W24"),
+                    planned: 3,
+                    completed: 2,
+                },
+            ]
+        );
+
+        let status = Status::compute(&workouts, &[], date("2024-06-15"));
+        assert_eq!(status.streak, 0);
+        assert_eq!(status.missed, [date("2024-06-14")]);
+        assert!(status
+            .render()
+            .contains("The session of 2024-06-14 was not logged"));
+
+        // A session marked done counts as completed, logged or not
+        let status = Status::compute(&workouts, &[date("2024-06-14")], date("2024-06-15"));
+        assert_eq!(status.streak, 3);
+        assert!(status.missed.is_empty());
+        assert_eq!(status.weeks[3].completed, 3);
+    }
+}
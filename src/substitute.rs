@@ -0,0 +1,155 @@
+use crate::{display, Exercise};
+
+// Enum for the reasons an exercise needs to be substituted
+#[derive(Debug, PartialEq, Clone, Copy, clap::ValueEnum)]
+pub enum Reason {
+    /// The equipment is missing or occupied, only bodyweight alternatives
+    NoEquipment,
+    /// The movement hurts, alternatives with a different pattern
+    Injury,
+    /// The exercise is too hard, only easier alternatives
+    TooHard,
+}
+
+/// A ranked alternative to an exercise.
+#[derive(Debug)]
+pub struct Suggestion<'a> {
+    pub exercise: &'a Exercise,
+    pub score: u32,
+}
+
+// --------------------------------------------------
+
+/// Finds an exercise in the library by its library name or its display name,
+/// ignoring case.
+pub fn find_exercise<'a>(name: &str, exercises: &'a [Exercise]) -> Option<&'a Exercise> {
+    let name = name.trim().to_lowercase();
+    exercises
+        .iter()
+        .find(|e| e.name.to_lowercase() == name || display::exercise_name(e).to_lowercase() == name)
+}
+
+// --------------------------------------------------
+
+// Whether an alternative is acceptable at all for the given reason
+fn is_allowed(target: &Exercise, alternative: &Exercise, reason: Option<Reason>) -> bool {
+    if alternative.name == target.name || alternative.exercise_type != target.exercise_type {
+        return false;
+    }
+    match reason {
+        None => true,
+        Some(Reason::NoEquipment) => alternative.bodyweight,
+        Some(Reason::TooHard) => alternative.exercise_level < target.exercise_level,
+        Some(Reason::Injury) => {
+            (target.pattern.is_none() || alternative.pattern != target.pattern)
+                && (target.progression.is_none() || alternative.progression != target.progression)
+        }
+    }
+}
+
+// Score how closely an alternative matches the exercise it replaces
+fn score(target: &Exercise, alternative: &Exercise, reason: Option<Reason>) -> u32 {
+    let mut score = 0;
+    if reason != Some(Reason::Injury) {
+        if target.pattern.is_some() && alternative.pattern == target.pattern {
+            score += 3;
+        }
+        if target.progression.is_some() && alternative.progression == target.progression {
+            score += 2;
+        }
+    }
+    if alternative.exercise_category == target.exercise_category {
+        score += 2;
+    }
+    if reason != Some(Reason::TooHard) && alternative.exercise_level == target.exercise_level {
+        score += 1;
+    }
+    let shared_muscles = alternative
+        .muscles
+        .iter()
+        .filter(|m| target.muscles.contains(m))
+        .count();
+    score + shared_muscles as u32
+}
+
+// --------------------------------------------------
+
+/// Ranks the alternatives to an exercise, best match first.
+///
+/// Alternatives always share the exercise type. They score points for sharing
+/// the movement pattern, progression chain, category, level and muscles, with
+/// the `reason` filtering out and de-emphasizing what does not fit.
+pub fn suggest<'a>(
+    target: &Exercise,
+    exercises: &'a [Exercise],
+    reason: Option<Reason>,
+) -> Vec<Suggestion<'a>> {
+    let mut suggestions: Vec<Suggestion> = exercises
+        .iter()
+        .filter(|e| is_allowed(target, e, reason))
+        .map(|e| Suggestion {
+            exercise: e,
+            score: score(target, e, reason),
+        })
+        .collect();
+    suggestions.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.exercise.name.cmp(&b.exercise.name))
+    });
+    suggestions
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExerciseLevel;
+
+    fn exercise(name: &str, level: ExerciseLevel, bodyweight: bool, pattern: &str) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_level: level,
+            bodyweight,
+            pattern: Some(String::from(pattern)),
+            muscles: vec![String::from("chest"), String::from("triceps")],
+            ..Default::default()
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_suggest() {
+        let exercises = vec![
+            exercise(
+                "bench_press",
+                ExerciseLevel::Intermediate,
+                false,
+                "horizontal_push",
+            ),
+            exercise("push_up", ExerciseLevel::Beginner, true, "horizontal_push"),
+            exercise("dip", ExerciseLevel::Intermediate, true, "vertical_push"),
+            exercise(
+                "ring_push_up",
+                ExerciseLevel::Intermediate,
+                false,
+                "horizontal_push",
+            ),
+        ];
+        let target = find_exercise("Bench Press", &exercises).unwrap();
+        let names = |reason| -> Vec<&str> {
+            suggest(target, &exercises, reason)
+                .iter()
+                .map(|s| s.exercise.name.as_str())
+                .collect()
+        };
+
+        assert_eq!(names(None), ["ring_push_up", "push_up", "dip"]);
+        assert_eq!(names(Some(Reason::NoEquipment)), ["push_up", "dip"]);
+        assert_eq!(names(Some(Reason::TooHard)), ["push_up"]);
+        assert_eq!(names(Some(Reason::Injury)), ["dip"]);
+        assert!(find_exercise("Deadlift", &exercises).is_none());
+    }
+}
@@ -0,0 +1,305 @@
+use crate::error::WodgenError;
+use crate::template::{Qualifier, Template};
+use crate::{Exercise, ExerciseType};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use log::info;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// File of the exercise library defining the nodes of the taxonomy
+const TAXONOMY_FILE: &str = "taxonomy.toml";
+
+// Children of the nodes of the built-in taxonomy, under the exercise types
+const BUILT_IN: [(&str, &[&str]); 5] = [
+    ("push", &["horizontal_push", "vertical_push"]),
+    ("pull", &["horizontal_pull", "vertical_pull"]),
+    ("legs", &["hinge", "squat", "lunge"]),
+    ("core", &["flexion", "anti_extension", "rotation"]),
+    ("rotation", &["anti_rotation"]),
+];
+
+/// A tree of movement patterns under the exercise types, e.g. push, then
+/// horizontal and vertical push. Exercises belong to the node named in their
+/// `pattern` column, and to every node above it.
+#[derive(Debug, Clone)]
+pub struct Taxonomy {
+    children: BTreeMap<String, Vec<String>>,
+}
+
+// --------------------------------------------------
+
+/// Normalizes the name of a node or a movement pattern, lowercase with
+/// underscores, so that `Anti-Rotation` matches `anti_rotation`.
+pub fn normalize(name: &str) -> String {
+    name.trim().to_lowercase().replace(['-', ' '], "_")
+}
+
+// Name of the node of an exercise type, e.g. `push`
+fn type_node(exercise_type: &ExerciseType) -> String {
+    format!("{:?}", exercise_type).to_lowercase()
+}
+
+/// Loads the taxonomy: the built-in one, with the nodes defined in the
+/// `taxonomy.toml` file of the exercise library taking precedence.
+///
+/// # Errors
+///
+/// This function will return an error if taxonomy.toml cannot be parsed, or
+/// `WodgenError::InvalidTaxonomy` if a node is not under an exercise type,
+/// has several parents or is under itself.
+pub fn load_taxonomy(exercise_library_dir: &Path) -> Result<Taxonomy> {
+    let mut taxonomy = Taxonomy::default();
+    let file_path = exercise_library_dir.join(TAXONOMY_FILE);
+    if file_path.is_file() {
+        let content = fs::read_to_string(&file_path)
+            .with_context(|| format!("Failed to open file: {:?}", file_path))?;
+        let nodes: BTreeMap<String, Vec<String>> = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse taxonomy: {:?}", file_path))?;
+        for (node, children) in nodes {
+            taxonomy.children.insert(
+                normalize(&node),
+                children.iter().map(|child| normalize(child)).collect(),
+            );
+        }
+        taxonomy.validate()?;
+    }
+    Ok(taxonomy)
+}
+
+// --------------------------------------------------
+
+impl Default for Taxonomy {
+    fn default() -> Taxonomy {
+        Taxonomy {
+            children: BUILT_IN
+                .iter()
+                .map(|(node, children)| {
+                    (
+                        node.to_string(),
+                        children.iter().map(|child| child.to_string()).collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Taxonomy {
+    // The node a node is a child of, none for the exercise types
+    fn parent(&self, node: &str) -> Option<&str> {
+        self.children
+            .iter()
+            .find(|(_, children)| children.iter().any(|child| child == node))
+            .map(|(parent, _)| parent.as_str())
+    }
+
+    // Checks that every node has one parent and leads up to an exercise type
+    fn validate(&self) -> Result<(), WodgenError> {
+        let types: Vec<String> = ExerciseType::value_variants()
+            .iter()
+            .map(type_node)
+            .collect();
+        for node in self.children.keys().chain(self.children.values().flatten()) {
+            let parents = self
+                .children
+                .values()
+                .filter(|children| children.contains(node))
+                .count();
+            if parents > 1 {
+                return Err(WodgenError::InvalidTaxonomy(
+                    node.clone(),
+                    "has several parents",
+                ));
+            }
+            let mut ancestor = node.as_str();
+            for _ in 0..=self.children.len() {
+                match self.parent(ancestor) {
+                    Some(parent) if parent == node => {
+                        return Err(WodgenError::InvalidTaxonomy(
+                            node.clone(),
+                            "is under itself",
+                        ))
+                    }
+                    Some(parent) => ancestor = parent,
+                    None => break,
+                }
+            }
+            if !types.iter().any(|t| t == ancestor) {
+                return Err(WodgenError::InvalidTaxonomy(
+                    node.clone(),
+                    "is not under an exercise type",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a movement pattern is the node or in its subtree.
+    pub fn contains(&self, node: &str, pattern: &str) -> bool {
+        let node = normalize(node);
+        let mut ancestor = Some(normalize(pattern));
+        // A node has at most one parent, a chain is never longer than the nodes
+        for _ in 0..=self.children.len() {
+            match ancestor {
+                Some(name) if name == node => return true,
+                Some(name) => ancestor = self.parent(&name).map(String::from),
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// Returns the node and every node under it.
+    pub fn subtree(&self, node: &str) -> Vec<String> {
+        let mut nodes = vec![normalize(node)];
+        let mut index = 0;
+        while index < nodes.len() {
+            if let Some(children) = self.children.get(&nodes[index]) {
+                for child in children {
+                    if !nodes.contains(child) {
+                        nodes.push(child.clone());
+                    }
+                }
+            }
+            index += 1;
+        }
+        nodes
+    }
+
+    /// Finds a node under an exercise type by its name, or by the name it
+    /// has under the type, e.g. `horizontal` for `horizontal_push`.
+    pub fn find(&self, exercise_type: &ExerciseType, name: &str) -> Option<String> {
+        let root = type_node(exercise_type);
+        let name = normalize(name);
+        [format!("{}_{}", name, root), name]
+            .into_iter()
+            .find(|node| node != &root && self.contains(&root, node))
+    }
+
+    /// Resolves the nodes the slots of a template name, so that they pick the
+    /// exercises of the whole subtree. A name that is not a node still picks
+    /// the exercises of that exact movement pattern.
+    pub fn resolve(&self, template: &mut Template) {
+        for block in &mut template.blocks {
+            for slot in block.types.iter_mut().flatten() {
+                if let Qualifier::Node(name) = &slot.qualifier {
+                    match self.find(&slot.exercise_type, name) {
+                        Some(node) => slot.patterns = self.subtree(&node),
+                        None => info!(
+                            "{:?} is not a node of the taxonomy under {:?}, matched as a movement pattern",
+                            name, slot.exercise_type
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Renders the tree under the exercise types, with how many exercises of
+    /// the library each node holds.
+    pub fn render(&self, exercises: &[Exercise]) -> String {
+        let mut output = String::new();
+        for exercise_type in ExerciseType::value_variants() {
+            let root = type_node(exercise_type);
+            let exercises: Vec<&Exercise> = exercises
+                .iter()
+                .filter(|e| e.exercise_type == *exercise_type)
+                .collect();
+            self.render_node(&root, &exercises, 0, &mut output);
+        }
+        output
+    }
+
+    // Renders a node and its children, indented by their depth
+    fn render_node(&self, node: &str, exercises: &[&Exercise], depth: usize, output: &mut String) {
+        let count = if depth == 0 {
+            exercises.len()
+        } else {
+            exercises
+                .iter()
+                .filter(|e| e.pattern.as_deref().is_some_and(|p| self.contains(node, p)))
+                .count()
+        };
+        output.push_str(&format!("{}{} ({})\n", "  ".repeat(depth), node, count));
+        for child in self.children.get(node).into_iter().flatten() {
+            self.render_node(child, exercises, depth + 1, output);
+        }
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subtree_matching() {
+        let taxonomy = Taxonomy::default();
+        assert!(taxonomy.contains("core", "anti-rotation"));
+        assert!(taxonomy.contains("Rotation", "anti_rotation"));
+        assert!(!taxonomy.contains("anti_rotation", "rotation"));
+        assert!(!taxonomy.contains("push", "squat"));
+        assert_eq!(
+            taxonomy.subtree("core"),
+            [
+                "core",
+                "flexion",
+                "anti_extension",
+                "rotation",
+                "anti_rotation"
+            ]
+        );
+        assert_eq!(
+            taxonomy.find(&ExerciseType::Push, "horizontal"),
+            Some(String::from("horizontal_push"))
+        );
+        assert_eq!(
+            taxonomy.find(&ExerciseType::Core, "rotation"),
+            Some(String::from("rotation"))
+        );
+        assert_eq!(taxonomy.find(&ExerciseType::Pull, "squat"), None);
+        assert_eq!(taxonomy.find(&ExerciseType::Pull, "pull"), None);
+
+        let mut template = Template::from_types(
+            &[
+                "core:rotation".parse().unwrap(),
+                "core:hollow".parse().unwrap(),
+            ],
+            1,
+        );
+        taxonomy.resolve(&mut template);
+        let slots = template.blocks[0].types();
+        assert_eq!(slots[0].patterns, ["rotation", "anti_rotation"]);
+        // Not a node, the exact pattern is matched
+        assert_eq!(slots[1].patterns, ["hollow"]);
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_load_taxonomy() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(load_taxonomy(tmp.path()).unwrap().contains("legs", "lunge"));
+
+        let file_path = tmp.path().join(TAXONOMY_FILE);
+        fs::write(
+            &file_path,
+            "legs = [\"Knee-Dominant\", \"hinge\"]\nknee_dominant = [\"squat\", \"lunge\"]\n",
+        )
+        .unwrap();
+        let taxonomy = load_taxonomy(tmp.path()).unwrap();
+        assert!(taxonomy.contains("knee_dominant", "lunge"));
+        assert!(!taxonomy.contains("knee_dominant", "hinge"));
+
+        fs::write(&file_path, "sprint = [\"acceleration\"]\n").unwrap();
+        let error = load_taxonomy(tmp.path()).unwrap_err();
+        assert!(error.to_string().contains("not under an exercise type"));
+
+        fs::write(&file_path, "hinge = [\"squat\"]\n").unwrap();
+        let error = load_taxonomy(tmp.path()).unwrap_err();
+        assert!(error.to_string().contains("several parents"));
+    }
+}
@@ -0,0 +1,597 @@
+use crate::category::{CategoryStrategy, Rotation};
+use crate::error::WodgenError;
+use crate::roster::deserialize_value_enum;
+use crate::taxonomy::normalize;
+use crate::{filter_by_type, Exercise, ExerciseCategory, ExerciseLevel, ExerciseType};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The blocks of super-sets a workout is made of, in order.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    /// Categories the groups pick from in turn, unless --category-strategy
+    /// sets them
+    #[serde(default, deserialize_with = "deserialize_value_enum")]
+    pub category_strategy: Option<CategoryStrategy>,
+    pub blocks: Vec<Block>,
+}
+
+/// A block of super-sets, picking one exercise per type in each group.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Block {
+    /// Name other blocks refer to in their conditions
+    pub name: Option<String>,
+    /// Exercise slots of each group, unset falls back to --types
+    pub types: Option<Vec<Slot>>,
+    #[serde(default = "default_groups")]
+    pub groups: u32,
+    /// Only pick exercises with at least one of these tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Only include the block when the condition holds for the exercises
+    /// selected in the blocks before it
+    pub when: Option<Condition>,
+    /// Number of exercises of each type per group, one for unlisted types
+    #[serde(default)]
+    pub per_group: Vec<TypeCount>,
+}
+
+/// How many exercises of a type each group picks, e.g. `push=2`, or `push=1-2`
+/// for a random count in a range.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct TypeCount {
+    pub exercise_type: ExerciseType,
+    pub min: u32,
+    pub max: u32,
+}
+
+/// An exercise to pick in each group: a type, e.g. `pull`, optionally narrowed
+/// down by a qualifier, e.g. `pull:any`, `legs:accessory` or
+/// `core:anti-rotation`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Slot {
+    pub exercise_type: ExerciseType,
+    pub qualifier: Qualifier,
+    /// Movement patterns the slot picks from, those of the subtree of its
+    /// node once resolved against the taxonomy
+    pub patterns: Vec<String>,
+}
+
+// Enum for what a slot picks among the exercises of its type: the category of
+// the group in the rotation, any category, one category or a node of the
+// taxonomy, stored with underscores, e.g. `anti_rotation`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum Qualifier {
+    #[default]
+    Rotation,
+    Any,
+    Category(ExerciseCategory),
+    Node(String),
+}
+
+/// A condition on the tags of the exercises selected so far.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Condition {
+    /// Holds if no selected exercise has this tag
+    pub none_tagged: Option<String>,
+    /// Holds if at least one selected exercise has this tag
+    pub any_tagged: Option<String>,
+    /// Names of the blocks, or labels of the groups, e.g. `B` or `B2`, to look
+    /// at, everything selected before when empty
+    #[serde(default, rename = "in")]
+    pub blocks: Vec<String>,
+}
+
+/// An exercise selected for the workout, as seen by the conditions.
+#[derive(Debug)]
+pub struct Selected<'a> {
+    /// Name of its block
+    pub block: Option<&'a str>,
+    /// Label of the exercise in its group, e.g. `B2`
+    pub label: String,
+    pub tags: &'a [String],
+}
+
+fn default_groups() -> u32 {
+    1
+}
+
+// --------------------------------------------------
+
+impl Template {
+    /// Returns the template of the default workout: `groups` super-sets of the
+    /// given types.
+    pub fn from_types(types: &[Slot], groups: u32) -> Template {
+        Template {
+            category_strategy: None,
+            blocks: vec![Block {
+                name: None,
+                types: Some(types.to_vec()),
+                groups,
+                tags: Vec::new(),
+                when: None,
+                per_group: Vec::new(),
+            }],
+        }
+    }
+
+    /// Sets the slots of the blocks that have none to `types`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a block has no types and `types`
+    /// is empty.
+    pub fn fill_types(&mut self, types: &[Slot]) -> Result<(), WodgenError> {
+        for block in &mut self.blocks {
+            if block.types.is_none() {
+                if types.is_empty() {
+                    return Err(WodgenError::NoBlockTypes(
+                        block.name.clone().unwrap_or_default(),
+                    ));
+                }
+                block.types = Some(types.to_vec());
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the per-group counts of the blocks that have none to `counts`.
+    pub fn fill_counts(&mut self, counts: &[TypeCount]) {
+        for block in &mut self.blocks {
+            if block.per_group.is_empty() {
+                block.per_group = counts.to_vec();
+            }
+        }
+    }
+
+    /// Sets the tags of the blocks that have none to `tags`.
+    pub fn fill_tags(&mut self, tags: &[String]) {
+        for block in &mut self.blocks {
+            if block.tags.is_empty() {
+                block.tags = tags.to_vec();
+            }
+        }
+    }
+
+    /// Returns every exercise type used by the template, without duplicates.
+    /// The category rotation over every group of the template.
+    pub fn rotation(&self) -> Rotation {
+        Rotation {
+            strategy: self.category_strategy.unwrap_or_default(),
+            groups: self.blocks.iter().map(|b| b.groups).sum(),
+        }
+    }
+
+    pub fn types(&self) -> Vec<ExerciseType> {
+        let mut types: Vec<ExerciseType> = Vec::new();
+        for slot in self.blocks.iter().flat_map(|b| b.types()) {
+            if !types.contains(&slot.exercise_type) {
+                types.push(slot.exercise_type.clone());
+            }
+        }
+        types
+    }
+}
+
+// --------------------------------------------------
+
+impl Block {
+    /// Returns the exercise slots of each group of the block.
+    pub fn types(&self) -> &[Slot] {
+        self.types.as_deref().unwrap_or_default()
+    }
+
+    /// Returns how many exercises of a type to pick for a group, drawn from the
+    /// range set in `per_group`.
+    pub fn count<R: Rng>(&self, exercise_type: &ExerciseType, rng: &mut R) -> u32 {
+        match self
+            .per_group
+            .iter()
+            .find(|c| c.exercise_type == *exercise_type)
+        {
+            Some(count) => rng.gen_range(count.min..=count.max),
+            None => 1,
+        }
+    }
+
+    /// Whether the block may pick the exercise, based on its tags.
+    pub fn accepts(&self, exercise: &Exercise) -> bool {
+        self.tags.is_empty() || self.tags.iter().any(|tag| exercise.tags.contains(tag))
+    }
+}
+
+// --------------------------------------------------
+
+impl Condition {
+    /// Evaluates the condition against the exercises selected so far.
+    pub fn holds(&self, selected: &[Selected]) -> bool {
+        let tags: Vec<&String> = selected
+            .iter()
+            .filter(|s| self.blocks.is_empty() || self.blocks.iter().any(|b| s.is_in(b)))
+            .flat_map(|s| s.tags.iter())
+            .collect();
+        self.none_tagged
+            .as_ref()
+            .is_none_or(|tag| !tags.contains(&tag))
+            && self
+                .any_tagged
+                .as_ref()
+                .is_none_or(|tag| tags.contains(&tag))
+    }
+}
+
+impl Selected<'_> {
+    // Whether the exercise is in a block or a group, given by its name or its
+    // label, e.g. `B` for the whole group or `B2`
+    fn is_in(&self, block_or_label: &str) -> bool {
+        let letter = self.label.trim_end_matches(|c: char| c.is_ascii_digit());
+        self.block == Some(block_or_label)
+            || self.label == block_or_label
+            || letter == block_or_label
+    }
+}
+
+// --------------------------------------------------
+
+impl Slot {
+    /// Whether an exercise may fill the slot whatever the group: it has the
+    /// type of the slot, and one of its movement patterns when it names a node.
+    pub fn accepts(&self, exercise: &Exercise) -> bool {
+        filter_by_type(exercise, &self.exercise_type)
+            && (self.patterns.is_empty()
+                || exercise
+                    .pattern
+                    .as_deref()
+                    .is_some_and(|p| self.patterns.contains(&normalize(p))))
+    }
+
+    /// Label of the slot in reports and prompts, e.g. `Pull` or `Pull (any)`.
+    pub fn label(&self) -> String {
+        match &self.qualifier {
+            Qualifier::Rotation => format!("{:?}", self.exercise_type),
+            qualifier => format!("{:?} ({})", self.exercise_type, qualifier),
+        }
+    }
+
+    /// Whether the category of an exercise fits the slot in a group: the one
+    /// of the rotation unless the slot sets it or takes any.
+    pub fn fits(
+        &self,
+        exercise: &Exercise,
+        group: u32,
+        level: &ExerciseLevel,
+        rotation: &Rotation,
+    ) -> bool {
+        match &self.qualifier {
+            Qualifier::Rotation => rotation.fits(exercise, group, level, &self.exercise_type),
+            Qualifier::Category(category) => exercise.exercise_category == *category,
+            Qualifier::Any | Qualifier::Node(_) => true,
+        }
+    }
+}
+
+impl From<ExerciseType> for Slot {
+    fn from(exercise_type: ExerciseType) -> Slot {
+        Slot {
+            exercise_type,
+            qualifier: Qualifier::Rotation,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+impl FromStr for Slot {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Slot, String> {
+        let (exercise_type, qualifier) = match s.split_once(':') {
+            Some((exercise_type, qualifier)) => (exercise_type, Some(qualifier.trim())),
+            None => (s, None),
+        };
+        let exercise_type = ExerciseType::from_str(exercise_type.trim(), true).map_err(|_| {
+            let possible_values: Vec<String> = ExerciseType::value_variants()
+                .iter()
+                .filter_map(|v| v.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect();
+            format!(
+                "invalid type {:?} [possible values: {}]",
+                exercise_type,
+                possible_values.join(", ")
+            )
+        })?;
+        let qualifier = match qualifier {
+            None => Qualifier::Rotation,
+            Some("") => {
+                return Err(format!(
+                    "invalid slot {:?}, expected TYPE:QUALIFIER, e.g. pull:any",
+                    s
+                ))
+            }
+            Some(q) if q.eq_ignore_ascii_case("any") => Qualifier::Any,
+            Some(q) => match ExerciseCategory::from_str(q, true) {
+                Ok(category) => Qualifier::Category(category),
+                Err(_) => Qualifier::Node(normalize(q)),
+            },
+        };
+        // A node matches its exact pattern until resolved against the taxonomy
+        let patterns = match &qualifier {
+            Qualifier::Node(node) => vec![node.clone()],
+            _ => Vec::new(),
+        };
+        Ok(Slot {
+            exercise_type,
+            qualifier,
+            patterns,
+        })
+    }
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let exercise_type = self
+            .exercise_type
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        match &self.qualifier {
+            Qualifier::Rotation => write!(f, "{}", exercise_type),
+            qualifier => write!(f, "{}:{}", exercise_type, qualifier),
+        }
+    }
+}
+
+impl fmt::Display for Qualifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Qualifier::Rotation => write!(f, "rotation"),
+            Qualifier::Any => write!(f, "any"),
+            Qualifier::Category(category) => write!(
+                f,
+                "{}",
+                category
+                    .to_possible_value()
+                    .map(|v| v.get_name().to_string())
+                    .unwrap_or_default()
+            ),
+            Qualifier::Node(node) => write!(f, "{}", node),
+        }
+    }
+}
+
+// Serialized the way it is written on the command line, e.g. "pull:any"
+impl Serialize for Slot {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl TryFrom<String> for Slot {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Slot, String> {
+        s.parse()
+    }
+}
+
+// --------------------------------------------------
+
+impl FromStr for TypeCount {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<TypeCount, String> {
+        let (exercise_type, range) = s
+            .split_once('=')
+            .ok_or_else(|| format!("invalid count {:?}, expected TYPE=COUNT, e.g. push=2", s))?;
+        let exercise_type = ExerciseType::from_str(exercise_type.trim(), true)?;
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<u32>()
+                .map_err(|_| format!("invalid count {:?} for {:?}", n, exercise_type))
+        };
+        let (min, max) = match range.split_once('-') {
+            Some((min, max)) => (parse(min)?, parse(max)?),
+            None => (parse(range)?, parse(range)?),
+        };
+        if min > max {
+            return Err(format!(
+                "invalid range {:?}, the minimum is above the maximum",
+                range
+            ));
+        }
+        Ok(TypeCount {
+            exercise_type,
+            min,
+            max,
+        })
+    }
+}
+
+impl fmt::Display for TypeCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let exercise_type = self
+            .exercise_type
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        if self.min == self.max {
+            write!(f, "{}={}", exercise_type, self.min)
+        } else {
+            write!(f, "{}={}-{}", exercise_type, self.min, self.max)
+        }
+    }
+}
+
+// Serialized the way it is written on the command line, e.g. "push=1-2"
+impl Serialize for TypeCount {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl TryFrom<String> for TypeCount {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<TypeCount, String> {
+        s.parse()
+    }
+}
+
+// --------------------------------------------------
+
+/// Loads a workout template from a TOML file.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or is not a
+/// valid template, e.g. because a block has an unknown exercise type.
+pub fn load_template(file_path: &Path) -> Result<Template> {
+    let content = fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to open file: {:?}", file_path))?;
+    let template: Template = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse template: {:?}", file_path))?;
+    Ok(template)
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_condition_on_selected_tags() {
+        let template: Template = toml::from_str(
+            r#"
+            [[blocks]]
+            name = "strength"
+            types = ["pull", "push"]
+            groups = 2
+
+            [[blocks]]
+            types = ["pull"]
+            tags = ["grip"]
+            when = { none_tagged = "grip", in = ["strength"] }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            template.types(),
+            vec![ExerciseType::Pull, ExerciseType::Push]
+        );
+        let condition = template.blocks[1].when.as_ref().unwrap();
+
+        let grip = [String::from("grip")];
+        let selected = |block, label: &str, tags| Selected {
+            block,
+            label: String::from(label),
+            tags,
+        };
+        assert!(condition.holds(&[]));
+        assert!(condition.holds(&[
+            selected(Some("strength"), "B1", &[]),
+            selected(None, "D1", &grip)
+        ]));
+        assert!(!condition.holds(&[selected(Some("strength"), "B1", &grip)]));
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_condition_on_group_labels() {
+        let condition: Condition =
+            toml::from_str("any_tagged = \"grip\"\nin = [\"B\", \"C2\"]").unwrap();
+        let grip = [String::from("grip")];
+        let holds = |label: &str| {
+            condition.holds(&[Selected {
+                block: None,
+                label: String::from(label),
+                tags: &grip,
+            }])
+        };
+        assert!(holds("B1"));
+        assert!(holds("B2"));
+        assert!(holds("C2"));
+        assert!(!holds("C1"));
+        assert!(!holds("D1"));
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_qualified_slots() {
+        let slot = |s: &str| s.parse::<Slot>().unwrap();
+        assert_eq!(slot("Push"), Slot::from(ExerciseType::Push));
+        assert_eq!(slot("pull:any").qualifier, Qualifier::Any);
+        assert_eq!(
+            slot("legs:accessory").qualifier,
+            Qualifier::Category(ExerciseCategory::Accessory)
+        );
+        assert_eq!(
+            slot("core:Anti-Rotation").qualifier,
+            Qualifier::Node(String::from("anti_rotation"))
+        );
+        assert_eq!(slot("core:anti-rotation").to_string(), "core:anti_rotation");
+        assert!("jump:any".parse::<Slot>().is_err());
+        assert!("pull:".parse::<Slot>().is_err());
+
+        let template: Template =
+            toml::from_str("[[blocks]]\ntypes = [\"pull:any\", \"pull\"]\n").unwrap();
+        assert_eq!(template.types(), vec![ExerciseType::Pull]);
+
+        let pallof = Exercise {
+            name: String::from("pallof_press"),
+            exercise_type: ExerciseType::Core,
+            exercise_category: ExerciseCategory::Accessory,
+            exercise_level: ExerciseLevel::Beginner,
+            exercise_programming: crate::ExerciseProgramming::Reps,
+            pattern: Some(String::from("anti_rotation")),
+            ..Default::default()
+        };
+        let level = ExerciseLevel::Beginner;
+        assert!(slot("core:anti-rotation").accepts(&pallof));
+        assert!(!slot("core:anti-extension").accepts(&pallof));
+        assert!(!slot("push:any").accepts(&pallof));
+        // The rotation asks for a secondary exercise in the first group
+        assert!(!slot("core").fits(&pallof, 0, &level, &Rotation::default()));
+        assert!(slot("core:any").fits(&pallof, 0, &level, &Rotation::default()));
+        assert!(slot("core:accessory").fits(&pallof, 0, &level, &Rotation::default()));
+        assert!(!slot("core:primary").fits(&pallof, 0, &level, &Rotation::default()));
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_per_group_counts() {
+        assert_eq!(
+            "push=1-2".parse::<TypeCount>(),
+            Ok(TypeCount {
+                exercise_type: ExerciseType::Push,
+                min: 1,
+                max: 2,
+            })
+        );
+        assert!("push".parse::<TypeCount>().is_err());
+        assert!("push=3-1".parse::<TypeCount>().is_err());
+        assert!("jump=1".parse::<TypeCount>().is_err());
+
+        let mut template =
+            Template::from_types(&[ExerciseType::Push.into(), ExerciseType::Core.into()], 1);
+        template.fill_counts(&["push=2".parse().unwrap()]);
+        let block = &template.blocks[0];
+        let mut rng = rand::thread_rng();
+        assert_eq!(block.count(&ExerciseType::Push, &mut rng), 2);
+        assert_eq!(block.count(&ExerciseType::Core, &mut rng), 1);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::history::{saved_workouts, workout_date};
+use crate::i18n::{Lang, Text};
+use crate::units::Units;
+use crate::{Exercise, ExerciseProgramming, ExerciseType, WorkoutExercise};
+use anyhow::Result;
+use chrono::{Duration, NaiveDate};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Suffix of the file names of the test days, e.g. `2024_06_01_test.csv`.
+pub const TEST_DAY_SUFFIX: &str = "_test";
+
+// Days between two test days when the config does not say
+const RETEST_DAYS: i64 = 42;
+
+// Minutes the exercises in distance are done for, as far as possible
+const DISTANCE_MINUTES: u32 = 12;
+
+// Order of the exercise types in a test day, the cooldown left out
+const TYPES: [ExerciseType; 4] = [
+    ExerciseType::Pull,
+    ExerciseType::Push,
+    ExerciseType::Legs,
+    ExerciseType::Core,
+];
+
+// --------------------------------------------------
+
+/// When the max-effort tests are retaken, under `[test_day]` in the user
+/// config: `every` days, 42 when unset.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Retest {
+    pub every: Option<i64>,
+}
+
+// --------------------------------------------------
+
+impl Retest {
+    /// The day the next test day is due, `every` days after the last one.
+    pub fn due(&self, last: NaiveDate) -> NaiveDate {
+        last + Duration::days(self.every.unwrap_or(RETEST_DAYS))
+    }
+}
+
+/// The day of the latest test day saved in a directory, none before the first.
+///
+/// # Errors
+///
+/// This function will return an error if the directory cannot be read.
+pub fn last_test_day(workouts_dir: &Path) -> Result<Option<NaiveDate>> {
+    Ok(saved_workouts(workouts_dir)?
+        .into_iter()
+        .filter(|(_, path)| {
+            path.file_stem()
+                .is_some_and(|stem| stem.to_string_lossy().ends_with(TEST_DAY_SUFFIX))
+        })
+        .map(|(modified, path)| workout_date(&path, modified))
+        .max())
+}
+
+/// Builds a test day from the active exercises flagged as testable, pull, push,
+/// legs then core in the order of the library, each its own group for a single
+/// max effort: the most reps in a set, the longest hold, or the longest
+/// distance in 12 minutes. What is logged counts as records like any workout.
+pub fn build(library: &[Exercise], lang: Lang, units: Units) -> Vec<WorkoutExercise> {
+    let mut rows = Vec::new();
+    for t in TYPES {
+        for e in library
+            .iter()
+            .filter(|e| e.testable && e.active && e.exercise_type == t)
+        {
+            let mut row = WorkoutExercise::from_exercise(rows.len() as u32 + 2, e, units);
+            row.sets = String::from("1");
+            row.goal = String::new();
+            let note = match e.exercise_programming {
+                ExerciseProgramming::Reps => lang.text(Text::MaxReps),
+                ExerciseProgramming::Time => lang.text(Text::MaxHold),
+                ExerciseProgramming::Distance => {
+                    row.time = format!("{}:00", DISTANCE_MINUTES);
+                    lang.text(Text::MaxDistance)
+                }
+            };
+            row.notes = [note, &row.notes]
+                .iter()
+                .filter(|note| !note.is_empty())
+                .copied()
+                .collect::<Vec<&str>>()
+                .join(": ");
+            rows.push(row);
+        }
+    }
+    rows
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ExerciseLevel;
+
+    fn exercise(
+        name: &str,
+        exercise_type: ExerciseType,
+        exercise_programming: ExerciseProgramming,
+        testable: bool,
+    ) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type,
+            exercise_level: ExerciseLevel::Beginner,
+            exercise_programming,
+            goal: Some(String::from("8")),
+            testable,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_test_day() {
+        let library = [
+            exercise("plank", ExerciseType::Core, ExerciseProgramming::Time, true),
+            exercise("dip", ExerciseType::Push, ExerciseProgramming::Reps, false),
+            exercise(
+                "run",
+                ExerciseType::Legs,
+                ExerciseProgramming::Distance,
+                true,
+            ),
+            exercise(
+                "pull_up",
+                ExerciseType::Pull,
+                ExerciseProgramming::Reps,
+                true,
+            ),
+        ];
+        let rows = build(&library, Lang::En, Units::Metric);
+        let names: Vec<&str> = rows.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["Pull Up", "Run", "Plank"]);
+        assert_eq!(rows[0].group, 2);
+        assert_eq!(rows[0].sets, "1");
+        assert_eq!(rows[0].goal, "");
+        assert_eq!(rows[0].notes, "max reps in one set");
+        assert_eq!(
+            (rows[1].distance.as_str(), rows[1].time.as_str()),
+            ("X", "12:00")
+        );
+        assert_eq!(rows[2].group, 4);
+        assert_eq!(rows[2].notes, "max hold");
+
+        let retest = Retest { every: Some(28) };
+        let last = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        assert_eq!(
+            retest.due(last),
+            NaiveDate::from_ymd_opt(2024, 6, 29).unwrap()
+        );
+        assert_eq!(
+            Retest::default().due(last),
+            NaiveDate::from_ymd_opt(2024, 7, 13).unwrap()
+        );
+
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(last_test_day(tmp.path()).unwrap(), None);
+        for name in [
+            "2024_06_01_test.csv",
+            "2024_06_20.csv",
+            "2024_05_01_test.csv",
+        ] {
+            std::fs::write(tmp.path().join(name), "group\n").unwrap();
+        }
+        assert_eq!(last_test_day(tmp.path()).unwrap(), Some(last));
+    }
+}
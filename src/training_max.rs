@@ -0,0 +1,276 @@
+use crate::csv_utils::{read_csv, write_csv};
+use crate::error::path_to_str;
+use crate::plates::{Implement, Inventory};
+use crate::units::{Units, Weight};
+use crate::WorkoutExercise;
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// File in the state directory holding the training maxes.
+pub const TRAINING_MAXES_FILE: &str = "training_maxes.csv";
+
+// Percentages of the training max and reps of the sets of each 5/3/1 week,
+// the last week being the deload
+const WENDLER_WEEKS: [[(f64, &str); 3]; 4] = [
+    [(0.65, "5"), (0.75, "5"), (0.85, "5+")],
+    [(0.70, "3"), (0.80, "3"), (0.90, "3+")],
+    [(0.75, "5"), (0.85, "3"), (0.95, "1+")],
+    [(0.40, "5"), (0.50, "5"), (0.60, "5")],
+];
+
+// Sets and reps of the linear scheme
+const LINEAR_SETS: u32 = 3;
+const LINEAR_REPS: u32 = 5;
+
+// Percentages of the first working load and reps of the warm-up sets
+const WARM_UP_SETS: [(f64, u32); 3] = [(0.4, 5), (0.6, 3), (0.8, 2)];
+
+// --------------------------------------------------
+
+// Enum for the progression schemes of the training max prescriptions
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Scheme {
+    /// 5/3/1: three weeks of sets at rising percentages of the training max and
+    /// a deload week, the training max grows by the increment every cycle
+    #[value(name = "531")]
+    #[serde(rename = "531")]
+    Wendler,
+    /// The load grows by the increment every session
+    #[value(name = "linear")]
+    #[serde(rename = "linear")]
+    Linear,
+}
+
+/// The training max of a weighted exercise, stored in kilograms.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainingMax {
+    /// Name of the exercise in the library
+    pub name: String,
+    pub training_max_kg: f64,
+    pub scheme: Scheme,
+    /// Load added every session (linear) or every cycle (5/3/1)
+    pub increment_kg: f64,
+    /// When the training max was set, the sessions are counted from that day
+    pub set_at: DateTime<Utc>,
+}
+
+// --------------------------------------------------
+
+/// Loads the training maxes, none when the file does not exist.
+///
+/// # Errors
+///
+/// This function will return an error if the file exists but cannot be parsed.
+pub fn load_training_maxes(file_path: &Path) -> Result<Vec<TrainingMax>> {
+    if !file_path.exists() {
+        return Ok(Vec::new());
+    }
+    read_csv::<TrainingMax>(path_to_str(file_path)?)
+}
+
+/// Sets the training max of an exercise, replacing the one it had and
+/// restarting its progression.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or written.
+pub fn set_training_max(file_path: &Path, training_max: TrainingMax) -> Result<()> {
+    let mut training_maxes = load_training_maxes(file_path)?;
+    training_maxes.retain(|tm| tm.name != training_max.name);
+    info!(
+        "Set the training max of {} to {}kg",
+        training_max.name, training_max.training_max_kg
+    );
+    training_maxes.push(training_max);
+    training_maxes.sort_by(|a, b| a.name.cmp(&b.name));
+    if let Some(state_dir) = file_path.parent() {
+        fs::create_dir_all(state_dir)?;
+    }
+    write_csv(path_to_str(file_path)?, training_maxes)
+}
+
+// --------------------------------------------------
+
+impl TrainingMax {
+    /// Counts the sessions of the exercise, shown as `name`, in the saved
+    /// workouts since the day the training max was set.
+    pub fn sessions(&self, name: &str, workouts: &[(NaiveDate, Vec<WorkoutExercise>)]) -> usize {
+        workouts
+            .iter()
+            .filter(|(date, _)| *date >= self.set_at.date_naive())
+            .filter(|(_, workout)| workout.iter().any(|e| e.name == name))
+            .count()
+    }
+
+    // The training max after `sessions` sessions of the exercise, and the
+    // share of it the first working set is done at
+    fn first_working_set(&self, sessions: usize) -> (f64, f64) {
+        match self.scheme {
+            Scheme::Wendler => {
+                let cycle = sessions / WENDLER_WEEKS.len();
+                let week = sessions % WENDLER_WEEKS.len();
+                (
+                    self.training_max_kg + self.increment_kg * cycle as f64,
+                    WENDLER_WEEKS[week][0].0,
+                )
+            }
+            Scheme::Linear => (
+                self.training_max_kg + self.increment_kg * sessions as f64,
+                1.0,
+            ),
+        }
+    }
+
+    /// Ramps up to the first working set of the next session: one warm-up set
+    /// per entry, its reps and its load, e.g. `5 @ 40kg`, rounded to what the
+    /// inventory can make.
+    pub fn warm_up(
+        &self,
+        sessions: usize,
+        inventory: &Inventory,
+        implement: Implement,
+        units: Units,
+    ) -> Vec<String> {
+        let (training_max, share) = self.first_working_set(sessions);
+        WARM_UP_SETS
+            .iter()
+            .map(|(warm_up_share, reps)| {
+                let weight = Weight::from_kilograms(training_max * share * warm_up_share);
+                format!(
+                    "{} @ {}",
+                    reps,
+                    inventory.load(weight, implement, units).render(units)
+                )
+            })
+            .collect()
+    }
+
+    /// Prescribes the next session after `sessions` sessions of the exercise:
+    /// the sets to do and a note with the loads, rounded to what the inventory
+    /// can make.
+    pub fn prescribe(
+        &self,
+        sessions: usize,
+        inventory: &Inventory,
+        implement: Implement,
+        units: Units,
+    ) -> (String, String) {
+        // A load as a share of the given training max
+        let load = |training_max: f64, share: f64| {
+            inventory
+                .load(
+                    Weight::from_kilograms(training_max * share),
+                    implement,
+                    units,
+                )
+                .render(units)
+        };
+        let (training_max, _) = self.first_working_set(sessions);
+        match self.scheme {
+            Scheme::Wendler => {
+                let week = sessions % WENDLER_WEEKS.len();
+                let sets: Vec<String> = WENDLER_WEEKS[week]
+                    .iter()
+                    .map(|(share, reps)| format!("{} @ {}", reps, load(training_max, *share)))
+                    .collect();
+                (
+                    WENDLER_WEEKS[week].len().to_string(),
+                    format!("5/3/1 week {}: {}", week + 1, sets.join(", ")),
+                )
+            }
+            Scheme::Linear => (
+                LINEAR_SETS.to_string(),
+                format!(
+                    "{}x{} @ {}",
+                    LINEAR_SETS,
+                    LINEAR_REPS,
+                    load(training_max, 1.0)
+                ),
+            ),
+        }
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn training_max(scheme: Scheme) -> TrainingMax {
+        TrainingMax {
+            name: String::from("back_squat"),
+            training_max_kg: 120.0,
+            scheme,
+            increment_kg: 5.0,
+            set_at: Utc::now(),
+        }
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_prescriptions() {
+        let inventory = Inventory::default();
+        let prescribe = |tm: &TrainingMax, sessions, units| {
+            tm.prescribe(sessions, &inventory, Implement::Barbell, units)
+        };
+        let wendler = training_max(Scheme::Wendler);
+        assert_eq!(
+            prescribe(&wendler, 0, Units::Metric),
+            (
+                String::from("3"),
+                String::from("5/3/1 week 1: 5 @ 77.5kg, 5 @ 90kg, 5+ @ 102.5kg")
+            )
+        );
+        // The second cycle starts from a training max of 125kg
+        assert_eq!(
+            prescribe(&wendler, 6, Units::Metric).1,
+            "5/3/1 week 3: 5 @ 95kg, 3 @ 107.5kg, 1+ @ 120kg"
+        );
+
+        let linear = training_max(Scheme::Linear);
+        assert_eq!(prescribe(&linear, 2, Units::Metric).1, "3x5 @ 130kg");
+        assert_eq!(prescribe(&linear, 0, Units::Imperial).1, "3x5 @ 265lb");
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_warm_up_ramps_to_the_first_working_set() {
+        let inventory = Inventory::default();
+        let warm_up = |tm: &TrainingMax, sessions| {
+            tm.warm_up(sessions, &inventory, Implement::Barbell, Units::Metric)
+        };
+        assert_eq!(
+            warm_up(&training_max(Scheme::Linear), 0),
+            ["5 @ 47.5kg", "3 @ 72.5kg", "2 @ 95kg"]
+        );
+        // Up to the 65% of the first set of 5/3/1 week 1
+        assert_eq!(
+            warm_up(&training_max(Scheme::Wendler), 0),
+            ["5 @ 30kg", "3 @ 47.5kg", "2 @ 62.5kg"]
+        );
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_set_training_max_replaces_the_old_one() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file_path = tmp.path().join(TRAINING_MAXES_FILE);
+        set_training_max(&file_path, training_max(Scheme::Wendler)).unwrap();
+        let mut updated = training_max(Scheme::Linear);
+        updated.training_max_kg = 125.0;
+        set_training_max(&file_path, updated).unwrap();
+
+        let training_maxes = load_training_maxes(&file_path).unwrap();
+        assert_eq!(training_maxes.len(), 1);
+        assert_eq!(training_maxes[0].training_max_kg, 125.0);
+        assert_eq!(training_maxes[0].scheme, Scheme::Linear);
+    }
+}
@@ -0,0 +1,304 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// Conversion factors to meters
+const METERS_PER_KILOMETER: f64 = 1000.0;
+const METERS_PER_MILE: f64 = 1609.344;
+const METERS_PER_YARD: f64 = 0.9144;
+const METERS_PER_FOOT: f64 = 0.3048;
+
+// Conversion factor to kilograms
+const KILOGRAMS_PER_POUND: f64 = 0.453_592_37;
+
+// Loads are rounded to multiples of these, in kilograms or pounds
+const KILOGRAMS_STEP: f64 = 2.5;
+const POUNDS_STEP: f64 = 5.0;
+
+// Distances from this many miles up are rendered in miles rather than yards
+const MIN_MILES: f64 = 0.2;
+
+// --------------------------------------------------
+
+// Enum for the unit systems prescriptions can be rendered in
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Units {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+// --------------------------------------------------
+
+/// Error returned when a prescription cannot be parsed into a typed value.
+#[derive(Debug, PartialEq)]
+pub struct ParseUnitError(String);
+
+impl fmt::Display for ParseUnitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid prescription: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseUnitError {}
+
+// --------------------------------------------------
+
+// Split a prescription like "400m" or "0.25 mi" into its number and unit
+fn split_value(input: &str) -> Result<(f64, String), ParseUnitError> {
+    let input = input.trim();
+    let unit_start = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(unit_start);
+    let number = number
+        .parse::<f64>()
+        .map_err(|_| ParseUnitError(input.to_string()))?;
+    Ok((number, unit.trim().to_lowercase()))
+}
+
+// Format a number with at most two decimals and no trailing zeros
+fn format_number(value: f64) -> String {
+    let formatted = format!("{:.2}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+// --------------------------------------------------
+
+/// A distance prescription, stored in meters.
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct Distance {
+    meters: f64,
+}
+
+impl Distance {
+    pub fn from_meters(meters: f64) -> Distance {
+        Distance { meters }
+    }
+
+    pub fn meters(&self) -> f64 {
+        self.meters
+    }
+
+    /// Renders the distance in the given unit system, e.g. `400m`, `1.5km`,
+    /// `0.25mi` or `110yd`.
+    pub fn render(&self, units: Units) -> String {
+        match units {
+            Units::Metric if self.meters >= METERS_PER_KILOMETER => {
+                format!("{}km", format_number(self.meters / METERS_PER_KILOMETER))
+            }
+            Units::Metric if self.meters >= 100.0 => {
+                format!("{}m", (self.meters / 10.0).round() * 10.0)
+            }
+            Units::Metric => format!("{}m", self.meters.round()),
+            Units::Imperial if self.meters >= MIN_MILES * METERS_PER_MILE => {
+                format!("{}mi", format_number(self.meters / METERS_PER_MILE))
+            }
+            Units::Imperial => format!("{}yd", (self.meters / METERS_PER_YARD).round()),
+        }
+    }
+}
+
+impl FromStr for Distance {
+    type Err = ParseUnitError;
+
+    /// Parses distances written in metric or imperial units. A bare number is
+    /// taken to be in meters.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (value, unit) = split_value(s)?;
+        let factor = match unit.as_str() {
+            "" | "m" => 1.0,
+            "km" => METERS_PER_KILOMETER,
+            "mi" | "mile" | "miles" => METERS_PER_MILE,
+            "yd" | "yard" | "yards" => METERS_PER_YARD,
+            "ft" | "foot" | "feet" => METERS_PER_FOOT,
+            _ => return Err(ParseUnitError(s.to_string())),
+        };
+        Ok(Distance::from_meters(value * factor))
+    }
+}
+
+// --------------------------------------------------
+
+/// A time prescription, stored in seconds.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Duration {
+    seconds: u32,
+}
+
+impl Duration {
+    pub fn from_seconds(seconds: u32) -> Duration {
+        Duration { seconds }
+    }
+
+    pub fn seconds(&self) -> u32 {
+        self.seconds
+    }
+
+    /// Renders the duration as `45s` below a minute and `m:ss` otherwise.
+    pub fn render(&self) -> String {
+        if self.seconds < 60 {
+            format!("{}s", self.seconds)
+        } else {
+            format!("{}:{:02}", self.seconds / 60, self.seconds % 60)
+        }
+    }
+}
+
+impl FromStr for Duration {
+    type Err = ParseUnitError;
+
+    /// Parses durations like `45`, `45s`, `2min` or `1:30`. A bare number is
+    /// taken to be in seconds.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((minutes, seconds)) = s.trim().split_once(':') {
+            let minutes = minutes
+                .parse::<u32>()
+                .map_err(|_| ParseUnitError(s.to_string()))?;
+            let seconds = seconds
+                .parse::<u32>()
+                .map_err(|_| ParseUnitError(s.to_string()))?;
+            // Minutes past what a u32 of seconds holds are not a duration
+            return minutes
+                .checked_mul(60)
+                .and_then(|m| m.checked_add(seconds))
+                .map(Duration::from_seconds)
+                .ok_or_else(|| ParseUnitError(s.to_string()));
+        }
+        let (value, unit) = split_value(s)?;
+        let factor = match unit.as_str() {
+            "" | "s" | "sec" => 1.0,
+            "min" | "m" => 60.0,
+            _ => return Err(ParseUnitError(s.to_string())),
+        };
+        Ok(Duration::from_seconds((value * factor).round() as u32))
+    }
+}
+
+// --------------------------------------------------
+
+/// A load, stored in kilograms.
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct Weight {
+    kilograms: f64,
+}
+
+impl Weight {
+    pub fn from_kilograms(kilograms: f64) -> Weight {
+        Weight { kilograms }
+    }
+
+    pub fn kilograms(&self) -> f64 {
+        self.kilograms
+    }
+
+    // The weight in the unit of the given system
+    fn value(&self, units: Units) -> f64 {
+        match units {
+            Units::Metric => self.kilograms,
+            Units::Imperial => self.kilograms / KILOGRAMS_PER_POUND,
+        }
+    }
+
+    /// Renders the weight in the given unit system, e.g. `102.5kg` or `225lb`.
+    pub fn render(&self, units: Units) -> String {
+        let unit = match units {
+            Units::Metric => "kg",
+            Units::Imperial => "lb",
+        };
+        format!("{}{}", format_number(self.value(units)), unit)
+    }
+
+    /// Rounds the weight to the nearest 2.5kg, or 5lb in imperial units.
+    pub fn round(&self, units: Units) -> Weight {
+        let (step, factor) = match units {
+            Units::Metric => (KILOGRAMS_STEP, 1.0),
+            Units::Imperial => (POUNDS_STEP, KILOGRAMS_PER_POUND),
+        };
+        let value = (self.value(units) / step).round() * step;
+        Weight::from_kilograms(value * factor)
+    }
+}
+
+impl FromStr for Weight {
+    type Err = ParseUnitError;
+
+    /// Parses weights like `120`, `120kg` or `265lb`. A bare number is taken to
+    /// be in kilograms.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (value, unit) = split_value(s)?;
+        let factor = match unit.as_str() {
+            "" | "kg" => 1.0,
+            "lb" | "lbs" => KILOGRAMS_PER_POUND,
+            _ => return Err(ParseUnitError(s.to_string())),
+        };
+        Ok(Weight::from_kilograms(value * factor))
+    }
+}
+
+impl<'de> Deserialize<'de> for Weight {
+    /// Deserializes a bare number as kilograms, or a string like `45lb`.
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // A config value, either a number or a string with a unit
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Number(f64),
+            Text(String),
+        }
+        match Value::deserialize(deserializer)? {
+            Value::Number(kilograms) => Ok(Weight::from_kilograms(kilograms)),
+            Value::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_round_trip() {
+        let distance: Distance = "400m".parse().unwrap();
+        assert_eq!(distance.render(Units::Imperial), "0.25mi");
+        let distance: Distance = "0.25mi".parse().unwrap();
+        assert_eq!(distance.render(Units::Metric), "400m");
+        let distance: Distance = "1.5 km".parse().unwrap();
+        assert_eq!(distance.render(Units::Metric), "1.5km");
+        let distance: Distance = "100".parse().unwrap();
+        assert_eq!(distance.render(Units::Imperial), "109yd");
+        assert!("400 furlongs".parse::<Distance>().is_err());
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_duration_parsing() {
+        assert_eq!("45".parse::<Duration>().unwrap().render(), "45s");
+        assert_eq!("2min".parse::<Duration>().unwrap().render(), "2:00");
+        assert_eq!("1:30".parse::<Duration>().unwrap().render(), "1:30");
+        assert!("soon".parse::<Duration>().is_err());
+        assert!("99999999:00".parse::<Duration>().is_err());
+        assert!("0:4294967295".parse::<Duration>().is_ok());
+        assert!("1:4294967295".parse::<Duration>().is_err());
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_weight_rounding() {
+        let weight: Weight = "101kg".parse().unwrap();
+        assert_eq!(weight.round(Units::Metric).render(Units::Metric), "100kg");
+        let weight: Weight = "227lb".parse().unwrap();
+        assert_eq!(
+            weight.round(Units::Imperial).render(Units::Imperial),
+            "225lb"
+        );
+        assert!("10 stone".parse::<Weight>().is_err());
+    }
+}
@@ -0,0 +1,500 @@
+use crate::history::logged_sets;
+use crate::units::Duration;
+use crate::{display, Exercise, WorkoutExercise};
+use chrono::{Datelike, NaiveDate};
+use clap::ValueEnum;
+use log::info;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Sets assumed for a planned exercise when the workout leaves the number open
+const DEFAULT_SETS: u32 = 3;
+
+// Default maintenance volume and maximum recoverable volume, in weekly sets
+const DEFAULT_MV: u32 = 6;
+const DEFAULT_MRV: u32 = 20;
+
+// Seconds of a timed set counting as one rep toward a reps target, and the
+// reps assumed per planned set when its goal gives none
+const SECONDS_PER_REP: u32 = 3;
+const DEFAULT_REPS: u32 = 8;
+
+// --------------------------------------------------
+
+/// The weekly working set thresholds of a muscle.
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Landmark {
+    /// Maintenance volume, the sets needed to keep what was built
+    pub mv: u32,
+    /// Maximum recoverable volume, the most sets that can be recovered from
+    pub mrv: u32,
+}
+
+/// The volume landmarks of the `[volume]` table of the user config, the same
+/// for every muscle unless overridden under `[volume.muscles]`.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VolumeLandmarks {
+    pub mv: u32,
+    pub mrv: u32,
+    pub muscles: HashMap<String, Landmark>,
+}
+
+// Enum for a muscle whose weekly volume is out of its landmarks
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Imbalance {
+    BelowMv,
+    AtMrv,
+}
+
+/// A muscle trained too little or too much this week.
+#[derive(Debug, PartialEq)]
+pub struct Flag {
+    pub muscle: String,
+    pub sets: u32,
+    pub landmark: Landmark,
+    pub imbalance: Imbalance,
+}
+
+/// The weekly targets of an area, an exercise type or a muscle, under
+/// `[targets.<area>]` in the user config: working sets, reps-equivalent or
+/// sessions training it.
+#[derive(Debug, Default, PartialEq, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeeklyTarget {
+    pub sets: Option<u32>,
+    pub reps: Option<u32>,
+    pub sessions: Option<u32>,
+}
+
+// Enum for what a weekly target counts
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Measure {
+    Sets,
+    Reps,
+    Sessions,
+}
+
+/// How far this week went toward a weekly target.
+#[derive(Debug, PartialEq)]
+pub struct TargetProgress {
+    pub area: String,
+    pub measure: Measure,
+    pub done: u32,
+    pub target: u32,
+}
+
+/// How the pool of a workout was rebalanced.
+#[derive(Debug, Default)]
+pub struct Rebalance {
+    /// Exercises left out for training a muscle that reached its MRV
+    pub rested: Vec<String>,
+    /// Exercises training a muscle below its MV, to be picked first
+    pub preferred: Vec<String>,
+}
+
+// --------------------------------------------------
+
+impl Default for VolumeLandmarks {
+    fn default() -> VolumeLandmarks {
+        VolumeLandmarks {
+            mv: DEFAULT_MV,
+            mrv: DEFAULT_MRV,
+            muscles: HashMap::new(),
+        }
+    }
+}
+
+impl VolumeLandmarks {
+    /// Returns the landmark of a muscle.
+    pub fn landmark(&self, muscle: &str) -> Landmark {
+        self.muscles.get(muscle).copied().unwrap_or(Landmark {
+            mv: self.mv,
+            mrv: self.mrv,
+        })
+    }
+}
+
+impl Measure {
+    fn unit(&self) -> &'static str {
+        match self {
+            Measure::Sets => "sets",
+            Measure::Reps => "reps",
+            Measure::Sessions => "sessions",
+        }
+    }
+}
+
+impl TargetProgress {
+    pub fn is_met(&self) -> bool {
+        self.done >= self.target
+    }
+
+    /// Renders the progress, e.g. `pull: 32 of 40 reps`.
+    pub fn render(&self) -> String {
+        format!(
+            "{}: {} of {} {}{}",
+            self.area,
+            self.done,
+            self.target,
+            self.measure.unit(),
+            if self.is_met() { ", met" } else { "" }
+        )
+    }
+}
+
+// --------------------------------------------------
+
+// The workouts of the current week, from Monday to `today`
+fn this_week(
+    workouts: &[(NaiveDate, Vec<WorkoutExercise>)],
+    today: NaiveDate,
+) -> impl Iterator<Item = &Vec<WorkoutExercise>> {
+    let monday = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+    workouts
+        .iter()
+        .filter(move |(date, _)| monday <= *date && *date <= today)
+        .map(|(_, workout)| workout)
+}
+
+// The areas an exercise trains: its type and its muscles, in lowercase
+fn areas(exercise: &Exercise) -> Vec<String> {
+    exercise
+        .exercise_type
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .into_iter()
+        .chain(exercise.muscles.iter().map(|m| m.trim().to_lowercase()))
+        .collect()
+}
+
+/// Returns the working sets of an exercise of a saved workout: the sets logged,
+/// or the sets planned when nothing is logged yet.
+pub fn working_sets(exercise: &WorkoutExercise) -> u32 {
+    match logged_sets(exercise) {
+        0 => exercise.sets.trim().parse().unwrap_or(DEFAULT_SETS),
+        sets => sets as u32,
+    }
+}
+
+/// Returns the reps-equivalent of an exercise of a saved workout: the reps
+/// logged plus one per 3 seconds of the time logged, or the working sets times
+/// the goal when nothing is logged yet, 8 reps a set without a goal.
+pub fn reps_equivalent(exercise: &WorkoutExercise) -> u32 {
+    if logged_sets(exercise) > 0 {
+        let values = |cell: &str| -> Vec<String> {
+            cell.split([',', ';', '/'])
+                .map(|v| v.trim().to_string())
+                .collect()
+        };
+        let reps: u32 = values(&exercise.reps)
+            .iter()
+            .filter_map(|v| v.parse::<u32>().ok())
+            .sum();
+        let seconds: u32 = values(&exercise.time)
+            .iter()
+            .filter_map(|v| v.parse::<Duration>().ok())
+            .map(|t| t.seconds())
+            .sum();
+        return reps + seconds / SECONDS_PER_REP;
+    }
+    let goal = exercise.goal.trim();
+    let per_set = goal
+        .parse::<u32>()
+        .ok()
+        .or_else(|| {
+            goal.parse::<Duration>()
+                .ok()
+                .map(|t| t.seconds() / SECONDS_PER_REP)
+        })
+        .unwrap_or(DEFAULT_REPS);
+    working_sets(exercise) * per_set
+}
+
+/// Counts the working sets per muscle of the workouts of the current week,
+/// from Monday to `today`.
+///
+/// The muscles of each exercise are looked up in the library, so exercises
+/// without muscles metadata are not counted.
+pub fn weekly_sets(
+    workouts: &[(NaiveDate, Vec<WorkoutExercise>)],
+    exercises: &[Exercise],
+    today: NaiveDate,
+) -> HashMap<String, u32> {
+    let library: HashMap<String, &Exercise> = exercises
+        .iter()
+        .map(|e| (display::exercise_name(e), e))
+        .collect();
+    let mut sets = HashMap::new();
+    for workout in this_week(workouts, today) {
+        for e in workout.iter().filter(|e| e.group > 0) {
+            let Some(exercise) = library.get(&e.name) else {
+                continue;
+            };
+            for muscle in &exercise.muscles {
+                *sets.entry(muscle.clone()).or_insert(0) += working_sets(e);
+            }
+        }
+    }
+    sets
+}
+
+/// Flags the muscles of the library trained below their MV this week, or that
+/// reached their MRV.
+pub fn check(
+    weekly_sets: &HashMap<String, u32>,
+    exercises: &[Exercise],
+    landmarks: &VolumeLandmarks,
+) -> Vec<Flag> {
+    let mut muscles: Vec<&String> = exercises.iter().flat_map(|e| &e.muscles).collect();
+    muscles.sort();
+    muscles.dedup();
+    muscles
+        .into_iter()
+        .filter_map(|muscle| {
+            let sets = weekly_sets.get(muscle).copied().unwrap_or(0);
+            let landmark = landmarks.landmark(muscle);
+            let imbalance = if sets >= landmark.mrv {
+                Imbalance::AtMrv
+            } else if sets < landmark.mv {
+                Imbalance::BelowMv
+            } else {
+                return None;
+            };
+            Some(Flag {
+                muscle: muscle.clone(),
+                sets,
+                landmark,
+                imbalance,
+            })
+        })
+        .collect()
+}
+
+/// Tracks the weekly targets over the workouts of the current week, from
+/// Monday to `today`, the areas in alphabetical order. Exercises count toward
+/// their type and the muscles the library gives them.
+pub fn progress(
+    workouts: &[(NaiveDate, Vec<WorkoutExercise>)],
+    exercises: &[Exercise],
+    targets: &HashMap<String, WeeklyTarget>,
+    today: NaiveDate,
+) -> Vec<TargetProgress> {
+    let library: HashMap<String, &Exercise> = exercises
+        .iter()
+        .map(|e| (display::exercise_name(e), e))
+        .collect();
+    // Sets, reps-equivalent and sessions per area
+    let mut done: HashMap<String, (u32, u32, u32)> = HashMap::new();
+    for workout in this_week(workouts, today) {
+        let mut trained: Vec<String> = Vec::new();
+        for e in workout.iter().filter(|e| e.group > 0) {
+            let Some(exercise) = library.get(&e.name) else {
+                continue;
+            };
+            for area in areas(exercise) {
+                let entry = done.entry(area.clone()).or_default();
+                entry.0 += working_sets(e);
+                entry.1 += reps_equivalent(e);
+                if !trained.contains(&area) {
+                    entry.2 += 1;
+                    trained.push(area);
+                }
+            }
+        }
+    }
+
+    let mut names: Vec<&String> = targets.keys().collect();
+    names.sort_by_key(|name| name.trim().to_lowercase());
+    let mut progress = Vec::new();
+    for name in names {
+        let area = name.trim().to_lowercase();
+        let (sets, reps, sessions) = done.get(&area).copied().unwrap_or_default();
+        let target = targets[name];
+        for (measure, target, done) in [
+            (Measure::Sets, target.sets, sets),
+            (Measure::Reps, target.reps, reps),
+            (Measure::Sessions, target.sessions, sessions),
+        ] {
+            if let Some(target) = target {
+                progress.push(TargetProgress {
+                    area: area.clone(),
+                    measure,
+                    done,
+                    target,
+                });
+            }
+        }
+    }
+    progress
+}
+
+/// Returns the exercises of the pool training an area behind one of its
+/// weekly targets, to be picked first.
+pub fn close_gaps(pool: &[Exercise], progress: &[TargetProgress]) -> Vec<String> {
+    let behind: Vec<&str> = progress
+        .iter()
+        .filter(|p| !p.is_met())
+        .map(|p| p.area.as_str())
+        .collect();
+    pool.iter()
+        .filter(|e| areas(e).iter().any(|area| behind.contains(&area.as_str())))
+        .map(|e| e.name.clone())
+        .collect()
+}
+
+/// Rebalances the pool of a workout: the exercises training a muscle that
+/// reached its MRV are left out, unless no other exercise of their type is
+/// left, and those training a muscle below its MV are preferred.
+pub fn rebalance(pool: &mut Vec<Exercise>, flags: &[Flag]) -> Rebalance {
+    let flagged = |exercise: &Exercise, imbalance: Imbalance| {
+        flags
+            .iter()
+            .any(|f| f.imbalance == imbalance && exercise.muscles.contains(&f.muscle))
+    };
+    let rested: Vec<String> = pool
+        .iter()
+        .filter(|e| {
+            flagged(e, Imbalance::AtMrv)
+                && pool
+                    .iter()
+                    .any(|o| o.exercise_type == e.exercise_type && !flagged(o, Imbalance::AtMrv))
+        })
+        .map(|e| e.name.clone())
+        .collect();
+    pool.retain(|e| !rested.contains(&e.name));
+    if !rested.is_empty() {
+        info!(
+            "Left out {} exercises training muscles at their MRV, {} exercises remaining",
+            rested.len(),
+            pool.len()
+        );
+    }
+    let preferred = pool
+        .iter()
+        .filter(|e| flagged(e, Imbalance::BelowMv))
+        .map(|e| e.name.clone())
+        .collect();
+    Rebalance { rested, preferred }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExerciseLevel, ExerciseType};
+
+    fn exercise(name: &str, exercise_type: ExerciseType, muscles: &[&str]) -> Exercise {
+        Exercise {
+            name: String::from(name),
+            exercise_type,
+            exercise_level: ExerciseLevel::Beginner,
+            muscles: muscles.iter().map(|m| m.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn row(name: &str, sets: &str, reps: &str) -> WorkoutExercise {
+        WorkoutExercise {
+            group: 2,
+            name: String::from(name),
+            sets: String::from(sets),
+            reps: String::from(reps),
+            ..Default::default()
+        }
+    }
+
+    fn date(day: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap()
+    }
+
+    // --------------------------------------------------
+
+    #[test]
+    fn test_weekly_sets_and_rebalance() {
+        let exercises = vec![
+            exercise("dip", ExerciseType::Push, &["chest", "triceps"]),
+            exercise("push_up", ExerciseType::Push, &["chest"]),
+            exercise("pike_push_up", ExerciseType::Push, &["shoulders"]),
+            exercise("pull_up", ExerciseType::Pull, &["lats"]),
+        ];
+        let workouts = vec![
+            // The week before does not count
+            (date("2024-06-07"), vec![row("Dip", "10", "")]),
+            (date("2024-06-10"), vec![row("Dip", "", "10/10/8/8")]),
+            (date("2024-06-12"), vec![row("Push Up", "", "X")]),
+        ];
+        let sets = weekly_sets(&workouts, &exercises, date("2024-06-12"));
+        assert_eq!(sets["chest"], 7);
+        assert_eq!(sets["triceps"], 4);
+
+        let landmarks: VolumeLandmarks = toml::from_str(
+            r#"
+            mv = 4
+            [muscles.chest]
+            mv = 2
+            mrv = 6
+            "#,
+        )
+        .unwrap();
+        let flags = check(&sets, &exercises, &landmarks);
+        let imbalances: Vec<(&str, Imbalance)> = flags
+            .iter()
+            .map(|f| (f.muscle.as_str(), f.imbalance))
+            .collect();
+        assert_eq!(
+            imbalances,
+            [
+                ("chest", Imbalance::AtMrv),
+                ("lats", Imbalance::BelowMv),
+                ("shoulders", Imbalance::BelowMv),
+            ]
+        );
+
+        let mut pool = exercises.clone();
+        let rebalance = rebalance(&mut pool, &flags);
+        let names: Vec<&str> = pool.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["pike_push_up", "pull_up"]);
+        assert_eq!(rebalance.rested, ["dip", "push_up"]);
+        assert_eq!(rebalance.preferred, ["pike_push_up", "pull_up"]);
+    }
+
+    #[test]
+    fn test_weekly_targets() {
+        let exercises = vec![
+            exercise("pull_up", ExerciseType::Pull, &["lats"]),
+            exercise("hang", ExerciseType::Pull, &["Forearms"]),
+            exercise("squat", ExerciseType::Legs, &["quads"]),
+        ];
+        let mut hang = row("Hang", "2", "");
+        hang.time = String::from("0:30/0:30");
+        let workouts = vec![
+            (date("2024-06-10"), vec![row("Pull Up", "", "10/8"), hang]),
+            (date("2024-06-11"), vec![row("Squat", "", "X")]),
+            (date("2024-06-12"), vec![row("Pull Up", "3", "X")]),
+        ];
+        let targets: HashMap<String, WeeklyTarget> = toml::from_str(
+            r#"
+            pull = { reps = 40, sessions = 2 }
+            Legs = { sessions = 2 }
+            forearms = { sets = 2 }
+            "#,
+        )
+        .unwrap();
+        let progress = progress(&workouts, &exercises, &targets, date("2024-06-12"));
+        let rendered: Vec<String> = progress.iter().map(TargetProgress::render).collect();
+        // 18 reps and 60 seconds logged, then 3 planned sets of 8
+        assert_eq!(
+            rendered,
+            [
+                "forearms: 2 of 2 sets, met",
+                "legs: 1 of 2 sessions",
+                "pull: 62 of 40 reps, met",
+                "pull: 2 of 2 sessions, met",
+            ]
+        );
+        assert_eq!(close_gaps(&exercises, &progress), ["squat"]);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::csv_utils::{append_csv, read_csv};
+use crate::error::path_to_str;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// File in the state directory holding the wellness check-ins.
+pub const WELLNESS_FILE: &str = "wellness.csv";
+
+// Days of check-ins the rolling score is averaged over
+const ROLLING_DAYS: i64 = 7;
+
+// Rolling scores below this are a low readiness, those from the other one up
+// a high readiness
+const LOW_SCORE: f64 = 40.0;
+const HIGH_SCORE: f64 = 75.0;
+
+// --------------------------------------------------
+
+// Enum for how ready for training the athlete is, taking a round off every
+// strength group when low and adding one when high. Auto reads it from the
+// wellness check-ins of the last days
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Readiness {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Auto,
+}
+
+/// A wellness check-in: how the athlete slept and feels, from 1 to 5, and how
+/// hard the last session was, from 1 to 10.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckIn {
+    pub checked_in_at: DateTime<Utc>,
+    /// Sleep quality, 5 being the best
+    pub sleep: u8,
+    /// Muscle soreness, 5 being the sorest
+    pub soreness: u8,
+    /// Motivation to train, 5 being the highest
+    pub motivation: u8,
+    /// Session RPE of the last session, 10 being a maximal effort
+    pub rpe: Option<u8>,
+}
+
+// --------------------------------------------------
+
+/// Loads the wellness check-ins, none when the file does not exist.
+///
+/// # Errors
+///
+/// This function will return an error if the file exists but cannot be parsed.
+pub fn load_check_ins(file_path: &Path) -> Result<Vec<CheckIn>> {
+    if !file_path.exists() {
+        return Ok(Vec::new());
+    }
+    read_csv::<CheckIn>(path_to_str(file_path)?)
+}
+
+/// Adds a check-in to the end of the wellness log.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be written.
+pub fn add_check_in(file_path: &Path, check_in: CheckIn) -> Result<()> {
+    info!(
+        "Wellness score of the check-in: {:.0}/100",
+        check_in.score()
+    );
+    if let Some(state_dir) = file_path.parent() {
+        fs::create_dir_all(state_dir)?;
+    }
+    append_csv(path_to_str(file_path)?, [check_in])
+}
+
+/// Averages the scores of the check-ins of the last 7 days, none without any.
+pub fn rolling_score(check_ins: &[CheckIn], now: DateTime<Utc>) -> Option<f64> {
+    let scores: Vec<f64> = check_ins
+        .iter()
+        .filter(|c| now.signed_duration_since(c.checked_in_at).num_days() < ROLLING_DAYS)
+        .map(CheckIn::score)
+        .collect();
+    if scores.is_empty() {
+        return None;
+    }
+    Some(scores.iter().sum::<f64>() / scores.len() as f64)
+}
+
+// --------------------------------------------------
+
+impl CheckIn {
+    /// Wellness score from 0 to 100, averaging the answers scaled so that
+    /// higher is better: good sleep, little soreness, high motivation and an
+    /// easy last session.
+    pub fn score(&self) -> f64 {
+        let mut answers = vec![
+            (f64::from(self.sleep) - 1.0) / 4.0,
+            (5.0 - f64::from(self.soreness)) / 4.0,
+            (f64::from(self.motivation) - 1.0) / 4.0,
+        ];
+        if let Some(rpe) = self.rpe {
+            answers.push((10.0 - f64::from(rpe)) / 9.0);
+        }
+        100.0 * answers.iter().sum::<f64>() / answers.len() as f64
+    }
+}
+
+impl Readiness {
+    /// The readiness of a rolling wellness score: low below 40, high from 75.
+    pub fn of_score(score: f64) -> Readiness {
+        if score < LOW_SCORE {
+            Readiness::Low
+        } else if score >= HIGH_SCORE {
+            Readiness::High
+        } else {
+            Readiness::Normal
+        }
+    }
+
+    /// The readiness to train with, the one of the rolling wellness score in
+    /// auto mode, normal when nothing was checked in lately.
+    pub fn resolve(self, check_ins: &[CheckIn], now: DateTime<Utc>) -> Readiness {
+        match self {
+            Readiness::Auto => rolling_score(check_ins, now).map_or(Readiness::Normal, |score| {
+                info!("Rolling wellness score: {:.0}/100", score);
+                Readiness::of_score(score)
+            }),
+            readiness => readiness,
+        }
+    }
+
+    /// Rounds of a group at this readiness, one less when low, never below
+    /// one, and one more when high.
+    pub fn rounds(&self, rounds: u32) -> u32 {
+        match self {
+            Readiness::Low => rounds.saturating_sub(1).max(1),
+            Readiness::High => rounds + 1,
+            Readiness::Normal | Readiness::Auto => rounds,
+        }
+    }
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn check_in(days_ago: i64, answers: (u8, u8, u8), rpe: Option<u8>) -> CheckIn {
+        CheckIn {
+            checked_in_at: Utc::now() - Duration::days(days_ago),
+            sleep: answers.0,
+            soreness: answers.1,
+            motivation: answers.2,
+            rpe,
+        }
+    }
+
+    #[test]
+    fn test_readiness_from_rolling_score() {
+        assert_eq!(check_in(0, (5, 1, 5), Some(1)).score(), 100.0);
+        assert_eq!(check_in(0, (1, 5, 1), None).score(), 0.0);
+        assert_eq!(check_in(0, (3, 3, 3), None).score(), 50.0);
+
+        let now = Utc::now();
+        assert_eq!(Readiness::Auto.resolve(&[], now), Readiness::Normal);
+        let check_ins = [
+            check_in(10, (5, 1, 5), None),
+            check_in(2, (2, 4, 2), Some(9)),
+            check_in(0, (2, 4, 3), None),
+        ];
+        // The check-in of 10 days ago is left out of the rolling score
+        assert!(rolling_score(&check_ins, now).unwrap() < LOW_SCORE);
+        assert_eq!(Readiness::Auto.resolve(&check_ins, now), Readiness::Low);
+        assert_eq!(Readiness::High.resolve(&check_ins, now), Readiness::High);
+        assert_eq!(Readiness::of_score(80.0), Readiness::High);
+
+        assert_eq!(Readiness::Low.rounds(3), 2);
+        assert_eq!(Readiness::Low.rounds(1), 1);
+        assert_eq!(Readiness::High.rounds(3), 4);
+        assert_eq!(Readiness::Normal.rounds(3), 3);
+    }
+}
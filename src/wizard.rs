@@ -0,0 +1,354 @@
+use crate::backup;
+use crate::equipment::{self, Equipment};
+use crate::media;
+use crate::rounds::Focus;
+use crate::{ExerciseLevel, BACKUP_LIBRARY_DIR, SNOOZED_FILE};
+use anyhow::{Context, Result};
+use chrono::Weekday;
+use clap::ValueEnum;
+use log::info;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+// Exercise types of the sessions of the split, given to the training days in
+// turn. A single training day trains everything
+const SESSIONS: [&[&str]; 4] = [
+    &["push", "core"],
+    &["pull", "legs"],
+    &["push", "pull"],
+    &["legs", "core"],
+];
+const FULL_BODY: &[&str] = &["push", "pull", "legs", "core"];
+
+// --------------------------------------------------
+
+/// What the first run asks about, to write the config and set up the library.
+#[derive(Debug, PartialEq)]
+pub struct Answers {
+    pub level: ExerciseLevel,
+    pub equipment: Vec<Equipment>,
+    /// Training days, Monday first
+    pub days: Vec<Weekday>,
+    pub focus: Focus,
+    pub starter: Starter,
+}
+
+// Enum for where the exercise library of a first run comes from: the library
+// bundled with wodgen, an archive of `wodgen backup` downloaded from a URL, or
+// nowhere for now
+#[derive(Debug, PartialEq)]
+pub enum Starter {
+    Bundled,
+    Download(String),
+    Skip,
+}
+
+// --------------------------------------------------
+
+// Ask until the answer parses, an empty line or the end of the input taking
+// the default
+fn ask<R: BufRead, W: Write, T>(
+    input: &mut R,
+    output: &mut W,
+    prompt: &str,
+    default: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<T> {
+    loop {
+        write!(output, "{} ({}): ", prompt, default)?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            writeln!(output)?;
+        }
+        let answer = match line.trim() {
+            "" => default,
+            answer => answer,
+        };
+        match parse(answer) {
+            Ok(value) => return Ok(value),
+            Err(error) => writeln!(output, "{}", error)?,
+        }
+    }
+}
+
+// The name a value of an enum is given on the command line, e.g. `beginner`
+fn value_name<T: ValueEnum>(value: &T) -> String {
+    value
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+fn parse_value<T: ValueEnum>(answer: &str) -> Result<T, String> {
+    T::from_str(answer, true).map_err(|_| {
+        let values: Vec<String> = T::value_variants().iter().map(value_name).collect();
+        format!("Please answer {}", values.join(", "))
+    })
+}
+
+fn parse_equipment(answer: &str) -> Result<Vec<Equipment>, String> {
+    if answer.eq_ignore_ascii_case("none") {
+        return Ok(Vec::new());
+    }
+    answer
+        .split(',')
+        .filter(|item| !item.trim().is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+fn parse_days(answer: &str) -> Result<Vec<Weekday>, String> {
+    let mut days = answer
+        .split([' ', ','])
+        .filter(|day| !day.is_empty())
+        .map(|day| {
+            day.parse::<Weekday>()
+                .map_err(|_| format!("Invalid day {:?}, e.g. mon wed fri", day))
+        })
+        .collect::<Result<Vec<Weekday>, String>>()?;
+    days.sort_by_key(Weekday::num_days_from_monday);
+    days.dedup();
+    if days.is_empty() {
+        return Err(String::from(
+            "Please give at least one day, e.g. mon wed fri",
+        ));
+    }
+    Ok(days)
+}
+
+fn parse_starter(answer: &str, bundled: bool) -> Result<Starter, String> {
+    match answer.to_lowercase().as_str() {
+        "c" | "copy" if bundled => Ok(Starter::Bundled),
+        "s" | "skip" => Ok(Starter::Skip),
+        url if url.starts_with("http://") || url.starts_with("https://") => {
+            Ok(Starter::Download(answer.to_string()))
+        }
+        _ if bundled => Err(String::from("Please answer copy, skip or a URL")),
+        _ => Err(String::from("Please answer skip or a URL")),
+    }
+}
+
+/// Asks about the level, the equipment, the training days and the goal, and
+/// where the exercise library comes from, `bundled` telling whether wodgen
+/// came with one.
+///
+/// # Errors
+///
+/// This function will return an error if the input cannot be read or the
+/// output written.
+pub fn interview<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    bundled: bool,
+) -> Result<Answers> {
+    writeln!(
+        output,
+        "Welcome to wodgen! A few questions to set up your training, press enter for the default."
+    )?;
+    let level = ask(
+        input,
+        output,
+        "Your level: beginner, intermediate or advanced",
+        "beginner",
+        parse_value::<ExerciseLevel>,
+    )?;
+    let equipment = ask(
+        input,
+        output,
+        "Equipment at hand, separated by commas, e.g. rings, 20kg dumbbells x2",
+        "none",
+        parse_equipment,
+    )?;
+    let days = ask(
+        input,
+        output,
+        "Training days, e.g. mon wed fri",
+        "mon wed fri",
+        parse_days,
+    )?;
+    let focus = ask(
+        input,
+        output,
+        "Your goal: strength, hypertrophy or endurance",
+        "hypertrophy",
+        parse_value::<Focus>,
+    )?;
+    let starter = if bundled {
+        ask(
+            input,
+            output,
+            "Exercise library: copy the starter set, skip, or the URL of a wodgen backup",
+            "copy",
+            |answer| parse_starter(answer, true),
+        )?
+    } else {
+        ask(
+            input,
+            output,
+            "Exercise library: the URL of a wodgen backup holding one, or skip",
+            "skip",
+            |answer| parse_starter(answer, false),
+        )?
+    };
+    Ok(Answers {
+        level,
+        equipment,
+        days,
+        focus,
+        starter,
+    })
+}
+
+// --------------------------------------------------
+
+impl Answers {
+    /// The exercise types of each training day.
+    pub fn sessions(&self) -> Vec<(Weekday, &'static [&'static str])> {
+        match self.days.as_slice() {
+            [day] => vec![(*day, FULL_BODY)],
+            days => days
+                .iter()
+                .zip(SESSIONS.iter().cycle())
+                .map(|(day, types)| (*day, *types))
+                .collect(),
+        }
+    }
+
+    /// The user config of the answers, a split of the training days at the
+    /// level and with the goal given.
+    pub fn render_config(&self) -> String {
+        let mut config =
+            String::from("# Written by wodgen setup, see the README for the other settings\n");
+        for (day, types) in self.sessions() {
+            let types: Vec<String> = types.iter().map(|t| format!("{:?}", t)).collect();
+            config.push_str(&format!(
+                "\n[split.{}]\ntypes = [{}]\nfocus = \"{}\"\nlevel = \"{}\"\n",
+                format!("{:?}", day).to_lowercase(),
+                types.join(", "),
+                value_name(&self.focus),
+                value_name(&self.level)
+            ));
+        }
+        config
+    }
+}
+
+// Copy the files of the bundled library, leaving out its snoozes
+fn copy_library(from: &Path, to: &Path) -> Result<usize> {
+    fs::create_dir_all(to)?;
+    let mut copied = 0;
+    for entry in fs::read_dir(from).with_context(|| format!("Failed to read {:?}", from))? {
+        let path = entry?.path();
+        if !path.is_file() || path.file_name().is_some_and(|name| name == SNOOZED_FILE) {
+            continue;
+        }
+        if let Some(name) = path.file_name() {
+            fs::copy(&path, to.join(name))
+                .with_context(|| format!("Failed to copy {:?} to {:?}", path, to))?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+/// Writes the user config and the equipment registry of the answers, and sets
+/// up the exercise library from the starter set when it does not exist yet.
+///
+/// # Errors
+///
+/// This function will return an error if a file cannot be written, or the
+/// starter set cannot be copied or downloaded.
+pub fn scaffold(
+    answers: &Answers,
+    config_file: &Path,
+    exercise_library_dir: &Path,
+    bundled: Option<&Path>,
+) -> Result<()> {
+    if let Some(config_dir) = config_file.parent() {
+        fs::create_dir_all(config_dir)?;
+    }
+    fs::write(config_file, answers.render_config())
+        .with_context(|| format!("Failed to write file: {:?}", config_file))?;
+    info!("Saved the user config to {:?}", config_file);
+    if !answers.equipment.is_empty() {
+        equipment::add_equipment(
+            &equipment::registry_file(config_file),
+            answers.equipment.clone(),
+        )?;
+    }
+
+    if exercise_library_dir.is_dir() {
+        info!(
+            "Keeping the exercise library already in {:?}",
+            exercise_library_dir
+        );
+        return Ok(());
+    }
+    match (&answers.starter, bundled) {
+        (Starter::Bundled, Some(bundled)) => {
+            let copied = copy_library(bundled, exercise_library_dir)?;
+            info!(
+                "Copied the {} files of the starter library to {:?}",
+                copied, exercise_library_dir
+            );
+        }
+        (Starter::Download(url), _) => {
+            fs::create_dir_all(exercise_library_dir)?;
+            let archive = exercise_library_dir.join("starter.tar.gz");
+            media::download(url, &archive)?;
+            let restored = backup::restore(&archive, &[(BACKUP_LIBRARY_DIR, exercise_library_dir)]);
+            fs::remove_file(&archive)?;
+            info!(
+                "Downloaded the {} files of the starter library to {:?}",
+                restored?, exercise_library_dir
+            );
+        }
+        _ => info!(
+            "No exercise library set up, copy one to {:?}",
+            exercise_library_dir
+        ),
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interview() {
+        let mut input = "expert\nadvanced\nrings, 20kg dumbbells x2\nmon, thu mon\n\n".as_bytes();
+        let mut output = Vec::new();
+        let answers = interview(&mut input, &mut output, true).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Please answer beginner, intermediate, advanced\n"));
+        assert_eq!(answers.level, ExerciseLevel::Advanced);
+        assert_eq!(answers.equipment.len(), 2);
+        assert_eq!(answers.days, [Weekday::Mon, Weekday::Thu]);
+        // The end of the input takes the defaults
+        assert_eq!(answers.focus, Focus::Hypertrophy);
+        assert_eq!(answers.starter, Starter::Bundled);
+
+        assert_eq!(
+            answers.render_config(),
+            "# Written by wodgen setup, see the README for the other settings\n\
+             \n[split.mon]\ntypes = [\"push\", \"core\"]\nfocus = \"hypertrophy\"\nlevel = \"advanced\"\n\
+             \n[split.thu]\ntypes = [\"pull\", \"legs\"]\nfocus = \"hypertrophy\"\nlevel = \"advanced\"\n"
+        );
+        let config: crate::config::UserConfig = toml::from_str(&answers.render_config()).unwrap();
+        assert_eq!(
+            config.split.day(Weekday::Thu).unwrap().level,
+            Some(ExerciseLevel::Advanced)
+        );
+
+        let mut input = "".as_bytes();
+        let answers = interview(&mut input, &mut Vec::new(), false).unwrap();
+        assert_eq!(answers.days, [Weekday::Mon, Weekday::Wed, Weekday::Fri]);
+        assert_eq!(answers.starter, Starter::Skip);
+        assert_eq!(answers.sessions()[2].1, ["push", "pull"]);
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use assert_cmd::Command;
 use predicates::prelude::*;
+use std::path::{Path, PathBuf};
 
 const PRG: &str = "wodgen";
 
@@ -31,7 +32,7 @@ fn usage() -> Result<()> {
 fn valid_type() -> Result<()> {
     for bad_type_arg in &["puush", "pul", "lgs", "sore"] {
         Command::cargo_bin(PRG)?
-            .args(&["-t", bad_type_arg])
+            .args(["-t", bad_type_arg])
             .assert()
             .failure()
             .stderr(predicate::str::contains(
@@ -46,7 +47,7 @@ fn valid_type() -> Result<()> {
 fn valid_level() -> Result<()> {
     for bad_level_arg in &["beginer", "intermdiate", "advand"] {
         Command::cargo_bin(PRG)?
-            .args(&["-l", bad_level_arg])
+            .args(["-l", bad_level_arg])
             .assert()
             .failure()
             .stderr(predicate::str::contains(
@@ -55,3 +56,3277 @@ fn valid_level() -> Result<()> {
     }
     Ok(())
 }
+
+// --------------------------------------------------
+// Copy the bundled exercise library into a scratch directory
+fn copy_library(dir: &Path) -> Result<PathBuf> {
+    let library_dir = dir.join("exercise_library");
+    std::fs::create_dir(&library_dir)?;
+    for entry in std::fs::read_dir("exercise_library")? {
+        let path = entry?.path();
+        std::fs::copy(&path, library_dir.join(path.file_name().unwrap()))?;
+    }
+    Ok(library_dir)
+}
+
+// Add a column to a library CSV file, with values computed from each row
+fn add_column(file: &Path, column: &str, value: fn(&str) -> &'static str) -> Result<()> {
+    let content = std::fs::read_to_string(file)?;
+    let lines: Vec<String> = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| format!("{},{}", line, if i == 0 { column } else { value(line) }))
+        .collect();
+    std::fs::write(file, lines.join("\n"))?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn generates_workout_in_given_dirs() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "core", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            r"\nWorkout \S+: push, core, \d+ exercises, ~\d+ min, saved to .+workouts.+\.csv, \d+ exercises snoozed\n$",
+        )?);
+
+    assert_eq!(std::fs::read_dir(&workouts_dir)?.count(), 1);
+
+    // The summary goes away with --quiet, the workout is saved all the same
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--quiet", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(tmp.path().join("quiet"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("exercises snoozed").not());
+    assert_eq!(std::fs::read_dir(tmp.path().join("quiet"))?.count(), 1);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn read_only_prints_the_workout_and_writes_nothing() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let snoozed = std::fs::read_to_string(library_dir.join("snoozed.csv"))?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "core", "--read-only", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("# Workout "))
+        .stdout(predicate::str::contains("not saved, 0 exercises snoozed"));
+
+    assert!(!workouts_dir.exists());
+    assert!(!library_dir.join("runs").exists());
+    assert_eq!(
+        std::fs::read_to_string(library_dir.join("snoozed.csv"))?,
+        snoozed
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn names_the_sessions_in_the_headers_and_file_names() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let config = tmp.path().join("config.toml");
+    std::fs::write(&config, "[naming]\nnouns = [\"Smoker\"]\n")?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--named-files", "--print", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            r"# Workout \S+ [A-Z][a-z]+day \w+ Smoker\n",
+        )?)
+        .stdout(predicate::str::is_match(
+            r"Workout \S+ \w+ \w+ Smoker: push,",
+        )?);
+
+    let files: Vec<String> = std::fs::read_dir(&workouts_dir)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+        .collect::<Result<_>>()?;
+    assert_eq!(files.len(), 1);
+    assert!(
+        predicate::str::is_match(r"^\d{4}_\d{2}_\d{2}_[a-z]+day_[a-z]+_smoker\.csv$")?
+            .eval(&files[0]),
+        "{}",
+        files[0]
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn compact_prints_one_line_with_the_workout_uri() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    let output = Command::cargo_bin(PRG)?
+        .args(["-t", "push", "core", "--compact", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(stdout.lines().count(), 1, "{}", stdout);
+    let (text, uri) = stdout.trim_end().split_once('\t').unwrap();
+    assert!(predicate::str::is_match(r"^Workout \S+: A1 .+, B1 .+, C1 ")?.eval(text));
+    assert!(uri.starts_with("wodgen://workout?v=1&title="));
+
+    // The URI imports like a share code
+    let imported_dir = tmp.path().join("imported");
+    Command::cargo_bin(PRG)?
+        .args(["import-share", uri, "-w"])
+        .arg(&imported_dir)
+        .assert()
+        .success();
+    assert_eq!(std::fs::read_dir(&imported_dir)?.count(), 1);
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--compact", "--json"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reads_the_constraints_of_the_run_from_a_file() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let constraints = tmp.path().join("constraints.toml");
+    std::fs::write(
+        &constraints,
+        "types = [\"pull\"]\nduration = 20\nlevel = \"beginner\"\nequipment = [\"pull-up bar\"]\n",
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args(["--print", "--constraints"])
+        .arg(&constraints)
+        .arg("-e")
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| B1 |"))
+        .stdout(predicate::str::contains("| D1 |").not())
+        .stdout(predicate::str::is_match(r"Workout \S+: pull, ")?);
+
+    // The groups set win over those of the duration, which stays the budget
+    std::fs::write(&constraints, "groups = 2\nduration = 60\n")?;
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--read-only", "--constraints"])
+        .arg(&constraints)
+        .arg("-e")
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("of the 60 min budget"))
+        .stdout(predicate::str::contains("| C1 |"))
+        .stdout(predicate::str::contains("| E1 |").not());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reads_options_from_env() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .env("WODGEN_TYPES", "pull,legs")
+        .env("WODGEN_LEVEL", "beginner")
+        .env("WODGEN_LIBRARY_DIR", &library_dir)
+        .env("WODGEN_WORKOUTS_DIR", &workouts_dir)
+        .assert()
+        .success();
+
+    assert_eq!(std::fs::read_dir(&workouts_dir)?.count(), 1);
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push"])
+        .env("WODGEN_LEVEL", "expert")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "[possible values: beginner, intermediate, advanced]",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn writes_workout_in_requested_language() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--lang", "de", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let content = std::fs::read_to_string(workout)?;
+    assert!(content.starts_with(
+        "# wodgen workout 3\ngruppe,name,sätze,distanz,zeit,wiederholungen,ziel,bild,video"
+    ));
+    assert!(content.contains("Technikblock"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn translates_exercise_names_from_the_names_file() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    std::fs::write(
+        library_dir.join("names.de.csv"),
+        "name,display_name\nplank_front,Unterarmstütz\n",
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "core", "--pin", "plank_front", "--lang", "de"])
+        .args(["-f", "csv,markdown", "--print", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| Unterarmstütz |"));
+
+    // The history keeps the untranslated names
+    for entry in std::fs::read_dir(&workouts_dir)? {
+        let path = entry?.path();
+        let content = std::fs::read_to_string(&path)?;
+        if path.extension().unwrap() == "csv" {
+            assert!(content.contains(",Plank Front,"));
+            assert!(!content.contains("Unterarmstütz"));
+        } else {
+            assert!(content.contains("| Unterarmstütz |"));
+        }
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn saves_workout_in_every_format() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "legs", "-f", "csv,markdown,html,pdf", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    let mut extensions: Vec<String> = std::fs::read_dir(&workouts_dir)?
+        .map(|entry| Ok(entry?.path().extension().unwrap().to_string_lossy().into()))
+        .collect::<Result<_>>()?;
+    extensions.sort();
+    assert_eq!(extensions, ["csv", "html", "md", "pdf"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn needs_a_calendar_to_save_the_session_to() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "legs", "--caldav", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(tmp.path().join("workouts"))
+        .env("WODGEN_CONFIG", tmp.path().join("config.toml"))
+        .env_remove("WODGEN_CALDAV_URL")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No CalDAV calendar"));
+
+    // Nothing is saved to the calendar on a read-only run
+    Command::cargo_bin(PRG)?
+        .args(["-t", "legs", "--caldav", "--read-only", "-e"])
+        .arg(&library_dir)
+        .env("WODGEN_CONFIG", tmp.path().join("config.toml"))
+        .env_remove("WODGEN_CALDAV_URL")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "the session is not saved to the calendar",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn writes_obsidian_notes_to_the_vault() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let vault = tmp.path().join("vault").join("Training");
+    let config = tmp.path().join("config.toml");
+    std::fs::write(
+        &config,
+        format!("[obsidian]\nvault = {:?}\ntags = [\"training\"]\n", vault),
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "legs", "-f", "csv,obsidian", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .env("WODGEN_CONFIG", &config)
+        .assert()
+        .success();
+
+    assert_eq!(std::fs::read_dir(&workouts_dir)?.count(), 1);
+    let note = std::fs::read_dir(&vault)?.next().unwrap()?.path();
+    assert_eq!(note.extension().unwrap(), "md");
+    let note = std::fs::read_to_string(note)?;
+    assert!(predicate::str::is_match(
+        r#"^---\ndate: \d{4}-\d{2}-\d{2}\ntypes:\n  - "legs"\ntags:\n  - "training"\nduration: \d+\n---\n"#
+    )?
+    .eval(&note));
+    assert!(predicate::str::is_match(r"\| B1 \| \[\[[^\]]+\]\] \|")?.eval(&note));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exports_a_bundle_of_the_workout_of_a_day() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let workouts_dir = tmp.path().join("workouts");
+    std::fs::create_dir(&workouts_dir)?;
+    let image = tmp.path().join("squat.png");
+    std::fs::write(&image, "png")?;
+    std::fs::write(
+        workouts_dir.join("2024_06_01.csv"),
+        format!(
+            "group,name,sets,distance,time,reps,goal,image,video,scaling,notes\n\
+             2,Squat,3,,,X,10,{},,,\n",
+            image.display()
+        ),
+    )?;
+    let bundle = tmp.path().join("bundle.zip");
+
+    Command::cargo_bin(PRG)?
+        .args(["export", "bundle", "2024-06-01", "-w"])
+        .arg(&workouts_dir)
+        .arg("--media-dir")
+        .arg(tmp.path().join("media"))
+        .arg("-o")
+        .arg(&bundle)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Bundled workout 2024_06_01 into"))
+        .stdout(predicate::str::contains("1 exercises, 5 files"));
+    let content = std::fs::read(&bundle)?;
+    assert!(content.starts_with(b"PK"));
+    let content = String::from_utf8_lossy(&content);
+    for file in ["2024_06_01.pdf", "media/0_squat.png", "manifest.json"] {
+        assert!(content.contains(file));
+    }
+
+    Command::cargo_bin(PRG)?
+        .args(["export", "bundle", "2024-06-02", "-w"])
+        .arg(&workouts_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No workout saved for 2024-06-02"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exports_the_intervals_of_a_workout() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let workouts_dir = tmp.path().join("workouts");
+    std::fs::create_dir(&workouts_dir)?;
+    std::fs::write(
+        workouts_dir.join("2024_06_01.csv"),
+        "group,name,sets,distance,time,reps,goal,image,video,scaling,notes\n\
+         2,Row Erg,4,,X,,45s,,,,\n",
+    )?;
+    std::fs::write(
+        workouts_dir.join("2024_06_02.csv"),
+        "group,name,sets,distance,time,reps,goal,image,video,scaling,notes\n\
+         2,Squat,3,,,X,10,,,,\n",
+    )?;
+    let output = tmp.path().join("intervals.zwo");
+
+    Command::cargo_bin(PRG)?
+        .args(["export", "intervals", "2024-06-01", "--rest", "15", "-w"])
+        .arg(&workouts_dir)
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Exported the intervals of 2024_06_01 (B) to",
+        ));
+    assert!(std::fs::read_to_string(&output)?.contains(
+        r#"<IntervalsT Repeat="4" OnDuration="45" OffDuration="15" OnPower="0.90" OffPower="0.50"/>"#
+    ));
+
+    Command::cargo_bin(PRG)?
+        .args(["export", "intervals", "2024-06-02", "-w"])
+        .arg(&workouts_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No interval block in workout"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn media_fetch_skips_local_media() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let media_dir = tmp.path().join("media");
+
+    Command::cargo_bin(PRG)?
+        .args(["media", "fetch", "-e"])
+        .arg(&library_dir)
+        .arg("--media-dir")
+        .arg(&media_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Downloaded 0 media files"));
+
+    assert_eq!(std::fs::read_dir(&media_dir)?.count(), 0);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn share_and_import_round_trip() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let partner_dir = tmp.path().join("partner");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "pull", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    let output = Command::cargo_bin(PRG)?
+        .args(["share", "-w"])
+        .arg(&workouts_dir)
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let code = stdout.lines().find(|l| l.starts_with("wod1.")).unwrap();
+
+    Command::cargo_bin(PRG)?
+        .args(["import-share", code, "-w"])
+        .arg(&partner_dir)
+        .assert()
+        .success();
+
+    let original = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let imported = std::fs::read_dir(&partner_dir)?.next().unwrap()?.path();
+    assert!(imported.to_string_lossy().ends_with("_shared.csv"));
+    assert_eq!(
+        std::fs::read_to_string(original)?,
+        std::fs::read_to_string(imported)?
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn generates_one_workout_per_athlete() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let roster = tmp.path().join("roster.toml");
+    std::fs::write(
+        &roster,
+        r#"
+        [[athletes]]
+        name = "Alice"
+        level = "beginner"
+        types = ["push", "core"]
+
+        [[athletes]]
+        name = "Bob"
+        exclude = ["squat"]
+        "#,
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args(["generate", "-t", "legs", "--roster"])
+        .arg(&roster)
+        .arg("-e")
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    for athlete in ["alice", "bob"] {
+        assert_eq!(std::fs::read_dir(workouts_dir.join(athlete))?.count(), 1);
+        assert!(library_dir
+            .join(format!("snoozed_{}.csv", athlete))
+            .exists());
+    }
+    let bob = std::fs::read_dir(workouts_dir.join("bob"))?
+        .next()
+        .unwrap()?
+        .path();
+    assert!(!std::fs::read_to_string(bob)?.contains(",Squat,"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn class_workout_lists_scaling_options() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "pull", "--class", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let content = std::fs::read_to_string(workout)?;
+    assert!(content
+        .lines()
+        .nth(1)
+        .unwrap()
+        .ends_with(",scaling,notes,programming,kind,interval"));
+    assert!(content.contains("Beginner: "));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn substitute_lists_bodyweight_alternatives() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+
+    Command::cargo_bin(PRG)?
+        .args([
+            "substitute",
+            "Dip - Regular",
+            "--reason",
+            "no-equipment",
+            "-e",
+        ])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1. "))
+        .stdout(predicate::str::contains("Dip - Regular (").not());
+
+    Command::cargo_bin(PRG)?
+        .args(["substitute", "no such exercise", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not found"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn shows_exercises_under_their_display_names() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    add_column(&library_dir.join("core.csv"), "display_name", |line| {
+        if line.starts_with("plank_front,") {
+            "RKC Plank"
+        } else {
+            ""
+        }
+    })?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "core", "--pin", "plank_front", "--read-only", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| RKC Plank |"))
+        .stdout(predicate::str::contains("Plank Front").not());
+
+    // The display name finds the exercise too
+    Command::cargo_bin(PRG)?
+        .args(["substitute", "rkc plank", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1. "));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn renders_goal_variables_from_history() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    std::fs::create_dir(&workouts_dir)?;
+    std::fs::write(
+        library_dir.join("legs.csv"),
+        "name,exercise_type,exercise_category,exercise_level,exercise_programming,bodyweight,goal,video\n\
+         squat,Legs,Primary,Beginner,Reps,true,beat {pr_reps},squat.mp4\n",
+    )?;
+    std::fs::write(
+        workouts_dir.join("2024_06_01.csv"),
+        "group,name,sets,distance,time,reps,goal,image,video,scaling\n\
+         2,Squat,,,,20/18,,,squat.mp4,\n",
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "legs", "-g", "1", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    let workout = std::fs::read_dir(&workouts_dir)?
+        .map(|entry| entry.unwrap().path())
+        .find(|path| !path.ends_with("2024_06_01.csv"))
+        .unwrap();
+    // With a hint at beating the last performance
+    assert!(std::fs::read_to_string(workout)?.contains(",beat 20 (+1 rep vs last time),"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn annotates_saved_workout() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--lang", "de", "--note", "Ruhig atmen", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let content = std::fs::read_to_string(&workout)?;
+    assert!(content.contains("\n,Notiz,,,,,,,,,Ruhig atmen,,exercise,\n"));
+
+    Command::cargo_bin(PRG)?
+        .args(["annotate"])
+        .arg(&workout)
+        .args(["skill block", "felt strong"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not found in workout"));
+
+    Command::cargo_bin(PRG)?
+        .args(["annotate"])
+        .arg(&workout)
+        .args(["technikblock", "felt strong"])
+        .assert()
+        .success();
+
+    let content = std::fs::read_to_string(&workout)?;
+    assert!(content.starts_with("# wodgen workout 3\ngruppe,"));
+    assert!(content.contains("A1,Technikblock,,,,,,,,,felt strong,,exercise,\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn picks_the_categories_of_the_strategy() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    std::fs::write(
+        library_dir.join("pull.csv"),
+        "name,exercise_type,exercise_category,exercise_level,exercise_programming,bodyweight,goal,video\n\
+         main_row,Pull,Primary,Intermediate,Reps,true,,row.mp4\n\
+         ring_row,Pull,Secondary,Intermediate,Reps,true,,ring.mp4\n\
+         towel_row,Pull,Secondary,Intermediate,Reps,true,,towel.mp4\n",
+    )?;
+    let template = tmp.path().join("pyramid.toml");
+    std::fs::write(
+        &template,
+        "category_strategy = \"pyramid\"\n\n[[blocks]]\ntypes = [\"pull\"]\ngroups = 3\n",
+    )?;
+
+    // Up to the main lift in the middle group and back down
+    Command::cargo_bin(PRG)?
+        .args(["--read-only", "--template"])
+        .arg(&template)
+        .arg("-e")
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Category strategy: Pyramid"))
+        .stdout(predicate::str::is_match(r"\| B1 \| (Ring|Towel) Row \|")?)
+        .stdout(predicate::str::contains("| C1 | Main Row |"))
+        .stdout(predicate::str::is_match(r"\| D1 \| (Ring|Towel) Row \|")?);
+
+    // The option wins over the template
+    Command::cargo_bin(PRG)?
+        .args([
+            "--read-only",
+            "--category-strategy",
+            "standard",
+            "--template",
+        ])
+        .arg(&template)
+        .arg("-e")
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| B1 | Main Row |"));
+
+    Command::cargo_bin(PRG)?
+        .args([
+            "-t",
+            "pull",
+            "--read-only",
+            "--category-strategy",
+            "accessory-only",
+            "-e",
+        ])
+        .arg(&library_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Need 2 Pull exercises at Accessory level, only 0 available",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn trains_the_calibrated_types_at_their_levels() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    std::fs::write(
+        library_dir.join("pull.csv"),
+        "name,exercise_type,exercise_category,exercise_level,exercise_programming,bodyweight,goal,video\n\
+         muscle_up,Pull,Primary,Advanced,Reps,true,,muscle.mp4\n\
+         band_row,Pull,Secondary,Beginner,Reps,true,,band.mp4\n",
+    )?;
+    let logged = tmp.path().join("logged.csv");
+    std::fs::write(&logged, "test,value\npush_ups,20\n")?;
+
+    Command::cargo_bin(PRG)?
+        .args(["calibrate", "--pull-ups", "14", "--import"])
+        .arg(&logged)
+        .arg("-e")
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pull: Advanced (14 pull-ups, on "))
+        .stdout(predicate::str::contains(
+            "Push: Intermediate (20 push-ups, on ",
+        ));
+    assert!(library_dir.join("calibration.csv").is_file());
+
+    // Pull is trained at the calibrated level over --level
+    let generate = |calibrated: bool| -> Result<assert_cmd::assert::Assert> {
+        let mut cmd = Command::cargo_bin(PRG)?;
+        cmd.args([
+            "-t",
+            "pull",
+            "-g",
+            "1",
+            "-l",
+            "beginner",
+            "--read-only",
+            "-e",
+        ])
+        .arg(&library_dir);
+        if calibrated {
+            cmd.arg("--calibrated");
+        }
+        Ok(cmd.assert().success())
+    };
+    generate(false)?.stdout(predicate::str::contains("| B1 | Band Row |"));
+    generate(true)?
+        .stdout(predicate::str::contains(
+            "Calibrated level of Pull: Advanced",
+        ))
+        .stdout(predicate::str::contains("| B1 | Muscle Up |"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn previews_the_new_and_repeated_exercises() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    std::fs::write(
+        library_dir.join("pull.csv"),
+        "name,exercise_type,exercise_category,exercise_level,exercise_programming,bodyweight,goal,video\n\
+         main_row,Pull,Primary,Intermediate,Reps,true,,row.mp4\n\
+         ring_row,Pull,Secondary,Intermediate,Reps,true,,ring.mp4\n",
+    )?;
+    let generate = |snoozed_file: &str| -> Result<assert_cmd::assert::Assert> {
+        Ok(Command::cargo_bin(PRG)?
+            .args(["-t", "pull", "-g", "1", "--preview-diff", "--snoozed-file"])
+            .arg(tmp.path().join(snoozed_file))
+            .arg("-e")
+            .arg(&library_dir)
+            .arg("-w")
+            .arg(&workouts_dir)
+            .assert()
+            .success())
+    };
+
+    generate("snoozed.csv")?.stdout(predicate::str::contains(
+        "No earlier Pull session, every exercise is new",
+    ));
+    // Unsnoozed, the same exercise is picked again
+    generate("other.csv")?
+        .stdout(predicate::str::is_match(
+            r"Compared to the last Pull session, on \d{4}-\d{2}-\d{2}:\n= Main Row \(group B\)\n0 new, 1 repeated\n",
+        )?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn simulates_runs_without_saving_anything() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    std::fs::write(
+        library_dir.join("pull.csv"),
+        "name,exercise_type,exercise_category,exercise_level,exercise_programming,bodyweight,goal,video\n\
+         main_row,Pull,Primary,Intermediate,Reps,true,,row.mp4\n\
+         ring_row,Pull,Accessory,Intermediate,Reps,true,,ring.mp4\n",
+    )?;
+    let snoozed = std::fs::read_to_string(library_dir.join("snoozed.csv"))?;
+    let simulate = |groups: &str| -> Result<assert_cmd::assert::Assert> {
+        Ok(Command::cargo_bin(PRG)?
+            .args(["simulate", "--runs", "5", "-t", "pull", "-g", groups, "-e"])
+            .arg(&library_dir)
+            .arg("-w")
+            .arg(&workouts_dir)
+            .assert()
+            .success())
+    };
+
+    simulate("1")?
+        .stdout(predicate::str::contains("5 runs, 5 generated, 0 failed"))
+        .stdout(predicate::str::contains("Average variety: 0.00"))
+        .stdout(predicate::str::contains("  Main Row: 5 runs (100%)"))
+        .stdout(predicate::str::contains("Never picked: Ring Row"))
+        .stdout(predicate::str::contains("| B1 |").not());
+    simulate("3")?
+        .stdout(predicate::str::contains("5 runs, 0 generated, 5 failed"))
+        .stdout(predicate::str::contains(
+            "  Need 1 Pull exercise at Primary level and 1 Pull exercise at Primary or Secondary level",
+        ));
+    assert!(!workouts_dir.exists());
+
+    // The seeds of the failed runs are saved for the fuzz tests
+    let seeds = tmp.path().join("fuzz_seeds.txt");
+    Command::cargo_bin(PRG)?
+        .args(["simulate", "--runs", "3", "--save-failing"])
+        .arg(&seeds)
+        .args(["-t", "pull", "-g", "3", "--seed", "40", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success();
+    let saved = std::fs::read_to_string(&seeds)?;
+    let seeds: Vec<&str> = saved
+        .lines()
+        .map(|l| l.split(' ').next().unwrap())
+        .collect();
+    assert_eq!(seeds, ["40", "41", "42"]);
+    assert!(saved.starts_with("40 -t pull -g 3 --seed 40 -e "));
+    assert_eq!(
+        std::fs::read_to_string(library_dir.join("snoozed.csv"))?,
+        snoozed
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn template_blocks_depend_on_selected_tags() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    std::fs::write(
+        library_dir.join("pull.csv"),
+        "name,exercise_type,exercise_category,exercise_level,exercise_programming,bodyweight,goal,video,tags\n\
+         towel_pull_up,Pull,Primary,Intermediate,Reps,true,,towel.mp4,grip;vertical\n\
+         plate_pinch,Pull,Secondary,Intermediate,Time,true,,pinch.mp4,grip\n",
+    )?;
+
+    for (condition, has_grip_block) in [("none_tagged", false), ("any_tagged", true)] {
+        let template = tmp.path().join(format!("{}.toml", condition));
+        std::fs::write(
+            &template,
+            format!(
+                r#"
+                [[blocks]]
+                name = "strength"
+                types = ["pull"]
+
+                [[blocks]]
+                tags = ["grip"]
+                when = {{ {} = "grip", in = ["strength"] }}
+                "#,
+                condition
+            ),
+        )?;
+        let workouts_dir = tmp.path().join(condition);
+
+        Command::cargo_bin(PRG)?
+            .args(["-t", "pull", "--template"])
+            .arg(&template)
+            .arg("-e")
+            .arg(&library_dir)
+            .arg("-w")
+            .arg(&workouts_dir)
+            .arg("--snoozed-file")
+            .arg(tmp.path().join(format!("snoozed_{}.csv", condition)))
+            .assert()
+            .success();
+
+        let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+        let content = std::fs::read_to_string(workout)?;
+        assert!(content.contains("B1,Towel Pull Up,"));
+        assert_eq!(content.contains("C1,Plate Pinch,"), has_grip_block);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn keeps_best_of_several_attempts() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--attempts", "0", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .failure();
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "pull", "--attempts", "3", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Attempt 3 scored"))
+        .stdout(predicate::str::contains("Kept the workout scoring"));
+
+    assert_eq!(std::fs::read_dir(&workouts_dir)?.count(), 1);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn explains_why_exercises_were_picked() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-x", "dip__regular", "--explain", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(tmp.path().join("workouts"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Group B, Push: "))
+        .stdout(predicate::str::contains("Group C, Push: "))
+        .stdout(predicate::str::contains(
+            "Skipped, excluded (1): Dip - Regular",
+        ))
+        .stdout(predicate::str::contains("Left out before the picks ("))
+        .stdout(predicate::str::contains("  excluded (1): Dip - Regular\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn fails_fast_when_the_pool_cannot_fill_the_groups() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-g", "6", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Need 3 Push exercises at Accessory level, only 1 available, add exercises to the library, reduce --groups or pass --auto-groups",
+        ));
+    assert!(!workouts_dir.exists());
+
+    // Or drops the groups it cannot fill
+    Command::cargo_bin(PRG)?
+        .args([
+            "-t",
+            "push",
+            "-g",
+            "6",
+            "--auto-groups",
+            "--read-only",
+            "-e",
+        ])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "The pool can fill 4 of the 6 groups",
+        ))
+        .stdout(predicate::str::contains("| E1 |"))
+        .stdout(predicate::str::contains("| G1 |").not());
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-g", "9"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("9 is not in 1..=8"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn checks_the_workout_against_the_time_budget() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-g", "3", "--duration", "10", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("past the 10 min budget"))
+        .stderr(predicate::str::is_match(
+            r"\n  C: \S+ work, \S+ rest, \S+ transitions, ",
+        )?)
+        .stderr(predicate::str::contains("  Total: "));
+    assert!(!workouts_dir.exists());
+
+    // Or drops the groups past it, estimated before the picks: a seed whose
+    // picks have no goal past the reps of the estimate
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-g", "4", "--auto-groups", "--duration", "15"])
+        .args(["--read-only", "--seed", "1", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "The 15 min budget fits 2 of the 4 groups",
+        ))
+        .stdout(predicate::str::contains("| C1 |"))
+        .stdout(predicate::str::contains("| E1 |").not());
+
+    Command::cargo_bin(PRG)?
+        .args([
+            "-t",
+            "push",
+            "-g",
+            "3",
+            "--duration",
+            "10",
+            "--allow-over-budget",
+            "--read-only",
+            "-e",
+        ])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("past the 10 min budget"))
+        .stdout(predicate::str::contains("| D1 |"));
+
+    Command::cargo_bin(PRG)?
+        .args([
+            "-t",
+            "push",
+            "-g",
+            "1",
+            "--duration",
+            "90",
+            "--read-only",
+            "-e",
+        ])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Estimated time:"))
+        .stdout(predicate::str::contains("of the 90 min budget"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn never_picks_retired_exercises() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    // Left empty, the others stay active
+    add_column(&library_dir.join("push.csv"), "active", |line| {
+        if line.starts_with("dip__regular,") {
+            "false"
+        } else {
+            ""
+        }
+    })?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--read-only", "--explain", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| Dip - Regular |").not())
+        .stdout(predicate::str::contains("  retired (1): Dip - Regular\n"))
+        .stdout(predicate::str::is_match(
+            r"Skipped, retired \(1\): Dip - Regular\n",
+        )?);
+
+    Command::cargo_bin(PRG)?
+        .args(["substitute", "Dip - Paused", "-n", "100", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dip - Regular").not());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn interactive_skip_removes_and_unsnoozes_exercise() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-g", "1", "--interactive", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .write_stdin("s\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[a]ccept / [r]eroll / [s]kip"));
+
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let content = std::fs::read_to_string(workout)?;
+    assert!(!content.lines().any(|line| line.starts_with("2,")));
+    let snoozed = std::fs::read_to_string(library_dir.join("snoozed.csv"))?;
+    assert_eq!(snoozed.lines().count(), 2);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn backup_and_restore_round_trip() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let config_dir = tmp.path().join("config");
+    let backup = tmp.path().join("wodgen.tar.gz");
+    std::fs::create_dir_all(config_dir.join("scripts"))?;
+    std::fs::write(config_dir.join("config.toml"), "body_mass_kg = 70.0\n")?;
+    std::fs::write(config_dir.join("scripts").join("ranking.rhai"), "")?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "core", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    for command in ["backup", "restore"] {
+        if command == "restore" {
+            std::fs::remove_dir_all(&library_dir)?;
+            std::fs::remove_dir_all(&workouts_dir)?;
+            std::fs::remove_dir_all(&config_dir)?;
+        }
+        Command::cargo_bin(PRG)?
+            .arg(command)
+            .arg(&backup)
+            .arg("-e")
+            .arg(&library_dir)
+            .arg("-w")
+            .arg(&workouts_dir)
+            .arg("--config")
+            .arg(config_dir.join("config.toml"))
+            .assert()
+            .success();
+    }
+
+    assert!(library_dir.join("snoozed.csv").exists());
+    assert!(config_dir.join("config.toml").exists());
+    assert!(config_dir.join("scripts").join("ranking.rhai").exists());
+    assert!(library_dir.join("core.csv").exists());
+    assert_eq!(std::fs::read_dir(&workouts_dir)?.count(), 1);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn state_dir_keeps_library_untouched() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let state_dir = tmp.path().join("state");
+    let library_files = std::fs::read_dir(&library_dir)?.count();
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "pull", "-g", "3", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(tmp.path().join("workouts"))
+        .arg("--state-dir")
+        .arg(&state_dir)
+        .assert()
+        .success();
+
+    assert_eq!(std::fs::read_dir(&library_dir)?.count(), library_files);
+    let snoozed = std::fs::read_to_string(state_dir.join("snoozed.csv"))?;
+    let names: Vec<&str> = snoozed
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').next().unwrap())
+        .collect();
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(names.len(), 8);
+    assert_eq!(names, sorted);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sync_merges_state_between_machines() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let remote = tmp.path().join("remote");
+    let mut machines = Vec::new();
+    for machine in ["laptop", "phone"] {
+        let dir = tmp.path().join(machine);
+        std::fs::create_dir_all(&dir)?;
+        let library_dir = copy_library(&dir)?;
+        let workouts_dir = dir.join("workouts");
+        Command::cargo_bin(PRG)?
+            .args(["-t", "push", "-e"])
+            .arg(&library_dir)
+            .arg("-w")
+            .arg(&workouts_dir)
+            .assert()
+            .success();
+        machines.push((library_dir, workouts_dir));
+    }
+
+    // Workouts are saved per day, so give the phone's its own name
+    let (_, phone_workouts) = &machines[1];
+    let phone_workout = std::fs::read_dir(phone_workouts)?.next().unwrap()?.path();
+    std::fs::rename(&phone_workout, phone_workouts.join("phone.csv"))?;
+
+    // Sync the laptop, the phone, then the laptop again
+    for (library_dir, workouts_dir) in [&machines[0], &machines[1], &machines[0]] {
+        Command::cargo_bin(PRG)?
+            .arg("sync")
+            .arg("--remote")
+            .arg(&remote)
+            .arg("-e")
+            .arg(library_dir)
+            .arg("-w")
+            .arg(workouts_dir)
+            .assert()
+            .success();
+    }
+
+    assert!(remote.join("wodgen-state.tar.gz").exists());
+    for (library_dir, workouts_dir) in &machines {
+        assert_eq!(std::fs::read_dir(workouts_dir)?.count(), 2);
+        let snoozed = std::fs::read_to_string(library_dir.join("snoozed.csv"))?;
+        let names: Vec<&str> = snoozed.lines().skip(1).collect();
+        assert!(!names.is_empty());
+    }
+    assert_eq!(
+        std::fs::read(machines[0].0.join("snoozed.csv"))?,
+        std::fs::read(machines[1].0.join("snoozed.csv"))?
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn generates_from_converted_toml_library() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .arg("convert")
+        .arg(library_dir.join("push.csv"))
+        .arg(library_dir.join("push.toml"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Converted"));
+    std::fs::remove_file(library_dir.join("push.csv"))?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("push.toml"));
+
+    assert_eq!(std::fs::read_dir(&workouts_dir)?.count(), 1);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn picks_several_exercises_of_a_type_per_group() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args([
+            "-t",
+            "push",
+            "core",
+            "-g",
+            "1",
+            "--per-group",
+            "push=2,core=0",
+            "--focus",
+            "strength",
+        ])
+        .arg("-e")
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let content = std::fs::read_to_string(workout)?;
+    let rows: Vec<&str> = content.lines().filter(|l| l.starts_with('B')).collect();
+    assert_eq!(rows.len(), 2);
+    assert!(rows[0].starts_with("B1,") && rows[1].starts_with("B2,"));
+    // Four rounds of strength work at the intermediate level
+    assert!(rows.iter().all(|row| row.split(',').nth(2) == Some("4")));
+    assert!(rows[0].contains("4 rounds of B1+B2 (total: "));
+    assert!(rows.iter().all(|row| row.contains("/push/")));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn fills_slots_qualified_by_pattern_and_category() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    add_column(&library_dir.join("pull.csv"), "pattern", |line| {
+        if line.starts_with("pull-up") {
+            "vertical_pull"
+        } else {
+            "horizontal_pull"
+        }
+    })?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "pull:vertical-pull,legs:accessory", "-g", "2", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let content = std::fs::read_to_string(workout)?;
+    let rows: Vec<&str> = content
+        .lines()
+        .filter(|l| l.starts_with('B') || l.starts_with('C'))
+        .collect();
+    assert_eq!(rows.len(), 4);
+    assert!(rows
+        .iter()
+        .filter(|row| row.contains("/pull/"))
+        .all(|row| row.contains("/pull/pull-up")));
+    // Both accessory legs exercises, whatever the category of the group
+    assert!(content.contains("calves_raise") && content.contains("hyper_reverse"));
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "pull:", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected TYPE:QUALIFIER"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn gates_exercises_behind_their_prerequisites() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    add_column(&library_dir.join("pull.csv"), "prerequisites", |line| {
+        if line.starts_with("pull-up__l-sit,") {
+            "pull-up__regular: 10"
+        } else {
+            ""
+        }
+    })?;
+
+    let goals = || -> Result<assert_cmd::assert::Assert> {
+        Ok(Command::cargo_bin(PRG)?
+            .args(["goals", "-e"])
+            .arg(&library_dir)
+            .arg("-w")
+            .arg(&workouts_dir)
+            .assert()
+            .success())
+    };
+    goals()?.stdout(predicate::str::contains(
+        "Pull-up - L-sit needs:\n  Pull-up - Regular: 10 reps (best: nothing logged)\n",
+    ));
+    Command::cargo_bin(PRG)?
+        .args(["-t", "pull", "--explain", "--read-only", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "prerequisites not met, see wodgen goals (1): Pull-up - L-sit",
+        ));
+
+    std::fs::create_dir(&workouts_dir)?;
+    std::fs::write(
+        workouts_dir.join("2024_06_03.csv"),
+        "group,name,sets,distance,time,reps,goal,image,video,scaling,notes\n\
+         2,Pull-up - Regular,3,,,10/8/7,,,,,\n",
+    )?;
+    goals()?.stdout(predicate::str::contains("Every exercise is unlocked"));
+    Ok(())
+}
+
+#[test]
+fn reports_the_progress_toward_the_goal() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    add_column(&library_dir.join("pull.csv"), "prerequisites", |line| {
+        if line.starts_with("pull-up__l-sit,") {
+            "pull-up__regular: 10"
+        } else {
+            ""
+        }
+    })?;
+
+    Command::cargo_bin(PRG)?
+        .args([
+            "goal",
+            "set",
+            "first_pull-up__l-sit",
+            "--by",
+            "2030-01-01",
+            "-e",
+        ])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Building toward Pull-up - L-sit: Pull-up - Regular",
+        ));
+    Command::cargo_bin(PRG)?
+        .args(["goal", "set", "first_planche", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("\"first_planche\" not found"));
+
+    Command::cargo_bin(PRG)?
+        .args(["status", "-w"])
+        .arg(&workouts_dir)
+        .arg("-e")
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Goal: Pull-up - L-sit by 2030-01-01, ",
+        ))
+        .stdout(predicate::str::contains(
+            "  0 of 1 prerequisites met\n  Pull-up - Regular: 10 reps (best: nothing logged)\n",
+        ));
+
+    Command::cargo_bin(PRG)?
+        .args(["goal", "clear", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success();
+    Command::cargo_bin(PRG)?
+        .args(["status", "-w"])
+        .arg(&workouts_dir)
+        .arg("-e")
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Goal:").not());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn saves_a_benchmark_with_its_previous_scores() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    std::fs::write(
+        library_dir.join("benchmarks.csv"),
+        "benchmark,group,exercise,sets,goal,notes\n\
+         Cindy,B1,pull-up__regular,20,5,\n\
+         Cindy,B2,plank_front,20,0:30,\n",
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args(["benchmark", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cindy\n"));
+    Command::cargo_bin(PRG)?
+        .args(["benchmark", "fran", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Benchmark \"fran\" not found"));
+
+    std::fs::create_dir(&workouts_dir)?;
+    std::fs::write(
+        workouts_dir.join("2024_06_03_benchmark_cindy.csv"),
+        "group,name,sets,distance,time,reps,goal,image,video,scaling,notes\n\
+         B1,Pull-up - Regular,20,,,5/5/4,5,,,,\n\
+         B2,Plank Front,20,,0:30/0:25,,0:30,,,,\n",
+    )?;
+    Command::cargo_bin(PRG)?
+        .args(["benchmark", "cindy", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pull-up - Regular"))
+        .stdout(predicate::str::contains(
+            "Previous scores:\n  2024-06-03: 14 reps, 55s\n",
+        ));
+    assert_eq!(std::fs::read_dir(&workouts_dir)?.count(), 2);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn saves_a_test_day_and_schedules_the_retest() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let config = tmp.path().join("config.toml");
+    std::fs::write(&config, "[test_day]\nevery = 28\n")?;
+
+    Command::cargo_bin(PRG)?
+        .args(["test-day", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .env("WODGEN_CONFIG", &config)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No exercise to test"));
+
+    add_column(&library_dir.join("pull.csv"), "testable", |line| {
+        if line.starts_with("pull-up__regular,") {
+            "true"
+        } else {
+            ""
+        }
+    })?;
+    Command::cargo_bin(PRG)?
+        .args(["test-day", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .env("WODGEN_CONFIG", &config)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pull-up - Regular"))
+        .stdout(predicate::str::contains("max reps in one set"))
+        .stdout(predicate::str::is_match(
+            r"the retest is due on \d{4}-\d{2}-\d{2}",
+        )?);
+    let files: Vec<String> = std::fs::read_dir(&workouts_dir)?
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(files.len(), 1);
+    assert!(files[0].ends_with("_test.csv"));
+
+    Command::cargo_bin(PRG)?
+        .args(["status", "-w"])
+        .arg(&workouts_dir)
+        .env("WODGEN_CONFIG", &config)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            r"Next test day: \d{4}-\d{2}-\d{2}\n",
+        )?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn picks_from_a_subtree_of_the_taxonomy() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    add_column(&library_dir.join("pull.csv"), "pattern", |line| {
+        if line.starts_with("pull-up__rings") {
+            "rings_pull_up"
+        } else if line.starts_with("pull-up") {
+            "pull_up"
+        } else {
+            "horizontal_pull"
+        }
+    })?;
+    std::fs::write(
+        library_dir.join("taxonomy.toml"),
+        "vertical_pull = [\"pull_up\"]\npull_up = [\"rings_pull_up\"]\n",
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args(["taxonomy", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "pull (36)\n  horizontal_pull (19)\n  vertical_pull (17)\n    pull_up (17)\n      rings_pull_up (6)\n",
+        ));
+
+    // The vertical pulls include the pull-ups on rings, two levels down
+    Command::cargo_bin(PRG)?
+        .args(["-t", "pull:vertical", "-g", "3", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let content = std::fs::read_to_string(workout)?;
+    let rows: Vec<&str> = content.lines().filter(|l| l.contains("/pull/")).collect();
+    assert_eq!(rows.len(), 3);
+    assert!(rows.iter().all(|row| row.contains("/pull/pull-up")));
+
+    std::fs::write(
+        library_dir.join("taxonomy.toml"),
+        "sprint = [\"acceleration\"]\n",
+    )?;
+    Command::cargo_bin(PRG)?
+        .args(["taxonomy", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not under an exercise type"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn asks_the_plugin_for_every_slot() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let requests = tmp.path().join("requests.jsonl");
+    let plugin = tmp.path().join("plugin.sh");
+    std::fs::write(
+        &plugin,
+        format!(
+            "cat >> {}\necho '{{\"veto\": [\"plank_front\"]}}'\n",
+            requests.display()
+        ),
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push,core", "-g", "1", "--plugin"])
+        .arg(format!("sh {}", plugin.display()))
+        .arg("-e")
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    // One request per slot, with the candidates of the slot
+    let requests = std::fs::read_to_string(requests)?;
+    let lines: Vec<&str> = requests.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(r#"{"version":1,"slot":"push","group":0,"#));
+    assert!(lines[1].contains(r#""slot":"core""#));
+    assert!(lines[1].contains(r#""candidates":[{"name":"#));
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    assert!(!std::fs::read_to_string(workout)?.contains("Plank Front"));
+
+    std::fs::write(&plugin, "echo 'no pick for you' >&2\nexit 2\n")?;
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--plugin"])
+        .arg(format!("sh {}", plugin.display()))
+        .arg("-e")
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no pick for you"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn runs_the_scripts_of_the_config_dir() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    std::fs::create_dir(tmp.path().join("scripts"))?;
+    std::fs::write(
+        tmp.path().join("scripts").join("rules.rhai"),
+        r#"
+        fn prescribe(exercise, row) {
+            if exercise.exercise_type == "Pull" { row.sets = 7; }
+            row
+        }
+        fn validate(workout) {
+            if !workout.some(|row| row.name.contains("Plank")) { ["No plank today"] }
+        }
+        "#,
+    )?;
+
+    let assert = Command::cargo_bin(PRG)?
+        .args(["-t", "pull", "-g", "1", "--json", "--config"])
+        .arg(tmp.path().join("config.toml"))
+        .arg("-e")
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .code(10);
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains(r#""warnings":["No plank today"]"#));
+
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let content = std::fs::read_to_string(workout)?;
+    let row = content.lines().find(|l| l.starts_with("B1,")).unwrap();
+    assert_eq!(row.split(',').nth(2), Some("7"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn emails_the_workout_through_the_smtp_server_of_the_config() -> Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let config = tmp.path().join("config.toml");
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    std::fs::write(
+        &config,
+        format!(
+            "[smtp]\nhost = \"127.0.0.1\"\nport = {}\nsecurity = \"none\"\n",
+            listener.local_addr()?.port()
+        ),
+    )?;
+    // Answer every command but DATA with 250 until the client quits
+    let server = std::thread::spawn(move || -> std::io::Result<String> {
+        let (mut stream, _) = listener.accept()?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        stream.write_all(b"220 localhost\r\n")?;
+        let (mut received, mut line) = (String::new(), String::new());
+        while reader.read_line(&mut line)? > 0 && !line.starts_with("QUIT") {
+            received.push_str(&line);
+            match line.as_str() {
+                "DATA\r\n" => stream.write_all(b"354 go ahead\r\n")?,
+                ".\r\n" => stream.write_all(b"250 queued\r\n")?,
+                _ if received.contains("DATA\r\n") => {}
+                _ => stream.write_all(b"250 ok\r\n")?,
+            }
+            line.clear();
+        }
+        stream.write_all(b"221 bye\r\n")?;
+        Ok(received)
+    });
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "pull", "--email", "client@example.com", "--config"])
+        .arg(&config)
+        .arg("-e")
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(tmp.path().join("workouts"))
+        .env("WODGEN_SMTP_FROM", "coach@example.com")
+        .assert()
+        .success();
+    let received = server.join().unwrap()?;
+    assert!(received.contains("RCPT TO:<client@example.com>"));
+    assert!(received.contains("From: coach@example.com"));
+    assert!(received.contains("Content-Type: text/csv"));
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "pull", "--email", "client@example.com", "--config"])
+        .arg(tmp.path().join("missing.toml"))
+        .arg("-e")
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(tmp.path().join("workouts"))
+        .env_remove("WODGEN_SMTP_HOST")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No SMTP server"));
+
+    // Nothing is emailed on a read-only run
+    Command::cargo_bin(PRG)?
+        .args(["-t", "pull", "--email", "client@example.com", "--read-only"])
+        .arg("--config")
+        .arg(tmp.path().join("missing.toml"))
+        .arg("-e")
+        .arg(&library_dir)
+        .env_remove("WODGEN_SMTP_HOST")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("the workout is not emailed"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn appends_finisher_before_cooldown() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "pull", "-g", "2", "--finisher", "legs", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let content = std::fs::read_to_string(workout)?;
+    let finisher: Vec<&str> = content.lines().filter(|l| l.contains("AMRAP")).collect();
+    assert!((1..=2).contains(&finisher.len()));
+    assert!(finisher.iter().all(|row| row.starts_with('D')));
+    // Two timed cooldown pieces after three groups
+    let cooldown: Vec<&str> = content.lines().filter(|l| l.starts_with('E')).collect();
+    assert_eq!(cooldown.len(), 2);
+    assert!(content.lines().last().unwrap().starts_with("E2,"));
+    assert!(cooldown
+        .iter()
+        .all(|row| row.split(',').nth(4) == Some("X")));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn travel_mode_keeps_the_session_compact() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-g", "4", "--travel", "-b", "false", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Bodyweight: true"));
+
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let content = std::fs::read_to_string(workout)?;
+    let groups: Vec<&str> = content
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').next().unwrap())
+        .collect();
+    assert_eq!(groups.last(), Some(&"D1"));
+    assert!(!groups.iter().any(|group| group.starts_with('E')));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn kids_mode_uses_beginner_level_and_playful_names() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "legs", "-l", "advanced", "--kids", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exercise level: Beginner"));
+
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let content = std::fs::read_to_string(workout)?;
+    assert!(content.contains("1,Playground"));
+    assert!(!content.contains("Skill Block"));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[test]
+fn reports_sessions_of_a_month() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    // Log the workout as if it was done in June 2024
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let logged = std::fs::read_to_string(&workout)?.replace(",X,", ",10,");
+    std::fs::write(workouts_dir.join("2024_06_03.csv"), logged)?;
+
+    Command::cargo_bin(PRG)?
+        .arg("report")
+        .arg("-e")
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Sessions completed: 0 of 1 planned",
+        ));
+
+    let report = tmp.path().join("june.html");
+    Command::cargo_bin(PRG)?
+        .args(["report", "--month", "2024-06", "-f", "html", "-o"])
+        .arg(&report)
+        .arg("-e")
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+    let html = std::fs::read_to_string(report)?;
+    assert!(html.contains("<h1>Report 2024-06</h1>"));
+    assert!(html.contains("Sessions completed: 1 of 1 planned (100% adherence)"));
+    assert!(html.contains("<td>Push</td>"));
+    assert!(html.contains("<h3>Sets per Session</h3>\n<svg "));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[test]
+fn status_shows_streak_and_adherence() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    Command::cargo_bin(PRG)?
+        .arg("status")
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Current streak: 0 sessions"))
+        .stdout(predicate::str::contains("0 of 1 planned (0%)"))
+        .stdout(predicate::str::contains("No session completed yet"));
+
+    // Logging today's workout completes it
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let logged = std::fs::read_to_string(&workout)?.replace(",X,", ",10,");
+    std::fs::write(&workout, logged)?;
+    Command::cargo_bin(PRG)?
+        .arg("status")
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Current streak: 1 session completed",
+        ))
+        .stdout(predicate::str::contains("1 of 1 planned (100%)"));
+    Ok(())
+}
+
+#[test]
+fn marks_sessions_done_for_status() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    // Done without logging anything
+    Command::cargo_bin(PRG)?
+        .args(["done", "--note", "ran out of time", "-w"])
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("done"));
+    let index = std::fs::read_to_string(workouts_dir.join("completions.csv"))?;
+    assert!(index.starts_with("date,completed_at,note\n"));
+    assert!(index.contains(",ran out of time"));
+
+    Command::cargo_bin(PRG)?
+        .arg("status")
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Current streak: 1 session completed",
+        ))
+        .stdout(predicate::str::contains("1 of 1 planned (100%)"));
+
+    Command::cargo_bin(PRG)?
+        .args(["done", "2024-06-02", "-w"])
+        .arg(&workouts_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No workout saved for 2024-06-02"));
+    Ok(())
+}
+
+#[test]
+fn releases_the_snoozes_of_skipped_workouts() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let config = tmp.path().join("config.toml");
+    std::fs::write(&config, "[snooze]\ndays = 100000\nrelease = 2\n")?;
+
+    // Both snoozed on 2024-06-03, only the push-ups of the workout of the day,
+    // never logged nor marked done
+    std::fs::write(
+        library_dir.join("snoozed.csv"),
+        "name,timestamp\npush-up__regular,1717416000\npush-up__wide,1717416000\n",
+    )?;
+    std::fs::create_dir(&workouts_dir)?;
+    std::fs::write(
+        workouts_dir.join("2024_06_03.csv"),
+        "group,name,sets,distance,time,reps,goal,image,video,scaling,notes\n\
+         2,Push-up - Regular,3,,,X,,,,,\n",
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "core", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .env("WODGEN_CONFIG", &config)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Released 1 snoozes"));
+
+    let snoozed = std::fs::read_to_string(library_dir.join("snoozed.csv"))?;
+    assert!(!snoozed.contains("push-up__regular"));
+    assert!(snoozed.contains("push-up__wide,1717416000"));
+    let history = std::fs::read_to_string(library_dir.join("snooze_history.csv"))?;
+    assert!(history.contains("push-up__regular,1717416000,"));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[test]
+fn tracks_the_weekly_targets() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let config = tmp.path().join("config.toml");
+    std::fs::write(&config, "[targets.pull]\nsessions = 2\n")?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "pull", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .env("WODGEN_CONFIG", &config)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Behind the weekly target of pull: 0 of 2 sessions",
+        ));
+    // The workout of today counts as planned
+    Command::cargo_bin(PRG)?
+        .args(["status", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .env("WODGEN_CONFIG", &config)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Weekly targets:\n  pull: 1 of 2 sessions\n",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[test]
+fn reads_spreadsheet_exports() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    // Save the library as a spreadsheet would in a locale with decimal commas
+    for entry in std::fs::read_dir(&library_dir)? {
+        let path = entry?.path();
+        let content = std::fs::read_to_string(&path)?.replace(',', ";");
+        std::fs::write(&path, format!("\u{feff}{}", content))?;
+    }
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .failure();
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--csv-delimiter", ";", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    assert!(std::fs::read_to_string(workout)?.starts_with("# wodgen workout 3\ngroup;name;sets;"));
+
+    Command::cargo_bin(PRG)?
+        .args(["report", "-w"])
+        .arg(&workouts_dir)
+        .arg("-e")
+        .arg(&library_dir)
+        .env("WODGEN_CSV_DELIMITER", ";")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0 of 1 planned"));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[test]
+fn charts_weekly_training() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let workouts_dir = tmp.path().join("workouts");
+    std::fs::create_dir(&workouts_dir)?;
+    std::fs::write(
+        workouts_dir.join("logged.csv"),
+        "group,name,sets,distance,time,reps,goal,image,video,scaling,notes\n\
+         B1,Dip - Regular,,,,10/10/8,,,,,\n",
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args(["chart", "volume", "--weeks", "4", "-w"])
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Sets logged per week"))
+        .stdout(predicate::str::contains("3 sets"));
+
+    Command::cargo_bin(PRG)?
+        .args(["chart", "progress", "--exercise", "dip__regular", "-w"])
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("10 reps"));
+
+    Command::cargo_bin(PRG)?
+        .args(["chart", "progress", "-w"])
+        .arg(&workouts_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--exercise"));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[test]
+fn prints_a_heatmap_of_the_training_days() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    std::fs::create_dir(&workouts_dir)?;
+    std::fs::write(
+        workouts_dir.join("logged.csv"),
+        "group,name,sets,distance,time,reps,goal,image,video,scaling,notes\n\
+         B1,Dip - Regular,,,,10/10/8,,,,,\n",
+    )?;
+    let heatmap = |args: &[&str]| -> Result<assert_cmd::assert::Assert> {
+        Ok(Command::cargo_bin(PRG)?
+            .arg("heatmap")
+            .args(args)
+            .args(["--weeks", "8", "-e"])
+            .arg(&library_dir)
+            .arg("-w")
+            .arg(&workouts_dir)
+            .assert())
+    };
+
+    heatmap(&[])?
+        .success()
+        .stdout(predicate::str::contains("Training days\n"))
+        .stdout(predicate::str::contains("█"))
+        .stdout(predicate::str::contains("1 active day in the last 8 weeks"));
+    heatmap(&["push"])?
+        .success()
+        .stdout(predicate::str::contains("Push exercises\n"))
+        .stdout(predicate::str::contains("1 active day in"));
+    heatmap(&["pull"])?
+        .success()
+        .stdout(predicate::str::contains("0 active days"));
+    heatmap(&["dip__regular"])?
+        .success()
+        .stdout(predicate::str::contains("Dip - Regular\n"))
+        .stdout(predicate::str::contains("1 active day in"));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[test]
+fn estimates_energy_from_met_values() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    // Give every push exercise a MET value
+    add_column(&library_dir.join("push.csv"), "met", |_| "5")?;
+    let config = tmp.path().join("config.toml");
+    std::fs::write(&config, "body_mass_kg = 80\n")?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-f", "markdown", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .env("WODGEN_CONFIG", &config)
+        .assert()
+        .success();
+
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let markdown = std::fs::read_to_string(workout)?;
+    assert!(markdown.contains("> **Estimated energy:** ~"));
+    assert!(markdown.contains(" kcal"));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[test]
+fn leaves_out_muscles_at_their_mrv() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    add_column(&library_dir.join("push.csv"), "muscles", |row| {
+        if row.starts_with("push-up") {
+            "chest"
+        } else {
+            "triceps"
+        }
+    })?;
+    let config = tmp.path().join("config.toml");
+    std::fs::write(&config, "[volume.muscles.chest]\nmv = 1\nmrv = 4\n")?;
+
+    // Four sets of push-ups were logged earlier today
+    std::fs::create_dir(&workouts_dir)?;
+    std::fs::write(
+        workouts_dir.join("logged.csv"),
+        "group,name,sets,distance,time,reps,goal,image,video,scaling,notes\n\
+         2,Push-up - Regular,,,,10/10/10/10,,,,,\n",
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .env("WODGEN_CONFIG", &config)
+        .assert()
+        .success();
+
+    let workout = std::fs::read_dir(&workouts_dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<PathBuf>>>()?
+        .into_iter()
+        .find(|path| !path.ends_with("logged.csv"))
+        .unwrap();
+    let workout = std::fs::read_to_string(workout)?;
+    assert!(workout.contains("Dip"));
+    assert!(!workout.contains("Push-up"));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[test]
+fn goes_easy_on_sore_areas() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    add_column(&library_dir.join("push.csv"), "muscles", |row| {
+        if row.starts_with("push-up") {
+            "chest"
+        } else {
+            "triceps"
+        }
+    })?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--sore", "Chest", "--explain", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("trains a sore area"));
+
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let workout = std::fs::read_to_string(workout)?;
+    assert!(workout.contains("\n,Sore,,,,,,,,,chest,,exercise,\n"));
+    assert!(workout.contains("Dip"));
+    assert!(!workout.contains("Push-up"));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[test]
+fn sets_readiness_from_check_ins() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args([
+            "checkin",
+            "--sleep",
+            "6",
+            "--soreness",
+            "1",
+            "--motivation",
+            "5",
+        ])
+        .arg("-e")
+        .arg(&library_dir)
+        .assert()
+        .failure();
+
+    Command::cargo_bin(PRG)?
+        .args([
+            "checkin",
+            "--sleep",
+            "1",
+            "--soreness",
+            "5",
+            "--motivation",
+            "2",
+        ])
+        .args(["--rpe", "9", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Low readiness"));
+    assert!(std::fs::read_to_string(library_dir.join("wellness.csv"))?
+        .starts_with("checked_in_at,sleep,soreness,motivation,rpe\n"));
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--readiness", "auto", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Readiness: Low"));
+
+    // One round less than the 3 of hypertrophy
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    assert!(std::fs::read_to_string(workout)?.contains("2 rounds of B1"));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[test]
+fn prescribes_loads_from_training_max() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    std::fs::write(
+        library_dir.join("legs.csv"),
+        "name,exercise_type,exercise_category,exercise_level,exercise_programming,bodyweight,goal,video\n\
+         back_squat,Legs,Primary,Intermediate,Reps,false,,\n\
+         pistol_squat,Legs,Primary,Intermediate,Reps,true,,\n",
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args(["tm", "set", "back squat", "120", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success();
+    Command::cargo_bin(PRG)?
+        .args(["tm", "set", "pistol_squat", "60", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("bodyweight exercise"));
+    Command::cargo_bin(PRG)?
+        .args(["tm", "list", "--units", "imperial", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Back Squat: 264.55lb (Wendler"));
+
+    Command::cargo_bin(PRG)?
+        .args([
+            "-t",
+            "legs",
+            "-g",
+            "1",
+            "-b",
+            "false",
+            "-x",
+            "pistol_squat",
+            "-e",
+        ])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let workout = std::fs::read_to_string(workout)?;
+    assert!(workout.contains("B1,Back Squat,3,"));
+    assert!(workout.contains("5/3/1 week 1: 5 @ 77.5kg, 5 @ 90kg, 5+ @ 102.5kg"));
+    // Warm-up sets ramp up to the first working set, right before it
+    let warm_up = workout.find("B1w,Warm-up,1,,,,5 @ 30kg,").unwrap();
+    assert!(warm_up < workout.find("B1w,Warm-up,1,,,,3 @ 47.5kg,").unwrap());
+    assert!(
+        workout.find("B1w,Warm-up,1,,,,2 @ 62.5kg,").unwrap()
+            < workout.find("B1,Back Squat").unwrap()
+    );
+
+    // Loads are made up from the plates of the inventory
+    let config = tmp.path().join("config.toml");
+    std::fs::write(&config, "[inventory]\nplates = [20, 20, 10, 5]\n")?;
+    let plates_dir = tmp.path().join("plates");
+    Command::cargo_bin(PRG)?
+        .args([
+            "-t",
+            "legs",
+            "-g",
+            "1",
+            "-b",
+            "false",
+            "-x",
+            "pistol_squat",
+            "-e",
+        ])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&plates_dir)
+        .arg("--snoozed-file")
+        .arg(tmp.path().join("snoozed.csv"))
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success();
+    let workout = std::fs::read_dir(&plates_dir)?.next().unwrap()?.path();
+    let workout = std::fs::read_to_string(workout)?;
+    assert!(workout.contains("5 @ 80kg (per side: 20kg + 10kg)"));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[test]
+fn fits_workouts_to_the_equipment_registry() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let config = tmp.path().join("config").join("config.toml");
+    std::fs::write(
+        library_dir.join("legs.csv"),
+        "name,exercise_type,exercise_category,exercise_level,exercise_programming,bodyweight,goal,video,equipment\n\
+         back_squat,Legs,Primary,Intermediate,Reps,false,,,barbell;rack\n\
+         goblet_squat,Legs,Primary,Intermediate,Reps,false,,,dumbbell\n",
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args([
+            "equipment",
+            "add",
+            "rings",
+            "40kg dumbbells x2",
+            "20kg plates x4",
+        ])
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success();
+    Command::cargo_bin(PRG)?
+        .args(["equipment", "remove", "plates", "--config"])
+        .arg(&config)
+        .assert()
+        .success();
+    Command::cargo_bin(PRG)?
+        .args(["equipment", "list", "--config"])
+        .arg(&config)
+        .assert()
+        .success()
+        .stdout("40kg dumbbells x2\nrings\n");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "legs", "-g", "1", "-b", "false", "--explain", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("needs equipment not at hand"));
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let workout = std::fs::read_to_string(workout)?;
+    assert!(workout.contains("Goblet Squat"));
+    assert!(!workout.contains("Back Squat"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn setup_scaffolds_the_config_and_library_and_generates_a_workout() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let config = tmp.path().join("config").join("config.toml");
+    let library_dir = tmp.path().join("exercise_library");
+    let workouts_dir = tmp.path().join("workouts");
+    let setup = || -> Result<Command> {
+        let mut cmd = Command::cargo_bin(PRG)?;
+        cmd.args(["setup", "--config"])
+            .arg(&config)
+            .arg("-e")
+            .arg(&library_dir)
+            .arg("-w")
+            .arg(&workouts_dir);
+        Ok(cmd)
+    };
+
+    setup()?
+        .write_stdin("intermediate\nrings\nthu mon\nstrength\n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Welcome to wodgen!"))
+        .stdout(predicate::str::contains("# Workout "));
+
+    let written = std::fs::read_to_string(&config)?;
+    assert!(written.contains(
+        "[split.mon]\ntypes = [\"push\", \"core\"]\nfocus = \"strength\"\nlevel = \"intermediate\"\n"
+    ));
+    assert!(written.contains("[split.thu]\ntypes = [\"pull\", \"legs\"]"));
+    Command::cargo_bin(PRG)?
+        .args(["equipment", "list", "--config"])
+        .arg(&config)
+        .assert()
+        .success()
+        .stdout("rings\n");
+    assert!(library_dir.join("push.csv").is_file());
+    assert_eq!(std::fs::read_dir(&workouts_dir)?.count(), 1);
+
+    setup()?
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exists already"));
+    Ok(())
+}
+
+// --------------------------------------------------
+
+#[test]
+fn today_follows_the_split() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let config = tmp.path().join("config.toml");
+
+    std::fs::write(&config, "[split]\nsat = []\n")?;
+    Command::cargo_bin(PRG)?
+        .args(["today", "--config"])
+        .arg(&config)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No session planned for"));
+
+    let days = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+    let split: String = days
+        .iter()
+        .map(|day| {
+            format!(
+                "[split.{}]\ntypes = [\"pull\"]\nfocus = \"grip\"\nduration = 45\n",
+                day
+            )
+        })
+        .collect();
+    std::fs::write(&config, &split)?;
+    Command::cargo_bin(PRG)?
+        .args(["today", "--config"])
+        .arg(&config)
+        .args(["-g", "1", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("# Workout "))
+        .stdout(predicate::str::contains("| B1 | "))
+        .stdout(predicate::str::contains("> **Note:** grip"));
+    let workout = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let workout = std::fs::read_to_string(workout)?;
+    assert_eq!(workout.lines().filter(|l| l.starts_with('B')).count(), 1);
+
+    // A long day gets as many groups as the library can fill, up to the most
+    std::fs::write(&config, split.replace("duration = 45", "duration = 200"))?;
+    Command::cargo_bin(PRG)?
+        .args(["today", "--config"])
+        .arg(&config)
+        .arg("-e")
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| B1 | "));
+    // A focus of the rounds sets --focus whatever its case
+    std::fs::write(&config, split.replace("\"grip\"", "\"Strength\""))?;
+    Command::cargo_bin(PRG)?
+        .args(["today", "--config"])
+        .arg(&config)
+        .args(["--read-only", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("> **Note:** Strength"));
+    std::fs::write(&config, split.replace("duration = 45", "duration = 0"))?;
+    Command::cargo_bin(PRG)?
+        .args(["today", "--config"])
+        .arg(&config)
+        .arg("-e")
+        .arg(&library_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("session of 0 minutes on Mon"));
+
+    // Every type of the split must be in the library
+    std::fs::write(&config, &split)?;
+    std::fs::write(
+        library_dir.join("pull.csv"),
+        "name,exercise_type,exercise_category,exercise_level,exercise_programming,bodyweight,goal,video\n",
+    )?;
+    Command::cargo_bin(PRG)?
+        .args(["today", "--config"])
+        .arg(&config)
+        .arg("-e")
+        .arg(&library_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "plans Pull exercises on Mon, but the exercise library has none",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn regenerates_with_the_options_of_the_last_run() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let state_dir = tmp.path().join("state");
+
+    Command::cargo_bin(PRG)?
+        .args(["regenerate", "--state-dir"])
+        .arg(&state_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No run found for any day"));
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "-g", "2", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .arg("--state-dir")
+        .arg(&state_dir)
+        .assert()
+        .success();
+    let workout_path = std::fs::read_dir(&workouts_dir)?.next().unwrap()?.path();
+    let picks = |workout: &str| -> Vec<String> {
+        workout
+            .lines()
+            .skip(2)
+            .map(|line| line.split(',').collect::<Vec<&str>>())
+            // Exercises only, which come with a video
+            .filter(|row| !row[8].is_empty())
+            .map(|row| row[1].to_string())
+            .collect()
+    };
+    let first = picks(&std::fs::read_to_string(&workout_path)?);
+    assert!(!first.is_empty());
+
+    // Left out by the run rather than by the snoozes
+    std::fs::remove_file(state_dir.join("snoozed.csv"))?;
+    Command::cargo_bin(PRG)?
+        .args(["regenerate", "--state-dir"])
+        .arg(&state_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+    assert_eq!(std::fs::read_dir(&workouts_dir)?.count(), 1);
+    let second = picks(&std::fs::read_to_string(&workout_path)?);
+    assert_eq!(second.len(), first.len());
+    assert!(second.iter().all(|name| !first.contains(name)));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn replays_a_workout_from_its_run_metadata() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    // The same seed picks the same exercises
+    let read_only = || -> Result<String> {
+        let output = Command::cargo_bin(PRG)?
+            .args(["-t", "push", "core", "--read-only", "--seed", "7", "-e"])
+            .arg(&library_dir)
+            .output()?;
+        // The rows of the printed workout, without the timed log lines
+        Ok(String::from_utf8(output.stdout)?
+            .lines()
+            .filter(|line| line.starts_with('|'))
+            .collect::<Vec<&str>>()
+            .join("\n"))
+    };
+    let workout = read_only()?;
+    assert!(workout.contains("| B1 |"));
+    assert_eq!(read_only()?, workout);
+
+    Command::cargo_bin(PRG)?
+        .args(["replay", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No saved workouts found"));
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "core", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"Seed: \d+")?);
+    assert!(library_dir.join("runs").is_dir());
+
+    Command::cargo_bin(PRG)?
+        .args(["replay", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No differences"));
+
+    // A different library may pick other exercises
+    std::fs::write(
+        library_dir.join("push.csv"),
+        std::fs::read_to_string(library_dir.join("push.csv"))? + "\n",
+    )?;
+    Command::cargo_bin(PRG)?
+        .args(["replay", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("The exercise library changed"));
+    Command::cargo_bin(PRG)?
+        .args(["regenerate", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("The exercise library changed"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn replays_a_run_as_it_was() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let generate = |workouts_dir: &str, seed: &str| -> Result<()> {
+        Command::cargo_bin(PRG)?
+            .args([
+                "-t",
+                "push",
+                "core",
+                "--snooze-mode",
+                "decay",
+                "--seed",
+                seed,
+            ])
+            .arg("-e")
+            .arg(&library_dir)
+            .arg("-w")
+            .arg(tmp.path().join(workouts_dir))
+            .assert()
+            .success();
+        Ok(())
+    };
+    let replay = || -> Result<assert_cmd::assert::Assert> {
+        Ok(Command::cargo_bin(PRG)?
+            .args(["replay", "-e"])
+            .arg(&library_dir)
+            .arg("-w")
+            .arg(tmp.path().join("bob"))
+            .assert())
+    };
+
+    // The decaying snoozes of the run weigh the replayed picks, not those of
+    // the runs after it, nor the sessions marked done since
+    generate("alice", "1")?;
+    generate("bob", "2")?;
+    generate("carol", "3")?;
+    Command::cargo_bin(PRG)?
+        .arg("done")
+        .arg("-w")
+        .arg(tmp.path().join("bob"))
+        .assert()
+        .success();
+    replay()?
+        .success()
+        .stdout(predicate::str::contains("No differences"))
+        .stdout(predicate::str::contains("changed since").not());
+
+    // The goal is read as it is now
+    Command::cargo_bin(PRG)?
+        .args(["goal", "set", "first_pull-up__l-sit", "-e"])
+        .arg(&library_dir)
+        .assert()
+        .success();
+    replay()?.success().stdout(
+        predicate::str::contains("goal.csv").and(predicate::str::contains("changed since")),
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reads_back_json_workouts() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--format", "csv,json", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .success();
+
+    let saved: Vec<PathBuf> = std::fs::read_dir(&workouts_dir)?
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    let csv = saved
+        .iter()
+        .find(|p| p.extension().unwrap() == "csv")
+        .unwrap();
+    let json = saved
+        .iter()
+        .find(|p| p.extension().unwrap() == "json")
+        .unwrap();
+    assert!(std::fs::read_to_string(json)?.contains("\"group\": \"A1\""));
+
+    Command::cargo_bin(PRG)?
+        .args(["diff"])
+        .arg(csv)
+        .arg(json)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No differences"));
+
+    Command::cargo_bin(PRG)?
+        .args(["annotate"])
+        .arg(json)
+        .args(["skill block", "felt strong"])
+        .assert()
+        .success();
+    assert!(std::fs::read_to_string(json)?.contains("\"notes\": \"felt strong\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn snoozes_last_the_days_of_their_type() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let state_dir = tmp.path().join("state");
+    std::fs::create_dir(&state_dir)?;
+
+    // Every cooldown exercise was picked two days ago
+    let two_days_ago = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs()
+        - 2 * 24 * 60 * 60;
+    let cooldown = std::fs::read_to_string(library_dir.join("cooldown.csv"))?;
+    let mut snoozed = String::from("name,timestamp\n");
+    for line in cooldown.lines().skip(1) {
+        let name = line.split(',').next().unwrap();
+        snoozed.push_str(&format!("{},{}\n", name, two_days_ago));
+    }
+    let config = tmp.path().join("config.toml");
+    let generate = || -> Result<assert_cmd::assert::Assert> {
+        std::fs::write(state_dir.join("snoozed.csv"), &snoozed)?;
+        Ok(Command::cargo_bin(PRG)?
+            .args(["-t", "push", "-e"])
+            .arg(&library_dir)
+            .arg("-w")
+            .arg(tmp.path().join("workouts"))
+            .arg("--state-dir")
+            .arg(&state_dir)
+            .arg("--config")
+            .arg(&config)
+            .assert())
+    };
+
+    generate()?
+        .failure()
+        .stderr(predicate::str::contains("No Cooldown exercises left"));
+    // Decaying snoozes only make them less likely
+    std::fs::write(state_dir.join("snoozed.csv"), &snoozed)?;
+    Command::cargo_bin(PRG)?
+        .args(["-t", "push", "--snooze-mode", "decay", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(tmp.path().join("decay"))
+        .arg("--state-dir")
+        .arg(&state_dir)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success();
+    std::fs::write(&config, "[snooze]\ncooldown = 1\n")?;
+    generate()?.success();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reports_results_as_json_with_exit_codes() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let generate = |extra: &[&str]| -> Result<assert_cmd::assert::Assert> {
+        Ok(Command::cargo_bin(PRG)?
+            .args(["-t", "push", "--json", "-e"])
+            .arg(&library_dir)
+            .arg("-w")
+            .arg(tmp.path().join("workouts"))
+            .args(extra)
+            .assert())
+    };
+    let result = |assert: &assert_cmd::assert::Assert| -> serde_json::Value {
+        let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+        assert_eq!(stdout.lines().count(), 1);
+        serde_json::from_str(&stdout).unwrap()
+    };
+
+    let ok = generate(&[])?.success();
+    let ok = result(&ok);
+    assert_eq!(ok["status"], "ok");
+    assert!(ok["files"][0].as_str().unwrap().ends_with(".csv"));
+    assert!(ok["exercises"].as_u64().unwrap() > 0);
+
+    let warned = generate(&["--pin", "no_such_exercise"])?.code(10);
+    let warned = result(&warned);
+    assert_eq!(warned["status"], "warnings");
+    assert!(warned["warnings"][0]
+        .as_str()
+        .unwrap()
+        .contains("no_such_exercise"));
+
+    // No cooldown exercise left to fill the workout
+    let excluded: Vec<String> = std::fs::read_to_string(library_dir.join("cooldown.csv"))?
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').next().unwrap().to_string())
+        .collect();
+    let unfilled = generate(&["-x", &excluded.join(",")])?.code(3);
+    assert_eq!(result(&unfilled)["status"], "error");
+
+    let missing = tmp.path().join("missing");
+    Command::cargo_bin(PRG)?
+        .args(["annotate"])
+        .arg(&missing)
+        .args(["plank", "felt strong"])
+        .assert()
+        .code(4);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bot_sends_workouts_and_records_logs_in_telegram() -> Result<()> {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let workouts_dir = tmp.path().join("workouts");
+    let roster = tmp.path().join("roster.toml");
+    std::fs::write(&roster, "[[athletes]]\nname = \"Alice\"\ntelegram = 42\n")?;
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let api = format!("http://{}", listener.local_addr()?);
+
+    // Answer the Bot API methods, with two batches of messages before
+    // refusing the token, which stops the bot. Chat 7 blocked the bot
+    let server = std::thread::spawn(move || -> std::io::Result<Vec<String>> {
+        let updates = [
+            r#"[{"update_id":1,"message":{"chat":{"id":42},"text":"/wod"}},
+                {"update_id":2,"message":{"chat":{"id":7},"text":"/wod"}}]"#,
+            r#"[{"update_id":3,"message":{"chat":{"id":42},"text":"B1 10/10/8"}}]"#,
+        ];
+        let (mut sent, mut polls) = (Vec::new(), 0);
+        loop {
+            let (stream, _) = listener.accept()?;
+            let mut reader = BufReader::new(stream.try_clone()?);
+            let (mut request_line, mut line, mut length) = (String::new(), String::new(), 0);
+            reader.read_line(&mut request_line)?;
+            while reader.read_line(&mut line)? > 2 {
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                line.clear();
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+
+            let answer = if request_line.contains("/getMe") {
+                String::from(r#"{"ok":true,"result":{"username":"wodgen_bot"}}"#)
+            } else if request_line.contains("/sendMessage") {
+                sent.push(String::from_utf8_lossy(&body).into_owned());
+                if sent.last().unwrap().contains(r#""chat_id":7"#) {
+                    String::from(r#"{"ok":false,"description":"Forbidden: bot was blocked"}"#)
+                } else {
+                    String::from(r#"{"ok":true,"result":{}}"#)
+                }
+            } else if polls < updates.len() {
+                polls += 1;
+                format!(r#"{{"ok":true,"result":{}}}"#, updates[polls - 1])
+            } else {
+                String::from(r#"{"ok":false,"description":"Unauthorized"}"#)
+            };
+            write!(
+                &stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                answer.len(),
+                answer
+            )?;
+            if answer.contains("Unauthorized") {
+                return Ok(sent);
+            }
+        }
+    });
+
+    Command::cargo_bin(PRG)?
+        .args(["bot", "telegram", "--token", "123:abc", "--api", &api])
+        .args(["-t", "pull", "-g", "1", "--roster"])
+        .arg(&roster)
+        .arg("-e")
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(&workouts_dir)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Forbidden: bot was blocked"))
+        .stderr(predicate::str::contains("Unauthorized"));
+    let sent = server.join().unwrap()?;
+    assert_eq!(sent.len(), 3);
+    assert!(sent[0].contains(r#""chat_id":42"#) && sent[0].contains("B1 "));
+    assert!(sent[1].contains("telegram = 7"));
+    assert!(sent[2].contains("Logged B1"));
+
+    let workout = std::fs::read_dir(workouts_dir.join("alice"))?
+        .next()
+        .unwrap()?
+        .path();
+    assert!(std::fs::read_to_string(workout)?.contains("10/10/8"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn archives_ended_snoozes_and_prints_their_history() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let library_dir = copy_library(tmp.path())?;
+    let state_dir = tmp.path().join("state");
+    std::fs::create_dir(&state_dir)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    let days_ago = |days: u64| now - days * 24 * 60 * 60;
+    // Dips snoozed a month ago, then two days ago, and squats ten days ago
+    std::fs::write(
+        state_dir.join("snoozed.csv"),
+        format!(
+            "name,timestamp\ndip__regular,{}\ndip__regular,{}\nsquat,{}\n",
+            days_ago(30),
+            days_ago(2),
+            days_ago(10)
+        ),
+    )?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-t", "pull", "-e"])
+        .arg(&library_dir)
+        .arg("-w")
+        .arg(tmp.path().join("workouts"))
+        .arg("--state-dir")
+        .arg(&state_dir)
+        .assert()
+        .success();
+    let history = std::fs::read_to_string(state_dir.join("snooze_history.csv"))?;
+    assert!(history.starts_with("name,timestamp,until\n"));
+    assert!(history.contains(&format!("dip__regular,{},", days_ago(30))));
+    assert!(history.contains(&format!("squat,{},", days_ago(10))));
+    let snoozed = std::fs::read_to_string(state_dir.join("snoozed.csv"))?;
+    assert!(snoozed.contains(&format!("dip__regular,{}", days_ago(2))));
+    assert!(!snoozed.contains("squat,"));
+
+    let history = |exercise: &str| -> Result<assert_cmd::assert::Assert> {
+        Ok(Command::cargo_bin(PRG)?
+            .args(["snooze", "history", exercise, "-e"])
+            .arg(&library_dir)
+            .arg("--state-dir")
+            .arg(&state_dir)
+            .arg("--config")
+            .arg(tmp.path().join("config.toml"))
+            .assert())
+    };
+    let assert = history("Dip - Regular")?.success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains("Dip - Regular was last blocked until "));
+    assert!(stdout.contains("snoozed 2 times"));
+    assert_eq!(
+        stdout.lines().filter(|l| l.ends_with("(snoozed)")).count(),
+        1
+    );
+    history("squat")?
+        .success()
+        .stdout(predicate::str::contains("Squat was last blocked on "));
+    history("plank")?
+        .success()
+        .stdout(predicate::str::ends_with("Plank was never snoozed\n"));
+    Ok(())
+}